@@ -0,0 +1,139 @@
+use image::RgbaImage;
+
+use crate::rect::Rect;
+
+/// Number of bytes of the RGBA pixels checked at once.
+const LANE_BYTES: usize = 16;
+
+/// Selects the alpha bytes of four RGBA pixels read as a little-endian `u128`.
+const ALPHA_MASK: u128 = 0xff00_0000_ff00_0000_ff00_0000_ff00_0000;
+
+/// Returns whether any pixel of `pixels`, a run of RGBA bytes, is not fully transparent.
+///
+/// Four pixels are tested per step by masking their alpha bytes, which compilers turn into
+/// vector instructions.
+fn any_visible(pixels: &[u8]) -> bool {
+    let lanes = pixels.chunks_exact(LANE_BYTES);
+    let rest = lanes.remainder();
+    lanes
+        .map(|lane| u128::from_le_bytes(lane.try_into().expect("lanes are 16 bytes")))
+        .any(|lane| lane & ALPHA_MASK != 0)
+        || rest.chunks_exact(4).any(|pixel| pixel[3] != 0)
+}
+
+/// Returns the index of the first pixel of `row` that is not fully transparent.
+fn first_visible(row: &[u8]) -> Option<usize> {
+    let lanes = row.len() / LANE_BYTES;
+    let lane = (0..lanes).find(|&lane| any_visible(&row[lane * LANE_BYTES..][..LANE_BYTES]));
+    let start = lane.unwrap_or(lanes) * LANE_BYTES;
+    row[start..]
+        .chunks_exact(4)
+        .position(|pixel| pixel[3] != 0)
+        .map(|index| start / 4 + index)
+}
+
+/// Returns the index of the last pixel of `row` that is not fully transparent.
+fn last_visible(row: &[u8]) -> Option<usize> {
+    // Lanes are aligned to the end of the row, leaving the remainder at its start.
+    let offset = row.len() % LANE_BYTES;
+    let lanes = row.len() / LANE_BYTES;
+    let lane = (0..lanes)
+        .rev()
+        .find(|&lane| any_visible(&row[offset + lane * LANE_BYTES..][..LANE_BYTES]));
+    let end = lane.map_or(offset, |lane| offset + (lane + 1) * LANE_BYTES);
+    row[..end].chunks_exact(4).rposition(|pixel| pixel[3] != 0)
+}
+
+/// Returns the smallest rectangle containing every non-transparent pixel of `image`,
+/// or `None` if the image is completely transparent.
+///
+/// Rows are scanned as byte slices: empty rows at the top and bottom are skipped first, then
+/// each remaining row is only searched outside the columns already known to be visible.
+pub(crate) fn opaque_bounds(image: &RgbaImage) -> Option<Rect> {
+    let stride = image.width() as usize * 4;
+    if stride == 0 {
+        return None;
+    }
+    let rows: Vec<&[u8]> = image.as_raw().chunks_exact(stride).collect();
+
+    let top = rows.iter().position(|row| any_visible(row))?;
+    let bottom = rows.iter().rposition(|row| any_visible(row))?;
+
+    let (mut left, mut right) = (usize::MAX, 0);
+    for row in &rows[top..=bottom] {
+        let unknown = left.min(image.width() as usize);
+        if let Some(x) = first_visible(&row[..unknown * 4]) {
+            left = x;
+        }
+        let known = (right + 1).min(image.width() as usize);
+        if let Some(x) = last_visible(&row[known * 4..]) {
+            right = known + x;
+        }
+        if left == 0 && right + 1 == image.width() as usize {
+            break;
+        }
+    }
+    // The right edge is only searched from column 1 on, so it is column 0 when no other
+    // column is visible, which `left` then is too.
+    let right = right.max(left);
+
+    Some(Rect::new(
+        left as u32,
+        top as u32,
+        (right - left + 1) as u32,
+        (bottom - top + 1) as u32,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use proptest::prelude::*;
+
+    /// Finds the bounds pixel by pixel, as a reference.
+    fn naive_bounds(image: &RgbaImage) -> Option<Rect> {
+        let visible: Vec<(u32, u32)> = image
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel[3] > 0)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let left = visible.iter().map(|&(x, _)| x).min()?;
+        let right = visible.iter().map(|&(x, _)| x).max()?;
+        let top = visible.iter().map(|&(_, y)| y).min()?;
+        let bottom = visible.iter().map(|&(_, y)| y).max()?;
+        Some(Rect::new(left, top, right - left + 1, bottom - top + 1))
+    }
+
+    #[test]
+    fn test_opaque_bounds() {
+        let mut image = RgbaImage::new(37, 9);
+        assert_eq!(opaque_bounds(&image), None);
+        assert_eq!(opaque_bounds(&RgbaImage::new(0, 4)), None);
+
+        image.put_pixel(0, 4, Rgba([0, 0, 0, 1]));
+        assert_eq!(opaque_bounds(&image), Some(Rect::new(0, 4, 1, 1)));
+        image.put_pixel(36, 2, Rgba([0, 0, 0, 255]));
+        image.put_pixel(20, 7, Rgba([255, 255, 255, 0]));
+        assert_eq!(opaque_bounds(&image), Some(Rect::new(0, 2, 37, 3)));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_matches_pixel_scan(
+            width in 1u32..40,
+            height in 1u32..6,
+            pixels in proptest::collection::vec((0u32..40, 0u32..6), 0..4),
+        ) {
+            let mut image = RgbaImage::from_pixel(width, height, Rgba([9, 9, 9, 0]));
+            for (x, y) in pixels {
+                if x < width && y < height {
+                    image.put_pixel(x, y, Rgba([0, 0, 0, 128]));
+                }
+            }
+            prop_assert_eq!(opaque_bounds(&image), naive_bounds(&image));
+        }
+    }
+}
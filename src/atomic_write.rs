@@ -0,0 +1,144 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::SpriteratorError;
+
+/// Distinguishes temporary files of concurrent writes within one process.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// What to do when an output file already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverwritePolicy {
+    /// Replace the existing file.
+    #[default]
+    Overwrite,
+    /// Keep the existing file and write nothing.
+    Skip,
+    /// Fail with `SpriteratorError::OutputExists`.
+    Error,
+}
+
+/// Returns a hidden temporary path next to `path`, so that renaming it stays on one filesystem.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map_or_else(Default::default, |name| name.to_string_lossy());
+    path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Writes a file through a temporary file renamed over `path` once complete, so an interrupted
+/// write never leaves a truncated file behind.
+///
+/// # Arguments
+/// - `path`: Path of the file to write.
+/// - `policy`: What to do if `path` already exists, checked before anything is written.
+/// - `write`: Writes the contents of the file.
+///
+/// # Returns
+/// A `Result` containing `false` if the file was skipped, or an error if writing fails.
+pub(crate) fn write_atomic<F>(
+    path: &Path,
+    policy: OverwritePolicy,
+    write: F,
+) -> Result<bool, Box<dyn Error>>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), Box<dyn Error>>,
+{
+    if path.exists() {
+        match policy {
+            OverwritePolicy::Overwrite => {}
+            OverwritePolicy::Skip => return Ok(false),
+            OverwritePolicy::Error => {
+                return Err(SpriteratorError::OutputExists {
+                    path: path.display().to_string(),
+                }
+                .into())
+            }
+        }
+    }
+
+    let temp = temp_path(path);
+    let result = File::create(&temp)
+        .map_err(Box::<dyn Error>::from)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write(&mut writer)?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+            Ok(())
+        })
+        .and_then(|()| Ok(std::fs::rename(&temp, path)?));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result.map(|()| true)
+}
+
+/// Writes `data` to `path` atomically, replacing any existing file.
+pub(crate) fn write_bytes(path: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    write_atomic(path, OverwritePolicy::Overwrite, |writer| {
+        Ok(writer.write_all(data)?)
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("spriterator_atomic_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_policies() {
+        let dir = dir("policies");
+        let path = dir.join("sheet.png");
+        let write = |policy, data: &'static [u8]| {
+            write_atomic(&path, policy, |writer| Ok(writer.write_all(data)?))
+        };
+
+        assert!(write(OverwritePolicy::Error, b"one").unwrap());
+        assert!(!write(OverwritePolicy::Skip, b"two").unwrap());
+        assert_eq!(std::fs::read(&path).unwrap(), b"one");
+
+        let error = write(OverwritePolicy::Error, b"three").unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<SpriteratorError>(),
+            Some(&SpriteratorError::OutputExists {
+                path: path.display().to_string()
+            })
+        );
+
+        assert!(write(OverwritePolicy::Overwrite, b"four").unwrap());
+        assert_eq!(std::fs::read(&path).unwrap(), b"four");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_failed_write_keeps_file() {
+        let dir = dir("failed");
+        let path = dir.join("sheet.png");
+        std::fs::write(&path, b"complete").unwrap();
+
+        let result = write_atomic(&path, OverwritePolicy::Overwrite, |writer| {
+            writer.write_all(b"trunc")?;
+            Err("interrupted".into())
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"complete");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,417 @@
+use image::RgbaImage;
+
+/// Number of power iterations used to find the principal axis of a block's colors.
+const AXIS_ITERATIONS: usize = 8;
+
+/// Interpolation weights of 4-bit BC7 indices, out of 64.
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// A GPU block compression format, encoding each 4×4 block of pixels in a fixed number of bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockCompression {
+    /// BC1 (DXT1): 8 bytes per block, RGB with 1-bit alpha. Pixels with alpha below 128
+    /// become fully transparent.
+    Bc1,
+    /// BC3 (DXT5): 16 bytes per block, RGB with interpolated 8-bit alpha.
+    Bc3,
+    /// BC7: 16 bytes per block, RGBA with higher fidelity than BC3. Blocks are encoded with a
+    /// single subset (mode 6), trading some quality on blocks with several distinct colors for
+    /// encoding speed.
+    Bc7,
+}
+
+impl BlockCompression {
+    /// Returns the number of bytes each 4×4 block is encoded in.
+    pub fn block_size(&self) -> usize {
+        match self {
+            BlockCompression::Bc1 => 8,
+            BlockCompression::Bc3 | BlockCompression::Bc7 => 16,
+        }
+    }
+}
+
+/// Compresses `image` block by block, in row-major block order.
+///
+/// Blocks overhanging the right or bottom edge repeat the last column or row, so sheets
+/// whose size is not a multiple of 4 compress without bleeding black into their edges.
+///
+/// # Arguments
+/// - `image`: The image to compress.
+/// - `compression`: The block compression format.
+pub(crate) fn compress(image: &RgbaImage, compression: BlockCompression) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let (columns, rows) = (width.div_ceil(4), height.div_ceil(4));
+    let mut data = Vec::with_capacity((columns * rows) as usize * compression.block_size());
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let block: [[u8; 4]; 16] = std::array::from_fn(|i| {
+                let x = (column * 4 + i as u32 % 4).min(width - 1);
+                let y = (row * 4 + i as u32 / 4).min(height - 1);
+                image.get_pixel(x, y).0
+            });
+            match compression {
+                BlockCompression::Bc1 => data.extend_from_slice(&bc1_block(&block)),
+                BlockCompression::Bc3 => {
+                    data.extend_from_slice(&alpha_block(&block));
+                    data.extend_from_slice(&color_block(&block, false));
+                }
+                BlockCompression::Bc7 => data.extend_from_slice(&bc7_block(&block)),
+            }
+        }
+    }
+
+    data
+}
+
+/// Fits a line through the first `N` channels of `pixels` along their principal axis.
+///
+/// # Returns
+/// The two ends of the line segment covering every pixel's projection, or the mean twice if
+/// the pixels are all the same or `pixels` is empty.
+fn fit_endpoints<const N: usize>(pixels: &[[u8; 4]]) -> ([f32; N], [f32; N]) {
+    if pixels.is_empty() {
+        return ([0.0; N], [0.0; N]);
+    }
+
+    let count = pixels.len() as f32;
+    let mean: [f32; N] =
+        std::array::from_fn(|c| pixels.iter().map(|p| f32::from(p[c])).sum::<f32>() / count);
+    let mut covariance = [[0.0f32; N]; N];
+    for pixel in pixels {
+        for i in 0..N {
+            for j in 0..N {
+                covariance[i][j] +=
+                    (f32::from(pixel[i]) - mean[i]) * (f32::from(pixel[j]) - mean[j]);
+            }
+        }
+    }
+
+    let mut axis: [f32; N] = std::array::from_fn(|c| {
+        let (min, max) = pixels
+            .iter()
+            .fold((255u8, 0u8), |(min, max), p| (min.min(p[c]), max.max(p[c])));
+        f32::from(max - min)
+    });
+    for _ in 0..AXIS_ITERATIONS {
+        let next: [f32; N] =
+            std::array::from_fn(|i| (0..N).map(|j| covariance[i][j] * axis[j]).sum());
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length <= f32::EPSILON {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+    let length = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if length <= f32::EPSILON {
+        return (mean, mean);
+    }
+    axis = axis.map(|v| v / length);
+
+    let (low, high) = pixels.iter().fold((f32::MAX, f32::MIN), |(low, high), p| {
+        let t: f32 = (0..N).map(|c| (f32::from(p[c]) - mean[c]) * axis[c]).sum();
+        (low.min(t), high.max(t))
+    });
+    let at = |t: f32| std::array::from_fn(|c| (mean[c] + axis[c] * t).clamp(0.0, 255.0));
+    (at(low), at(high))
+}
+
+/// Returns the squared distance between the first `N` channels of two colors.
+fn distance<const N: usize>(a: &[u8; 4], b: &[u8; 4]) -> u32 {
+    (0..N)
+        .map(|c| (i32::from(a[c]) - i32::from(b[c])).pow(2) as u32)
+        .sum()
+}
+
+/// Returns the index of the palette entry closest to `pixel`.
+fn closest<const N: usize>(palette: &[[u8; 4]], pixel: &[u8; 4]) -> usize {
+    (0..palette.len())
+        .min_by_key(|&i| distance::<N>(&palette[i], pixel))
+        .expect("palettes are not empty")
+}
+
+/// Quantizes an RGB color to RGB565.
+fn to_565(color: [f32; 3]) -> u16 {
+    let r = (color[0] * 31.0 / 255.0).round() as u16;
+    let g = (color[1] * 63.0 / 255.0).round() as u16;
+    let b = (color[2] * 31.0 / 255.0).round() as u16;
+    r << 11 | g << 5 | b
+}
+
+/// Expands an RGB565 color to 8 bits per channel, as decoders do.
+fn from_565(color: u16) -> [u8; 4] {
+    let (r, g, b) = (
+        (color >> 11) as u8,
+        (color >> 5 & 63) as u8,
+        (color & 31) as u8,
+    );
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]
+}
+
+/// Encodes a BC1 block, using the 3-color mode with a transparent entry if any pixel has
+/// alpha below 128.
+fn bc1_block(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    color_block(block, block.iter().any(|p| p[3] < 128))
+}
+
+/// Encodes the color part of a BC1 or BC3 block.
+///
+/// # Arguments
+/// - `block`: The pixels of the block, in row-major order.
+/// - `punch_through`: Whether to use the 3-color mode, mapping pixels with alpha below 128 to
+///   the transparent entry. BC3 always uses the 4-color mode.
+fn color_block(block: &[[u8; 4]; 16], punch_through: bool) -> [u8; 8] {
+    let opaque: Vec<[u8; 4]> = block
+        .iter()
+        .copied()
+        .filter(|p| !punch_through || p[3] >= 128)
+        .collect();
+    let (start, end) = fit_endpoints::<3>(&opaque);
+    let (mut color0, mut color1) = (to_565(start), to_565(end));
+
+    let mut indices = 0u32;
+    if punch_through {
+        if color0 > color1 {
+            std::mem::swap(&mut color0, &mut color1);
+        }
+        let (a, b) = (from_565(color0), from_565(color1));
+        let palette: [[u8; 4]; 3] = [a, b, std::array::from_fn(|c| mix(a[c], b[c], 1, 2))];
+        for (i, pixel) in block.iter().enumerate() {
+            let index = if pixel[3] < 128 {
+                3
+            } else {
+                closest::<3>(&palette, pixel) as u32
+            };
+            indices |= index << (2 * i);
+        }
+    } else {
+        if color0 < color1 {
+            std::mem::swap(&mut color0, &mut color1);
+        }
+        if color0 != color1 {
+            let (a, b) = (from_565(color0), from_565(color1));
+            let palette: [[u8; 4]; 4] = [
+                a,
+                b,
+                std::array::from_fn(|c| mix(a[c], b[c], 1, 3)),
+                std::array::from_fn(|c| mix(a[c], b[c], 2, 3)),
+            ];
+            for (i, pixel) in block.iter().enumerate() {
+                indices |= (closest::<3>(&palette, pixel) as u32) << (2 * i);
+            }
+        }
+    }
+
+    let mut data = [0; 8];
+    data[0..2].copy_from_slice(&color0.to_le_bytes());
+    data[2..4].copy_from_slice(&color1.to_le_bytes());
+    data[4..8].copy_from_slice(&indices.to_le_bytes());
+    data
+}
+
+/// Interpolates `numerator / denominator` of the way from `a` to `b`.
+fn mix(a: u8, b: u8, numerator: u32, denominator: u32) -> u8 {
+    ((u32::from(a) * (denominator - numerator) + u32::from(b) * numerator) / denominator) as u8
+}
+
+/// Encodes the alpha part of a BC3 block, interpolating 8 levels between the extreme alphas.
+fn alpha_block(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let alpha0 = block
+        .iter()
+        .map(|p| p[3])
+        .max()
+        .expect("blocks are not empty");
+    let alpha1 = block
+        .iter()
+        .map(|p| p[3])
+        .min()
+        .expect("blocks are not empty");
+
+    let mut indices = 0u64;
+    if alpha0 != alpha1 {
+        let levels: [u8; 8] = std::array::from_fn(|i| match i {
+            0 => alpha0,
+            1 => alpha1,
+            _ => mix(alpha0, alpha1, i as u32 - 1, 7),
+        });
+        for (i, pixel) in block.iter().enumerate() {
+            let index = (0..8)
+                .min_by_key(|&level| levels[level].abs_diff(pixel[3]))
+                .expect("levels are not empty");
+            indices |= (index as u64) << (3 * i);
+        }
+    }
+
+    let mut data = [0; 8];
+    data[0] = alpha0;
+    data[1] = alpha1;
+    data[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    data
+}
+
+/// Quantizes a BC7 mode 6 endpoint to 7 bits per channel plus a shared low bit.
+///
+/// # Returns
+/// The 7-bit channels and the low bit giving the smallest error.
+fn quantize_endpoint(endpoint: [f32; 4]) -> ([u8; 4], u8) {
+    (0..2u8)
+        .map(|bit| {
+            let channels =
+                endpoint.map(|v| ((v - f32::from(bit)) / 2.0).round().clamp(0.0, 127.0) as u8);
+            let error: f32 = (0..4)
+                .map(|c| (f32::from(channels[c] << 1 | bit) - endpoint[c]).powi(2))
+                .sum();
+            (channels, bit, error)
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(channels, bit, _)| (channels, bit))
+        .expect("there are two low bits")
+}
+
+/// Appends the low `count` bits of values to a 128-bit block.
+struct BitWriter {
+    bits: u128,
+    position: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= u128::from(value & ((1 << count) - 1)) << self.position;
+        self.position += count;
+    }
+}
+
+/// Encodes a BC7 block in mode 6: one subset with RGBA endpoints and 4-bit indices.
+fn bc7_block(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let (start, end) = fit_endpoints::<4>(block);
+    let mut endpoints = [quantize_endpoint(start), quantize_endpoint(end)];
+    let expand = |(channels, bit): ([u8; 4], u8)| channels.map(|v| v << 1 | bit);
+
+    let (a, b) = (expand(endpoints[0]), expand(endpoints[1]));
+    let palette: [[u8; 4]; 16] = std::array::from_fn(|i| {
+        std::array::from_fn(|c| {
+            let weight = BC7_WEIGHTS[i];
+            ((u32::from(a[c]) * (64 - weight) + u32::from(b[c]) * weight + 32) >> 6) as u8
+        })
+    });
+    let mut indices: [u32; 16] = std::array::from_fn(|i| closest::<4>(&palette, &block[i]) as u32);
+
+    // The first index is stored without its high bit, so it must be below 8.
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        indices = indices.map(|index| 15 - index);
+    }
+
+    let mut writer = BitWriter {
+        bits: 0,
+        position: 0,
+    };
+    writer.write(1 << 6, 7);
+    for c in 0..4 {
+        writer.write(u32::from(endpoints[0].0[c]), 7);
+        writer.write(u32::from(endpoints[1].0[c]), 7);
+    }
+    writer.write(u32::from(endpoints[0].1), 1);
+    writer.write(u32::from(endpoints[1].1), 1);
+    for (i, &index) in indices.iter().enumerate() {
+        writer.write(index, if i == 0 { 3 } else { 4 });
+    }
+    writer.bits.to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Decodes a BC7 mode 6 block, the only mode the encoder writes.
+    fn decode_bc7(data: &[u8]) -> [[u8; 4]; 16] {
+        let bits = u128::from_le_bytes(data.try_into().unwrap());
+        let mut position = 0;
+        let mut read = |count: u32| {
+            let value = (bits >> position) as u32 & ((1 << count) - 1);
+            position += count;
+            value
+        };
+        assert_eq!(read(7), 1 << 6);
+        let mut endpoints = [[0u32; 4]; 2];
+        for channel in 0..4 {
+            for endpoint in &mut endpoints {
+                endpoint[channel] = read(7);
+            }
+        }
+        for endpoint in &mut endpoints {
+            let bit = read(1);
+            *endpoint = endpoint.map(|v| v << 1 | bit);
+        }
+        std::array::from_fn(|i| {
+            let weight = BC7_WEIGHTS[read(if i == 0 { 3 } else { 4 }) as usize];
+            std::array::from_fn(|c| {
+                ((endpoints[0][c] * (64 - weight) + endpoints[1][c] * weight + 32) >> 6) as u8
+            })
+        })
+    }
+
+    /// A diagonal gradient, whose colors lie on a line in every block.
+    fn gradient(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let t = x + y;
+            Rgba([(t * 8) as u8, (t * 4) as u8, 96, (255 - t * 4) as u8])
+        })
+    }
+
+    #[test]
+    fn test_block_count() {
+        for compression in [
+            BlockCompression::Bc1,
+            BlockCompression::Bc3,
+            BlockCompression::Bc7,
+        ] {
+            let data = compress(&gradient(10, 5), compression);
+            assert_eq!(data.len(), 3 * 2 * compression.block_size());
+        }
+    }
+
+    #[test]
+    fn test_bc7_round_trip() {
+        let image = gradient(16, 16);
+        let data = compress(&image, BlockCompression::Bc7);
+        for (block, encoded) in data.chunks(16).enumerate() {
+            let (bx, by) = (block as u32 % 4 * 4, block as u32 / 4 * 4);
+            for (i, decoded) in decode_bc7(encoded).iter().enumerate() {
+                let original = image.get_pixel(bx + i as u32 % 4, by + i as u32 / 4);
+                for c in 0..4 {
+                    assert!(
+                        decoded[c].abs_diff(original[c]) <= 6,
+                        "{decoded:?} {original:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_flat_blocks() {
+        let block = [[255, 0, 0, 255]; 16];
+        assert_eq!(from_565(u16::from_le_bytes([0, 0xf8])), block[0]);
+        assert_eq!(&bc1_block(&block)[..4], &[0, 0xf8, 0, 0xf8]);
+        assert_eq!(alpha_block(&block), [255, 255, 0, 0, 0, 0, 0, 0]);
+
+        let mut cutout = block;
+        cutout[5] = [255, 0, 0, 100];
+        let encoded = bc1_block(&cutout);
+        let indices = u32::from_le_bytes(encoded[4..8].try_into().unwrap());
+        assert!(
+            u16::from_le_bytes([encoded[0], encoded[1]])
+                <= u16::from_le_bytes([encoded[2], encoded[3]])
+        );
+        assert_eq!(indices >> 10 & 3, 3);
+        assert_eq!(indices & 3, 0);
+
+        // Mode 6 shares the low bit of an endpoint between its channels, so mixing odd and
+        // even channels is off by one.
+        for pixel in decode_bc7(&bc7_block(&block)) {
+            assert!((0..4).all(|c| pixel[c].abs_diff(block[0][c]) <= 1));
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::hash::Hasher;
+use std::path::Path;
+
+use crate::atomic_write::write_bytes;
+
+/// Version of the cache file format, bumped whenever fingerprints are computed differently so
+/// that older caches are discarded.
+const CACHE_VERSION: u32 = 1;
+
+/// 64-bit FNV-1a hasher, which unlike the standard library's hasher is stable across
+/// platforms and releases, so its results can be persisted.
+pub(crate) struct Fingerprint(u64);
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fingerprint {
+    /// Hashes the `Debug` representation of `value`, for settings that do not implement `Hash`.
+    pub(crate) fn add(&mut self, value: &impl fmt::Debug) {
+        self.write(format!("{:?}", value).as_bytes());
+        // Separates consecutive values, so that `"ab", "c"` and `"a", "bc"` differ.
+        self.write_u8(0xff);
+    }
+}
+
+impl Hasher for Fingerprint {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Recorded state of a sheet of the previous build.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedSheet {
+    /// Fingerprint of the configuration, the layout and the pixels of the frames of the sheet.
+    pub fingerprint: u64,
+    pub width: u32,
+    pub height: u32,
+    pub frames: usize,
+}
+
+/// Fingerprints of the previous build, persisted by `Spriterator::generate_incremental`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct BuildCache {
+    pub version: u32,
+    /// Fingerprint of the configuration.
+    pub config: u64,
+    /// Fingerprint of the input files, read without decoding them.
+    pub inputs: u64,
+    pub sheets: Vec<CachedSheet>,
+}
+
+impl BuildCache {
+    pub(crate) fn new(config: u64, inputs: u64, sheets: Vec<CachedSheet>) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            config,
+            inputs,
+            sheets,
+        }
+    }
+
+    /// Reads the cache at `path`.
+    ///
+    /// # Returns
+    /// The cache, or an empty one forcing a full build if the file is missing, unreadable or
+    /// written by another version.
+    pub(crate) fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<BuildCache>(&data).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        write_bytes(path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Returns whether a build with these fingerprints would produce the recorded sheets.
+    pub(crate) fn is_current(&self, config: u64, inputs: u64) -> bool {
+        self.config == config && self.inputs == inputs && !self.sheets.is_empty()
+    }
+
+    /// Returns the fingerprint recorded for the sheet at index `page`, if any.
+    pub(crate) fn sheet(&self, page: usize) -> Option<&CachedSheet> {
+        self.sheets.get(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        let mut fingerprint = Fingerprint::default();
+        fingerprint.write(b"");
+        assert_eq!(fingerprint.finish(), 0xcbf2_9ce4_8422_2325);
+
+        let hash = |values: &[&str]| {
+            let mut fingerprint = Fingerprint::default();
+            values.iter().for_each(|value| fingerprint.add(value));
+            fingerprint.finish()
+        };
+        assert_ne!(hash(&["ab", "c"]), hash(&["a", "bc"]));
+        assert_eq!(hash(&["ab", "c"]), hash(&["ab", "c"]));
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let path = std::env::temp_dir().join("spriterator_build_cache.json");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(BuildCache::load(&path), BuildCache::default());
+
+        let sheet = CachedSheet {
+            fingerprint: u64::MAX,
+            width: 4,
+            height: 2,
+            frames: 3,
+        };
+        let cache = BuildCache::new(1, 2, vec![sheet.clone()]);
+        cache.save(&path).unwrap();
+        let loaded = BuildCache::load(&path);
+        assert!(loaded.is_current(1, 2));
+        assert!(!loaded.is_current(1, 3));
+        assert_eq!(loaded.sheet(0), Some(&sheet));
+
+        std::fs::write(&path, "{").unwrap();
+        assert_eq!(BuildCache::load(&path), BuildCache::default());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,160 @@
+use image::RgbaImage;
+use std::collections::HashSet;
+
+/// Maximum number of colors an indexed (8-bit palette) image can hold.
+const PALETTE_SIZE: usize = 256;
+
+/// Describes how the alpha channel of a sheet is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaUsage {
+    /// Every pixel is fully opaque.
+    Opaque,
+    /// Pixels are either fully opaque or fully transparent.
+    Binary,
+    /// At least one pixel is partially transparent.
+    Translucent,
+}
+
+/// The smallest output encoding that can represent a sheet without loss.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestedEncoding {
+    /// 8-bit palette, with a transparency table when alpha is used.
+    Indexed,
+    /// Single gray channel.
+    Grayscale,
+    /// Gray channel plus alpha.
+    GrayscaleAlpha,
+    /// Three color channels.
+    Rgb,
+    /// Three color channels plus alpha.
+    Rgba,
+}
+
+/// Color statistics of a generated sheet.
+#[derive(Debug, Clone)]
+pub struct ColorReport {
+    unique_colors: usize,
+    alpha_usage: AlphaUsage,
+    is_grayscale: bool,
+}
+
+impl ColorReport {
+    /// Analyzes the pixels of `image`.
+    ///
+    /// Fully transparent pixels are counted as a single color regardless of their RGB values,
+    /// since encoders are free to collapse them.
+    ///
+    /// # Arguments
+    /// - `image`: The image to analyze.
+    ///
+    /// # Returns
+    /// A `ColorReport` describing the image.
+    pub fn new(image: &RgbaImage) -> Self {
+        let mut colors: HashSet<[u8; 4]> = HashSet::new();
+        let mut alpha_usage = AlphaUsage::Opaque;
+        let mut is_grayscale = true;
+
+        for pixel in image.pixels() {
+            let [r, g, b, a] = pixel.0;
+
+            match a {
+                0 => {
+                    colors.insert([0, 0, 0, 0]);
+                    if alpha_usage == AlphaUsage::Opaque {
+                        alpha_usage = AlphaUsage::Binary;
+                    }
+                    continue;
+                }
+                255 => {}
+                _ => alpha_usage = AlphaUsage::Translucent,
+            }
+
+            if r != g || g != b {
+                is_grayscale = false;
+            }
+            colors.insert(pixel.0);
+        }
+
+        Self {
+            unique_colors: colors.len(),
+            alpha_usage,
+            is_grayscale,
+        }
+    }
+
+    pub fn get_unique_colors(&self) -> usize {
+        self.unique_colors
+    }
+
+    pub fn get_alpha_usage(&self) -> AlphaUsage {
+        self.alpha_usage
+    }
+
+    pub fn is_grayscale(&self) -> bool {
+        self.is_grayscale
+    }
+
+    /// Returns `true` if every color of the sheet fits into an 8-bit palette.
+    pub fn fits_palette(&self) -> bool {
+        self.unique_colors <= PALETTE_SIZE
+    }
+
+    /// Suggests the most compact lossless encoding for the analyzed sheet.
+    pub fn suggested_encoding(&self) -> SuggestedEncoding {
+        let has_alpha = self.alpha_usage != AlphaUsage::Opaque;
+
+        if self.fits_palette() && !self.is_grayscale {
+            return SuggestedEncoding::Indexed;
+        }
+
+        match (self.is_grayscale, has_alpha) {
+            (true, false) => SuggestedEncoding::Grayscale,
+            (true, true) => SuggestedEncoding::GrayscaleAlpha,
+            (false, false) => SuggestedEncoding::Rgb,
+            (false, true) => SuggestedEncoding::Rgba,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_transparent_image() {
+        let report = ColorReport::new(&RgbaImage::new(4, 4));
+        assert_eq!(report.get_unique_colors(), 1);
+        assert_eq!(report.get_alpha_usage(), AlphaUsage::Binary);
+        assert!(report.fits_palette());
+    }
+
+    #[test]
+    fn test_opaque_color_image() {
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 1, Rgba([0, 255, 0, 255]));
+        let report = ColorReport::new(&image);
+        assert_eq!(report.get_unique_colors(), 2);
+        assert_eq!(report.get_alpha_usage(), AlphaUsage::Opaque);
+        assert!(!report.is_grayscale());
+        assert_eq!(report.suggested_encoding(), SuggestedEncoding::Indexed);
+    }
+
+    #[test]
+    fn test_translucent_grayscale_image() {
+        let mut image = RgbaImage::new(20, 20);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let index = x * 20 + y;
+            let value = (index % 256) as u8;
+            *pixel = Rgba([value, value, value, 100 + (index / 256) as u8]);
+        }
+        let report = ColorReport::new(&image);
+        assert_eq!(report.get_unique_colors(), 400);
+        assert_eq!(report.get_alpha_usage(), AlphaUsage::Translucent);
+        assert!(!report.fits_palette());
+        assert_eq!(
+            report.suggested_encoding(),
+            SuggestedEncoding::GrayscaleAlpha
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use image::{ImageDecoder, ImageFormat, ImageReader, RgbaImage};
+use std::io::Cursor;
+
+/// Encoding gamma of sRGB as PNG records it; files within `GAMMA_TOLERANCE` of it are treated
+/// as sRGB, as most decoders do.
+const SRGB_GAMMA: f32 = 1.0 / 2.2;
+
+/// Largest difference from `SRGB_GAMMA` still treated as sRGB.
+const GAMMA_TOLERANCE: f32 = 0.01;
+
+/// Color space information embedded in an input image.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ColorProfile {
+    /// The image declares itself sRGB.
+    Srgb,
+    /// The image was encoded with this gamma, as recorded by a PNG gAMA chunk.
+    Gamma(f32),
+    /// An embedded ICC profile.
+    Icc(Vec<u8>),
+}
+
+/// Reads the color space information embedded in an encoded image.
+///
+/// PNG images are checked for iCCP, sRGB and gAMA chunks in that order of precedence; other
+/// formats for an embedded ICC profile only.
+///
+/// # Returns
+/// `None` if the image embeds no such information, and is assumed to be sRGB.
+pub(crate) fn read_profile(data: &[u8], format: Option<ImageFormat>) -> Option<ColorProfile> {
+    if format == Some(ImageFormat::Png) {
+        let reader = png::Decoder::new(Cursor::new(data)).read_info().ok()?;
+        let info = reader.info();
+        if let Some(icc) = &info.icc_profile {
+            return Some(ColorProfile::Icc(icc.to_vec()));
+        }
+        if info.srgb.is_some() {
+            return Some(ColorProfile::Srgb);
+        }
+        return info
+            .gama_chunk
+            .map(|gamma| ColorProfile::Gamma(gamma.into_scaled() as f32 / 100_000.0));
+    }
+
+    let reader = match format {
+        Some(format) => ImageReader::with_format(Cursor::new(data), format),
+        None => ImageReader::new(Cursor::new(data))
+            .with_guessed_format()
+            .ok()?,
+    };
+    let icc = reader.into_decoder().ok()?.icc_profile().ok()??;
+    Some(ColorProfile::Icc(icc))
+}
+
+/// Converts `image` from the color space described by `profile` to sRGB, in place.
+///
+/// # Returns
+/// An error if the profile cannot be converted, such as an ICC profile that is malformed, is
+/// not an RGB profile, or needs the `icc` feature.
+pub(crate) fn convert_to_srgb(image: &mut RgbaImage, profile: &ColorProfile) -> Result<(), String> {
+    match profile {
+        ColorProfile::Srgb => Ok(()),
+        ColorProfile::Gamma(gamma) => {
+            if !(gamma.is_finite() && *gamma > 0.0) {
+                return Err(format!("Invalid gamma {}.", gamma));
+            }
+            if (gamma - SRGB_GAMMA).abs() > GAMMA_TOLERANCE {
+                let table: [u8; 256] = std::array::from_fn(|value| {
+                    let linear = (value as f32 / 255.0).powf(1.0 / gamma);
+                    (encode_srgb(linear) * 255.0).round() as u8
+                });
+                for pixel in image.pixels_mut() {
+                    for channel in &mut pixel.0[..3] {
+                        *channel = table[usize::from(*channel)];
+                    }
+                }
+            }
+            Ok(())
+        }
+        ColorProfile::Icc(icc) => convert_icc(image, icc),
+    }
+}
+
+/// Applies the sRGB transfer function to a linear value in `0.0..=1.0`.
+fn encode_srgb(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(feature = "icc")]
+fn convert_icc(image: &mut RgbaImage, icc: &[u8]) -> Result<(), String> {
+    use moxcms::{DataColorSpace, Layout, TransformOptions};
+
+    let source = moxcms::ColorProfile::new_from_slice(icc).map_err(|e| e.to_string())?;
+    if source.color_space != DataColorSpace::Rgb {
+        return Err(format!(
+            "ICC profiles of {:?} data are not supported.",
+            source.color_space
+        ));
+    }
+    let transform = source
+        .create_transform_8bit(
+            Layout::Rgba,
+            &moxcms::ColorProfile::new_srgb(),
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .map_err(|e| e.to_string())?;
+    let input = image.as_raw().clone();
+    let output: &mut [u8] = image.as_mut();
+    transform
+        .transform(&input, output)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "icc"))]
+fn convert_icc(_image: &mut RgbaImage, _icc: &[u8]) -> Result<(), String> {
+    Err("Converting ICC profiles requires the icc feature.".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Encodes a 1×1 PNG, letting `configure` add color chunks.
+    fn png(configure: impl FnOnce(&mut png::Encoder<&mut Vec<u8>>)) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 1, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        configure(&mut encoder);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[128, 128, 128, 255])
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_read_png_profile() {
+        let read = |data: Vec<u8>| read_profile(&data, Some(ImageFormat::Png));
+        assert_eq!(read(png(|_| {})), None);
+        assert_eq!(
+            read(png(
+                |encoder| encoder.set_source_gamma(png::ScaledFloat::new(1.0))
+            )),
+            Some(ColorProfile::Gamma(1.0))
+        );
+        assert_eq!(
+            read(png(|encoder| {
+                encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual)
+            })),
+            Some(ColorProfile::Srgb)
+        );
+    }
+
+    #[test]
+    fn test_convert_gamma() {
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([128, 0, 255, 100]));
+        convert_to_srgb(&mut image, &ColorProfile::Gamma(SRGB_GAMMA)).unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgba([128, 0, 255, 100]));
+
+        // Linear mid-grey is lighter in sRGB; alpha is untouched.
+        convert_to_srgb(&mut image, &ColorProfile::Gamma(1.0)).unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgba([188, 0, 255, 100]));
+        assert!(convert_to_srgb(&mut image, &ColorProfile::Gamma(0.0)).is_err());
+    }
+
+    #[cfg(feature = "icc")]
+    #[test]
+    fn test_convert_icc() {
+        let p3 = moxcms::ColorProfile::new_display_p3().encode().unwrap();
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([200, 100, 50, 200]));
+        convert_to_srgb(&mut image, &ColorProfile::Icc(p3)).unwrap();
+        // Display P3 has a wider gamut, so the same values are more saturated in sRGB.
+        let pixel = image.get_pixel(0, 0);
+        assert!(pixel[0] > 200 && pixel[2] < 50, "{:?}", pixel);
+        assert_eq!(pixel[3], 200);
+
+        let srgb = moxcms::ColorProfile::new_srgb().encode().unwrap();
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([10, 120, 200, 255]));
+        convert_to_srgb(&mut image, &ColorProfile::Icc(srgb)).unwrap();
+        let pixel = image.get_pixel(0, 0);
+        assert!([10u8, 120, 200]
+            .into_iter()
+            .zip(pixel.0)
+            .all(|(a, b)| a.abs_diff(b) <= 1));
+
+        assert!(convert_to_srgb(&mut image, &ColorProfile::Icc(vec![1, 2, 3])).is_err());
+    }
+
+    #[cfg(not(feature = "icc"))]
+    #[test]
+    fn test_icc_needs_feature() {
+        let mut image = RgbaImage::new(1, 1);
+        assert!(convert_to_srgb(&mut image, &ColorProfile::Icc(Vec::new())).is_err());
+    }
+}
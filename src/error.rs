@@ -0,0 +1,107 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors reported while generating sprite sheets.
+///
+/// Functions of this crate return `Box<dyn Error>`; these variants can be recovered with
+/// `downcast_ref::<SpriteratorError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpriteratorError {
+    /// Sheet, resize or input image dimensions are zero or too large to allocate.
+    InvalidDimensions {
+        what: String,
+        width: u32,
+        height: u32,
+    },
+    /// An image is larger than the maximum sheet dimensions.
+    ImageTooLarge {
+        name: String,
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+    /// The input directory contains no usable images.
+    NoImages {
+        dir_path: String,
+        extensions: Vec<String>,
+    },
+    /// The configuration combines settings that cannot work together.
+    InvalidConfiguration { diagnostics: Vec<String> },
+    /// The generated sheets exceed strict engine limits.
+    EngineLimitsExceeded { violations: Vec<String> },
+    /// A single image encodes to more bytes than allowed per sheet.
+    EncodedSizeExceeded {
+        size: usize,
+        max_encoded_bytes: usize,
+    },
+    /// Metadata read back from disk is malformed or inconsistent.
+    InvalidMetadata { diagnostics: Vec<String> },
+    /// An output file exists and the overwrite policy forbids replacing it.
+    OutputExists { path: String },
+}
+
+impl fmt::Display for SpriteratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpriteratorError::InvalidDimensions {
+                what,
+                width,
+                height,
+            } => write!(f, "Invalid {} dimensions {}x{}.", what, width, height),
+            SpriteratorError::ImageTooLarge {
+                name,
+                width,
+                height,
+                max_width,
+                max_height,
+            } => write!(
+                f,
+                "Image {} dimensions {}x{} exceed max dimensions {}x{}.",
+                name, width, height, max_width, max_height
+            ),
+            SpriteratorError::NoImages {
+                dir_path,
+                extensions,
+            } => write!(
+                f,
+                "No images with supported extensions {:?} were found in the specified directory: {}",
+                extensions, dir_path
+            ),
+            SpriteratorError::InvalidConfiguration { diagnostics } => {
+                write!(f, "Invalid configuration:")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n- {}", diagnostic)?;
+                }
+                Ok(())
+            }
+            SpriteratorError::EngineLimitsExceeded { violations } => {
+                write!(f, "Engine limits exceeded:")?;
+                for violation in violations {
+                    write!(f, "\n- {}", violation)?;
+                }
+                Ok(())
+            }
+            SpriteratorError::EncodedSizeExceeded {
+                size,
+                max_encoded_bytes,
+            } => write!(
+                f,
+                "A single image encodes to {} bytes, exceeding the maximum of {} bytes per sheet.",
+                size, max_encoded_bytes
+            ),
+            SpriteratorError::InvalidMetadata { diagnostics } => {
+                write!(f, "Invalid metadata:")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n- {}", diagnostic)?;
+                }
+                Ok(())
+            }
+            SpriteratorError::OutputExists { path } => {
+                write!(f, "Output file already exists: {}", path)
+            }
+        }
+    }
+}
+
+impl Error for SpriteratorError {}
@@ -0,0 +1,123 @@
+//! Writers for the metadata formats understood by game engines and web frameworks.
+
+use std::error::Error;
+use std::path::{Component, Path, PathBuf};
+
+pub(crate) mod aseprite;
+pub(crate) mod bevy;
+pub(crate) mod bmfont;
+pub(crate) mod cocos;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+pub(crate) mod config;
+pub(crate) mod css;
+pub(crate) mod easeljs;
+pub(crate) mod godot;
+pub(crate) mod libgdx;
+pub(crate) mod paper2d;
+pub(crate) mod phaser;
+pub(crate) mod pixi;
+pub(crate) mod plist;
+pub(crate) mod png_text;
+pub(crate) mod preview;
+pub(crate) mod sparrow;
+pub(crate) mod spine;
+pub(crate) mod spritekit;
+pub(crate) mod table;
+pub(crate) mod texture_packer;
+pub(crate) mod tiled;
+pub(crate) mod typescript;
+pub(crate) mod unity;
+
+/// Identifies this crate in the `meta` sections of exported metadata.
+pub(crate) const APP: &str = env!("CARGO_PKG_REPOSITORY");
+pub(crate) const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Checks that one image path was given for each sheet of a multi-page export.
+pub(crate) fn check_page_count(sheets: usize, images: &[&str]) -> Result<(), Box<dyn Error>> {
+    if sheets != images.len() {
+        return Err(format!(
+            "Expected one image path per sheet, got {} paths for {} sheets.",
+            images.len(),
+            sheets
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Removes the file extension from a frame name such as `hero/walk.png`.
+pub(crate) fn strip_extension(name: &str) -> &str {
+    let file_start = name.rfind('/').map_or(0, |slash| slash + 1);
+    match name.rfind('.') {
+        Some(dot) if dot > file_start => &name[..dot],
+        _ => name,
+    }
+}
+
+/// Returns the frame file name `name` as a path relative to an output directory.
+///
+/// Names that could escape the directory, such as `../hero.png`, `/tmp/hero.png` or Windows
+/// drive prefixes, are rejected.
+pub(crate) fn relative_output_path(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(
+                    format!("Frame name {:?} points outside the output directory.", name).into(),
+                );
+            }
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return Err(format!("Frame name {:?} is not a valid file name.", name).into());
+    }
+    Ok(path)
+}
+
+/// Escapes text for use in XML attribute values and character data.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_extension() {
+        assert_eq!(strip_extension("hero/walk.png"), "hero/walk");
+        assert_eq!(strip_extension("v1.2/walk"), "v1.2/walk");
+        assert_eq!(strip_extension(".hidden"), ".hidden");
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+
+    #[test]
+    fn test_relative_output_path() {
+        assert_eq!(
+            relative_output_path("./hero/walk.png").unwrap(),
+            Path::new("hero/walk.png")
+        );
+        assert!(relative_output_path("../walk.png").is_err());
+        assert!(relative_output_path("hero/../../walk.png").is_err());
+        assert!(relative_output_path("/tmp/walk.png").is_err());
+        assert!(relative_output_path("").is_err());
+    }
+}
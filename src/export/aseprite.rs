@@ -0,0 +1,93 @@
+use serde_json::{json, Value};
+
+use crate::export::libgdx::region_name;
+use crate::export::texture_packer::{meta, named_frame_entry};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Builds an Aseprite `--data` document in its array form.
+///
+/// Frames named like `walk_01.png` are grouped into the frame tag `walk`, ordered by their
+/// numeric suffix. Frames are listed tag by tag in order of first appearance, so every tag
+/// covers a contiguous `from`..=`to` range; the sheet itself is left unchanged. Frames of
+/// animated source images keep their own duration, other frames are shown for `duration`.
+pub(crate) fn sprite_sheet(sprite: &Sprite, image: &str, duration: u32) -> Value {
+    let mut groups: Vec<(&str, Vec<(i64, &Frame)>)> = Vec::new();
+    for frame in sprite.get_frames() {
+        let (name, index) = region_name(frame.get_name());
+        let key = if index < 0 { frame.get_name() } else { name };
+        match groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, frames)) if index >= 0 => frames.push((index, frame)),
+            _ => groups.push((key, vec![(index, frame)])),
+        }
+    }
+
+    let mut frames = Vec::new();
+    let mut tags = Vec::new();
+    for (name, mut group) in groups {
+        group.sort_by_key(|&(index, _)| index);
+        if group[0].0 >= 0 {
+            tags.push(json!({
+                "name": name,
+                "from": frames.len(),
+                "to": frames.len() + group.len() - 1,
+                "direction": "forward",
+            }));
+        }
+        for (_, frame) in group {
+            let mut entry = named_frame_entry(frame);
+            entry["duration"] = json!(frame.get_duration().unwrap_or(duration));
+            frames.push(entry);
+        }
+    }
+
+    let mut meta = meta(sprite, image);
+    meta["frameTags"] = json!(tags);
+    meta["layers"] = json!([]);
+    meta["slices"] = json!([]);
+
+    json!({
+        "frames": frames,
+        "meta": meta,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_sprite_sheet() {
+        let mut sprite = Sprite::new(RgbaImage::new(64, 16));
+        sprite.add_named_frame("walk_2.png", 0, 0, 16, 16);
+        sprite.add_named_frame("idle.png", 16, 0, 16, 16);
+        sprite.add_named_frame("walk_1.png", 32, 0, 16, 16);
+        sprite.add_named_frame("jump_1.png", 48, 0, 16, 16);
+        let document = sprite_sheet(&sprite, "sheet.png", 120);
+
+        let names: Vec<&str> = document["frames"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|frame| frame["filename"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["walk_1.png", "walk_2.png", "idle.png", "jump_1.png"]
+        );
+        assert_eq!(document["frames"][0]["duration"], 120);
+        assert_eq!(
+            document["frames"][0]["frame"],
+            json!({ "x": 32, "y": 0, "w": 16, "h": 16 })
+        );
+        assert_eq!(
+            document["meta"]["frameTags"],
+            json!([
+                { "name": "walk", "from": 0, "to": 1, "direction": "forward" },
+                { "name": "jump", "from": 3, "to": 3, "direction": "forward" },
+            ])
+        );
+        assert_eq!(document["meta"]["image"], "sheet.png");
+    }
+}
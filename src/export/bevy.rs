@@ -0,0 +1,124 @@
+use std::fmt::Write;
+
+use crate::sprite::Sprite;
+
+/// Builds a RON document that deserializes into Bevy's `TextureAtlasLayout`.
+///
+/// Frames become textures in packing order, so a frame's index in `Sprite::get_frames`
+/// is its index in the layout.
+pub(crate) fn layout_ron(sprite: &Sprite) -> String {
+    let (width, height) = sprite.get_image().dimensions();
+    let mut ron = format!("(\n    size: ({}, {}),\n    textures: [\n", width, height);
+
+    for frame in sprite.get_frames() {
+        let rect = frame.rect();
+        let _ = writeln!(
+            ron,
+            "        (min: ({}, {}), max: ({}, {})), // {}",
+            rect.x,
+            rect.y,
+            rect.right(),
+            rect.bottom(),
+            frame.get_name()
+        );
+    }
+
+    ron.push_str("    ],\n)\n");
+    ron
+}
+
+/// Turns `name` into a valid Rust identifier in snake case.
+fn identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !identifier.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+/// Builds Rust source with a function `name` returning the `TextureAtlasLayout` of the sheet,
+/// and a `<NAME>_FRAMES` constant listing frame names by layout index.
+pub(crate) fn layout_rust(sprite: &Sprite, name: &str) -> String {
+    let function = identifier(name);
+    let (width, height) = sprite.get_image().dimensions();
+    let frames = sprite.get_frames();
+
+    let mut code = String::from("// Generated by spriterator. Do not edit.\n\n");
+    code.push_str("use bevy::prelude::{TextureAtlasLayout, URect, UVec2};\n\n");
+
+    let _ = writeln!(
+        code,
+        "pub const {}_FRAMES: [&str; {}] = [",
+        function.to_ascii_uppercase(),
+        frames.len()
+    );
+    for frame in frames {
+        let _ = writeln!(code, "    {:?},", frame.get_name());
+    }
+    code.push_str("];\n\n");
+
+    let _ = writeln!(code, "pub fn {}() -> TextureAtlasLayout {{", function);
+    let _ = writeln!(
+        code,
+        "    let mut layout = TextureAtlasLayout::new_empty(UVec2::new({}, {}));",
+        width, height
+    );
+    for frame in frames {
+        let rect = frame.rect();
+        let _ = writeln!(
+            code,
+            "    layout.add_texture(URect::new({}, {}, {}, {}));",
+            rect.x,
+            rect.y,
+            rect.right(),
+            rect.bottom()
+        );
+    }
+    code.push_str("    layout\n}\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn sprite() -> Sprite {
+        let mut sprite = Sprite::new(RgbaImage::new(32, 16));
+        sprite.add_named_frame("hero/idle.png", 0, 0, 16, 16);
+        sprite.add_named_frame("hero/walk.png", 16, 0, 8, 12);
+        sprite
+    }
+
+    #[test]
+    fn test_layout_ron() {
+        assert_eq!(
+            layout_ron(&sprite()),
+            "(\n    size: (32, 16),\n    textures: [\n\
+             \x20       (min: (0, 0), max: (16, 16)), // hero/idle.png\n\
+             \x20       (min: (16, 0), max: (24, 12)), // hero/walk.png\n\
+             \x20   ],\n)\n"
+        );
+    }
+
+    #[test]
+    fn test_layout_rust() {
+        let code = layout_rust(&sprite(), "Hero-Sheet");
+        assert!(
+            code.contains("pub const HERO_SHEET_FRAMES: [&str; 2] = [\n    \"hero/idle.png\",\n")
+        );
+        assert!(code.contains("pub fn hero_sheet() -> TextureAtlasLayout {"));
+        assert!(code.contains("TextureAtlasLayout::new_empty(UVec2::new(32, 16));"));
+        assert!(code.contains("layout.add_texture(URect::new(16, 0, 24, 12));"));
+        assert_eq!(identifier("2x"), "_2x");
+    }
+}
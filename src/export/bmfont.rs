@@ -0,0 +1,274 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::export::{check_page_count, strip_extension};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Placement of a glyph relative to the cursor, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphMetrics {
+    pub xoffset: i32,
+    pub yoffset: i32,
+    pub xadvance: i32,
+}
+
+/// Options for the AngelCode BMFont exporter.
+#[derive(Debug, Clone)]
+pub struct BmFontOptions {
+    face: String,
+    size: u32,
+    line_height: u32,
+    base: u32,
+    metrics: HashMap<u32, GlyphMetrics>,
+}
+
+impl BmFontOptions {
+    /// Creates options for a font named `face` with glyphs `size` pixels high.
+    ///
+    /// The line height and base line default to `size`.
+    pub fn new(face: &str, size: u32) -> Self {
+        Self {
+            face: face.to_string(),
+            size,
+            line_height: size,
+            base: size,
+            metrics: HashMap::new(),
+        }
+    }
+
+    /// Sets the distance between the tops of consecutive lines.
+    pub fn with_line_height(mut self, line_height: u32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    /// Sets the distance from the top of a line to the base line of its glyphs.
+    pub fn with_base(mut self, base: u32) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Sets the metrics of the glyph for `codepoint`.
+    ///
+    /// Glyphs without metrics are placed at their trim offset and advance by their untrimmed width.
+    pub fn with_glyph_metrics(mut self, codepoint: u32, metrics: GlyphMetrics) -> Self {
+        self.metrics.insert(codepoint, metrics);
+        self
+    }
+
+    /// Reads glyph metrics from a sidecar JSON object keyed by glyph name, such as
+    /// `{"U+0041": {"xoffset": 0, "yoffset": 2, "xadvance": 11}}`.
+    ///
+    /// Keys use the same codepoint notation as glyph file names; missing fields default to `0`.
+    pub fn with_metrics_json(mut self, json: &str) -> Result<Self, Box<dyn Error>> {
+        let document: Value = serde_json::from_str(json)?;
+        let entries = document
+            .as_object()
+            .ok_or("Glyph metrics must be a JSON object keyed by glyph name.")?;
+
+        for (name, entry) in entries {
+            let codepoint = codepoint(name)
+                .ok_or_else(|| format!("Glyph metrics key {:?} is not a codepoint.", name))?;
+            let field = |key: &str| entry[key].as_i64().unwrap_or(0) as i32;
+            self.metrics.insert(
+                codepoint,
+                GlyphMetrics {
+                    xoffset: field("xoffset"),
+                    yoffset: field("yoffset"),
+                    xadvance: field("xadvance"),
+                },
+            );
+        }
+
+        Ok(self)
+    }
+}
+
+/// Parses a glyph name such as `fonts/U+0041.png`, `u+41` or `65.png` into its codepoint.
+pub(crate) fn codepoint(name: &str) -> Option<u32> {
+    let name = strip_extension(name);
+    let name = &name[name.rfind('/').map_or(0, |slash| slash + 1)..];
+
+    match name.get(..2) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("U+") => {
+            u32::from_str_radix(&name[2..], 16).ok()
+        }
+        _ => name.parse().ok(),
+    }
+}
+
+/// Returns the BMFont channel mask of a frame: all channels, or the one it was packed into.
+fn channel_mask(frame: &Frame) -> u8 {
+    match frame.get_channel() {
+        Some(0) => 4,
+        Some(1) => 2,
+        Some(2) => 1,
+        Some(_) => 8,
+        None => 15,
+    }
+}
+
+/// Builds a BMFont descriptor in the text format with one page per sheet.
+pub(crate) fn font(
+    sprites: &[Sprite],
+    images: &[&str],
+    options: &BmFontOptions,
+) -> Result<String, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+
+    let mut chars = Vec::new();
+    for (page, sprite) in sprites.iter().enumerate() {
+        for frame in sprite.get_frames() {
+            let id = codepoint(frame.get_name()).ok_or_else(|| {
+                format!(
+                    "Glyph {:?} is not named by a codepoint such as U+0041 or 65.",
+                    frame.get_name()
+                )
+            })?;
+            chars.push((id, page, frame));
+        }
+    }
+    chars.sort_by_key(|&(id, _, _)| id);
+
+    let scale_width = sprites
+        .iter()
+        .map(|sprite| sprite.get_image().width())
+        .max();
+    let scale_height = sprites
+        .iter()
+        .map(|sprite| sprite.get_image().height())
+        .max();
+
+    let mut fnt = String::new();
+    let _ = writeln!(
+        fnt,
+        "info face=\"{}\" size={} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=1 aa=1 padding=0,0,0,0 spacing=0,0",
+        options.face.replace('"', "'"),
+        options.size
+    );
+    let _ = writeln!(
+        fnt,
+        "common lineHeight={} base={} scaleW={} scaleH={} pages={} packed={}",
+        options.line_height,
+        options.base,
+        scale_width.unwrap_or(0),
+        scale_height.unwrap_or(0),
+        sprites.len(),
+        u8::from(
+            chars
+                .iter()
+                .any(|(_, _, frame)| frame.get_channel().is_some())
+        )
+    );
+    for (page, image) in images.iter().enumerate() {
+        let _ = writeln!(fnt, "page id={} file=\"{}\"", page, image);
+    }
+
+    let _ = writeln!(fnt, "chars count={}", chars.len());
+    for (id, page, frame) in chars {
+        let metrics = options.metrics.get(&id).copied().unwrap_or(GlyphMetrics {
+            xoffset: frame.get_offset_x() as i32,
+            yoffset: frame.get_offset_y() as i32,
+            xadvance: frame.get_source_width() as i32,
+        });
+        let _ = writeln!(
+            fnt,
+            "char id={} x={} y={} width={} height={} xoffset={} yoffset={} xadvance={} page={} chnl={}",
+            id,
+            frame.get_x(),
+            frame.get_y(),
+            frame.get_width(),
+            frame.get_height(),
+            metrics.xoffset,
+            metrics.yoffset,
+            metrics.xadvance,
+            page,
+            channel_mask(frame)
+        );
+    }
+
+    Ok(fnt)
+}
+
+/// Writes an AngelCode BMFont `.fnt` descriptor in the text format for glyphs packed into sheets.
+///
+/// Glyph images must be named by their codepoint, either as `U+0041.png` or `65.png`.
+///
+/// # Arguments
+/// - `path`: Path of the descriptor to write.
+/// - `sprites`: The generated sheets, one font page each.
+/// - `images`: Path of each saved sheet as referenced from the descriptor, in the same order.
+/// - `options`: Font name, size, line metrics and per-glyph metrics.
+pub fn export_bmfont(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+    options: &BmFontOptions,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, font(sprites, images, options)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_codepoint() {
+        assert_eq!(codepoint("glyphs/U+0041.png"), Some(65));
+        assert_eq!(codepoint("u+1F600"), Some(0x1F600));
+        assert_eq!(codepoint("66.png"), Some(66));
+        assert_eq!(codepoint("A.png"), None);
+    }
+
+    #[test]
+    fn test_font() {
+        let first = Sprite::with_frames(
+            RgbaImage::new(64, 32),
+            vec![
+                Frame::named("U+0042.png", 10, 0, 8, 12),
+                Frame::named("65.png", 0, 0, 8, 10).with_source(10, 14, 1, 3),
+            ],
+        );
+        let second = Sprite::with_frames(
+            RgbaImage::new(32, 16),
+            vec![Frame::named("32.png", 0, 0, 1, 1)],
+        );
+        let options = BmFontOptions::new("Pixel", 14)
+            .with_base(11)
+            .with_metrics_json(r#"{"U+0042": {"xoffset": 1, "yoffset": 2, "xadvance": 9}}"#)
+            .unwrap();
+        let fnt = font(&[first, second], &["font_0.png", "font_1.png"], &options).unwrap();
+        let lines: Vec<&str> = fnt.lines().collect();
+
+        assert!(lines[0].starts_with("info face=\"Pixel\" size=14 "));
+        assert_eq!(
+            lines[1],
+            "common lineHeight=14 base=11 scaleW=64 scaleH=32 pages=2 packed=0"
+        );
+        assert_eq!(lines[3], "page id=1 file=\"font_1.png\"");
+        assert_eq!(lines[4], "chars count=3");
+        assert_eq!(
+            lines[5],
+            "char id=32 x=0 y=0 width=1 height=1 xoffset=0 yoffset=0 xadvance=1 page=1 chnl=15"
+        );
+        assert_eq!(
+            lines[6],
+            "char id=65 x=0 y=0 width=8 height=10 xoffset=1 yoffset=3 xadvance=10 page=0 chnl=15"
+        );
+        assert_eq!(
+            lines[7],
+            "char id=66 x=10 y=0 width=8 height=12 xoffset=1 yoffset=2 xadvance=9 page=0 chnl=15"
+        );
+
+        let unnamed = Sprite::with_frames(
+            RgbaImage::new(8, 8),
+            vec![Frame::named("A.png", 0, 0, 8, 8)],
+        );
+        assert!(font(&[unnamed], &["font.png"], &options).is_err());
+    }
+}
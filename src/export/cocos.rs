@@ -0,0 +1,112 @@
+use crate::export::plist::Plist;
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Formats a value given in half pixels, as used for centered offsets.
+fn half_pixels(value: i64) -> String {
+    if value % 2 == 0 {
+        (value / 2).to_string()
+    } else {
+        format!("{}", value as f64 / 2.0)
+    }
+}
+
+/// Returns the offset of the trimmed frame's center from the source image's center,
+/// with the y axis pointing up as Cocos2d expects.
+pub(crate) fn sprite_offset(frame: &Frame) -> String {
+    let x = 2 * frame.get_offset_x() as i64 + frame.get_width() as i64
+        - frame.get_source_width() as i64;
+    let y = frame.get_source_height() as i64
+        - 2 * frame.get_offset_y() as i64
+        - frame.get_height() as i64;
+    format!("{{{},{}}}", half_pixels(x), half_pixels(y))
+}
+
+fn frame_entry(frame: &Frame) -> Plist {
+    Plist::Dict(vec![
+        ("aliases".to_string(), Plist::Array(Vec::new())),
+        (
+            "spriteOffset".to_string(),
+            Plist::string(sprite_offset(frame)),
+        ),
+        (
+            "spriteSize".to_string(),
+            Plist::string(format!("{{{},{}}}", frame.get_width(), frame.get_height())),
+        ),
+        (
+            "spriteSourceSize".to_string(),
+            Plist::string(format!(
+                "{{{},{}}}",
+                frame.get_source_width(),
+                frame.get_source_height()
+            )),
+        ),
+        (
+            "textureRect".to_string(),
+            Plist::string(format!(
+                "{{{{{},{}}},{{{},{}}}}}",
+                frame.get_x(),
+                frame.get_y(),
+                frame.get_width(),
+                frame.get_height()
+            )),
+        ),
+        ("textureRotated".to_string(), Plist::Bool(false)),
+    ])
+}
+
+/// Builds a Cocos2d-x sprite frame property list in format version 3.
+pub(crate) fn sprite_frames(sprite: &Sprite, image: &str) -> Plist {
+    let frames = sprite
+        .get_frames()
+        .iter()
+        .map(|frame| (frame.get_name().to_string(), frame_entry(frame)))
+        .collect();
+
+    let (width, height) = sprite.get_image().dimensions();
+    let metadata = Plist::Dict(vec![
+        ("format".to_string(), Plist::Integer(3)),
+        ("pixelFormat".to_string(), Plist::string("RGBA8888")),
+        ("premultiplyAlpha".to_string(), Plist::Bool(false)),
+        ("realTextureFileName".to_string(), Plist::string(image)),
+        (
+            "size".to_string(),
+            Plist::string(format!("{{{},{}}}", width, height)),
+        ),
+        ("textureFileName".to_string(), Plist::string(image)),
+    ]);
+
+    Plist::Dict(vec![
+        ("frames".to_string(), Plist::Dict(frames)),
+        ("metadata".to_string(), metadata),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_sprite_offset() {
+        assert_eq!(sprite_offset(&Frame::named("a.png", 0, 0, 10, 10)), "{0,0}");
+        // 4×4 kept area at (1, 2) of an 8×8 image: center (3, 4) vs (4, 4).
+        let trimmed = Frame::named("a.png", 0, 0, 4, 4).with_source(8, 8, 1, 2);
+        assert_eq!(sprite_offset(&trimmed), "{-1,0}");
+        let odd = Frame::named("a.png", 0, 0, 3, 3).with_source(8, 8, 0, 0);
+        assert_eq!(sprite_offset(&odd), "{-2.5,2.5}");
+    }
+
+    #[test]
+    fn test_sprite_frames() {
+        let frames = vec![Frame::named("hero/idle.png", 2, 3, 10, 20)];
+        let sprite = Sprite::with_frames(RgbaImage::new(32, 32), frames);
+        let xml = sprite_frames(&sprite, "sheet.png").to_xml();
+
+        assert!(xml.contains("<key>hero/idle.png</key>"));
+        assert!(xml.contains("<string>{{2,3},{10,20}}</string>"));
+        assert!(xml.contains("<key>format</key>\n            <integer>3</integer>"));
+        assert!(xml.contains("<key>textureFileName</key>\n            <string>sheet.png</string>"));
+        assert!(xml.contains("<key>size</key>\n            <string>{32,32}</string>"));
+    }
+}
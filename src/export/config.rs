@@ -0,0 +1,45 @@
+//! YAML and TOML renderings of the TexturePacker "JSON (hash)" document, so that every
+//! format carries the same metadata.
+
+use std::error::Error;
+
+use crate::export::texture_packer::json_hash;
+use crate::sprite::Sprite;
+
+#[cfg(feature = "yaml")]
+pub(crate) fn yaml(sprite: &Sprite, image: &str) -> Result<String, Box<dyn Error>> {
+    Ok(serde_yaml::to_string(&json_hash(sprite, image))?)
+}
+
+#[cfg(feature = "toml")]
+pub(crate) fn toml(sprite: &Sprite, image: &str) -> Result<String, Box<dyn Error>> {
+    Ok(toml::to_string_pretty(&json_hash(sprite, image))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use serde_json::Value;
+
+    fn sprite() -> Sprite {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("a.png", 0, 0, 10, 20);
+        sprite.add_named_frame("b/c.png", 10, 0, 20, 20);
+        sprite
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_matches_json() {
+        let document: Value = serde_yaml::from_str(&yaml(&sprite(), "sheet.png").unwrap()).unwrap();
+        assert_eq!(document, json_hash(&sprite(), "sheet.png"));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_matches_json() {
+        let document: Value = toml::from_str(&toml(&sprite(), "sheet.png").unwrap()).unwrap();
+        assert_eq!(document, json_hash(&sprite(), "sheet.png"));
+    }
+}
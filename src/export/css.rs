@@ -0,0 +1,206 @@
+use std::fmt::Write;
+
+use crate::export::strip_extension;
+use crate::sprite::Sprite;
+
+/// Options for the CSS stylesheet exporter.
+#[derive(Debug, Clone)]
+pub struct CssOptions {
+    prefix: String,
+    url_template: String,
+}
+
+impl Default for CssOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CssOptions {
+    /// Creates options producing classes like `.sprite-hero-walk` with the sheet path used as the URL.
+    pub fn new() -> Self {
+        Self {
+            prefix: "sprite-".to_string(),
+            url_template: "{image}".to_string(),
+        }
+    }
+
+    /// Sets the prefix prepended to every class name.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Sets the template of the `background-image` URL; `{image}` is replaced with the sheet path.
+    pub fn with_url_template(mut self, url_template: &str) -> Self {
+        self.url_template = url_template.to_string();
+        self
+    }
+
+    pub(crate) fn get_prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub(crate) fn url(&self, image: &str) -> String {
+        self.url_template.replace("{image}", image)
+    }
+}
+
+/// Turns a frame name such as `hero/walk 01.png` into a CSS identifier such as `hero-walk-01`.
+pub(crate) fn identifier(name: &str) -> String {
+    strip_extension(name)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Formats a sheet coordinate as a negative `background-position` offset.
+pub(crate) fn offset(value: u32) -> String {
+    if value == 0 {
+        "0".to_string()
+    } else {
+        format!("-{}px", value)
+    }
+}
+
+/// Builds a stylesheet with one class per frame.
+pub(crate) fn stylesheet(sprite: &Sprite, image: &str, options: &CssOptions) -> String {
+    let mut css = String::new();
+    let url = options.url(image);
+
+    for frame in sprite.get_frames() {
+        let _ = writeln!(
+            css,
+            ".{}{} {{\n  background-image: url(\"{}\");\n  background-position: {} {};\n  width: {}px;\n  height: {}px;\n}}\n",
+            options.get_prefix(),
+            identifier(frame.get_name()),
+            url,
+            offset(frame.get_x()),
+            offset(frame.get_y()),
+            frame.get_width(),
+            frame.get_height(),
+        );
+    }
+
+    css
+}
+
+/// Builds an SCSS sprite map with a `sprite($name)` mixin applying a frame to any selector.
+pub(crate) fn scss(sprite: &Sprite, image: &str) -> String {
+    let mut scss = format!("$sprite-image: \"{}\";\n\n$sprites: (\n", image);
+
+    for frame in sprite.get_frames() {
+        let _ = writeln!(
+            scss,
+            "  \"{}\": (x: {}px, y: {}px, width: {}px, height: {}px),",
+            identifier(frame.get_name()),
+            frame.get_x(),
+            frame.get_y(),
+            frame.get_width(),
+            frame.get_height(),
+        );
+    }
+
+    scss.push_str(
+        ");
+
+@mixin sprite($name) {
+  $frame: map-get($sprites, $name);
+  background-image: url($sprite-image);
+  background-position: (0 - map-get($frame, x)) (0 - map-get($frame, y));
+  width: map-get($frame, width);
+  height: map-get($frame, height);
+}
+",
+    );
+
+    scss
+}
+
+/// Builds LESS guarded mixins so that `.sprite("name")` applies a frame to any selector.
+pub(crate) fn less(sprite: &Sprite, image: &str) -> String {
+    let mut less = format!(
+        "@sprite-image: \"{}\";
+
+.sprite-frame(@x, @y, @width, @height) {{
+  background-image: url(@sprite-image);
+  background-position: -@x -@y;
+  width: @width;
+  height: @height;
+}}
+
+",
+        image
+    );
+
+    for frame in sprite.get_frames() {
+        let _ = writeln!(
+            less,
+            ".sprite(@name) when (@name = \"{}\") {{ .sprite-frame({}px, {}px, {}px, {}px); }}",
+            identifier(frame.get_name()),
+            frame.get_x(),
+            frame.get_y(),
+            frame.get_width(),
+            frame.get_height(),
+        );
+    }
+
+    less
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier("hero/walk 01.png"), "hero-walk-01");
+        assert_eq!(identifier("icons.v2/save"), "icons-v2-save");
+        assert_eq!(identifier(".hidden"), "-hidden");
+    }
+
+    #[test]
+    fn test_stylesheet() {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("save.png", 0, 0, 10, 20);
+        sprite.add_named_frame("open.png", 10, 5, 20, 15);
+
+        let options = CssOptions::new()
+            .with_prefix("icon-")
+            .with_url_template("/static/{image}?v=2");
+        let css = stylesheet(&sprite, "sheet.png", &options);
+
+        assert!(css.contains(".icon-save {\n  background-image: url(\"/static/sheet.png?v=2\");\n  background-position: 0 0;\n  width: 10px;\n  height: 20px;\n}"));
+        assert!(css.contains(".icon-open {"));
+        assert!(css.contains("background-position: -10px -5px;"));
+    }
+
+    #[test]
+    fn test_scss_and_less() {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("icons/save.png", 10, 5, 20, 15);
+
+        let scss = scss(&sprite, "sheet.png");
+        assert!(scss.starts_with("$sprite-image: \"sheet.png\";"));
+        assert!(scss.contains("\"icons-save\": (x: 10px, y: 5px, width: 20px, height: 15px),"));
+        let compiled = grass::from_string(
+            format!("{}\n.save {{ @include sprite(\"icons-save\"); }}\n", scss),
+            &grass::Options::default(),
+        )
+        .unwrap();
+        assert!(compiled.contains("  background-position: -10px -5px;\n"));
+        assert!(compiled.contains("  width: 20px;\n"));
+
+        let less = less(&sprite, "sheet.png");
+        assert!(less.contains(
+            ".sprite(@name) when (@name = \"icons-save\") { .sprite-frame(10px, 5px, 20px, 15px); }"
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use serde_json::{json, Map, Value};
+
+use crate::export::check_page_count;
+use crate::export::libgdx::region_name;
+use crate::sprite::Sprite;
+
+/// Builds a CreateJS SpriteSheet document with frames from every sheet.
+///
+/// Frames are listed as `[x, y, width, height, imageIndex, regX, regY]`, with the registration
+/// point placed at the top left corner of the untrimmed source image. Frames named like
+/// `walk_01.png` form the animation `walk`, ordered by their numeric suffix; any other frame
+/// becomes a single-frame animation named after it.
+pub(crate) fn sprite_sheet(
+    sprites: &[Sprite],
+    images: &[&str],
+    framerate: f64,
+) -> Result<Value, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+
+    let mut frames = Vec::new();
+    let mut animations: BTreeMap<&str, Vec<(i64, usize)>> = BTreeMap::new();
+
+    for (page, sprite) in sprites.iter().enumerate() {
+        for frame in sprite.get_frames() {
+            let (name, index) = region_name(frame.get_name());
+            animations
+                .entry(name)
+                .or_default()
+                .push((index, frames.len()));
+
+            frames.push(json!([
+                frame.get_x(),
+                frame.get_y(),
+                frame.get_width(),
+                frame.get_height(),
+                page,
+                -i64::from(frame.get_offset_x()),
+                -i64::from(frame.get_offset_y()),
+            ]));
+        }
+    }
+
+    let animations: Map<String, Value> = animations
+        .into_iter()
+        .map(|(name, mut sequence)| {
+            sequence.sort_by_key(|&(index, _)| index);
+            let sequence: Vec<usize> = sequence.into_iter().map(|(_, frame)| frame).collect();
+            (name.to_string(), json!({ "frames": sequence }))
+        })
+        .collect();
+
+    Ok(json!({
+        "framerate": framerate,
+        "images": images,
+        "frames": frames,
+        "animations": animations,
+    }))
+}
+
+/// Writes a CreateJS/EaselJS SpriteSheet JSON document describing every generated sheet,
+/// ready to pass to `new createjs.SpriteSheet(data)`.
+///
+/// Frames named like `walk_01.png` form the animation `walk`, ordered by their numeric suffix;
+/// any other frame becomes a single-frame animation named after it.
+///
+/// # Arguments
+/// - `path`: Path of the JSON file to write.
+/// - `sprites`: The generated sheets.
+/// - `images`: Path of each saved sheet as referenced from the JSON file, in the same order.
+/// - `framerate`: Playback speed of the animations in frames per second.
+pub fn export_easeljs_spritesheet(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+    framerate: f64,
+) -> Result<(), Box<dyn Error>> {
+    let document = sprite_sheet(sprites, images, framerate)?;
+    std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_sprite_sheet() {
+        let mut first = Sprite::new(RgbaImage::new(30, 20));
+        first.add_named_frame("walk_02.png", 0, 0, 10, 20);
+        first.add_named_frame("idle.png", 10, 0, 20, 20);
+        let mut second = Sprite::new(RgbaImage::new(10, 10));
+        second.add_named_frame("walk_01.png", 0, 0, 10, 10);
+
+        let document = sprite_sheet(&[first, second], &["a.png", "b.png"], 12.0).unwrap();
+        assert_eq!(document["framerate"], 12.0);
+        assert_eq!(document["images"], json!(["a.png", "b.png"]));
+        assert_eq!(document["frames"][1], json!([10, 0, 20, 20, 0, 0, 0]));
+        assert_eq!(document["frames"][2], json!([0, 0, 10, 10, 1, 0, 0]));
+        assert_eq!(
+            document["animations"],
+            json!({ "idle": { "frames": [1] }, "walk": { "frames": [2, 0] } })
+        );
+
+        assert!(sprite_sheet(&[], &["a.png"], 12.0).is_err());
+    }
+}
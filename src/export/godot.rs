@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::export::libgdx::region_name;
+use crate::export::{check_page_count, relative_output_path, strip_extension};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Quotes text as a Godot string literal.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Writes the `atlas`, `region` and, for trimmed frames, `margin` properties of an AtlasTexture.
+fn write_atlas_properties(resource: &mut String, texture: &str, frame: &Frame) {
+    let _ = writeln!(resource, "atlas = {}", texture);
+    let _ = writeln!(
+        resource,
+        "region = Rect2({}, {}, {}, {})",
+        frame.get_x(),
+        frame.get_y(),
+        frame.get_width(),
+        frame.get_height()
+    );
+    if frame.is_trimmed() {
+        let _ = writeln!(
+            resource,
+            "margin = Rect2({}, {}, {}, {})",
+            frame.get_offset_x(),
+            frame.get_offset_y(),
+            frame.get_source_width().saturating_sub(frame.get_width()),
+            frame.get_source_height().saturating_sub(frame.get_height())
+        );
+    }
+}
+
+/// Builds a standalone AtlasTexture resource for a frame of the sheet at `image`.
+pub(crate) fn atlas_texture(frame: &Frame, image: &str) -> String {
+    let mut resource = String::new();
+    let _ = writeln!(
+        resource,
+        "[gd_resource type=\"AtlasTexture\" load_steps=2 format=3]"
+    );
+    let _ = writeln!(resource);
+    let _ = writeln!(
+        resource,
+        "[ext_resource type=\"Texture2D\" path={} id=\"1\"]",
+        quote(image)
+    );
+    let _ = writeln!(resource);
+    let _ = writeln!(resource, "[resource]");
+    write_atlas_properties(&mut resource, "ExtResource(\"1\")", frame);
+    resource
+}
+
+/// Builds a SpriteFrames resource with one animation per frame sequence.
+///
+/// Frames named like `walk_01.png` form the animation `walk`, ordered by their numeric suffix;
+/// any other frame becomes a single-frame animation named after it.
+pub(crate) fn sprite_frames(
+    sprites: &[Sprite],
+    images: &[&str],
+    speed: f64,
+) -> Result<String, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+
+    let mut animations: BTreeMap<&str, Vec<(i64, usize)>> = BTreeMap::new();
+    let mut sub_resources = String::new();
+    let mut count = 0;
+
+    for (page, sprite) in sprites.iter().enumerate() {
+        for frame in sprite.get_frames() {
+            count += 1;
+            let (name, index) = region_name(frame.get_name());
+            animations.entry(name).or_default().push((index, count));
+
+            let _ = writeln!(
+                sub_resources,
+                "[sub_resource type=\"AtlasTexture\" id=\"AtlasTexture_{}\"]",
+                count
+            );
+            write_atlas_properties(
+                &mut sub_resources,
+                &format!("ExtResource(\"{}\")", page + 1),
+                frame,
+            );
+            let _ = writeln!(sub_resources);
+        }
+    }
+
+    let mut resource = String::new();
+    let _ = writeln!(
+        resource,
+        "[gd_resource type=\"SpriteFrames\" load_steps={} format=3]",
+        images.len() + count + 1
+    );
+    let _ = writeln!(resource);
+    for (page, image) in images.iter().enumerate() {
+        let _ = writeln!(
+            resource,
+            "[ext_resource type=\"Texture2D\" path={} id=\"{}\"]",
+            quote(image),
+            page + 1
+        );
+    }
+    let _ = writeln!(resource);
+    resource.push_str(&sub_resources);
+
+    let animations: Vec<String> = animations
+        .into_iter()
+        .map(|(name, mut frames)| {
+            frames.sort_by_key(|&(index, _)| index);
+            let frames: Vec<String> = frames
+                .iter()
+                .map(|(_, id)| {
+                    format!(
+                        "{{\n\"duration\": 1.0,\n\"texture\": SubResource(\"AtlasTexture_{}\")\n}}",
+                        id
+                    )
+                })
+                .collect();
+            format!(
+                "{{\n\"frames\": [{}],\n\"loop\": true,\n\"name\": &{},\n\"speed\": {:?}\n}}",
+                frames.join(", "),
+                quote(name),
+                speed
+            )
+        })
+        .collect();
+
+    let _ = writeln!(resource, "[resource]");
+    let _ = writeln!(resource, "animations = [{}]", animations.join(", "));
+    Ok(resource)
+}
+
+/// Writes a Godot SpriteFrames `.tres` resource with the animations found across all sheets.
+///
+/// Frames named like `walk_01.png` form the animation `walk`, ordered by their numeric suffix;
+/// any other frame becomes a single-frame animation named after it.
+///
+/// # Arguments
+/// - `path`: Path of the resource file to write.
+/// - `sprites`: The generated sheets.
+/// - `images`: Resource path of each saved sheet, such as `res://sheets/sheet-0.png`, in the same order.
+/// - `speed`: Playback speed of every animation in frames per second.
+pub fn export_godot_sprite_frames(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+    speed: f64,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, sprite_frames(sprites, images, speed)?)?;
+    Ok(())
+}
+
+/// Writes one AtlasTexture `.tres` resource per frame of `sprite` into `dir`,
+/// named after the frame without its extension.
+///
+/// Frame names that would place a resource outside `dir` are rejected. The resources refer
+/// to the sheet by its resource path `image`, so they resolve wherever they are written.
+pub(crate) fn write_atlas_textures(
+    sprite: &Sprite,
+    dir: &str,
+    image: &str,
+) -> Result<(), Box<dyn Error>> {
+    for frame in sprite.get_frames() {
+        let file_name = format!("{}.tres", strip_extension(frame.get_name()));
+        let path = Path::new(dir).join(relative_output_path(&file_name)?);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, atlas_texture(frame, image))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_atlas_texture() {
+        let frame = Frame::named("hero.png", 4, 6, 10, 12).with_source(16, 20, 2, 3);
+        assert_eq!(
+            atlas_texture(&frame, "res://sheet.png"),
+            "[gd_resource type=\"AtlasTexture\" load_steps=2 format=3]\n\n\
+             [ext_resource type=\"Texture2D\" path=\"res://sheet.png\" id=\"1\"]\n\n\
+             [resource]\n\
+             atlas = ExtResource(\"1\")\n\
+             region = Rect2(4, 6, 10, 12)\n\
+             margin = Rect2(2, 3, 6, 8)\n"
+        );
+    }
+
+    #[test]
+    fn test_sprite_frames() {
+        let mut first = Sprite::new(RgbaImage::new(32, 16));
+        first.add_named_frame("walk_2.png", 0, 0, 16, 16);
+        first.add_named_frame("idle.png", 16, 0, 16, 16);
+        let mut second = Sprite::new(RgbaImage::new(16, 16));
+        second.add_named_frame("walk_1.png", 0, 0, 16, 16);
+
+        let resource = sprite_frames(
+            &[first, second],
+            &["res://sheet-0.png", "res://sheet-1.png"],
+            8.0,
+        )
+        .unwrap();
+
+        assert!(resource.starts_with("[gd_resource type=\"SpriteFrames\" load_steps=6 format=3]"));
+        assert!(resource
+            .contains("[ext_resource type=\"Texture2D\" path=\"res://sheet-1.png\" id=\"2\"]"));
+        assert!(resource.contains(
+            "[sub_resource type=\"AtlasTexture\" id=\"AtlasTexture_3\"]\natlas = ExtResource(\"2\")\n"
+        ));
+        let walk = resource.find("&\"walk\"").unwrap();
+        let idle = resource.find("&\"idle\"").unwrap();
+        assert!(idle < walk);
+        let frames = &resource[resource.rfind("\"frames\"").unwrap()..walk];
+        assert!(frames.find("AtlasTexture_3").unwrap() < frames.find("AtlasTexture_1").unwrap());
+        assert!(resource.contains("\"speed\": 8.0"));
+        assert!(sprite_frames(&[], &["res://extra.png"], 8.0).is_err());
+    }
+
+    #[test]
+    fn test_write_atlas_textures() {
+        let dir = std::env::temp_dir().join("spriterator_godot_atlas_textures");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut sprite = Sprite::new(RgbaImage::new(16, 16));
+        sprite.add_named_frame("hero/walk.png", 0, 0, 8, 8);
+        write_atlas_textures(&sprite, dir.to_str().unwrap(), "res://sheet.png").unwrap();
+        let resource = std::fs::read_to_string(dir.join("hero/walk.tres")).unwrap();
+        assert!(resource.contains("path=\"res://sheet.png\""));
+
+        let mut escaping = Sprite::new(RgbaImage::new(16, 16));
+        escaping.add_named_frame("../escaped.png", 0, 0, 8, 8);
+        assert!(write_atlas_textures(&escaping, dir.to_str().unwrap(), "res://sheet.png").is_err());
+        assert!(!dir.with_file_name("escaped.tres").exists());
+    }
+}
@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::export::{check_page_count, strip_extension};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Splits a frame name into the region name and animation index the way libGDX's packer does:
+/// `walk_01.png` becomes `("walk", 1)`, names without a numeric suffix get index `-1`.
+pub(crate) fn region_name(name: &str) -> (&str, i64) {
+    let name = strip_extension(name);
+
+    if let Some((base, suffix)) = name.rsplit_once('_') {
+        if !base.is_empty() && !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(index) = suffix.parse() {
+                return (base, index);
+            }
+        }
+    }
+
+    (name, -1)
+}
+
+/// Returns the offset of the packed area from the bottom-left corner of the source image.
+pub(crate) fn bottom_left_offset(frame: &Frame) -> (u32, u32) {
+    (
+        frame.get_offset_x(),
+        frame
+            .get_source_height()
+            .saturating_sub(frame.get_offset_y())
+            .saturating_sub(frame.get_height()),
+    )
+}
+
+pub(crate) fn write_region(atlas: &mut String, frame: &Frame) {
+    let (name, index) = region_name(frame.get_name());
+    let (offset_x, offset_y) = bottom_left_offset(frame);
+
+    let _ = writeln!(atlas, "{}", name);
+    let _ = writeln!(atlas, "  rotate: false");
+    let _ = writeln!(atlas, "  xy: {}, {}", frame.get_x(), frame.get_y());
+    let _ = writeln!(
+        atlas,
+        "  size: {}, {}",
+        frame.get_width(),
+        frame.get_height()
+    );
+    let _ = writeln!(
+        atlas,
+        "  orig: {}, {}",
+        frame.get_source_width(),
+        frame.get_source_height()
+    );
+    let _ = writeln!(atlas, "  offset: {}, {}", offset_x, offset_y);
+    let _ = writeln!(atlas, "  index: {}", index);
+}
+
+/// Builds a libGDX texture atlas with one page per sheet.
+pub(crate) fn atlas(sprites: &[Sprite], images: &[&str]) -> Result<String, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+
+    let mut atlas = String::new();
+    for (sprite, image) in sprites.iter().zip(images) {
+        let (width, height) = sprite.get_image().dimensions();
+        let _ = writeln!(atlas);
+        let _ = writeln!(atlas, "{}", image);
+        let _ = writeln!(atlas, "size: {}, {}", width, height);
+        let _ = writeln!(atlas, "format: RGBA8888");
+        let _ = writeln!(atlas, "filter: Linear,Linear");
+        let _ = writeln!(atlas, "repeat: none");
+
+        for frame in sprite.get_frames() {
+            write_region(&mut atlas, frame);
+        }
+    }
+
+    Ok(atlas)
+}
+
+/// Writes a libGDX `.atlas` file describing every generated sheet.
+///
+/// # Arguments
+/// - `path`: Path of the atlas file to write.
+/// - `sprites`: The generated sheets, one atlas page each.
+/// - `images`: Path of each saved sheet as referenced from the atlas file, in the same order.
+pub fn export_libgdx_atlas(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, atlas(sprites, images)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_region_name() {
+        assert_eq!(region_name("hero/walk_01.png"), ("hero/walk", 1));
+        assert_eq!(region_name("hero/idle.png"), ("hero/idle", -1));
+        assert_eq!(region_name("_7.png"), ("_7", -1));
+    }
+
+    #[test]
+    fn test_atlas() {
+        let first = Sprite::with_frames(
+            RgbaImage::new(64, 32),
+            vec![Frame::named("walk_02.png", 4, 6, 10, 12).with_source(16, 20, 2, 3)],
+        );
+        let second = Sprite::with_frames(
+            RgbaImage::new(16, 16),
+            vec![Frame::named("idle.png", 0, 0, 16, 16)],
+        );
+        let atlas = atlas(&[first, second], &["sheet1.png", "sheet2.png"]).unwrap();
+
+        assert!(atlas.starts_with(
+            "\nsheet1.png\nsize: 64, 32\nformat: RGBA8888\nfilter: Linear,Linear\nrepeat: none\n"
+        ));
+        assert!(atlas.contains(
+            "walk\n  rotate: false\n  xy: 4, 6\n  size: 10, 12\n  orig: 16, 20\n  offset: 2, 5\n  index: 2\n"
+        ));
+        assert!(atlas.contains("\nsheet2.png\nsize: 16, 16\n"));
+        assert!(atlas.contains("idle\n  rotate: false\n  xy: 0, 0\n"));
+        assert!(super::atlas(&[], &["extra.png"]).is_err());
+    }
+}
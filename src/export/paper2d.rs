@@ -0,0 +1,39 @@
+use serde_json::{json, Value};
+
+use crate::export::texture_packer::json_hash;
+use crate::sprite::Sprite;
+
+/// Builds the JSON hash document read by Paper2D's sprite sheet importer.
+///
+/// Each frame carries a `pivot` normalized to its untrimmed image with the origin at the
+/// top-left corner, as Unreal Engine expects: its own pivot if set, otherwise `pivot`.
+pub(crate) fn sprite_sheet(sprite: &Sprite, image: &str, pivot: (f64, f64)) -> Value {
+    let mut document = json_hash(sprite, image);
+
+    for frame in sprite.get_frames() {
+        let (x, y) = frame.get_pivot().unwrap_or(pivot);
+        document["frames"][frame.get_name()]["pivot"] = json!({ "x": x, "y": y });
+    }
+    document["meta"]["target"] = json!("paper2d");
+
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_sprite_sheet() {
+        let mut sprite = Sprite::new(RgbaImage::new(32, 16));
+        sprite.add_named_frame("hero/idle.png", 0, 0, 16, 16);
+        let document = sprite_sheet(&sprite, "sheet.png", (0.5, 1.0));
+
+        let frame = &document["frames"]["hero/idle.png"];
+        assert_eq!(frame["frame"], json!({ "x": 0, "y": 0, "w": 16, "h": 16 }));
+        assert_eq!(frame["pivot"], json!({ "x": 0.5, "y": 1.0 }));
+        assert_eq!(document["meta"]["target"], "paper2d");
+        assert_eq!(document["meta"]["image"], "sheet.png");
+    }
+}
@@ -0,0 +1,91 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+use crate::export::texture_packer::named_frame_entry;
+use crate::export::{check_page_count, APP, VERSION};
+use crate::sprite::Sprite;
+
+/// Describes one sheet as an entry of the `textures` array.
+fn texture(sprite: &Sprite, image: &str) -> Value {
+    let frames: Vec<Value> = sprite.get_frames().iter().map(named_frame_entry).collect();
+
+    json!({
+        "image": image,
+        "format": "RGBA8888",
+        "size": {
+            "w": sprite.get_image().width(),
+            "h": sprite.get_image().height(),
+        },
+        "scale": 1,
+        "frames": frames,
+    })
+}
+
+/// Builds a Phaser 3 multiatlas document with one texture per sheet.
+pub(crate) fn multiatlas(sprites: &[Sprite], images: &[&str]) -> Result<Value, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+
+    let textures: Vec<Value> = sprites
+        .iter()
+        .zip(images)
+        .map(|(sprite, image)| texture(sprite, image))
+        .collect();
+
+    Ok(json!({
+        "textures": textures,
+        "meta": {
+            "app": APP,
+            "version": VERSION,
+        },
+    }))
+}
+
+/// Writes a Phaser 3 multiatlas JSON file, loadable with `this.load.multiatlas()`.
+///
+/// # Arguments
+/// - `path`: Path of the JSON file to write.
+/// - `sprites`: The generated sheets, one texture each.
+/// - `images`: Path of each saved sheet relative to the multiatlas path, in the same order.
+pub fn export_phaser_multiatlas(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    let document = multiatlas(sprites, images)?;
+    std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_multiatlas() {
+        let first = Sprite::with_frames(
+            RgbaImage::new(64, 32),
+            vec![Frame::named("walk.png", 4, 6, 10, 12).with_source(16, 20, 2, 3)],
+        );
+        let second = Sprite::with_frames(
+            RgbaImage::new(16, 16),
+            vec![Frame::named("idle.png", 0, 0, 16, 16)],
+        );
+        let document = multiatlas(&[first, second], &["sheet1.png", "sheet2.png"]).unwrap();
+
+        let textures = document["textures"].as_array().unwrap();
+        assert_eq!(textures.len(), 2);
+        assert_eq!(textures[0]["image"], "sheet1.png");
+        assert_eq!(textures[0]["size"], json!({ "w": 64, "h": 32 }));
+        assert_eq!(textures[0]["frames"][0]["filename"], "walk.png");
+        assert_eq!(textures[0]["frames"][0]["trimmed"], true);
+        assert_eq!(
+            textures[0]["frames"][0]["spriteSourceSize"],
+            json!({ "x": 2, "y": 3, "w": 10, "h": 12 })
+        );
+        assert_eq!(textures[1]["frames"][0]["filename"], "idle.png");
+        assert_eq!(document["meta"]["app"], APP);
+        assert!(multiatlas(&[], &["extra.png"]).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use serde_json::{json, Value};
+use std::error::Error;
+use std::path::Path;
+
+use crate::export::check_page_count;
+use crate::export::texture_packer::json_hash;
+use crate::sprite::Sprite;
+
+/// Builds the PixiJS spritesheet of each sheet, linking every page to the others through
+/// `meta.related_multi_packs` so that loading one page loads them all.
+pub(crate) fn multipack(
+    sprites: &[Sprite],
+    images: &[&str],
+    pages: &[&str],
+) -> Result<Vec<Value>, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+    check_page_count(sprites.len(), pages)?;
+
+    Ok(sprites
+        .iter()
+        .zip(images)
+        .enumerate()
+        .map(|(index, (sprite, image))| {
+            let mut document = json_hash(sprite, image);
+            let related: Vec<&str> = pages
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != index)
+                .map(|(_, page)| *page)
+                .collect();
+            document["meta"]["related_multi_packs"] = json!(related);
+            document
+        })
+        .collect())
+}
+
+/// Writes one PixiJS spritesheet JSON file per sheet, linked together as a multipack.
+///
+/// The pages are referenced from each other by file name, so they must be stored in
+/// the same directory.
+///
+/// # Arguments
+/// - `paths`: Path of the JSON file to write for each sheet.
+/// - `sprites`: The generated sheets.
+/// - `images`: Path of each saved sheet as referenced from its JSON file, in the same order.
+pub fn export_pixi_multipack(
+    paths: &[&str],
+    sprites: &[Sprite],
+    images: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    let pages = paths
+        .iter()
+        .map(|path| {
+            Path::new(path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| format!("Invalid spritesheet path {:?}.", path).into())
+        })
+        .collect::<Result<Vec<&str>, Box<dyn Error>>>()?;
+
+    for (path, document) in paths.iter().zip(multipack(sprites, images, &pages)?) {
+        std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_multipack() {
+        let sprites: Vec<Sprite> = (0..3)
+            .map(|index| {
+                let mut sprite = Sprite::new(RgbaImage::new(16, 16));
+                sprite.add_named_frame(&format!("{}.png", index), 0, 0, 16, 16);
+                sprite
+            })
+            .collect();
+        let documents = multipack(
+            &sprites,
+            &["sheet-0.png", "sheet-1.png", "sheet-2.png"],
+            &["sheet-0.json", "sheet-1.json", "sheet-2.json"],
+        )
+        .unwrap();
+
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[1]["meta"]["image"], "sheet-1.png");
+        assert_eq!(
+            documents[1]["meta"]["related_multi_packs"],
+            json!(["sheet-0.json", "sheet-2.json"])
+        );
+        assert!(documents[0]["frames"].get("0.png").is_some());
+        assert!(multipack(&sprites, &["sheet-0.png"], &["sheet-0.json"]).is_err());
+    }
+}
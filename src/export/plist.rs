@@ -0,0 +1,91 @@
+use crate::export::escape_xml;
+
+/// A value of an Apple XML property list.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Plist {
+    Dict(Vec<(String, Plist)>),
+    Array(Vec<Plist>),
+    String(String),
+    Integer(i64),
+    Bool(bool),
+}
+
+impl Plist {
+    pub(crate) fn string(value: impl Into<String>) -> Self {
+        Plist::String(value.into())
+    }
+
+    /// Serializes the value as a complete XML property list document.
+    pub(crate) fn to_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n",
+        );
+        self.write(&mut xml, 1);
+        xml.push_str("</plist>\n");
+        xml
+    }
+
+    fn write(&self, xml: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth);
+
+        match self {
+            Plist::Dict(entries) if entries.is_empty() => {
+                xml.push_str(&format!("{}<dict/>\n", indent));
+            }
+            Plist::Dict(entries) => {
+                xml.push_str(&format!("{}<dict>\n", indent));
+                for (key, value) in entries {
+                    xml.push_str(&format!("{}    <key>{}</key>\n", indent, escape_xml(key)));
+                    value.write(xml, depth + 1);
+                }
+                xml.push_str(&format!("{}</dict>\n", indent));
+            }
+            Plist::Array(items) if items.is_empty() => {
+                xml.push_str(&format!("{}<array/>\n", indent));
+            }
+            Plist::Array(items) => {
+                xml.push_str(&format!("{}<array>\n", indent));
+                for item in items {
+                    item.write(xml, depth + 1);
+                }
+                xml.push_str(&format!("{}</array>\n", indent));
+            }
+            Plist::String(value) => {
+                xml.push_str(&format!(
+                    "{}<string>{}</string>\n",
+                    indent,
+                    escape_xml(value)
+                ));
+            }
+            Plist::Integer(value) => {
+                xml.push_str(&format!("{}<integer>{}</integer>\n", indent, value));
+            }
+            Plist::Bool(value) => {
+                xml.push_str(&format!("{}<{}/>\n", indent, value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_xml() {
+        let plist = Plist::Dict(vec![
+            ("name".to_string(), Plist::string("a & b")),
+            ("count".to_string(), Plist::Integer(3)),
+            ("flag".to_string(), Plist::Bool(false)),
+            ("items".to_string(), Plist::Array(Vec::new())),
+        ]);
+        let xml = plist.to_xml();
+        assert!(xml.contains("<plist version=\"1.0\">\n    <dict>\n        <key>name</key>\n        <string>a &amp; b</string>\n"));
+        assert!(xml.contains("        <integer>3</integer>\n"));
+        assert!(xml.contains("        <false/>\n"));
+        assert!(xml.contains("        <array/>\n"));
+        assert!(xml.ends_with("    </dict>\n</plist>\n"));
+    }
+}
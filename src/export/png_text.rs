@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+
+use png::text_metadata::{EncodableTextChunk, ITXtChunk, ZTXtChunk};
+
+/// Keyword of the text chunk holding embedded metadata.
+pub(crate) const KEYWORD: &str = "spriterator";
+
+/// Length of the PNG signature followed by the IHDR chunk, which must come first.
+const HEADER_LENGTH: usize = 8 + 4 + 4 + 13 + 4;
+
+/// Inserts `text` into an encoded PNG as a compressed text chunk right after the header,
+/// so that decoders see it before any image data.
+///
+/// Latin-1 text is stored in a zTXt chunk; anything else, such as non-Latin frame names,
+/// in a compressed iTXt chunk, which holds UTF-8.
+pub(crate) fn embed(png: &[u8], text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut chunk = Vec::new();
+    if text.chars().all(|c| u32::from(c) <= 0xff) {
+        ZTXtChunk::new(KEYWORD, text).encode(&mut chunk)?;
+    } else {
+        let mut itxt = ITXtChunk::new(KEYWORD, text);
+        itxt.compressed = true;
+        itxt.encode(&mut chunk)?;
+    }
+    insert_after_header(png, &chunk)
+}
+
+/// Marks an encoded PNG as sRGB by inserting an sRGB chunk with the perceptual rendering
+/// intent right after the header, where it must precede the palette and image data.
+pub(crate) fn tag_srgb(png: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut chunk = Vec::with_capacity(13);
+    chunk.extend_from_slice(&1u32.to_be_bytes());
+    chunk.extend_from_slice(b"sRGB");
+    chunk.push(0);
+    chunk.extend_from_slice(&crc32fast::hash(&chunk[4..]).to_be_bytes());
+    insert_after_header(png, &chunk)
+}
+
+/// Inserts an encoded chunk into a PNG right after its IHDR chunk.
+fn insert_after_header(png: &[u8], chunk: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    if png.len() < HEADER_LENGTH || &png[12..16] != b"IHDR" {
+        return Err("Expected a PNG starting with an IHDR chunk.".into());
+    }
+
+    let mut data = Vec::with_capacity(png.len() + chunk.len());
+    data.extend_from_slice(&png[..HEADER_LENGTH]);
+    data.extend_from_slice(chunk);
+    data.extend_from_slice(&png[HEADER_LENGTH..]);
+    Ok(data)
+}
+
+/// Reads metadata embedded by `Sprite::save_png_with_metadata` back from a PNG.
+///
+/// # Arguments
+/// - `path`: Path of the PNG file.
+///
+/// # Returns
+/// A `Result` containing the embedded JSON document, `None` if the PNG has none,
+/// or an error if the file cannot be read or is not a PNG.
+pub fn read_png_metadata(path: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let reader = png::Decoder::new(BufReader::new(File::open(path)?)).read_info()?;
+    let info = reader.info();
+
+    if let Some(chunk) = info
+        .compressed_latin1_text
+        .iter()
+        .find(|chunk| chunk.keyword == KEYWORD)
+    {
+        return Ok(Some(chunk.get_text()?));
+    }
+    if let Some(chunk) = info.utf8_text.iter().find(|chunk| chunk.keyword == KEYWORD) {
+        return Ok(Some(chunk.get_text()?));
+    }
+    Ok(info
+        .uncompressed_latin1_text
+        .iter()
+        .find(|chunk| chunk.keyword == KEYWORD)
+        .map(|chunk| chunk.text.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_rejects_other_data() {
+        assert!(embed(b"GIF89a", "{}").is_err());
+        assert!(tag_srgb(b"GIF89a").is_err());
+    }
+}
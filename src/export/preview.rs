@@ -0,0 +1,87 @@
+use std::error::Error;
+use std::fmt::Write;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::export::escape_xml;
+use crate::output_format::{OutputFormat, PngOptions};
+use crate::sprite::Sprite;
+
+/// Builds a standalone HTML page showing the sheet, embedded as a PNG data URI, with an
+/// outline per frame that reveals its name and coordinates on hover.
+pub(crate) fn page(sprite: &Sprite) -> Result<String, Box<dyn Error>> {
+    let png = sprite.encode(&OutputFormat::Png(PngOptions::new()))?;
+
+    let image = sprite.get_image();
+    let mut html = format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Sprite sheet preview</title>
+<style>
+body {{ font-family: sans-serif; background: #333; color: #eee; }}
+.sheet {{ position: relative; width: {width}px; height: {height}px; background: repeating-conic-gradient(#888 0 25%, #aaa 0 50%) 0 0 / 16px 16px; }}
+.sheet img {{ display: block; image-rendering: pixelated; }}
+.frame {{ position: absolute; box-sizing: border-box; border: 1px solid rgba(0, 255, 255, 0.4); }}
+.frame:hover {{ border-color: #f0f; background: rgba(255, 0, 255, 0.15); }}
+.frame span {{ display: none; position: absolute; left: 0; top: 100%; z-index: 1; padding: 2px 4px; background: #000; white-space: nowrap; font-size: 12px; }}
+.frame:hover span {{ display: block; }}
+</style>
+</head>
+<body>
+<p>{width} &times; {height}, {frames} frames</p>
+<div class=\"sheet\">
+<img src=\"data:image/png;base64,{data}\" width=\"{width}\" height=\"{height}\" alt=\"\">
+",
+        width = image.width(),
+        height = image.height(),
+        frames = sprite.get_frames().len(),
+        data = STANDARD.encode(&png),
+    );
+
+    for frame in sprite.get_frames() {
+        let label = format!(
+            "{} ({}, {}) {}&times;{}",
+            escape_xml(frame.get_name()),
+            frame.get_x(),
+            frame.get_y(),
+            frame.get_width(),
+            frame.get_height(),
+        );
+        let _ = writeln!(
+            html,
+            "<div class=\"frame\" style=\"left: {}px; top: {}px; width: {}px; height: {}px\"><span>{}</span></div>",
+            frame.get_x(),
+            frame.get_y(),
+            frame.get_width(),
+            frame.get_height(),
+            label,
+        );
+    }
+
+    html.push_str("</div>\n</body>\n</html>\n");
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_page() {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("save.png", 0, 0, 10, 20);
+        sprite.add_named_frame("<open>.png", 10, 5, 20, 15);
+
+        let html = page(&sprite).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<img src=\"data:image/png;base64,iVBORw0KGgo"));
+        assert!(html.contains("<p>30 &times; 20, 2 frames</p>"));
+        assert!(html.contains("style=\"left: 10px; top: 5px; width: 20px; height: 15px\""));
+        assert!(html.contains("<span>&lt;open&gt;.png (10, 5) 20&times;15</span>"));
+        assert!(!html.contains("<open>"));
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt::Write;
+
+use crate::export::{escape_xml, strip_extension};
+use crate::sprite::Sprite;
+
+/// Builds a Sparrow/Starling `<TextureAtlas>` document.
+///
+/// Trimmed frames carry `frameX`/`frameY` offsets (negative, as Starling expects) and
+/// `frameWidth`/`frameHeight` with the size of the untrimmed image.
+pub(crate) fn texture_atlas(sprite: &Sprite, image: &str) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<TextureAtlas imagePath=\"{}\">\n",
+        escape_xml(image)
+    );
+
+    for frame in sprite.get_frames() {
+        let _ = write!(
+            xml,
+            "    <SubTexture name=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+            escape_xml(strip_extension(frame.get_name())),
+            frame.get_x(),
+            frame.get_y(),
+            frame.get_width(),
+            frame.get_height(),
+        );
+        if frame.is_trimmed() {
+            let _ = write!(
+                xml,
+                " frameX=\"-{}\" frameY=\"-{}\" frameWidth=\"{}\" frameHeight=\"{}\"",
+                frame.get_offset_x(),
+                frame.get_offset_y(),
+                frame.get_source_width(),
+                frame.get_source_height(),
+            );
+        }
+        xml.push_str("/>\n");
+    }
+
+    xml.push_str("</TextureAtlas>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_texture_atlas() {
+        let frames = vec![
+            Frame::named("walk_01.png", 0, 0, 10, 20),
+            Frame::named("walk_02.png", 10, 0, 8, 16).with_source(12, 20, 2, 3),
+        ];
+        let sprite = Sprite::with_frames(RgbaImage::new(18, 20), frames);
+        let xml = texture_atlas(&sprite, "hero & co.png");
+
+        assert!(xml.contains("<TextureAtlas imagePath=\"hero &amp; co.png\">"));
+        assert!(xml
+            .contains("<SubTexture name=\"walk_01\" x=\"0\" y=\"0\" width=\"10\" height=\"20\"/>"));
+        assert!(xml.contains(
+            "<SubTexture name=\"walk_02\" x=\"10\" y=\"0\" width=\"8\" height=\"16\" frameX=\"-2\" frameY=\"-3\" frameWidth=\"12\" frameHeight=\"20\"/>"
+        ));
+        assert!(xml.ends_with("</TextureAtlas>\n"));
+    }
+}
@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::export::check_page_count;
+use crate::export::libgdx::{bottom_left_offset, region_name};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Writes a region in the compact Spine 4 syntax, omitting fields that hold their default value.
+fn write_region(atlas: &mut String, frame: &Frame) {
+    let (name, index) = region_name(frame.get_name());
+
+    let _ = writeln!(atlas, "{}", name);
+    let _ = writeln!(
+        atlas,
+        "bounds:{},{},{},{}",
+        frame.get_x(),
+        frame.get_y(),
+        frame.get_width(),
+        frame.get_height()
+    );
+    if frame.is_trimmed() {
+        let (offset_x, offset_y) = bottom_left_offset(frame);
+        let _ = writeln!(
+            atlas,
+            "offsets:{},{},{},{}",
+            offset_x,
+            offset_y,
+            frame.get_source_width(),
+            frame.get_source_height()
+        );
+    }
+    if index != -1 {
+        let _ = writeln!(atlas, "index:{}", index);
+    }
+}
+
+/// Builds a Spine atlas with one page per sheet.
+pub(crate) fn atlas(sprites: &[Sprite], images: &[&str]) -> Result<String, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+
+    let mut atlas = String::new();
+    for (page, (sprite, image)) in sprites.iter().zip(images).enumerate() {
+        let (width, height) = sprite.get_image().dimensions();
+        if page > 0 {
+            let _ = writeln!(atlas);
+        }
+        let _ = writeln!(atlas, "{}", image);
+        let _ = writeln!(atlas, "size:{},{}", width, height);
+        let _ = writeln!(atlas, "filter:Linear,Linear");
+
+        for frame in sprite.get_frames() {
+            write_region(&mut atlas, frame);
+        }
+    }
+
+    Ok(atlas)
+}
+
+/// Writes a Spine runtime `.atlas` file describing every generated sheet.
+///
+/// Unlike the libGDX format, pages and regions use the Spine 4 syntax, where rotation,
+/// untrimmed offsets and missing animation indices are left out.
+///
+/// # Arguments
+/// - `path`: Path of the atlas file to write.
+/// - `sprites`: The generated sheets, one atlas page each.
+/// - `images`: Path of each saved sheet as referenced from the atlas file, in the same order.
+pub fn export_spine_atlas(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, atlas(sprites, images)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_atlas() {
+        let first = Sprite::with_frames(
+            RgbaImage::new(64, 32),
+            vec![
+                Frame::named("walk_02.png", 4, 6, 10, 12).with_source(16, 20, 2, 3),
+                Frame::named("head.png", 20, 0, 8, 8),
+            ],
+        );
+        let second = Sprite::with_frames(
+            RgbaImage::new(16, 16),
+            vec![Frame::named("idle.png", 0, 0, 16, 16)],
+        );
+        let atlas = atlas(&[first, second], &["sheet1.png", "sheet2.png"]).unwrap();
+
+        assert_eq!(
+            atlas,
+            "sheet1.png\nsize:64,32\nfilter:Linear,Linear\n\
+             walk\nbounds:4,6,10,12\noffsets:2,5,16,20\nindex:2\n\
+             head\nbounds:20,0,8,8\n\
+             \nsheet2.png\nsize:16,16\nfilter:Linear,Linear\n\
+             idle\nbounds:0,0,16,16\n"
+        );
+        assert!(super::atlas(&[], &["extra.png"]).is_err());
+    }
+}
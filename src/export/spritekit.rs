@@ -0,0 +1,135 @@
+use std::error::Error;
+
+use image::RgbaImage;
+
+use crate::export::check_page_count;
+use crate::export::cocos::sprite_offset;
+use crate::export::plist::Plist;
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Returns `true` if every pixel of the frame is fully opaque, letting SpriteKit skip blending.
+fn is_fully_opaque(image: &RgbaImage, frame: &Frame) -> bool {
+    (frame.get_y()..frame.rect().bottom()).all(|y| {
+        (frame.get_x()..frame.rect().right()).all(|x| {
+            image
+                .get_pixel_checked(x, y)
+                .is_some_and(|pixel| pixel[3] == 255)
+        })
+    })
+}
+
+fn subimage(image: &RgbaImage, frame: &Frame) -> Plist {
+    Plist::Dict(vec![
+        (
+            "isFullyOpaque".to_string(),
+            Plist::Bool(is_fully_opaque(image, frame)),
+        ),
+        ("name".to_string(), Plist::string(frame.get_name())),
+        (
+            "spriteOffset".to_string(),
+            Plist::string(sprite_offset(frame)),
+        ),
+        (
+            "spriteSourceSize".to_string(),
+            Plist::string(format!(
+                "{{{},{}}}",
+                frame.get_source_width(),
+                frame.get_source_height()
+            )),
+        ),
+        (
+            "textureRect".to_string(),
+            Plist::string(format!(
+                "{{{{{},{}}},{{{},{}}}}}",
+                frame.get_x(),
+                frame.get_y(),
+                frame.get_width(),
+                frame.get_height()
+            )),
+        ),
+        ("textureRotated".to_string(), Plist::Bool(false)),
+    ])
+}
+
+/// Builds the property list Xcode writes into a compiled `.atlasc` texture atlas,
+/// with one image per sheet.
+pub(crate) fn atlasc(sprites: &[Sprite], images: &[&str]) -> Result<Plist, Box<dyn Error>> {
+    check_page_count(sprites.len(), images)?;
+
+    let images = sprites
+        .iter()
+        .zip(images)
+        .map(|(sprite, image)| {
+            let (width, height) = sprite.get_image().dimensions();
+            let subimages = sprite
+                .get_frames()
+                .iter()
+                .map(|frame| subimage(sprite.get_image(), frame))
+                .collect();
+
+            Plist::Dict(vec![
+                ("path".to_string(), Plist::string(*image)),
+                (
+                    "size".to_string(),
+                    Plist::string(format!("{{{},{}}}", width, height)),
+                ),
+                ("subimages".to_string(), Plist::Array(subimages)),
+            ])
+        })
+        .collect();
+
+    Ok(Plist::Dict(vec![
+        ("format".to_string(), Plist::string("APPL")),
+        ("images".to_string(), Plist::Array(images)),
+        ("version".to_string(), Plist::Integer(1)),
+    ]))
+}
+
+/// Writes the property list of a compiled SpriteKit texture atlas describing every generated
+/// sheet, so that `SKTextureAtlas` loads it as if Xcode had built the atlas.
+///
+/// Place the file and the sheets in a `Name.atlasc` folder, naming the file `Name.plist`.
+///
+/// # Arguments
+/// - `path`: Path of the property list to write.
+/// - `sprites`: The generated sheets, one atlas image each.
+/// - `images`: Path of each saved sheet relative to the `.atlasc` folder, in the same order.
+pub fn export_spritekit_atlasc(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, atlasc(sprites, images)?.to_xml())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_atlasc() {
+        let mut image = RgbaImage::from_pixel(30, 20, Rgba([255, 0, 0, 255]));
+        image.put_pixel(29, 19, Rgba([0, 0, 0, 0]));
+        let mut sprite = Sprite::new(image);
+        sprite.add_named_frame("a.png", 0, 0, 10, 20);
+        sprite.add_named_frame("b.png", 10, 0, 20, 20);
+
+        let xml = atlasc(&[sprite], &["atlas.1.png"]).unwrap().to_xml();
+        assert!(xml.contains("<key>format</key>\n        <string>APPL</string>"));
+        assert!(xml.contains("<key>path</key>\n                <string>atlas.1.png</string>"));
+        assert!(xml.contains("<string>{30,20}</string>"));
+        assert!(xml.contains("<string>{{10,0},{20,20}}</string>"));
+        assert!(xml.contains(
+            "<key>isFullyOpaque</key>\n                        <true/>\n                        <key>name</key>\n                        <string>a.png</string>"
+        ));
+        assert!(xml.contains(
+            "<key>isFullyOpaque</key>\n                        <false/>\n                        <key>name</key>\n                        <string>b.png</string>"
+        ));
+        assert!(xml.contains("<key>version</key>\n        <integer>1</integer>"));
+
+        assert!(atlasc(&[], &["atlas.1.png"]).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::Write;
+
+use crate::sprite::Sprite;
+
+const COLUMNS: [&str; 11] = [
+    "name", "x", "y", "w", "h", "rotated", "trimmed", "source_w", "source_h", "offset_x",
+    "offset_y",
+];
+
+/// Quotes a field if it contains the delimiter, quotes or line breaks.
+fn field(value: &str, delimiter: char) -> String {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Builds a table with a header row and one row per frame, separated by `delimiter`.
+pub(crate) fn frame_table(sprite: &Sprite, delimiter: char) -> String {
+    let separator = delimiter.to_string();
+    let mut table = COLUMNS.join(&separator);
+    table.push('\n');
+
+    for frame in sprite.get_frames() {
+        let _ = writeln!(
+            table,
+            "{}",
+            [
+                field(frame.get_name(), delimiter),
+                frame.get_x().to_string(),
+                frame.get_y().to_string(),
+                frame.get_width().to_string(),
+                frame.get_height().to_string(),
+                false.to_string(),
+                frame.is_trimmed().to_string(),
+                frame.get_source_width().to_string(),
+                frame.get_source_height().to_string(),
+                frame.get_offset_x().to_string(),
+                frame.get_offset_y().to_string(),
+            ]
+            .join(&separator)
+        );
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_frame_table() {
+        let sprite = Sprite::with_frames(
+            RgbaImage::new(32, 32),
+            vec![
+                Frame::named("a.png", 0, 0, 8, 8),
+                Frame::named("b, \"c\".png", 8, 0, 6, 4).with_source(10, 10, 2, 3),
+            ],
+        );
+
+        assert_eq!(
+            frame_table(&sprite, ','),
+            "name,x,y,w,h,rotated,trimmed,source_w,source_h,offset_x,offset_y\n\
+             a.png,0,0,8,8,false,false,8,8,0,0\n\
+             \"b, \"\"c\"\".png\",8,0,6,4,false,true,10,10,2,3\n"
+        );
+        assert!(frame_table(&sprite, '\t').contains("\n\"b, \"\"c\"\".png\"\t8\t0\t"));
+    }
+}
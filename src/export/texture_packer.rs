@@ -0,0 +1,146 @@
+use serde_json::{json, Map, Value};
+
+use crate::export::{APP, VERSION};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Describes a single frame the way TexturePacker does, without its name.
+fn frame_entry(frame: &Frame) -> Value {
+    let mut entry = json!({
+        "frame": {
+            "x": frame.get_x(),
+            "y": frame.get_y(),
+            "w": frame.get_width(),
+            "h": frame.get_height(),
+        },
+        "rotated": false,
+        "trimmed": frame.is_trimmed(),
+        "spriteSourceSize": {
+            "x": frame.get_offset_x(),
+            "y": frame.get_offset_y(),
+            "w": frame.get_width(),
+            "h": frame.get_height(),
+        },
+        "sourceSize": {
+            "w": frame.get_source_width(),
+            "h": frame.get_source_height(),
+        },
+    });
+
+    if let Some(channel) = frame.get_channel() {
+        entry["channel"] = json!(channel);
+    }
+    if let Some(duration) = frame.get_duration() {
+        entry["duration"] = json!(duration);
+    }
+    if let Some((x, y)) = frame.get_pivot() {
+        entry["pivot"] = json!({ "x": x, "y": y });
+    }
+
+    entry
+}
+
+/// Describes a single frame with its name in a leading `filename` field, as array formats do.
+pub(crate) fn named_frame_entry(frame: &Frame) -> Value {
+    let mut entry = Map::new();
+    entry.insert("filename".to_string(), json!(frame.get_name()));
+    if let Value::Object(fields) = frame_entry(frame) {
+        entry.extend(fields);
+    }
+    Value::Object(entry)
+}
+
+pub(crate) fn meta(sprite: &Sprite, image: &str) -> Value {
+    let mut meta = json!({
+        "app": APP,
+        "version": VERSION,
+        "image": image,
+        "format": "RGBA8888",
+        "size": {
+            "w": sprite.get_image().width(),
+            "h": sprite.get_image().height(),
+        },
+        "scale": "1",
+    });
+
+    if let Some(layer) = sprite.get_layer() {
+        meta["layer"] = json!(layer);
+    }
+
+    meta
+}
+
+/// Builds the TexturePacker "JSON (hash)" document, keyed by frame name.
+pub(crate) fn json_hash(sprite: &Sprite, image: &str) -> Value {
+    let frames: Map<String, Value> = sprite
+        .get_frames()
+        .iter()
+        .map(|frame| (frame.get_name().to_string(), frame_entry(frame)))
+        .collect();
+
+    json!({
+        "frames": frames,
+        "meta": meta(sprite, image),
+    })
+}
+
+/// Builds the TexturePacker "JSON (array)" document, keeping frames in packing order.
+pub(crate) fn json_array(sprite: &Sprite, image: &str) -> Value {
+    let frames: Vec<Value> = sprite.get_frames().iter().map(named_frame_entry).collect();
+
+    json!({
+        "frames": frames,
+        "meta": meta(sprite, image),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn sprite() -> Sprite {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("a.png", 0, 0, 10, 20);
+        sprite.add_named_frame("b/c.png", 10, 0, 20, 20);
+        sprite
+    }
+
+    #[test]
+    fn test_json_hash() {
+        let document = json_hash(&sprite(), "sheet.png");
+        assert_eq!(document["meta"]["image"], "sheet.png");
+        assert_eq!(document["meta"]["size"], json!({ "w": 30, "h": 20 }));
+
+        let frames = document["frames"].as_object().unwrap();
+        assert_eq!(frames.keys().collect::<Vec<_>>(), vec!["a.png", "b/c.png"]);
+        assert_eq!(
+            frames["b/c.png"]["frame"],
+            json!({ "x": 10, "y": 0, "w": 20, "h": 20 })
+        );
+        assert_eq!(frames["b/c.png"]["trimmed"], false);
+        assert_eq!(frames["b/c.png"]["sourceSize"], json!({ "w": 20, "h": 20 }));
+    }
+
+    #[test]
+    fn test_json_array() {
+        let document = json_array(&sprite(), "sheet.png");
+        let frames = document["frames"].as_array().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0]["filename"], "a.png");
+        assert_eq!(frames[1]["filename"], "b/c.png");
+        assert_eq!(
+            frames[1]["frame"],
+            json!({ "x": 10, "y": 0, "w": 20, "h": 20 })
+        );
+        assert_eq!(document["meta"]["image"], "sheet.png");
+        assert!(document["meta"].get("layer").is_none());
+    }
+
+    #[test]
+    fn test_meta_layer() {
+        let mut sprite = sprite();
+        sprite.set_layer(3);
+        assert_eq!(json_hash(&sprite, "sheet.png")["meta"]["layer"], 3);
+    }
+}
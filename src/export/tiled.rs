@@ -0,0 +1,155 @@
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::export::escape_xml;
+use crate::sprite::Sprite;
+
+/// Builds a Tiled `<tileset>` for a sheet generated with a uniform grid.
+///
+/// Tiles span the cells without their extrusion and padding, which Tiled skips together with
+/// the sheet margin and the spacing between cells through `margin` and `spacing`. Each tile
+/// records the name of its frame in a `name` property.
+pub(crate) fn tileset(sprite: &Sprite, image: &str, name: &str) -> Result<String, Box<dyn Error>> {
+    let slots = sprite.get_slots();
+    let frames = sprite.get_frames();
+    let not_a_grid = || "A tileset can only be exported from sheets generated on a grid.";
+
+    let (first_slot, first_frame) = match (slots.first(), frames.first()) {
+        (Some(slot), Some(frame)) if slots.len() == frames.len() => (slot, frame),
+        _ => return Err(not_a_grid().into()),
+    };
+    if slots
+        .iter()
+        .any(|slot| (slot.w, slot.h) != (first_slot.w, first_slot.h))
+    {
+        return Err(not_a_grid().into());
+    }
+
+    let border = first_frame
+        .get_x()
+        .checked_sub(first_slot.x)
+        .filter(|&border| first_frame.get_y().checked_sub(first_slot.y) == Some(border))
+        .filter(|border| border.saturating_mul(2) <= first_slot.w.min(first_slot.h))
+        .ok_or_else(not_a_grid)?;
+    if first_slot.x != first_slot.y {
+        return Err(not_a_grid().into());
+    }
+    let margin = first_frame.get_x();
+    let gap = slots
+        .iter()
+        .find(|slot| slot.y == first_slot.y && slot.x > first_slot.x)
+        .map(|slot| slot.x - first_slot.right())
+        .or_else(|| {
+            slots
+                .iter()
+                .find(|slot| slot.y > first_slot.y)
+                .map(|slot| slot.y - first_slot.bottom())
+        })
+        .unwrap_or(0);
+    let spacing = border * 2 + gap;
+    let tile_width = first_slot.w - border * 2;
+    let tile_height = first_slot.h - border * 2;
+    let columns = slots.iter().filter(|slot| slot.y == first_slot.y).count();
+    let (width, height) = sprite.get_image().dimensions();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<tileset version=\"1.10\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" spacing=\"{}\" margin=\"{}\" tilecount=\"{}\" columns=\"{}\">",
+        escape_xml(name),
+        tile_width,
+        tile_height,
+        spacing,
+        margin,
+        frames.len(),
+        columns
+    );
+    let _ = writeln!(
+        xml,
+        " <image source=\"{}\" width=\"{}\" height=\"{}\"/>",
+        escape_xml(image),
+        width,
+        height
+    );
+    for (id, frame) in frames.iter().enumerate() {
+        let _ = writeln!(
+            xml,
+            " <tile id=\"{}\">\n  <properties>\n   <property name=\"name\" value=\"{}\"/>\n  </properties>\n </tile>",
+            id,
+            escape_xml(frame.get_name())
+        );
+    }
+    xml.push_str("</tileset>\n");
+
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+    use crate::rect::Rect;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_tileset() {
+        let frames = (0..3)
+            .map(|index| {
+                Frame::named(
+                    &format!("{}.png", index),
+                    2 + (index % 2) * 20,
+                    2 + (index / 2) * 20,
+                    16,
+                    16,
+                )
+            })
+            .collect();
+        let mut sprite = Sprite::with_frames(RgbaImage::new(40, 40), frames);
+        let slots = (0..3)
+            .map(|index| Rect::new((index % 2) * 20, (index / 2) * 20, 20, 20))
+            .collect();
+        sprite.set_layout((40, 40), slots);
+
+        let xml = tileset(&sprite, "tiles.png", "Terrain").unwrap();
+        assert!(xml.contains(
+            "<tileset version=\"1.10\" name=\"Terrain\" tilewidth=\"16\" tileheight=\"16\" spacing=\"4\" margin=\"2\" tilecount=\"3\" columns=\"2\">"
+        ));
+        assert!(xml.contains(" <image source=\"tiles.png\" width=\"40\" height=\"40\"/>"));
+        assert!(xml.contains(
+            "<tile id=\"2\">\n  <properties>\n   <property name=\"name\" value=\"2.png\"/>"
+        ));
+
+        let mut shelf = Sprite::new(RgbaImage::new(30, 10));
+        shelf.add_named_frame("a.png", 0, 0, 10, 10);
+        shelf.add_named_frame("b.png", 10, 0, 20, 10);
+        shelf.set_layout(
+            (30, 10),
+            vec![Rect::new(0, 0, 10, 10), Rect::new(10, 0, 20, 10)],
+        );
+        assert!(tileset(&shelf, "tiles.png", "Terrain").is_err());
+    }
+
+    #[test]
+    fn test_tileset_margin_and_spacing() {
+        // 16×16 tiles with 2 pixels of extrusion, 3 pixels of spacing and a 5 pixel margin.
+        let frames = (0..4)
+            .map(|index| {
+                Frame::named(
+                    &format!("{}.png", index),
+                    7 + (index % 2) * 23,
+                    7 + (index / 2) * 23,
+                    16,
+                    16,
+                )
+            })
+            .collect();
+        let mut sprite = Sprite::with_frames(RgbaImage::new(48, 48), frames);
+        let slots = (0..4)
+            .map(|index| Rect::new(5 + (index % 2) * 23, 5 + (index / 2) * 23, 20, 20))
+            .collect();
+        sprite.set_layout((48, 48), slots);
+
+        let xml = tileset(&sprite, "tiles.png", "Terrain").unwrap();
+        assert!(xml.contains("tilewidth=\"16\" tileheight=\"16\" spacing=\"7\" margin=\"7\""));
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use serde_json::json;
+
+use crate::export::strip_extension;
+use crate::sprite::Sprite;
+
+/// Turns a frame name such as `hero/walk 01.png` into an enum member name such as `HeroWalk01`.
+pub(crate) fn member(name: &str) -> String {
+    let member: String = strip_extension(name)
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    if member.starts_with(|c: char| c.is_ascii_digit()) || member.is_empty() {
+        format!("_{}", member)
+    } else {
+        member
+    }
+}
+
+/// Returns `name`, or `name` with the first free numeric suffix such as `_2` if it is taken.
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    let name = if taken.contains(&name) {
+        (2..)
+            .map(|suffix| format!("{}_{}", name, suffix))
+            .find(|candidate| !taken.contains(candidate))
+            .expect("some suffix is free")
+    } else {
+        name
+    };
+    taken.insert(name.clone());
+    name
+}
+
+/// Builds a TypeScript module with a `const` map of frames keyed by name without extension,
+/// a `SpriteName` type of its keys and, when requested, a string enum of the same names.
+///
+/// Keys and enum members that would collide, such as those of `a.png` and `a.gif`, get a
+/// numeric suffix in frame order, so the second becomes `a_2`.
+pub(crate) fn module(sprite: &Sprite, image: &str, with_enum: bool) -> String {
+    let mut ts = format!(
+        "export const image = {};\n\nexport const sprites = {{\n",
+        json!(image)
+    );
+
+    let mut taken = HashSet::new();
+    let keys: Vec<String> = sprite
+        .get_frames()
+        .iter()
+        .map(|frame| unique(strip_extension(frame.get_name()).to_string(), &mut taken))
+        .collect();
+    for (frame, key) in sprite.get_frames().iter().zip(&keys) {
+        let _ = writeln!(
+            ts,
+            "  {}: {{ x: {}, y: {}, w: {}, h: {} }},",
+            json!(key),
+            frame.get_x(),
+            frame.get_y(),
+            frame.get_width(),
+            frame.get_height(),
+        );
+    }
+
+    ts.push_str("} as const;\n\nexport type SpriteName = keyof typeof sprites;\n");
+
+    if with_enum {
+        ts.push_str("\nexport enum Sprites {\n");
+        let mut taken = HashSet::new();
+        for key in &keys {
+            let _ = writeln!(
+                ts,
+                "  {} = {},",
+                unique(member(key), &mut taken),
+                json!(key)
+            );
+        }
+        ts.push_str("}\n");
+    }
+
+    ts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_member() {
+        assert_eq!(member("hero/walk 01.png"), "HeroWalk01");
+        assert_eq!(member("save.png"), "Save");
+        assert_eq!(member("2x/icon"), "_2xIcon");
+        assert_eq!(member("--.png"), "_");
+    }
+
+    #[test]
+    fn test_module() {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("save.png", 0, 0, 10, 20);
+        sprite.add_named_frame("icons/\"open\".png", 10, 5, 20, 15);
+
+        let ts = module(&sprite, "sheet.png", false);
+        assert!(ts.starts_with("export const image = \"sheet.png\";"));
+        assert!(ts.contains("  \"save\": { x: 0, y: 0, w: 10, h: 20 },\n"));
+        assert!(ts.contains("  \"icons/\\\"open\\\"\": { x: 10, y: 5, w: 20, h: 15 },\n"));
+        assert!(ts.contains("} as const;"));
+        assert!(ts.contains("export type SpriteName = keyof typeof sprites;"));
+        assert!(!ts.contains("enum"));
+
+        let ts = module(&sprite, "sheet.png", true);
+        assert!(ts.contains(
+            "export enum Sprites {\n  Save = \"save\",\n  IconsOpen = \"icons/\\\"open\\\"\",\n}"
+        ));
+    }
+
+    #[test]
+    fn test_module_collisions() {
+        let mut sprite = Sprite::new(RgbaImage::new(40, 10));
+        sprite.add_named_frame("a.png", 0, 0, 10, 10);
+        sprite.add_named_frame("a.gif", 10, 0, 10, 10);
+        sprite.add_named_frame("hero/walk.png", 20, 0, 10, 10);
+        sprite.add_named_frame("hero_walk.png", 30, 0, 10, 10);
+
+        let ts = module(&sprite, "sheet.png", true);
+        assert!(ts.contains("  \"a\": { x: 0,"));
+        assert!(ts.contains("  \"a_2\": { x: 10,"));
+        assert!(ts.contains(
+            "  A = \"a\",\n  A2 = \"a_2\",\n  HeroWalk = \"hero/walk\",\n  HeroWalk_2 = \"hero_walk\",\n"
+        ));
+    }
+}
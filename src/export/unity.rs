@@ -0,0 +1,149 @@
+use serde_json::{json, Value};
+
+use crate::export::libgdx::bottom_left_offset;
+use crate::export::{strip_extension, APP, VERSION};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Options for the Unity sprite metadata exporter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnityOptions {
+    pixels_per_unit: f64,
+    pivot: (f64, f64),
+}
+
+impl Default for UnityOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnityOptions {
+    /// Creates options with Unity's defaults: 100 pixels per unit and centered pivots.
+    pub fn new() -> Self {
+        Self {
+            pixels_per_unit: 100.0,
+            pivot: (0.5, 0.5),
+        }
+    }
+
+    /// Sets how many pixels of the sheet make up one world unit.
+    pub fn with_pixels_per_unit(mut self, pixels_per_unit: f64) -> Self {
+        self.pixels_per_unit = pixels_per_unit;
+        self
+    }
+
+    /// Sets the pivot of sprites without their own, see `Frame::get_pivot`, normalized to
+    /// the untrimmed image with the origin at its bottom-left corner.
+    pub fn with_pivot(mut self, x: f64, y: f64) -> Self {
+        self.pivot = (x, y);
+        self
+    }
+}
+
+/// Converts the pivot from the untrimmed image to the trimmed rect, so that trimmed sprites
+/// stay in place relative to their pivot.
+fn frame_pivot(frame: &Frame, pivot: (f64, f64)) -> (f64, f64) {
+    let (offset_x, offset_y) = bottom_left_offset(frame);
+    let convert = |pivot: f64, source: u32, offset: u32, size: u32| {
+        if size == 0 {
+            pivot
+        } else {
+            (pivot * source as f64 - offset as f64) / size as f64
+        }
+    };
+
+    (
+        convert(
+            pivot.0,
+            frame.get_source_width(),
+            offset_x,
+            frame.get_width(),
+        ),
+        convert(
+            pivot.1,
+            frame.get_source_height(),
+            offset_y,
+            frame.get_height(),
+        ),
+    )
+}
+
+/// Builds the sprite metadata, with rects in Unity's bottom-left pixel space.
+pub(crate) fn sprite_metadata(sprite: &Sprite, image: &str, options: &UnityOptions) -> Value {
+    let (width, height) = sprite.get_image().dimensions();
+
+    let sprites: Vec<Value> = sprite
+        .get_frames()
+        .iter()
+        .map(|frame| {
+            let pivot = frame
+                .get_pivot()
+                .map_or(options.pivot, |(x, y)| (x, 1.0 - y));
+            let (pivot_x, pivot_y) = frame_pivot(frame, pivot);
+            json!({
+                "name": strip_extension(frame.get_name()),
+                "rect": {
+                    "x": frame.get_x(),
+                    "y": height.saturating_sub(frame.get_y()).saturating_sub(frame.get_height()),
+                    "width": frame.get_width(),
+                    "height": frame.get_height(),
+                },
+                "pivot": { "x": pivot_x, "y": pivot_y },
+            })
+        })
+        .collect();
+
+    json!({
+        "app": APP,
+        "version": VERSION,
+        "texture": image,
+        "width": width,
+        "height": height,
+        "pixelsPerUnit": options.pixels_per_unit,
+        "sprites": sprites,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_sprite_metadata() {
+        let sprite = Sprite::with_frames(
+            RgbaImage::new(64, 32),
+            vec![
+                Frame::named("hero/walk.png", 4, 6, 10, 12).with_source(20, 20, 2, 3),
+                Frame::named("idle.png", 20, 0, 8, 8),
+                Frame::named("top.png", 28, 0, 8, 8).with_pivot(Some((0.25, 0.0))),
+            ],
+        );
+        let document = sprite_metadata(
+            &sprite,
+            "sheet.png",
+            &UnityOptions::new().with_pixels_per_unit(32.0),
+        );
+
+        assert_eq!(document["texture"], "sheet.png");
+        assert_eq!(document["pixelsPerUnit"], 32.0);
+        let walk = &document["sprites"][0];
+        assert_eq!(walk["name"], "hero/walk");
+        assert_eq!(
+            walk["rect"],
+            json!({ "x": 4, "y": 14, "width": 10, "height": 12 })
+        );
+        // The untrimmed center (10, 10) lies 8 pixels right of the rect and 5 pixels above it.
+        assert_eq!(walk["pivot"], json!({ "x": 0.8, "y": 5.0 / 12.0 }));
+        assert_eq!(
+            document["sprites"][1]["pivot"],
+            json!({ "x": 0.5, "y": 0.5 })
+        );
+        assert_eq!(document["sprites"][1]["rect"]["y"], 24);
+        assert_eq!(
+            document["sprites"][2]["pivot"],
+            json!({ "x": 0.25, "y": 1.0 })
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::export::bmfont::{self, BmFontOptions};
+use crate::export::css::CssOptions;
+use crate::export::unity::UnityOptions;
+use crate::export::{check_page_count, easeljs, godot, libgdx, phaser, pixi, spine, spritekit};
+use crate::metadata::export_metadata;
+use crate::sprite::Sprite;
+
+/// Where exporters write their files and how they refer to the saved sheets.
+#[derive(Debug, Clone)]
+pub struct ExportContext {
+    dir: PathBuf,
+    name: String,
+    images: Option<Vec<String>>,
+}
+
+impl ExportContext {
+    /// Creates a context writing files named after `name` into `dir`.
+    ///
+    /// # Arguments
+    /// - `dir`: Directory the exported files are written to.
+    /// - `name`: Base name of the exported files, such as `atlas` for `atlas.json`.
+    pub fn new(dir: impl AsRef<Path>, name: &str) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            name: name.to_string(),
+            images: None,
+        }
+    }
+
+    /// Sets the paths of the saved sheets as referenced from exported metadata, one per sheet.
+    ///
+    /// Defaults to the PNG file names `file_name` gives each sheet.
+    pub fn with_images(mut self, images: Vec<String>) -> Self {
+        self.images = Some(images);
+        self
+    }
+
+    pub fn get_dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the file name of one sheet's output, such as `atlas.json` for a single sheet
+    /// or `atlas-1.json` for the second of several.
+    ///
+    /// # Arguments
+    /// - `index`: Index of the sheet.
+    /// - `sheets`: Number of sheets being exported.
+    /// - `extension`: Extension of the file, without the leading dot.
+    pub fn file_name(&self, index: usize, sheets: usize, extension: &str) -> String {
+        if sheets == 1 {
+            format!("{}.{}", self.name, extension)
+        } else {
+            format!("{}-{}.{}", self.name, index, extension)
+        }
+    }
+
+    /// Returns the path of a file in the output directory.
+    pub fn path(&self, file_name: &str) -> Result<String, Box<dyn Error>> {
+        let path = self.dir.join(file_name);
+        path.to_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("Output path {} is not valid UTF-8.", path.display()).into())
+    }
+
+    /// Returns the path of each of `sheets` saved sheets as referenced from exported metadata.
+    pub fn images(&self, sheets: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        match &self.images {
+            Some(images) => {
+                let paths: Vec<&str> = images.iter().map(String::as_str).collect();
+                check_page_count(sheets, &paths)?;
+                Ok(images.clone())
+            }
+            None => Ok((0..sheets)
+                .map(|index| self.file_name(index, sheets, "png"))
+                .collect()),
+        }
+    }
+}
+
+/// An output format that writes the metadata of generated sheets.
+///
+/// Implement this trait and add the exporter to an `ExporterRegistry` to make a custom format
+/// selectable by name alongside the built-in ones.
+///
+/// Closures taking the sheets and the context implement this trait.
+pub trait Exporter: Send + Sync {
+    /// Writes the metadata of `sprites`.
+    ///
+    /// # Arguments
+    /// - `sprites`: The generated sheets, as returned by `Spriterator::generate`.
+    /// - `ctx`: Where to write files and how to refer to the saved sheets.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or failure.
+    fn export(&self, sprites: &[Sprite], ctx: &ExportContext) -> Result<(), Box<dyn Error>>;
+}
+
+impl<F> Exporter for F
+where
+    F: Fn(&[Sprite], &ExportContext) -> Result<(), Box<dyn Error>> + Send + Sync,
+{
+    fn export(&self, sprites: &[Sprite], ctx: &ExportContext) -> Result<(), Box<dyn Error>> {
+        self(sprites, ctx)
+    }
+}
+
+impl fmt::Debug for dyn Exporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Exporter")
+    }
+}
+
+/// Writes the metadata of one sheet, given the file path and the sheet's image path.
+type SheetExport = fn(&Sprite, &str, &str) -> Result<(), Box<dyn Error>>;
+
+/// Writes the metadata of several sheets, given the file path, the sheets and their image paths.
+type MultiPageExport = fn(&str, &[Sprite], &[&str]) -> Result<(), Box<dyn Error>>;
+
+/// Writes one file per sheet with `export`.
+fn per_sheet(extension: &'static str, export: SheetExport) -> impl Exporter {
+    move |sprites: &[Sprite], ctx: &ExportContext| {
+        let images = ctx.images(sprites.len())?;
+        for (index, (sprite, image)) in sprites.iter().zip(&images).enumerate() {
+            let path = ctx.path(&ctx.file_name(index, sprites.len(), extension))?;
+            export(sprite, &path, image)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a single file describing every sheet with `export`.
+fn multi_page(extension: &'static str, export: MultiPageExport) -> impl Exporter {
+    move |sprites: &[Sprite], ctx: &ExportContext| {
+        let images = ctx.images(sprites.len())?;
+        let images: Vec<&str> = images.iter().map(String::as_str).collect();
+        let path = ctx.path(&format!("{}.{}", ctx.get_name(), extension))?;
+        export(&path, sprites, &images)
+    }
+}
+
+/// A set of exporters selectable by name.
+#[derive(Debug)]
+pub struct ExporterRegistry {
+    exporters: Vec<(String, Box<dyn Exporter>)>,
+}
+
+impl Default for ExporterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExporterRegistry {
+    /// Creates a registry holding the built-in exporters, with default options.
+    ///
+    /// See `names` for the available exporters. Formats sharing a file extension use
+    /// compound extensions such as `array.json` so that several can be exported at once.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register("native", multi_page("meta.json", export_metadata));
+        registry.register("json-hash", per_sheet("json", Sprite::export_json_hash));
+        registry.register(
+            "json-array",
+            per_sheet("array.json", Sprite::export_json_array),
+        );
+        registry.register(
+            "csv",
+            per_sheet("csv", |sprite, path, _| sprite.export_csv(path)),
+        );
+        registry.register(
+            "tsv",
+            per_sheet("tsv", |sprite, path, _| sprite.export_tsv(path)),
+        );
+        registry.register(
+            "css",
+            per_sheet("css", |sprite, path, image| {
+                sprite.export_css(path, image, &CssOptions::new())
+            }),
+        );
+        registry.register("scss", per_sheet("scss", Sprite::export_scss));
+        registry.register("less", per_sheet("less", Sprite::export_less));
+        registry.register(
+            "typescript",
+            per_sheet("ts", |sprite, path, image| {
+                sprite.export_typescript(path, image, true)
+            }),
+        );
+        registry.register(
+            "preview-html",
+            per_sheet("html", |sprite, path, _| sprite.export_preview_html(path)),
+        );
+        registry.register("sparrow", per_sheet("xml", Sprite::export_sparrow_xml));
+        registry.register("cocos", per_sheet("plist", Sprite::export_cocos_plist));
+        registry.register(
+            "aseprite",
+            per_sheet("aseprite.json", |sprite, path, image| {
+                sprite.export_aseprite_json(path, image, 100)
+            }),
+        );
+        registry.register(
+            "paper2d",
+            per_sheet("paper2d.json", |sprite, path, image| {
+                sprite.export_paper2d_json(path, image, (0.5, 0.5))
+            }),
+        );
+        registry.register(
+            "unity",
+            per_sheet("unity.json", |sprite, path, image| {
+                sprite.export_unity_json(path, image, &UnityOptions::new())
+            }),
+        );
+        #[cfg(feature = "yaml")]
+        registry.register("yaml", per_sheet("yaml", Sprite::export_yaml));
+        #[cfg(feature = "toml")]
+        registry.register("toml", per_sheet("toml", Sprite::export_toml));
+        registry.register("libgdx", multi_page("atlas", libgdx::export_libgdx_atlas));
+        registry.register("spine", multi_page("atlas.txt", spine::export_spine_atlas));
+        registry.register(
+            "phaser",
+            multi_page("multiatlas.json", phaser::export_phaser_multiatlas),
+        );
+        registry.register(
+            "spritekit",
+            multi_page("atlasc.plist", spritekit::export_spritekit_atlasc),
+        );
+        registry.register(
+            "easeljs",
+            multi_page("easeljs.json", |path, sprites, images| {
+                easeljs::export_easeljs_spritesheet(path, sprites, images, 30.0)
+            }),
+        );
+        registry.register(
+            "godot",
+            multi_page("tres", |path, sprites, images| {
+                godot::export_godot_sprite_frames(path, sprites, images, 5.0)
+            }),
+        );
+        registry.register("godot-atlas", |sprites: &[Sprite], ctx: &ExportContext| {
+            let dir = ctx.path("")?;
+            for (sprite, image) in sprites.iter().zip(ctx.images(sprites.len())?) {
+                sprite.export_godot_atlas_textures(&dir, &image)?;
+            }
+            Ok(())
+        });
+        registry.register("pixi", |sprites: &[Sprite], ctx: &ExportContext| {
+            let paths = (0..sprites.len())
+                .map(|index| ctx.path(&ctx.file_name(index, sprites.len(), "pixi.json")))
+                .collect::<Result<Vec<String>, _>>()?;
+            let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+            let images = ctx.images(sprites.len())?;
+            let images: Vec<&str> = images.iter().map(String::as_str).collect();
+            pixi::export_pixi_multipack(&paths, sprites, &images)
+        });
+        registry.register("tiled", |sprites: &[Sprite], ctx: &ExportContext| {
+            let images = ctx.images(sprites.len())?;
+            for (index, (sprite, image)) in sprites.iter().zip(&images).enumerate() {
+                let path = ctx.path(&ctx.file_name(index, sprites.len(), "tsx"))?;
+                sprite.export_tiled_tsx(&path, image, ctx.get_name())?;
+            }
+            Ok(())
+        });
+        // Glyphs are as high as the tallest untrimmed glyph.
+        registry.register("bmfont", |sprites: &[Sprite], ctx: &ExportContext| {
+            let size = sprites
+                .iter()
+                .flat_map(Sprite::get_frames)
+                .map(|frame| frame.get_source_height())
+                .max()
+                .unwrap_or(0);
+            let images = ctx.images(sprites.len())?;
+            let images: Vec<&str> = images.iter().map(String::as_str).collect();
+            let path = ctx.path(&format!("{}.fnt", ctx.get_name()))?;
+            bmfont::export_bmfont(
+                &path,
+                sprites,
+                &images,
+                &BmFontOptions::new(ctx.get_name(), size),
+            )
+        });
+        registry.register(
+            "bevy-ron",
+            per_sheet("ron", |sprite, path, _| sprite.export_bevy_ron(path)),
+        );
+        registry.register("bevy-rust", |sprites: &[Sprite], ctx: &ExportContext| {
+            for (index, sprite) in sprites.iter().enumerate() {
+                let file_name = ctx.file_name(index, sprites.len(), "rs");
+                let name = file_name.trim_end_matches(".rs");
+                sprite.export_bevy_rust(&ctx.path(&file_name)?, name)?;
+            }
+            Ok(())
+        });
+        registry
+    }
+
+    /// Creates a registry without any exporters.
+    pub fn empty() -> Self {
+        Self {
+            exporters: Vec::new(),
+        }
+    }
+
+    /// Adds an exporter, replacing any exporter already registered under `name`.
+    ///
+    /// # Arguments
+    /// - `name`: Name the exporter is selected by.
+    /// - `exporter`: The exporter.
+    pub fn register(&mut self, name: &str, exporter: impl Exporter + 'static) {
+        let exporter: Box<dyn Exporter> = Box::new(exporter);
+        match self.exporters.iter_mut().find(|(known, _)| known == name) {
+            Some(entry) => entry.1 = exporter,
+            None => self.exporters.push((name.to_string(), exporter)),
+        }
+    }
+
+    /// Returns the names of the registered exporters, in registration order.
+    pub fn names(&self) -> Vec<&str> {
+        self.exporters
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Looks up an exporter by name.
+    pub fn get(&self, name: &str) -> Option<&dyn Exporter> {
+        self.exporters
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, exporter)| exporter.as_ref())
+    }
+
+    /// Runs the exporter registered under `name`.
+    ///
+    /// # Arguments
+    /// - `name`: Name of the exporter.
+    /// - `sprites`: The generated sheets, as returned by `Spriterator::generate`.
+    /// - `ctx`: Where to write files and how to refer to the saved sheets.
+    ///
+    /// # Returns
+    /// A `Result` indicating success, or an error if the name is unknown or exporting fails.
+    pub fn export(
+        &self,
+        name: &str,
+        sprites: &[Sprite],
+        ctx: &ExportContext,
+    ) -> Result<(), Box<dyn Error>> {
+        let exporter = self.get(name).ok_or_else(|| {
+            format!(
+                "Unknown exporter \"{}\". Available exporters: {}.",
+                name,
+                self.names().join(", ")
+            )
+        })?;
+        exporter.export(sprites, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rect::Rect;
+    use image::RgbaImage;
+    use std::fs;
+
+    fn sprites(name: &str) -> Vec<Sprite> {
+        (0..2)
+            .map(|_| {
+                let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+                sprite.add_named_frame(name, 0, 0, 10, 20);
+                sprite.set_layout((30, 20), vec![Rect::new(0, 0, 10, 20)]);
+                sprite
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_context() {
+        let ctx = ExportContext::new("out", "atlas");
+        assert_eq!(ctx.file_name(0, 1, "json"), "atlas.json");
+        assert_eq!(ctx.file_name(1, 2, "json"), "atlas-1.json");
+        assert_eq!(ctx.images(2).unwrap(), vec!["atlas-0.png", "atlas-1.png"]);
+
+        let ctx = ctx.with_images(vec!["sheet.webp".to_string()]);
+        assert_eq!(ctx.images(1).unwrap(), vec!["sheet.webp"]);
+        assert!(ctx.images(2).is_err());
+    }
+
+    #[test]
+    fn test_built_in_exporters() {
+        let registry = ExporterRegistry::new();
+        let mut expected = vec![
+            "native",
+            "json-hash",
+            "json-array",
+            "csv",
+            "tsv",
+            "css",
+            "scss",
+            "less",
+            "typescript",
+            "preview-html",
+            "sparrow",
+            "cocos",
+            "aseprite",
+            "paper2d",
+            "unity",
+        ];
+        #[cfg(feature = "yaml")]
+        expected.push("yaml");
+        #[cfg(feature = "toml")]
+        expected.push("toml");
+        expected.extend([
+            "libgdx",
+            "spine",
+            "phaser",
+            "spritekit",
+            "easeljs",
+            "godot",
+            "godot-atlas",
+            "pixi",
+            "tiled",
+            "bmfont",
+            "bevy-ron",
+            "bevy-rust",
+        ]);
+        assert_eq!(registry.names(), expected);
+
+        let dir = std::env::temp_dir().join("spriterator_test_built_in_exporters");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let ctx = ExportContext::new(&dir, "atlas");
+        for name in expected {
+            // Glyphs are named by their codepoint.
+            let frame = if name == "bmfont" { "65.png" } else { "a.png" };
+            registry
+                .export(name, &sprites(frame), &ctx)
+                .unwrap_or_else(|error| panic!("{} failed: {}", name, error));
+        }
+        assert!(dir.join("atlas-1.tsx").exists());
+        assert!(dir.join("atlas-1.pixi.json").exists());
+        assert!(dir.join("atlas.fnt").exists());
+        assert!(dir.join("a.tres").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_registry() {
+        let dir = std::env::temp_dir().join("spriterator_test_exporter_registry");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let ctx = ExportContext::new(&dir, "atlas");
+
+        let mut registry = ExporterRegistry::new();
+        assert!(registry.names().contains(&"json-hash"));
+        registry
+            .export("json-hash", &sprites("a.png"), &ctx)
+            .unwrap();
+        registry.export("libgdx", &sprites("a.png"), &ctx).unwrap();
+        assert!(dir.join("atlas-0.json").exists());
+        assert!(dir.join("atlas-1.json").exists());
+        assert!(fs::read_to_string(dir.join("atlas.atlas"))
+            .unwrap()
+            .contains("atlas-1.png"));
+
+        let error = registry
+            .export("custom", &sprites("a.png"), &ctx)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Unknown exporter \"custom\"."));
+
+        registry.register("custom", |sprites: &[Sprite], ctx: &ExportContext| {
+            let path = ctx.path("frames.txt")?;
+            fs::write(path, sprites.len().to_string())?;
+            Ok(())
+        });
+        registry.export("custom", &sprites("a.png"), &ctx).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("frames.txt")).unwrap(), "2");
+        assert_eq!(registry.names().last(), Some(&"custom"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
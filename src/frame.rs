@@ -1,34 +1,153 @@
-#[derive(Debug)]
+use crate::rect::Rect;
+
+#[derive(Debug, Clone)]
 pub struct Frame {
-    x: u32,
-    y: u32,
-    width: u32,
-    height: u32,
+    name: String,
+    rect: Rect,
+    trimmed: bool,
+    source_width: u32,
+    source_height: u32,
+    offset_x: u32,
+    offset_y: u32,
+    channel: Option<u8>,
+    duration: Option<u32>,
+    pivot: Option<(f64, f64)>,
 }
 
 impl Frame {
+    /// Creates an unnamed frame, see `named`.
     pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self::named("", x, y, width, height)
+    }
+
+    /// Creates a frame named after its source image, as written to the exported metadata.
+    pub fn named(name: &str, x: u32, y: u32, width: u32, height: u32) -> Self {
         Self {
-            x,
-            y,
-            width,
-            height,
+            name: name.to_string(),
+            rect: Rect::new(x, y, width, height),
+            trimmed: false,
+            source_width: width,
+            source_height: height,
+            offset_x: 0,
+            offset_y: 0,
+            channel: None,
+            duration: None,
+            pivot: None,
         }
     }
 
+    /// Marks the frame as trimmed from a `source_width`×`source_height` image,
+    /// with the kept area starting at `offset_x`, `offset_y` within it.
+    pub(crate) fn with_source(
+        mut self,
+        source_width: u32,
+        source_height: u32,
+        offset_x: u32,
+        offset_y: u32,
+    ) -> Self {
+        self.trimmed = true;
+        self.source_width = source_width;
+        self.source_height = source_height;
+        self.offset_x = offset_x;
+        self.offset_y = offset_y;
+        self
+    }
+
+    /// Moves the frame to `x`, `y` on its sheet.
+    pub(crate) fn at(mut self, x: u32, y: u32) -> Self {
+        self.rect.x = x;
+        self.rect.y = y;
+        self
+    }
+
+    /// Sets how long the frame is shown in milliseconds.
+    pub(crate) fn with_duration(mut self, duration: Option<u32>) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the pivot of the frame, normalized to its untrimmed source image.
+    pub(crate) fn with_pivot(mut self, pivot: Option<(f64, f64)>) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    /// Marks the frame as a mask stored in a single channel of the sheet.
+    pub(crate) fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Returns the name of the frame, the path of its source image relative to the input directory.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
     pub fn get_x(&self) -> u32 {
-        self.x
+        self.rect.x
     }
 
     pub fn get_y(&self) -> u32 {
-        self.y
+        self.rect.y
     }
 
     pub fn get_width(&self) -> u32 {
-        self.width
+        self.rect.w
     }
 
     pub fn get_height(&self) -> u32 {
-        self.height
+        self.rect.h
+    }
+
+    /// Returns `true` if transparent borders were removed from the source image.
+    pub fn is_trimmed(&self) -> bool {
+        self.trimmed
+    }
+
+    /// Returns the width of the source image before trimming.
+    pub fn get_source_width(&self) -> u32 {
+        self.source_width
+    }
+
+    /// Returns the height of the source image before trimming.
+    pub fn get_source_height(&self) -> u32 {
+        self.source_height
+    }
+
+    /// Returns the horizontal offset of the packed area within the source image.
+    pub fn get_offset_x(&self) -> u32 {
+        self.offset_x
+    }
+
+    /// Returns the vertical offset of the packed area within the source image.
+    pub fn get_offset_y(&self) -> u32 {
+        self.offset_y
+    }
+
+    /// Returns the channel holding the frame in channel packing mode:
+    /// `0` for red, `1` for green, `2` for blue and `3` for alpha.
+    pub fn get_channel(&self) -> Option<u8> {
+        self.channel
+    }
+
+    /// Returns how long the frame is shown in milliseconds, for frames of animated source images.
+    pub fn get_duration(&self) -> Option<u32> {
+        self.duration
+    }
+
+    /// Returns the pivot set for the frame's source image, normalized to the untrimmed image
+    /// with the origin at the top-left corner. Exporters with a pivot option use it instead.
+    pub fn get_pivot(&self) -> Option<(f64, f64)> {
+        self.pivot
+    }
+
+    /// Returns the area the frame occupies on the sheet.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Returns the center of the frame on the sheet.
+    pub fn center(&self) -> (f32, f32) {
+        self.rect.center()
     }
 }
@@ -0,0 +1,368 @@
+use image::RgbaImage;
+use std::error::Error;
+use std::future::Future;
+use std::sync::{mpsc, Arc};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
+
+/// Copies the sheet texture into a buffer of packed RGBA pixels, zeroing the color of fully
+/// transparent pixels unless `keep_hidden` is set for channel-packed masks, then extrudes the
+/// edges of every frame outward by `extrude` pixels.
+const SHADER: &str = r"
+struct Params {
+    width: u32,
+    height: u32,
+    keep_hidden: u32,
+    extrude: u32,
+}
+
+struct Frame {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+@group(0) @binding(0) var sheet: texture_2d<u32>;
+@group(0) @binding(1) var<storage, read_write> pixels: array<u32>;
+@group(0) @binding(2) var<uniform> params: Params;
+@group(0) @binding(3) var<storage, read> frames: array<Frame>;
+
+fn load(position: vec2<u32>) -> u32 {
+    let color = textureLoad(sheet, position, 0);
+    if color.a == 0u && params.keep_hidden == 0u {
+        return 0u;
+    }
+    return color.r | (color.g << 8u) | (color.b << 16u) | (color.a << 24u);
+}
+
+@compute @workgroup_size(8, 8)
+fn copy(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    pixels[id.y * params.width + id.x] = load(id.xy);
+}
+
+// Runs once per pixel of the extruded area of frame `id.z`, repeating its nearest edge pixel.
+@compute @workgroup_size(8, 8)
+fn extrude(@builtin(global_invocation_id) id: vec3<u32>) {
+    let frame = frames[id.z];
+    let x = frame.x - min(frame.x, params.extrude) + id.x;
+    let y = frame.y - min(frame.y, params.extrude) + id.y;
+    let right = frame.x + frame.width;
+    let bottom = frame.y + frame.height;
+    if x >= min(right + params.extrude, params.width)
+        || y >= min(bottom + params.extrude, params.height) {
+        return;
+    }
+    if x >= frame.x && x < right && y >= frame.y && y < bottom {
+        return;
+    }
+    let source = vec2<u32>(clamp(x, frame.x, right - 1u), clamp(y, frame.y, bottom - 1u));
+    pixels[y * params.width + x] = load(source);
+}
+";
+
+/// Side of the square workgroups of the shader, in pixels.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Composes sheets on a GPU: frames are uploaded into their place in a texture, which is
+/// then extruded and read back.
+#[derive(Debug)]
+pub(crate) struct GpuCompositor {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    bind_group_layout: wgpu::BindGroupLayout,
+    copy: wgpu::ComputePipeline,
+    extrude: wgpu::ComputePipeline,
+    limits: wgpu::Limits,
+}
+
+impl GpuCompositor {
+    /// Opens the default GPU adapter.
+    ///
+    /// # Returns
+    /// The compositor, or a description of why no suitable adapter could be opened.
+    pub(crate) fn new() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .map_err(|error| error.to_string())?;
+        let limits = adapter.limits();
+        let (device, queue) = block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("spriterator"),
+            required_limits: limits.clone(),
+            ..Default::default()
+        }))
+        .map_err(|error| error.to_string())?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("spriterator compose"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let buffer = |ty| wgpu::BindingType::Buffer {
+            ty,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("spriterator compose"),
+            entries: &[
+                entry(
+                    0,
+                    wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                ),
+                entry(
+                    1,
+                    buffer(wgpu::BufferBindingType::Storage { read_only: false }),
+                ),
+                entry(2, buffer(wgpu::BufferBindingType::Uniform)),
+                entry(
+                    3,
+                    buffer(wgpu::BufferBindingType::Storage { read_only: true }),
+                ),
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("spriterator compose"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("spriterator compose"),
+                layout: Some(&layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let (copy, extrude) = (pipeline("copy"), pipeline("extrude"));
+
+        Ok(Self {
+            device,
+            queue,
+            bind_group_layout,
+            copy,
+            extrude,
+            limits,
+        })
+    }
+
+    /// Places each image at its position on a transparent `width`×`height` sheet, then repeats
+    /// the edge pixels of each image outward by `extrude` pixels.
+    ///
+    /// Images are copied like `blit` does on the CPU, which is what blending onto a transparent
+    /// sheet amounts to as the images do not overlap. With `keep_hidden`, the color of fully
+    /// transparent pixels is kept too, as for channel-packed masks.
+    ///
+    /// # Returns
+    /// A `Result` containing the sheet, or `None` if it exceeds the limits of the adapter.
+    pub(crate) fn compose(
+        &self,
+        width: u32,
+        height: u32,
+        placements: &[(&RgbaImage, u32, u32)],
+        keep_hidden: bool,
+        extrude: u32,
+    ) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+        let bytes = u64::from(width) * u64::from(height) * 4;
+        if width == 0
+            || height == 0
+            || width.max(height) > self.limits.max_texture_dimension_2d
+            || bytes > u64::from(self.limits.max_storage_buffer_binding_size)
+            || bytes > self.limits.max_buffer_size
+            || placements.len() > self.limits.max_compute_workgroups_per_dimension as usize
+        {
+            return Ok(None);
+        }
+
+        // Frames are extruded within the sheet, like `extrude` does on the CPU.
+        let frames: Vec<[u32; 4]> = placements
+            .iter()
+            .filter(|(image, _, _)| image.width() > 0 && image.height() > 0)
+            .map(|&(image, x, y)| [x, y, image.width(), image.height()])
+            .collect();
+        let extruded = |frame: &[u32; 4], axis: usize| {
+            let start = frame[axis] - frame[axis].min(extrude);
+            let end = (frame[axis] + frame[axis + 2] + extrude).min([width, height][axis]);
+            end - start
+        };
+        let extrude_size = frames.iter().fold((0, 0), |(x, y), frame| {
+            (x.max(extruded(frame, 0)), y.max(extruded(frame, 1)))
+        });
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("spriterator sheet"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Uint,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        for &(image, x, y) in placements {
+            if image.width() == 0 || image.height() == 0 {
+                continue;
+            }
+            self.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                image.as_raw(),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(image.width() * 4),
+                    rows_per_image: Some(image.height()),
+                },
+                wgpu::Extent3d {
+                    width: image.width(),
+                    height: image.height(),
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let pixels = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("spriterator pixels"),
+            size: bytes,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("spriterator readback"),
+            size: bytes,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("spriterator params"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params_data: Vec<u8> = [width, height, u32::from(keep_hidden), extrude]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        self.queue.write_buffer(&params, 0, &params_data);
+        // Bindings cannot be empty, so a sheet without frames still binds one.
+        let frames_data: Vec<u8> = frames
+            .iter()
+            .chain(frames.is_empty().then_some(&[0; 4]))
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let frames_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("spriterator frames"),
+            size: frames_data.len() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&frames_buffer, 0, &frames_data);
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("spriterator compose"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: pixels.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: frames_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.set_pipeline(&self.copy);
+            pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+            if extrude > 0 && !frames.is_empty() {
+                pass.set_pipeline(&self.extrude);
+                pass.dispatch_workgroups(
+                    extrude_size.0.div_ceil(WORKGROUP_SIZE),
+                    extrude_size.1.div_ceil(WORKGROUP_SIZE),
+                    frames.len() as u32,
+                );
+            }
+        }
+        encoder.copy_buffer_to_buffer(&pixels, 0, &readback, 0, bytes);
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        readback.map_async(wgpu::MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait)?;
+        receiver.recv()??;
+
+        let data = readback.get_mapped_range(..).to_vec();
+        readback.unmap();
+        Ok(RgbaImage::from_raw(width, height, data))
+    }
+}
+
+/// Wakes a thread parked in `block_on`.
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs `future` to completion on the current thread, parking it until the future is woken.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        std::thread::park();
+    }
+}
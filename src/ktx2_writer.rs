@@ -0,0 +1,491 @@
+#[cfg(feature = "basis")]
+use basis_universal::{BasisTextureFormat, ColorSpace, Compressor, CompressorParams, Transcoder};
+use image::RgbaImage;
+use ktx2::{
+    ChannelTypeQualifiers, ColorModel, ColorPrimaries, DataFormatFlags, DfdBlockHeaderBasic,
+    DfdHeader, Format, Header, Index, LevelIndex, SampleInformation, SupercompressionScheme,
+    TransferFunction,
+};
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+use std::error::Error;
+use std::num::NonZeroU8;
+
+use crate::block_compression::{compress, BlockCompression};
+use crate::output_format::Ktx2Options;
+
+/// Value of the `KTXwriter` key, naming the tool that wrote a file.
+const WRITER: &str = concat!("spriterator ", env!("CARGO_PKG_VERSION"));
+
+/// How texels of a Vulkan format are laid out, as described by the data format descriptor.
+struct Layout {
+    /// The Vulkan format, or `None` for formats transcoded at load time.
+    format: Option<Format>,
+    color_model: ColorModel,
+    /// Width and height of a texel block in pixels.
+    block_dimension: u8,
+    /// Number of bytes of a texel block.
+    block_bytes: u8,
+    samples: Vec<SampleInformation>,
+}
+
+/// Describes one channel of a texel block.
+///
+/// # Arguments
+/// - `bit_offset`: Offset of the channel within the block, in bits.
+/// - `bit_length`: Length of the channel, in bits.
+/// - `channel_type`: Channel identifier, whose meaning depends on the color model.
+/// - `upper`: Value of the channel representing 1.0.
+fn sample(bit_offset: u16, bit_length: u8, channel_type: u8, upper: u32) -> SampleInformation {
+    SampleInformation {
+        bit_offset,
+        bit_length: NonZeroU8::new(bit_length).expect("samples are not empty"),
+        channel_type,
+        channel_type_qualifiers: ChannelTypeQualifiers::empty(),
+        sample_positions: [0; 4],
+        lower: 0,
+        upper,
+    }
+}
+
+/// Describes the alpha channel of a texel block like `sample`. Alpha is always linear, so
+/// its sample is marked as such when the color channels are sRGB encoded.
+fn alpha_sample(bit_offset: u16, bit_length: u8, upper: u32, linear: bool) -> SampleInformation {
+    let mut alpha = sample(bit_offset, bit_length, 15, upper);
+    if !linear {
+        alpha.channel_type_qualifiers = ChannelTypeQualifiers::LINEAR;
+    }
+    alpha
+}
+
+/// Returns the layout of uncompressed RGBA8 texels, or of the given block compression, in
+/// an sRGB format unless `linear`.
+fn layout(compression: Option<BlockCompression>, linear: bool) -> Layout {
+    let format = |unorm, srgb| Some(if linear { unorm } else { srgb });
+    match compression {
+        None => Layout {
+            format: format(Format::R8G8B8A8_UNORM, Format::R8G8B8A8_SRGB),
+            color_model: ColorModel::RGBSDA,
+            block_dimension: 1,
+            block_bytes: 4,
+            samples: vec![
+                sample(0, 8, 0, 255),
+                sample(8, 8, 1, 255),
+                sample(16, 8, 2, 255),
+                alpha_sample(24, 8, 255, linear),
+            ],
+        },
+        Some(BlockCompression::Bc1) => Layout {
+            format: format(Format::BC1_RGBA_UNORM_BLOCK, Format::BC1_RGBA_SRGB_BLOCK),
+            color_model: ColorModel::BC1A,
+            block_dimension: 4,
+            block_bytes: 8,
+            samples: vec![sample(0, 64, 1, u32::MAX)],
+        },
+        Some(BlockCompression::Bc3) => Layout {
+            format: format(Format::BC3_UNORM_BLOCK, Format::BC3_SRGB_BLOCK),
+            color_model: ColorModel::BC3,
+            block_dimension: 4,
+            block_bytes: 16,
+            samples: vec![
+                alpha_sample(0, 64, u32::MAX, linear),
+                sample(64, 64, 0, u32::MAX),
+            ],
+        },
+        Some(BlockCompression::Bc7) => Layout {
+            format: format(Format::BC7_UNORM_BLOCK, Format::BC7_SRGB_BLOCK),
+            color_model: ColorModel::BC7,
+            block_dimension: 4,
+            block_bytes: 16,
+            samples: vec![sample(0, 128, 0, u32::MAX)],
+        },
+    }
+}
+
+/// Returns the layout of Basis Universal UASTC blocks of `image`, whose sample tells
+/// transcoders whether to keep the alpha channel.
+#[cfg(feature = "basis")]
+fn uastc_layout(image: &RgbaImage) -> Layout {
+    let alpha = image.pixels().any(|pixel| pixel[3] < 255);
+    Layout {
+        format: None,
+        color_model: ColorModel::UASTC,
+        block_dimension: 4,
+        block_bytes: 16,
+        samples: vec![sample(0, 128, if alpha { 3 } else { 0 }, u32::MAX)],
+    }
+}
+
+/// Encodes each level as Basis Universal UASTC blocks, in row-major block order, measuring
+/// the error of sRGB texels perceptually unless `linear`.
+#[cfg(feature = "basis")]
+fn uastc(levels: &[&RgbaImage], linear: bool) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    let mut params = CompressorParams::new();
+    params.set_basis_format(BasisTextureFormat::UASTC4x4);
+    params.set_color_space(if linear {
+        ColorSpace::Linear
+    } else {
+        ColorSpace::Srgb
+    });
+    for (index, image) in levels.iter().enumerate() {
+        params.source_image_mut(index as u32).init(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            4,
+        );
+    }
+
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let mut compressor = Compressor::new(threads as u32);
+    // The parameters hold whole RGBA8 images and the default quality levels.
+    unsafe {
+        if !compressor.init(&params) {
+            return Err("Failed to set up the Basis Universal encoder.".into());
+        }
+        compressor
+            .process()
+            .map_err(|error| format!("Failed to encode UASTC blocks: {:?}", error))?;
+    }
+
+    // Every level is a separate image of the .basis file, whose UASTC blocks are stored as is.
+    let basis = compressor.basis_file();
+    let transcoder = Transcoder::new();
+    (0..levels.len() as u32)
+        .map(|index| {
+            let info = transcoder
+                .image_level_info(basis, index, 0)
+                .ok_or("The Basis Universal encoder wrote an incomplete file.")?;
+            let start = info.m_rgb_file_ofs as usize;
+            Ok(basis[start..start + info.m_rgb_file_len as usize].to_vec())
+        })
+        .collect()
+}
+
+/// Returns the texels of each level, before supercompression.
+fn level_texels(
+    levels: &[&RgbaImage],
+    options: &Ktx2Options,
+) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    #[cfg(feature = "basis")]
+    if options.is_uastc() {
+        return uastc(levels, options.is_linear());
+    }
+
+    Ok(levels
+        .iter()
+        .map(|image| match options.get_compression() {
+            Some(compression) => compress(image, compression),
+            None => image.as_raw().clone(),
+        })
+        .collect())
+}
+
+/// Encodes mipmap levels as a KTX2 texture.
+///
+/// # Arguments
+/// - `levels`: The mipmap levels, the largest first.
+/// - `options`: Texel format and supercompression of the texture.
+///
+/// # Returns
+/// A `Result` containing the contents of the KTX2 file, or an error if UASTC encoding fails.
+pub(crate) fn encode_ktx2(
+    levels: &[&RgbaImage],
+    options: &Ktx2Options,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let first = levels[0];
+    #[cfg(feature = "basis")]
+    let layout = if options.is_uastc() {
+        uastc_layout(first)
+    } else {
+        layout(options.get_compression(), options.is_linear())
+    };
+    #[cfg(not(feature = "basis"))]
+    let layout = layout(options.get_compression(), options.is_linear());
+    // Each level holds its stored bytes and the length of its texels once inflated.
+    let levels: Vec<(Vec<u8>, usize)> = level_texels(levels, options)?
+        .into_iter()
+        .map(|texels| {
+            let length = texels.len();
+            if options.is_zstd() {
+                (
+                    compress_to_vec(texels.as_slice(), CompressionLevel::Fastest),
+                    length,
+                )
+            } else {
+                (texels, length)
+            }
+        })
+        .collect();
+
+    // Supercompressed levels have no fixed number of bytes per block.
+    let bytes_plane = if options.is_zstd() {
+        0
+    } else {
+        layout.block_bytes
+    };
+    let dimension = NonZeroU8::new(layout.block_dimension).expect("blocks are not empty");
+    let block_size = DfdHeader::LENGTH
+        + DfdBlockHeaderBasic::LENGTH
+        + SampleInformation::LENGTH * layout.samples.len();
+    let mut dfd = Vec::new();
+    dfd.extend_from_slice(&(4 + block_size as u32).to_le_bytes());
+    dfd.extend_from_slice(&DfdHeader::BASIC.as_bytes(block_size as u16));
+    dfd.extend_from_slice(
+        &DfdBlockHeaderBasic {
+            color_model: Some(layout.color_model),
+            color_primaries: Some(ColorPrimaries::BT709),
+            transfer_function: Some(if options.is_linear() {
+                TransferFunction::Linear
+            } else {
+                TransferFunction::SRGB
+            }),
+            flags: DataFormatFlags::STRAIGHT_ALPHA,
+            texel_block_dimensions: [dimension, dimension, NonZeroU8::MIN, NonZeroU8::MIN],
+            bytes_planes: [bytes_plane, 0, 0, 0, 0, 0, 0, 0],
+        }
+        .as_bytes(),
+    );
+    for sample in &layout.samples {
+        dfd.extend_from_slice(&sample.as_bytes());
+    }
+
+    let entry = format!("KTXwriter\0{}\0", WRITER);
+    let mut kvd = Vec::new();
+    kvd.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+    kvd.extend_from_slice(entry.as_bytes());
+    kvd.resize(kvd.len().next_multiple_of(4), 0);
+
+    let dfd_offset = Header::LENGTH + LevelIndex::LENGTH * levels.len();
+    let kvd_offset = dfd_offset + dfd.len();
+    // Uncompressed levels start on a whole block; block sizes are all multiples of 4.
+    let alignment = if options.is_zstd() {
+        1
+    } else {
+        usize::from(layout.block_bytes)
+    };
+
+    // Level data is stored from the smallest level to the largest.
+    let mut offsets = vec![0; levels.len()];
+    let mut end = kvd_offset + kvd.len();
+    for (offset, (data, _)) in offsets.iter_mut().zip(&levels).rev() {
+        *offset = end.next_multiple_of(alignment);
+        end = *offset + data.len();
+    }
+
+    let header = Header {
+        format: layout.format,
+        type_size: 1,
+        pixel_width: first.width(),
+        pixel_height: first.height(),
+        pixel_depth: 0,
+        layer_count: 0,
+        face_count: 1,
+        level_count: levels.len() as u32,
+        supercompression_scheme: options
+            .is_zstd()
+            .then_some(SupercompressionScheme::Zstandard),
+        index: Index {
+            dfd_byte_offset: dfd_offset as u32,
+            dfd_byte_length: dfd.len() as u32,
+            kvd_byte_offset: kvd_offset as u32,
+            kvd_byte_length: kvd.len() as u32,
+            sgd_byte_offset: 0,
+            sgd_byte_length: 0,
+        },
+    };
+
+    let mut file = Vec::with_capacity(end);
+    file.extend_from_slice(&header.as_bytes());
+    for (&offset, (data, length)) in offsets.iter().zip(&levels) {
+        let index = LevelIndex {
+            byte_offset: offset as u64,
+            byte_length: data.len() as u64,
+            uncompressed_byte_length: *length as u64,
+        };
+        file.extend_from_slice(&index.as_bytes());
+    }
+    file.extend_from_slice(&dfd);
+    file.extend_from_slice(&kvd);
+    for (&offset, (data, _)) in offsets.iter().zip(&levels).rev() {
+        file.resize(offset, 0);
+        file.extend_from_slice(data);
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use ktx2::{DfdBlockBasic, Reader};
+    use std::io::Read;
+
+    fn image() -> RgbaImage {
+        RgbaImage::from_fn(8, 4, |x, y| Rgba([(x * 30) as u8, (y * 60) as u8, 7, 255]))
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let image = image();
+        let data = encode_ktx2(&[&image], &Ktx2Options::new().with_zstd(false)).unwrap();
+        let reader = Reader::new(data.as_slice()).unwrap();
+        let header = reader.header();
+        assert_eq!(header.format, Some(Format::R8G8B8A8_SRGB));
+        assert_eq!((header.pixel_width, header.pixel_height), (8, 4));
+        assert_eq!(header.supercompression_scheme, None);
+
+        let levels: Vec<_> = reader.levels().collect();
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].data, image.as_raw().as_slice());
+
+        let block = reader.dfd_blocks().next().unwrap();
+        let basic = DfdBlockBasic::parse(block.data).unwrap();
+        assert_eq!(basic.header.color_model, Some(ColorModel::RGBSDA));
+        assert_eq!(basic.header.transfer_function, Some(TransferFunction::SRGB));
+        assert_eq!(basic.header.bytes_planes[0], 4);
+        let samples: Vec<_> = basic.sample_information().collect();
+        assert_eq!(samples.len(), 4);
+        assert_eq!(
+            samples[3].channel_type_qualifiers,
+            ChannelTypeQualifiers::LINEAR
+        );
+
+        let (key, value) = reader.key_value_data().next().unwrap();
+        assert_eq!(key, "KTXwriter");
+        assert!(value.starts_with(b"spriterator "));
+    }
+
+    #[test]
+    fn test_block_compressed_and_supercompressed() {
+        let image = image();
+        let options = Ktx2Options::new().with_compression(Some(BlockCompression::Bc7));
+        let data = encode_ktx2(&[&image], &options).unwrap();
+        let reader = Reader::new(data.as_slice()).unwrap();
+        let header = reader.header();
+        assert_eq!(header.format, Some(Format::BC7_SRGB_BLOCK));
+        assert_eq!(
+            header.supercompression_scheme,
+            Some(SupercompressionScheme::Zstandard)
+        );
+
+        let level = reader.levels().next().unwrap();
+        let mut texels = Vec::new();
+        ruzstd::decoding::StreamingDecoder::new(level.data)
+            .unwrap()
+            .read_to_end(&mut texels)
+            .unwrap();
+        assert_eq!(texels, compress(&image, BlockCompression::Bc7));
+        assert_eq!(level.uncompressed_byte_length, texels.len() as u64);
+    }
+
+    #[test]
+    fn test_linear() {
+        let image = image();
+        for (compression, format) in [
+            (None, Format::R8G8B8A8_UNORM),
+            (Some(BlockCompression::Bc1), Format::BC1_RGBA_UNORM_BLOCK),
+            (Some(BlockCompression::Bc3), Format::BC3_UNORM_BLOCK),
+            (Some(BlockCompression::Bc7), Format::BC7_UNORM_BLOCK),
+        ] {
+            let options = Ktx2Options::new()
+                .with_compression(compression)
+                .with_linear(true);
+            let data = encode_ktx2(&[&image], &options).unwrap();
+            let reader = Reader::new(data.as_slice()).unwrap();
+            assert_eq!(reader.header().format, Some(format));
+            let block = reader.dfd_blocks().next().unwrap();
+            let basic = DfdBlockBasic::parse(block.data).unwrap();
+            assert_eq!(
+                basic.header.transfer_function,
+                Some(TransferFunction::Linear)
+            );
+            assert!(basic
+                .sample_information()
+                .all(|sample| sample.channel_type_qualifiers.is_empty()));
+        }
+    }
+
+    #[test]
+    fn test_levels() {
+        let image = image();
+        let mipmaps = crate::mipmap::mipmaps(&image, &[]);
+        let levels: Vec<_> = std::iter::once(&image).chain(&mipmaps).collect();
+        let options = Ktx2Options::new()
+            .with_compression(Some(BlockCompression::Bc1))
+            .with_zstd(false);
+        let data = encode_ktx2(&levels, &options).unwrap();
+        let reader = Reader::new(data.as_slice()).unwrap();
+        assert_eq!(reader.header().level_count, 4);
+
+        let stored: Vec<_> = reader.levels().collect();
+        for (level, image) in stored.iter().zip(&levels) {
+            assert_eq!(
+                level.data,
+                compress(image, BlockCompression::Bc1).as_slice()
+            );
+        }
+        // Smaller levels come first in the file.
+        let offset = |level: &ktx2::Level| level.data.as_ptr() as usize;
+        assert!(stored
+            .windows(2)
+            .all(|pair| offset(&pair[1]) < offset(&pair[0])));
+        assert!(stored
+            .iter()
+            .all(|level| (offset(level) - data.as_ptr() as usize).is_multiple_of(8)));
+    }
+
+    #[cfg(feature = "basis")]
+    #[test]
+    fn test_uastc() {
+        use basis_universal::{TranscodeParameters, TranscoderTextureFormat};
+
+        let mut image = image();
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        let options = Ktx2Options::new().with_uastc(true).with_zstd(false);
+        let data = encode_ktx2(&[&image], &options).unwrap();
+        let reader = Reader::new(data.as_slice()).unwrap();
+        assert_eq!(reader.header().format, None);
+        let block = reader.dfd_blocks().next().unwrap();
+        let basic = DfdBlockBasic::parse(block.data).unwrap();
+        assert_eq!(basic.header.color_model, Some(ColorModel::UASTC));
+        assert_eq!(basic.sample_information().next().unwrap().channel_type, 3);
+
+        // The stored blocks are those of a .basis file, which transcodes close to the image.
+        let mut params = CompressorParams::new();
+        params.set_basis_format(BasisTextureFormat::UASTC4x4);
+        params
+            .source_image_mut(0)
+            .init(image.as_raw(), image.width(), image.height(), 4);
+        let mut compressor = Compressor::new(1);
+        unsafe {
+            assert!(compressor.init(&params));
+            compressor.process().unwrap();
+        }
+        let basis = compressor.basis_file();
+        let mut transcoder = Transcoder::new();
+        let info = transcoder.image_level_info(basis, 0, 0).unwrap();
+        let start = info.m_rgb_file_ofs as usize;
+        let level = reader.levels().next().unwrap();
+        assert_eq!(level.data.len(), 2 * 16);
+        assert_eq!(
+            level.data,
+            &basis[start..start + info.m_rgb_file_len as usize]
+        );
+
+        transcoder.prepare_transcoding(basis).unwrap();
+        let decoded = transcoder
+            .transcode_image_level(
+                basis,
+                TranscoderTextureFormat::RGBA32,
+                TranscodeParameters::default(),
+            )
+            .unwrap();
+        let error: u32 = decoded
+            .iter()
+            .zip(image.as_raw())
+            .map(|(decoded, original)| u32::from(decoded.abs_diff(*original)))
+            .sum();
+        assert!(error / decoded.len() as u32 <= 8);
+        assert!(decoded[3] < 16);
+    }
+}
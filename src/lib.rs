@@ -1,5 +1,100 @@
+mod alpha_scan;
+mod atomic_write;
+#[cfg(any(feature = "dds", feature = "ktx2"))]
+mod block_compression;
+mod build_cache;
+mod color_report;
+mod color_space;
+mod error;
+mod export;
+mod exporter;
 mod frame;
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "ktx2")]
+mod ktx2_writer;
+mod limits;
+mod metadata;
+mod metrics;
+mod mipmap;
+mod output_format;
+mod output_template;
+mod packing_report;
+#[cfg(feature = "parallel")]
+mod parallel_png;
+mod post_process;
+mod profile;
+mod quality_sweep;
+mod quantize;
+mod rect;
+mod resize_cache;
+#[cfg(feature = "encryption")]
+mod scramble;
+mod sheet_layout;
+mod source;
 mod sprite;
 mod spriterator;
+mod strip_metadata;
+#[cfg(test)]
+mod test_util;
+mod unpack;
+mod warning;
 
-pub use spriterator::Spriterator;
+pub use atomic_write::OverwritePolicy;
+#[cfg(any(feature = "dds", feature = "ktx2"))]
+pub use block_compression::BlockCompression;
+pub use color_report::{AlphaUsage, ColorReport, SuggestedEncoding};
+pub use error::SpriteratorError;
+pub use export::bmfont::{export_bmfont, BmFontOptions, GlyphMetrics};
+pub use export::css::CssOptions;
+pub use export::easeljs::export_easeljs_spritesheet;
+pub use export::godot::export_godot_sprite_frames;
+pub use export::libgdx::export_libgdx_atlas;
+pub use export::phaser::export_phaser_multiatlas;
+pub use export::pixi::export_pixi_multipack;
+pub use export::png_text::read_png_metadata;
+pub use export::spine::export_spine_atlas;
+pub use export::spritekit::export_spritekit_atlasc;
+pub use export::unity::UnityOptions;
+pub use exporter::{ExportContext, Exporter, ExporterRegistry};
+pub use frame::Frame;
+pub use limits::EngineLimits;
+#[cfg(feature = "schema")]
+pub use metadata::metadata_schema;
+pub use metadata::{
+    export_metadata, validate_metadata, FrameMetadata, Metadata, SheetMetadata, METADATA_VERSION,
+};
+#[cfg(feature = "avif")]
+pub use output_format::AvifOptions;
+#[cfg(feature = "dds")]
+pub use output_format::DdsOptions;
+#[cfg(feature = "ktx2")]
+pub use output_format::Ktx2Options;
+#[cfg(feature = "oxipng")]
+pub use output_format::PngOptimization;
+pub use output_format::{
+    IndexedPngOptions, JpegOptions, OutputFormat, PngBitDepth, PngOptions, WebPOptions,
+};
+pub use output_template::{save_all, OutputTemplate};
+pub use packing_report::{PackingReport, SheetStats};
+pub use post_process::SheetPostProcessor;
+pub use profile::Profile;
+pub use quality_sweep::{LossyFormat, QualitySweep, SweepResult};
+pub use quantize::Dithering;
+pub use rect::Rect;
+#[cfg(feature = "encryption")]
+pub use scramble::Scrambler;
+pub use sheet_layout::SheetLayout;
+#[cfg(feature = "http")]
+pub use source::http::UrlSource;
+pub use source::manifest::ManifestSource;
+pub use source::sheet::SheetSource;
+#[cfg(feature = "tar")]
+pub use source::tar::TarSource;
+#[cfg(feature = "zip")]
+pub use source::zip::ZipSource;
+pub use source::{DirectorySource, ImageSource, NamedImage};
+pub use sprite::Sprite;
+pub use spriterator::{SpriteIter, Spriterator};
+pub use unpack::{unpack, write_frames};
+pub use warning::Warning;
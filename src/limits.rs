@@ -0,0 +1,175 @@
+use crate::sprite::Sprite;
+
+/// Limits of a target engine or device that generated sheets are checked against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EngineLimits {
+    max_texture_size: Option<u32>,
+    max_frames: Option<usize>,
+    max_pages: Option<usize>,
+    strict: bool,
+}
+
+impl EngineLimits {
+    /// Creates limits that allow everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest width and height of a texture.
+    pub fn with_max_texture_size(mut self, max_texture_size: Option<u32>) -> Self {
+        self.max_texture_size = max_texture_size;
+        self
+    }
+
+    /// Sets the largest number of frames across all sheets.
+    pub fn with_max_frames(mut self, max_frames: Option<usize>) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Sets the largest number of sheets.
+    pub fn with_max_pages(mut self, max_pages: Option<usize>) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Makes generation fail when a limit is exceeded, instead of raising warnings.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Describes every limit the generated sheets exceed.
+    pub fn check(&self, sprites: &[Sprite]) -> Vec<String> {
+        let sheets: Vec<((u32, u32), usize)> = sprites
+            .iter()
+            .map(|sprite| (sprite.get_image().dimensions(), sprite.get_frames().len()))
+            .collect();
+        self.check_sizes(&sheets)
+    }
+
+    /// Describes every limit exceeded by sheets of the given dimensions and frame counts,
+    /// for sheets that were not composed again.
+    pub(crate) fn check_sizes(&self, sheets: &[((u32, u32), usize)]) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if let Some(max_texture_size) = self.max_texture_size {
+            for (page, &((width, height), _)) in sheets.iter().enumerate() {
+                if width > max_texture_size || height > max_texture_size {
+                    violations.push(format!(
+                        "Sheet {} is {}x{}, exceeding the maximum texture size of {}.",
+                        page, width, height, max_texture_size
+                    ));
+                }
+            }
+        }
+
+        if let Some(max_frames) = self.max_frames {
+            let frames: usize = sheets.iter().map(|&(_, frames)| frames).sum();
+            if frames > max_frames {
+                violations.push(format!(
+                    "{} frames exceed the maximum of {}.",
+                    frames, max_frames
+                ));
+            }
+        }
+
+        if let Some(max_pages) = self.max_pages {
+            if sheets.len() > max_pages {
+                violations.push(format!(
+                    "{} sheets exceed the maximum of {}.",
+                    sheets.len(),
+                    max_pages
+                ));
+            }
+        }
+
+        violations
+    }
+
+    /// Describes every limit exceeded by the sheet at index `page` of sheets produced one at a
+    /// time, where `frames` counts the frames of the sheets before it.
+    ///
+    /// The frame and page limits are reported once, by the sheet that first exceeds them.
+    pub(crate) fn check_sheet(&self, page: usize, sprite: &Sprite, frames: usize) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if let Some(max_texture_size) = self.max_texture_size {
+            let (width, height) = sprite.get_image().dimensions();
+            if width > max_texture_size || height > max_texture_size {
+                violations.push(format!(
+                    "Sheet {} is {}x{}, exceeding the maximum texture size of {}.",
+                    page, width, height, max_texture_size
+                ));
+            }
+        }
+
+        if let Some(max_frames) = self.max_frames {
+            let total = frames + sprite.get_frames().len();
+            if frames <= max_frames && total > max_frames {
+                violations.push(format!(
+                    "{} frames exceed the maximum of {}.",
+                    total, max_frames
+                ));
+            }
+        }
+
+        if self.max_pages == Some(page) {
+            violations.push(format!(
+                "{} sheets exceed the maximum of {}.",
+                page + 1,
+                page
+            ));
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_check() {
+        let mut sprites = vec![
+            Sprite::new(RgbaImage::new(2048, 1024)),
+            Sprite::new(RgbaImage::new(512, 512)),
+        ];
+        sprites[0].add_named_frame("a.png", 0, 0, 8, 8);
+        sprites[1].add_named_frame("b.png", 0, 0, 8, 8);
+        sprites[1].add_named_frame("c.png", 8, 0, 8, 8);
+
+        let limits = EngineLimits::new()
+            .with_max_texture_size(Some(1024))
+            .with_max_frames(Some(2))
+            .with_max_pages(Some(1));
+        assert_eq!(
+            limits.check(&sprites),
+            vec![
+                "Sheet 0 is 2048x1024, exceeding the maximum texture size of 1024.",
+                "3 frames exceed the maximum of 2.",
+                "2 sheets exceed the maximum of 1.",
+            ]
+        );
+        assert!(EngineLimits::new().check(&sprites).is_empty());
+
+        assert_eq!(
+            limits.check_sheet(0, &sprites[0], 0),
+            vec!["Sheet 0 is 2048x1024, exceeding the maximum texture size of 1024."]
+        );
+        assert_eq!(
+            limits.check_sheet(1, &sprites[1], 1),
+            vec![
+                "3 frames exceed the maximum of 2.",
+                "2 sheets exceed the maximum of 1.",
+            ]
+        );
+        assert!(limits.check_sheet(2, &sprites[1], 3).is_empty());
+    }
+}
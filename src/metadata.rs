@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SpriteratorError;
+use crate::export::{check_page_count, APP};
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Version of the native metadata format written by `Metadata::new`.
+///
+/// The version is bumped whenever a field is removed or changes meaning; new optional fields
+/// may be added without a bump.
+pub const METADATA_VERSION: u32 = 1;
+
+/// The native metadata format of this crate, describing every generated sheet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Metadata {
+    /// Version of the format, see `METADATA_VERSION`.
+    pub version: u32,
+    /// The tool that wrote the metadata.
+    pub app: String,
+    /// The generated sheets.
+    pub sheets: Vec<SheetMetadata>,
+}
+
+/// A single sheet of the native metadata format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SheetMetadata {
+    /// Path of the saved sheet.
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    /// Array texture layer of the sheet, set when generated in array texture mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<u32>,
+    /// The frames placed on the sheet, in packing order.
+    pub frames: Vec<FrameMetadata>,
+}
+
+/// A single frame of the native metadata format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FrameMetadata {
+    /// Path of the source image relative to the input directory, with `/` separators.
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Whether transparent borders were trimmed from the source image.
+    pub trimmed: bool,
+    pub source_width: u32,
+    pub source_height: u32,
+    /// Position of the kept area within the source image.
+    pub offset_x: u32,
+    pub offset_y: u32,
+    /// Channel holding the frame when channel packing is used, from 0 (red) to 3 (alpha).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u8>,
+    /// How long the frame is shown in milliseconds, for frames of animated source images.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    /// Pivot normalized to the untrimmed source image with the origin at the top-left corner,
+    /// if set by its source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pivot: Option<(f64, f64)>,
+}
+
+impl FrameMetadata {
+    pub(crate) fn new(frame: &Frame) -> Self {
+        Self {
+            name: frame.get_name().to_string(),
+            x: frame.get_x(),
+            y: frame.get_y(),
+            width: frame.get_width(),
+            height: frame.get_height(),
+            trimmed: frame.is_trimmed(),
+            source_width: frame.get_source_width(),
+            source_height: frame.get_source_height(),
+            offset_x: frame.get_offset_x(),
+            offset_y: frame.get_offset_y(),
+            channel: frame.get_channel(),
+            duration: frame.get_duration(),
+            pivot: frame.get_pivot(),
+        }
+    }
+}
+
+impl Metadata {
+    /// Describes the generated sheets in the native metadata format.
+    ///
+    /// # Arguments
+    /// - `sprites`: The generated sheets.
+    /// - `images`: Path of each saved sheet as referenced from the metadata, in the same order.
+    ///
+    /// # Returns
+    /// A `Result` containing the metadata, or an error if the number of paths does not match.
+    pub fn new(sprites: &[Sprite], images: &[&str]) -> Result<Self, Box<dyn Error>> {
+        check_page_count(sprites.len(), images)?;
+
+        Ok(Self {
+            version: METADATA_VERSION,
+            app: APP.to_string(),
+            sheets: sprites
+                .iter()
+                .zip(images)
+                .map(|(sprite, image)| SheetMetadata {
+                    image: image.to_string(),
+                    width: sprite.get_image().width(),
+                    height: sprite.get_image().height(),
+                    layer: sprite.get_layer(),
+                    frames: sprite.get_frames().iter().map(FrameMetadata::new).collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns every inconsistency of the metadata, such as frames outside their sheet.
+    fn diagnostics(&self) -> Vec<String> {
+        let mut diagnostics = Vec::new();
+
+        if self.version != METADATA_VERSION {
+            diagnostics.push(format!(
+                "Unsupported version {}; expected {}.",
+                self.version, METADATA_VERSION
+            ));
+        }
+
+        for (index, sheet) in self.sheets.iter().enumerate() {
+            let mut names = HashSet::new();
+            for frame in &sheet.frames {
+                let at = format!("Frame \"{}\" of sheet {}", frame.name, index);
+                if frame.name.is_empty() {
+                    diagnostics.push(format!("Sheet {} has a frame without a name.", index));
+                }
+                if !names.insert(frame.name.as_str()) {
+                    diagnostics.push(format!("{} is listed more than once.", at));
+                }
+                if u64::from(frame.x) + u64::from(frame.width) > u64::from(sheet.width)
+                    || u64::from(frame.y) + u64::from(frame.height) > u64::from(sheet.height)
+                {
+                    diagnostics.push(format!(
+                        "{} lies outside the {}x{} sheet.",
+                        at, sheet.width, sheet.height
+                    ));
+                }
+                if u64::from(frame.offset_x) + u64::from(frame.width)
+                    > u64::from(frame.source_width)
+                    || u64::from(frame.offset_y) + u64::from(frame.height)
+                        > u64::from(frame.source_height)
+                {
+                    diagnostics.push(format!(
+                        "{} lies outside its {}x{} source image.",
+                        at, frame.source_width, frame.source_height
+                    ));
+                }
+                if frame.channel.is_some_and(|channel| channel > 3) {
+                    diagnostics.push(format!("{} uses a channel other than 0 to 3.", at));
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Serializes the metadata as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Writes the native metadata of every generated sheet as JSON.
+///
+/// # Arguments
+/// - `path`: Path of the JSON file to write.
+/// - `sprites`: The generated sheets.
+/// - `images`: Path of each saved sheet as referenced from the JSON file, in the same order.
+pub fn export_metadata(
+    path: &str,
+    sprites: &[Sprite],
+    images: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, Metadata::new(sprites, images)?.to_json()?)?;
+    Ok(())
+}
+
+/// Parses native metadata JSON and checks it for consistency.
+///
+/// # Arguments
+/// - `json`: The metadata document.
+///
+/// # Returns
+/// A `Result` containing the parsed metadata, or `SpriteratorError::InvalidMetadata` listing
+/// every problem found.
+pub fn validate_metadata(json: &str) -> Result<Metadata, SpriteratorError> {
+    let metadata: Metadata =
+        serde_json::from_str(json).map_err(|error| SpriteratorError::InvalidMetadata {
+            diagnostics: vec![error.to_string()],
+        })?;
+
+    let diagnostics = metadata.diagnostics();
+    if diagnostics.is_empty() {
+        Ok(metadata)
+    } else {
+        Err(SpriteratorError::InvalidMetadata { diagnostics })
+    }
+}
+
+/// Returns the JSON Schema of the native metadata format.
+#[cfg(feature = "schema")]
+pub fn metadata_schema() -> String {
+    let schema = schemars::schema_for!(Metadata);
+    serde_json::to_string_pretty(&schema).expect("schemas always serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn sprites() -> Vec<Sprite> {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("a.png", 0, 0, 10, 20);
+        sprite.add_named_frame("b/c.png", 10, 0, 20, 20);
+        vec![sprite]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let metadata = Metadata::new(&sprites(), &["sheet.png"]).unwrap();
+        let json = metadata.to_json().unwrap();
+        assert!(json.contains("\"version\": 1"));
+        assert!(!json.contains("channel"));
+        assert_eq!(validate_metadata(&json).unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_validate_metadata() {
+        let mut metadata = Metadata::new(&sprites(), &["sheet.png"]).unwrap();
+        metadata.version = 2;
+        metadata.sheets[0].frames[1].x = 11;
+        metadata.sheets[0].frames[1].name = "a.png".to_string();
+
+        let error = validate_metadata(&metadata.to_json().unwrap()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid metadata:\n\
+             - Unsupported version 2; expected 1.\n\
+             - Frame \"a.png\" of sheet 0 is listed more than once.\n\
+             - Frame \"a.png\" of sheet 0 lies outside the 30x20 sheet."
+        );
+
+        assert!(matches!(
+            validate_metadata("{\"version\": 1}"),
+            Err(SpriteratorError::InvalidMetadata { .. })
+        ));
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_metadata_schema() {
+        let schema: serde_json::Value = serde_json::from_str(&metadata_schema()).unwrap();
+        assert_eq!(schema["title"], "Metadata");
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&"sheets".into()));
+    }
+}
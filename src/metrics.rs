@@ -0,0 +1,126 @@
+use image::RgbaImage;
+
+/// Side of the square windows SSIM is computed over.
+const SSIM_WINDOW: u32 = 8;
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Premultiplies the color by alpha so that differences hidden under transparent pixels,
+/// which lossy encoders are free to discard, do not count as errors.
+fn premultiplied(pixel: &image::Rgba<u8>) -> [f64; 4] {
+    let alpha = pixel[3] as f64 / 255.0;
+    [
+        pixel[0] as f64 * alpha,
+        pixel[1] as f64 * alpha,
+        pixel[2] as f64 * alpha,
+        pixel[3] as f64,
+    ]
+}
+
+fn luma(pixel: &image::Rgba<u8>) -> f64 {
+    let [r, g, b, _] = premultiplied(pixel);
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+/// Computes the peak signal-to-noise ratio in decibels between two images of equal size.
+///
+/// Identical images yield `f64::INFINITY`.
+pub(crate) fn psnr(reference: &RgbaImage, distorted: &RgbaImage) -> f64 {
+    let mut squared_error = 0.0;
+    let mut samples = 0u64;
+
+    for (a, b) in reference.pixels().zip(distorted.pixels()) {
+        for (x, y) in premultiplied(a).iter().zip(premultiplied(b).iter()) {
+            squared_error += (x - y) * (x - y);
+            samples += 1;
+        }
+    }
+
+    if samples == 0 || squared_error == 0.0 {
+        return f64::INFINITY;
+    }
+
+    let mse = squared_error / samples as f64;
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+/// Computes the mean structural similarity of the luma of two images of equal size,
+/// averaged over non-overlapping windows.
+pub(crate) fn ssim(reference: &RgbaImage, distorted: &RgbaImage) -> f64 {
+    let (width, height) = reference.dimensions();
+    let mut total = 0.0;
+    let mut windows = 0u64;
+
+    for window_y in (0..height).step_by(SSIM_WINDOW as usize) {
+        for window_x in (0..width).step_by(SSIM_WINDOW as usize) {
+            let window_width = SSIM_WINDOW.min(width - window_x);
+            let window_height = SSIM_WINDOW.min(height - window_y);
+            let count = (window_width * window_height) as f64;
+
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) =
+                (0.0, 0.0, 0.0, 0.0, 0.0);
+            for y in window_y..window_y + window_height {
+                for x in window_x..window_x + window_width {
+                    let a = luma(reference.get_pixel(x, y));
+                    let b = luma(distorted.get_pixel(x, y));
+                    sum_a += a;
+                    sum_b += b;
+                    sum_aa += a * a;
+                    sum_bb += b * b;
+                    sum_ab += a * b;
+                }
+            }
+
+            let mean_a = sum_a / count;
+            let mean_b = sum_b / count;
+            let variance_a = sum_aa / count - mean_a * mean_a;
+            let variance_b = sum_bb / count - mean_b * mean_b;
+            let covariance = sum_ab / count - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1)
+                    * (variance_a + variance_b + SSIM_C2));
+            windows += 1;
+        }
+    }
+
+    if windows == 0 {
+        return 1.0;
+    }
+
+    total / windows as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_identical_images() {
+        let image = RgbaImage::from_pixel(16, 16, Rgba([10, 200, 30, 255]));
+        assert_eq!(psnr(&image, &image), f64::INFINITY);
+        assert!((ssim(&image, &image) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hidden_differences_are_ignored() {
+        let reference = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 0]));
+        let distorted = RgbaImage::from_pixel(4, 4, Rgba([0, 255, 0, 0]));
+        assert_eq!(psnr(&reference, &distorted), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_distortion_lowers_scores() {
+        let mut reference = RgbaImage::new(16, 16);
+        for (x, y, pixel) in reference.enumerate_pixels_mut() {
+            *pixel = Rgba([(x * 16) as u8, (y * 16) as u8, 0, 255]);
+        }
+        let mut distorted = reference.clone();
+        for pixel in distorted.pixels_mut() {
+            pixel[0] = pixel[0].saturating_add(40);
+        }
+        assert!(psnr(&reference, &distorted) < 30.0);
+        assert!(ssim(&reference, &distorted) < 1.0);
+    }
+}
@@ -0,0 +1,148 @@
+use image::{Rgba, RgbaImage};
+
+use crate::rect::Rect;
+
+/// Builds the mipmap chain of `image` below level 0, down to 1×1.
+///
+/// Each level halves the previous one, rounding down. Every pixel of a level averages the
+/// pixels of its 2×2 source block that belong to the same region, so frames never bleed into
+/// their neighbours or into empty space, however small the level. Averaging weighs colors by
+/// their alpha, keeping transparent pixels from darkening edges.
+///
+/// # Arguments
+/// - `image`: Level 0 of the chain.
+/// - `regions`: Areas downsampled apart from each other and from the rest of the image,
+///   such as the slots of frames including their extrusion and padding.
+///
+/// # Returns
+/// The levels from 1 onwards, empty if `image` is already 1×1.
+pub(crate) fn mipmaps(image: &RgbaImage, regions: &[Rect]) -> Vec<RgbaImage> {
+    let (width, height) = image.dimensions();
+    let mut owners = vec![0usize; width as usize * height as usize];
+    for (index, region) in regions.iter().enumerate() {
+        for y in region.y..region.bottom().min(height) {
+            for x in region.x..region.right().min(width) {
+                owners[(y * width + x) as usize] = index + 1;
+            }
+        }
+    }
+
+    let mut levels = Vec::new();
+    let mut previous = image;
+    while previous.width() > 1 || previous.height() > 1 {
+        let (level, level_owners) = downsample(previous, &owners);
+        levels.push(level);
+        owners = level_owners;
+        previous = levels.last().expect("a level was just pushed");
+    }
+    levels
+}
+
+/// Halves `image`, keeping the pixels of each owner apart.
+///
+/// # Returns
+/// The downsampled image and the owner of each of its pixels: the owner of most pixels of
+/// its source block, or of the top-left one on ties.
+fn downsample(image: &RgbaImage, owners: &[usize]) -> (RgbaImage, Vec<usize>) {
+    let (width, height) = image.dimensions();
+    let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+    let mut level = RgbaImage::new(half_width, half_height);
+    let mut level_owners = Vec::with_capacity(half_width as usize * half_height as usize);
+
+    for y in 0..half_height {
+        for x in 0..half_width {
+            let block: Vec<(u32, u32)> = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .iter()
+                .map(|&(dx, dy)| ((2 * x + dx).min(width - 1), (2 * y + dy).min(height - 1)))
+                .collect();
+            let owner_of = |&(sx, sy): &(u32, u32)| owners[(sy * width + sx) as usize];
+            let owner = block
+                .iter()
+                .map(owner_of)
+                .max_by_key(|&owner| {
+                    let count = block.iter().filter(|p| owner_of(p) == owner).count();
+                    // Prefer the earliest pixel among equally common owners.
+                    let first = block.iter().position(|p| owner_of(p) == owner);
+                    (count, std::cmp::Reverse(first))
+                })
+                .expect("blocks are not empty");
+
+            let pixels: Vec<&Rgba<u8>> = block
+                .iter()
+                .filter(|p| owner_of(p) == owner)
+                .map(|&(sx, sy)| image.get_pixel(sx, sy))
+                .collect();
+            level.put_pixel(x, y, average(&pixels));
+            level_owners.push(owner);
+        }
+    }
+
+    (level, level_owners)
+}
+
+/// Averages `pixels`, weighing their colors by alpha.
+fn average(pixels: &[&Rgba<u8>]) -> Rgba<u8> {
+    let count = pixels.len() as u32;
+    let alpha: u32 = pixels.iter().map(|p| u32::from(p[3])).sum();
+    let mut average = [0u8; 4];
+    for (c, value) in average.iter_mut().take(3).enumerate() {
+        let sum: u32 = if alpha == 0 {
+            pixels.iter().map(|p| u32::from(p[c])).sum::<u32>() * 255
+        } else {
+            pixels
+                .iter()
+                .map(|p| u32::from(p[c]) * u32::from(p[3]))
+                .sum()
+        };
+        let weight = if alpha == 0 { count * 255 } else { alpha };
+        *value = ((sum + weight / 2) / weight) as u8;
+    }
+    average[3] = ((alpha + count / 2) / count) as u8;
+    Rgba(average)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_sizes() {
+        let levels = mipmaps(&RgbaImage::new(10, 3), &[]);
+        let sizes: Vec<_> = levels.iter().map(|level| level.dimensions()).collect();
+        assert_eq!(sizes, vec![(5, 1), (2, 1), (1, 1)]);
+        assert!(mipmaps(&RgbaImage::new(1, 1), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_regions_do_not_bleed() {
+        // Two 3-pixel wide frames side by side, so their second level shares a source block.
+        let image = RgbaImage::from_fn(8, 4, |x, _| match x {
+            0..=2 => Rgba([255, 0, 0, 255]),
+            3..=5 => Rgba([0, 0, 255, 255]),
+            _ => Rgba([0, 0, 0, 0]),
+        });
+        let regions = [Rect::new(0, 0, 3, 4), Rect::new(3, 0, 3, 4)];
+
+        for level in mipmaps(&image, &regions) {
+            for pixel in level.pixels() {
+                let pure = [[255, 0, 0, 255], [0, 0, 255, 255], [0, 0, 0, 0]];
+                assert!(pure.contains(&pixel.0), "{:?} mixes regions", pixel);
+            }
+        }
+
+        let blended = mipmaps(&image, &[]);
+        assert!(blended[0]
+            .pixels()
+            .any(|pixel| pixel[0] > 0 && pixel[2] > 0));
+    }
+
+    #[test]
+    fn test_transparent_pixels_keep_color() {
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 0]));
+        image.put_pixel(0, 0, Rgba([200, 100, 50, 255]));
+        assert_eq!(
+            mipmaps(&image, &[])[0].get_pixel(0, 0),
+            &Rgba([200, 100, 50, 64])
+        );
+    }
+}
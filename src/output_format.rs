@@ -0,0 +1,1153 @@
+#[cfg(feature = "avif")]
+use image::codecs::avif::AvifEncoder;
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::tga::TgaEncoder;
+use image::codecs::tiff::TiffEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{ExtendedColorType, ImageEncoder, Rgb, RgbImage, Rgba, RgbaImage};
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::{Seek, Write};
+
+#[cfg(feature = "dds")]
+use crate::block_compression::compress;
+#[cfg(any(feature = "dds", feature = "ktx2"))]
+use crate::block_compression::BlockCompression;
+use crate::error::SpriteratorError;
+use crate::export::png_text::tag_srgb;
+#[cfg(feature = "ktx2")]
+use crate::ktx2_writer::encode_ktx2;
+#[cfg(feature = "parallel")]
+use crate::parallel_png;
+
+#[cfg(any(feature = "dds", feature = "ktx2"))]
+use crate::mipmap::mipmaps;
+use crate::quantize::{quantize, Dithering};
+use crate::rect::Rect;
+
+/// Largest width and height of a WebP image.
+const WEBP_MAX_SIZE: u32 = 16383;
+
+/// Number of bits per channel of a PNG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PngBitDepth {
+    Eight,
+    /// Sixteen bits per channel, for pipelines that expect high bit depth input. Every 8-bit
+    /// value is scaled to the full 16-bit range, so no precision is gained.
+    Sixteen,
+}
+
+/// Settings of the oxipng pass run over encoded PNGs, available with the `oxipng` feature.
+///
+/// The pass is lossless: it tries every filter strategy and color type reduction and keeps
+/// the smallest result.
+#[cfg(feature = "oxipng")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PngOptimization {
+    level: u8,
+    zopfli: bool,
+}
+
+#[cfg(feature = "oxipng")]
+impl Default for PngOptimization {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "oxipng")]
+impl PngOptimization {
+    /// Number of Zopfli iterations, the value oxipng recommends for most files.
+    const ZOPFLI_ITERATIONS: u8 = 15;
+
+    /// Creates settings matching oxipng's default preset 2, without Zopfli.
+    pub fn new() -> Self {
+        Self {
+            level: 2,
+            zopfli: false,
+        }
+    }
+
+    /// Sets the oxipng preset in the range `0..=6`, where higher presets try more combinations.
+    pub fn with_level(mut self, level: u8) -> Self {
+        self.level = level.min(6);
+        self
+    }
+
+    /// Compresses with Zopfli, which is much slower but usually saves a few more percent.
+    pub fn with_zopfli(mut self, zopfli: bool) -> Self {
+        self.zopfli = zopfli;
+        self
+    }
+
+    pub fn get_level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn is_zopfli(&self) -> bool {
+        self.zopfli
+    }
+
+    /// Optimizes an encoded PNG, keeping 16 bits per channel if `keep_bit_depth` is set.
+    fn optimize(&self, data: &[u8], keep_bit_depth: bool) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut options = oxipng::Options::from_preset(self.level);
+        options.bit_depth_reduction = !keep_bit_depth;
+        if self.zopfli {
+            options.deflate = oxipng::Deflaters::Zopfli {
+                iterations: std::num::NonZeroU8::new(Self::ZOPFLI_ITERATIONS)
+                    .expect("iterations are not zero"),
+            };
+        }
+        Ok(oxipng::optimize_from_memory(data, &options)?)
+    }
+}
+
+/// Settings of the PNG encoder.
+///
+/// With the `parallel` feature, sheets of more than a megabyte are filtered and compressed in
+/// chunks of rows across rayon's thread pool when it has several threads. They decode to the
+/// same pixels, but the files differ from single-threaded ones and are slightly larger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PngOptions {
+    compression: CompressionType,
+    filter: FilterType,
+    bit_depth: PngBitDepth,
+    srgb: bool,
+    #[cfg(feature = "oxipng")]
+    optimization: Option<PngOptimization>,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PngOptions {
+    /// Creates the options `image` uses when saving by path: default compression, adaptive
+    /// filtering and 8 bits per channel.
+    pub fn new() -> Self {
+        Self {
+            compression: CompressionType::Default,
+            filter: FilterType::Adaptive,
+            bit_depth: PngBitDepth::Eight,
+            srgb: false,
+            #[cfg(feature = "oxipng")]
+            optimization: None,
+        }
+    }
+
+    /// Sets the trade-off between encoding speed and file size.
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the filter applied to each row before compression.
+    pub fn with_filter(mut self, filter: FilterType) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets the number of bits per channel.
+    pub fn with_bit_depth(mut self, bit_depth: PngBitDepth) -> Self {
+        self.bit_depth = bit_depth;
+        self
+    }
+
+    /// Marks the PNG as sRGB with an sRGB chunk, so that color managed viewers do not guess.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Runs an oxipng pass over the encoded PNG, or skips it with `None`.
+    #[cfg(feature = "oxipng")]
+    pub fn with_optimization(mut self, optimization: Option<PngOptimization>) -> Self {
+        self.optimization = optimization;
+        self
+    }
+
+    pub fn get_compression(&self) -> CompressionType {
+        self.compression
+    }
+
+    pub fn get_filter(&self) -> FilterType {
+        self.filter
+    }
+
+    pub fn get_bit_depth(&self) -> PngBitDepth {
+        self.bit_depth
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.srgb
+    }
+
+    #[cfg(feature = "oxipng")]
+    pub fn get_optimization(&self) -> Option<PngOptimization> {
+        self.optimization
+    }
+}
+
+/// Settings of indexed PNG output, which reduces a sheet to a palette of at most 256 colors.
+///
+/// Sheets that already use few enough colors, such as most pixel art, keep every color exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexedPngOptions {
+    max_colors: u16,
+    dithering: Dithering,
+    srgb: bool,
+    #[cfg(feature = "oxipng")]
+    optimization: Option<PngOptimization>,
+}
+
+impl Default for IndexedPngOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndexedPngOptions {
+    /// Creates options allowing 256 colors, without dithering.
+    pub fn new() -> Self {
+        Self {
+            max_colors: 256,
+            dithering: Dithering::None,
+            srgb: false,
+            #[cfg(feature = "oxipng")]
+            optimization: None,
+        }
+    }
+
+    /// Sets the maximum size of the palette in the range `2..=256`.
+    pub fn with_max_colors(mut self, max_colors: u16) -> Self {
+        self.max_colors = max_colors.clamp(2, 256);
+        self
+    }
+
+    /// Sets how the error of quantized colors is spread, when the sheet has too many colors.
+    pub fn with_dithering(mut self, dithering: Dithering) -> Self {
+        self.dithering = dithering;
+        self
+    }
+
+    pub fn get_max_colors(&self) -> u16 {
+        self.max_colors
+    }
+
+    /// Runs an oxipng pass over the encoded PNG, or skips it with `None`.
+    #[cfg(feature = "oxipng")]
+    pub fn with_optimization(mut self, optimization: Option<PngOptimization>) -> Self {
+        self.optimization = optimization;
+        self
+    }
+
+    pub fn get_dithering(&self) -> Dithering {
+        self.dithering
+    }
+
+    /// Marks the PNG as sRGB with an sRGB chunk, so that color managed viewers do not guess.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.srgb
+    }
+
+    #[cfg(feature = "oxipng")]
+    pub fn get_optimization(&self) -> Option<PngOptimization> {
+        self.optimization
+    }
+}
+
+/// Settings of the JPEG encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JpegOptions {
+    quality: u8,
+    background: Rgb<u8>,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JpegOptions {
+    /// Creates options with a quality of 90 and a white background.
+    pub fn new() -> Self {
+        Self {
+            quality: 90,
+            background: Rgb([255, 255, 255]),
+        }
+    }
+
+    /// Sets the quality in the range `1..=100`.
+    pub fn with_quality(mut self, quality: u8) -> Self {
+        self.quality = quality.clamp(1, 100);
+        self
+    }
+
+    /// Sets the color transparent pixels are composited over, since JPEG has no alpha channel.
+    pub fn with_background(mut self, background: Rgb<u8>) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn get_quality(&self) -> u8 {
+        self.quality
+    }
+
+    pub fn get_background(&self) -> Rgb<u8> {
+        self.background
+    }
+}
+
+/// Composites `image` over an opaque `background`, dropping its alpha channel.
+fn flatten(image: &RgbaImage, background: Rgb<u8>) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *image.get_pixel(x, y);
+        let blend = |color: u8, background: u8| {
+            ((u32::from(color) * u32::from(a) + u32::from(background) * (255 - u32::from(a)) + 127)
+                / 255) as u8
+        };
+        Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    })
+}
+
+/// Settings of the WebP encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WebPOptions {
+    quality: Option<u8>,
+}
+
+impl Default for WebPOptions {
+    fn default() -> Self {
+        Self::lossless()
+    }
+}
+
+impl WebPOptions {
+    /// Creates options for lossless encoding, which keeps every pixel intact.
+    pub fn lossless() -> Self {
+        Self { quality: None }
+    }
+
+    /// Creates options for lossy encoding at a quality in the range `0..=100`,
+    /// available with the `webp` feature.
+    #[cfg(feature = "webp")]
+    pub fn lossy(quality: u8) -> Self {
+        Self {
+            quality: Some(quality.min(100)),
+        }
+    }
+
+    /// Returns the quality of lossy encoding, or `None` for lossless encoding.
+    pub fn get_quality(&self) -> Option<u8> {
+        self.quality
+    }
+}
+
+/// Settings of the AVIF encoder, available with the `avif` feature.
+#[cfg(feature = "avif")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AvifOptions {
+    quality: u8,
+    speed: u8,
+}
+
+#[cfg(feature = "avif")]
+impl Default for AvifOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "avif")]
+impl AvifOptions {
+    /// Creates options with a quality of 80 and a speed of 4.
+    pub fn new() -> Self {
+        Self {
+            quality: 80,
+            speed: 4,
+        }
+    }
+
+    /// Sets the quality in the range `1..=100`.
+    pub fn with_quality(mut self, quality: u8) -> Self {
+        self.quality = quality.clamp(1, 100);
+        self
+    }
+
+    /// Sets the encoder speed in the range `1..=10`, where higher values encode faster
+    /// at the cost of a larger file.
+    pub fn with_speed(mut self, speed: u8) -> Self {
+        self.speed = speed.clamp(1, 10);
+        self
+    }
+
+    pub fn get_quality(&self) -> u8 {
+        self.quality
+    }
+
+    pub fn get_speed(&self) -> u8 {
+        self.speed
+    }
+}
+
+/// Settings of the DDS encoder, available with the `dds` feature.
+#[cfg(feature = "dds")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DdsOptions {
+    compression: BlockCompression,
+    mipmaps: bool,
+}
+
+#[cfg(feature = "dds")]
+impl Default for DdsOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "dds")]
+impl DdsOptions {
+    /// Creates options compressing with BC3, which keeps smooth alpha and loads everywhere.
+    pub fn new() -> Self {
+        Self {
+            compression: BlockCompression::Bc3,
+            mipmaps: false,
+        }
+    }
+
+    /// Sets the block compression format.
+    pub fn with_compression(mut self, compression: BlockCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets whether the texture includes the full mipmap chain, downsampled without
+    /// bleeding between frames.
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub fn get_compression(&self) -> BlockCompression {
+        self.compression
+    }
+
+    pub fn is_mipmapped(&self) -> bool {
+        self.mipmaps
+    }
+}
+
+/// Builds a DDS file holding `levels`, the largest first, block-compressed as `options`
+/// specify.
+///
+/// BC1 and BC3 use the legacy `DXT1` and `DXT5` headers read by every loader, while BC7
+/// needs the DX10 header extension.
+#[cfg(feature = "dds")]
+fn dds(levels: &[&RgbaImage], options: &DdsOptions) -> Result<ddsfile::Dds, Box<dyn Error>> {
+    use ddsfile::{
+        AlphaMode, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, NewD3dParams, NewDxgiParams,
+    };
+
+    let (width, height) = levels[0].dimensions();
+    let mipmap_levels = Some(levels.len() as u32);
+    let legacy = |format| {
+        Dds::new_d3d(NewD3dParams {
+            height,
+            width,
+            depth: None,
+            format,
+            mipmap_levels,
+            caps2: None,
+        })
+    };
+    let mut dds = match options.compression {
+        BlockCompression::Bc1 => legacy(D3DFormat::DXT1)?,
+        BlockCompression::Bc3 => legacy(D3DFormat::DXT5)?,
+        BlockCompression::Bc7 => Dds::new_dxgi(NewDxgiParams {
+            height,
+            width,
+            depth: None,
+            format: DxgiFormat::BC7_UNorm,
+            mipmap_levels,
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Straight,
+        })?,
+    };
+    dds.data = levels
+        .iter()
+        .flat_map(|level| compress(level, options.compression))
+        .collect();
+    Ok(dds)
+}
+
+/// Settings of the KTX2 encoder, available with the `ktx2` feature.
+#[cfg(feature = "ktx2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ktx2Options {
+    compression: Option<BlockCompression>,
+    #[cfg(feature = "basis")]
+    uastc: bool,
+    zstd: bool,
+    mipmaps: bool,
+    linear: bool,
+}
+
+#[cfg(feature = "ktx2")]
+impl Default for Ktx2Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "ktx2")]
+impl Ktx2Options {
+    /// Creates options storing uncompressed sRGB RGBA8 texels, supercompressed with Zstandard.
+    pub fn new() -> Self {
+        Self {
+            compression: None,
+            #[cfg(feature = "basis")]
+            uastc: false,
+            zstd: true,
+            mipmaps: false,
+            linear: false,
+        }
+    }
+
+    /// Sets the block compression of the texels, or `None` to store them uncompressed.
+    pub fn with_compression(mut self, compression: Option<BlockCompression>) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Stores the texels as Basis Universal UASTC blocks, which engines transcode at load time
+    /// to whichever GPU format the platform supports, available with the `basis` feature.
+    /// The block compression is ignored while set.
+    #[cfg(feature = "basis")]
+    pub fn with_uastc(mut self, uastc: bool) -> Self {
+        self.uastc = uastc;
+        self
+    }
+
+    /// Sets whether the texels are supercompressed with Zstandard, shrinking the file while
+    /// keeping its GPU format once inflated.
+    pub fn with_zstd(mut self, zstd: bool) -> Self {
+        self.zstd = zstd;
+        self
+    }
+
+    /// Sets whether the texture includes the full mipmap chain, downsampled without
+    /// bleeding between frames.
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Sets whether the texels are stored as linear values, in UNORM formats, instead of as sRGB
+    /// encoded colors, for data textures such as normal or mask maps. Disabled by default.
+    pub fn with_linear(mut self, linear: bool) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    pub fn get_compression(&self) -> Option<BlockCompression> {
+        self.compression
+    }
+
+    #[cfg(feature = "basis")]
+    pub fn is_uastc(&self) -> bool {
+        self.uastc
+    }
+
+    pub fn is_zstd(&self) -> bool {
+        self.zstd
+    }
+
+    pub fn is_mipmapped(&self) -> bool {
+        self.mipmaps
+    }
+
+    pub fn is_linear(&self) -> bool {
+        self.linear
+    }
+}
+
+/// Returns the mipmaps of `image` below level 0 if `mipmapped`, or none otherwise.
+#[cfg(any(feature = "dds", feature = "ktx2"))]
+fn mip_levels(image: &RgbaImage, regions: &[Rect], mipmapped: bool) -> Vec<RgbaImage> {
+    if mipmapped {
+        mipmaps(image, regions)
+    } else {
+        Vec::new()
+    }
+}
+
+/// An image format a sheet can be saved in, with the settings of its encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png(PngOptions),
+    IndexedPng(IndexedPngOptions),
+    /// JPEG. The alpha channel is discarded.
+    Jpeg(JpegOptions),
+    Bmp,
+    Tga,
+    Tiff,
+    WebP(WebPOptions),
+    #[cfg(feature = "avif")]
+    Avif(AvifOptions),
+    /// DDS with GPU block compression. Blocks are 4×4 pixels, so frames not aligned to the
+    /// block grid share blocks with their neighbours; see `Spriterator::with_block_alignment`.
+    #[cfg(feature = "dds")]
+    Dds(DdsOptions),
+    /// KTX2, uncompressed or block-compressed, with optional Zstandard supercompression.
+    #[cfg(feature = "ktx2")]
+    Ktx2(Ktx2Options),
+}
+
+impl OutputFormat {
+    /// Returns the format with default settings for a file extension such as `png` or `JPG`,
+    /// or `None` if no format uses it.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png(PngOptions::new())),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg(JpegOptions::new())),
+            "bmp" => Some(OutputFormat::Bmp),
+            "tga" => Some(OutputFormat::Tga),
+            "tif" | "tiff" => Some(OutputFormat::Tiff),
+            "webp" => Some(OutputFormat::WebP(WebPOptions::lossless())),
+            #[cfg(feature = "avif")]
+            "avif" => Some(OutputFormat::Avif(AvifOptions::new())),
+            #[cfg(feature = "dds")]
+            "dds" => Some(OutputFormat::Dds(DdsOptions::new())),
+            #[cfg(feature = "ktx2")]
+            "ktx2" => Some(OutputFormat::Ktx2(Ktx2Options::new())),
+            _ => None,
+        }
+    }
+
+    /// Returns the file extension conventionally used for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png(_) => "png",
+            OutputFormat::IndexedPng(_) => "png",
+            OutputFormat::Jpeg(_) => "jpg",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::Tga => "tga",
+            OutputFormat::Tiff => "tiff",
+            OutputFormat::WebP(_) => "webp",
+            #[cfg(feature = "avif")]
+            OutputFormat::Avif(_) => "avif",
+            #[cfg(feature = "dds")]
+            OutputFormat::Dds(_) => "dds",
+            #[cfg(feature = "ktx2")]
+            OutputFormat::Ktx2(_) => "ktx2",
+        }
+    }
+
+    /// Splits a PNG format into its oxipng settings and the same format without them.
+    #[cfg(feature = "oxipng")]
+    fn split_optimization(&self) -> Option<(PngOptimization, OutputFormat)> {
+        match *self {
+            OutputFormat::Png(options) => options.optimization.map(|optimization| {
+                (
+                    optimization,
+                    OutputFormat::Png(options.with_optimization(None)),
+                )
+            }),
+            OutputFormat::IndexedPng(options) => options.optimization.map(|optimization| {
+                (
+                    optimization,
+                    OutputFormat::IndexedPng(options.with_optimization(None)),
+                )
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the same PNG format without its sRGB chunk, if it has one.
+    fn without_srgb(&self) -> Option<OutputFormat> {
+        match *self {
+            OutputFormat::Png(options) if options.srgb => {
+                Some(OutputFormat::Png(options.with_srgb(false)))
+            }
+            OutputFormat::IndexedPng(options) if options.srgb => {
+                Some(OutputFormat::IndexedPng(options.with_srgb(false)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes `image` in this format into `writer`.
+    ///
+    /// # Arguments
+    /// - `image`: The sheet to encode.
+    /// - `regions`: Areas kept apart when downsampling mipmaps, such as frame slots.
+    /// - `writer`: Destination of the encoded image.
+    #[cfg_attr(
+        not(any(feature = "dds", feature = "ktx2")),
+        allow(clippy::only_used_in_recursion)
+    )]
+    pub(crate) fn encode<W: Write + Seek>(
+        &self,
+        image: &RgbaImage,
+        regions: &[Rect],
+        mut writer: W,
+    ) -> Result<(), Box<dyn Error>> {
+        // The chunk is added last, as optimizers may drop chunks they do not need.
+        if let Some(format) = self.without_srgb() {
+            let mut data = std::io::Cursor::new(Vec::new());
+            format.encode(image, regions, &mut data)?;
+            writer.write_all(&tag_srgb(data.get_ref())?)?;
+            return Ok(());
+        }
+
+        #[cfg(feature = "oxipng")]
+        if let Some((optimization, format)) = self.split_optimization() {
+            let mut data = std::io::Cursor::new(Vec::new());
+            format.encode(image, regions, &mut data)?;
+            let sixteen = matches!(
+                format,
+                OutputFormat::Png(options) if options.bit_depth == PngBitDepth::Sixteen
+            );
+            writer.write_all(&optimization.optimize(data.get_ref(), sixteen)?)?;
+            return Ok(());
+        }
+
+        let (width, height) = image.dimensions();
+        let data = image.as_raw();
+        let color = ExtendedColorType::Rgba8;
+
+        match self {
+            OutputFormat::Png(options) => {
+                let (samples, color) = match options.bit_depth {
+                    PngBitDepth::Eight => (Cow::Borrowed(data), color),
+                    PngBitDepth::Sixteen => {
+                        let wide: Vec<u8> = data
+                            .iter()
+                            .flat_map(|&value| (u16::from(value) * 257).to_ne_bytes())
+                            .collect();
+                        (Cow::Owned(wide), ExtendedColorType::Rgba16)
+                    }
+                };
+                #[cfg(feature = "parallel")]
+                if parallel_png::is_worth_splitting(
+                    width as usize * usize::from(color.bits_per_pixel() / 8),
+                    height,
+                ) {
+                    // Both bytes of a widened sample are equal, so the samples are big-endian
+                    // as well as native-endian.
+                    parallel_png::encode(
+                        &mut writer,
+                        &samples,
+                        width,
+                        height,
+                        (color.bits_per_pixel() / 4) as u8,
+                        options.compression,
+                        options.filter,
+                    )?;
+                    writer.flush()?;
+                    return Ok(());
+                }
+                PngEncoder::new_with_quality(&mut writer, options.compression, options.filter)
+                    .write_image(&samples, width, height, color)?
+            }
+            OutputFormat::IndexedPng(options) => {
+                let indexed = quantize(image, options.max_colors.into(), options.dithering);
+                let mut encoder = png::Encoder::new(&mut writer, width, height);
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_depth(
+                    png::BitDepth::from_u8(indexed.bit_depth()).expect("bit depths are valid"),
+                );
+                encoder.set_palette(
+                    indexed
+                        .palette
+                        .iter()
+                        .flat_map(|color| [color[0], color[1], color[2]])
+                        .collect::<Vec<_>>(),
+                );
+                if let Some(last) = indexed.palette.iter().rposition(|color| color[3] < 255) {
+                    encoder.set_trns(
+                        indexed.palette[..=last]
+                            .iter()
+                            .map(|color| color[3])
+                            .collect::<Vec<_>>(),
+                    );
+                }
+                encoder
+                    .write_header()?
+                    .write_image_data(&indexed.packed(width))?
+            }
+            OutputFormat::Jpeg(options) => {
+                let rgb = flatten(image, options.background);
+                JpegEncoder::new_with_quality(&mut writer, options.quality).write_image(
+                    rgb.as_raw(),
+                    width,
+                    height,
+                    ExtendedColorType::Rgb8,
+                )?
+            }
+            OutputFormat::Bmp => {
+                BmpEncoder::new(&mut writer).write_image(data, width, height, color)?
+            }
+            OutputFormat::Tga => {
+                TgaEncoder::new(&mut writer).write_image(data, width, height, color)?
+            }
+            OutputFormat::Tiff => {
+                TiffEncoder::new(&mut writer).write_image(data, width, height, color)?
+            }
+            OutputFormat::WebP(options) => {
+                if width > WEBP_MAX_SIZE || height > WEBP_MAX_SIZE {
+                    return Err(SpriteratorError::ImageTooLarge {
+                        name: "WebP sheet".to_string(),
+                        width,
+                        height,
+                        max_width: WEBP_MAX_SIZE,
+                        max_height: WEBP_MAX_SIZE,
+                    }
+                    .into());
+                }
+                match options.quality {
+                    None => WebPEncoder::new_lossless(&mut writer)
+                        .write_image(data, width, height, color)?,
+                    #[cfg(feature = "webp")]
+                    Some(quality) => writer.write_all(
+                        &webp::Encoder::from_rgba(data, width, height)
+                            .encode_simple(false, quality as f32)
+                            .map_err(|error| format!("Failed to encode WebP: {:?}", error))?,
+                    )?,
+                    #[cfg(not(feature = "webp"))]
+                    Some(_) => unreachable!("lossy WebP options require the webp feature"),
+                }
+            }
+            #[cfg(feature = "avif")]
+            OutputFormat::Avif(options) => {
+                AvifEncoder::new_with_speed_quality(&mut writer, options.speed, options.quality)
+                    .write_image(data, width, height, color)?
+            }
+            #[cfg(feature = "dds")]
+            OutputFormat::Dds(options) => {
+                let mipmaps = mip_levels(image, regions, options.mipmaps);
+                let levels: Vec<_> = std::iter::once(image).chain(&mipmaps).collect();
+                dds(&levels, options)?.write(&mut writer)?
+            }
+            #[cfg(feature = "ktx2")]
+            OutputFormat::Ktx2(options) => {
+                let mipmaps = mip_levels(image, regions, options.mipmaps);
+                let levels: Vec<_> = std::iter::once(image).chain(&mipmaps).collect();
+                writer.write_all(&encode_ktx2(&levels, options)?)?
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageFormat;
+    use std::io::Cursor;
+
+    fn encode(format: OutputFormat) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(8, 4, Rgba([200, 100, 50, 255]));
+        let mut data = Cursor::new(Vec::new());
+        format.encode(&image, &[], &mut data).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_encode_matches_format() {
+        let formats = [
+            (OutputFormat::Png(PngOptions::new()), ImageFormat::Png),
+            (OutputFormat::Jpeg(JpegOptions::new()), ImageFormat::Jpeg),
+            (OutputFormat::Bmp, ImageFormat::Bmp),
+            (OutputFormat::Tiff, ImageFormat::Tiff),
+        ];
+        for (format, expected) in formats {
+            let data = encode(format);
+            assert_eq!(image::guess_format(&data).unwrap(), expected);
+            let decoded = image::load_from_memory_with_format(&data, expected).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (8, 4));
+        }
+        assert!(!encode(OutputFormat::Tga).is_empty());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_png_in_chunks() {
+        let image = RgbaImage::from_fn(700, 500, |x, y| Rgba([x as u8, y as u8, 7, 255]));
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        for bit_depth in [PngBitDepth::Eight, PngBitDepth::Sixteen] {
+            let format = OutputFormat::Png(PngOptions::new().with_bit_depth(bit_depth));
+            let mut data = Cursor::new(Vec::new());
+            let encoded = pool.install(|| format.encode(&image, &[], &mut data).is_ok());
+            assert!(encoded);
+            let decoded = image::load_from_memory(data.get_ref()).unwrap();
+            assert_eq!(decoded.to_rgba8(), image);
+        }
+    }
+
+    #[test]
+    fn test_webp_lossless() {
+        let data = encode(OutputFormat::WebP(WebPOptions::lossless()));
+        assert_eq!(image::guess_format(&data).unwrap(), ImageFormat::WebP);
+        let decoded = image::load_from_memory(&data).unwrap().to_rgba8();
+        assert_eq!(
+            decoded,
+            RgbaImage::from_pixel(8, 4, Rgba([200, 100, 50, 255]))
+        );
+    }
+
+    #[cfg(feature = "avif")]
+    #[test]
+    fn test_avif() {
+        let options = AvifOptions::new().with_quality(0).with_speed(20);
+        assert_eq!((options.get_quality(), options.get_speed()), (1, 10));
+        let data = encode(OutputFormat::Avif(options));
+        assert_eq!(&data[4..12], b"ftypavif");
+        assert_eq!(OutputFormat::Avif(options).extension(), "avif");
+    }
+
+    #[cfg(feature = "dds")]
+    #[test]
+    fn test_dds() {
+        let image = RgbaImage::from_fn(12, 8, |x, _| {
+            Rgba([
+                (x * 20) as u8,
+                (x * 10) as u8,
+                80,
+                if x < 6 { 255 } else { 0 },
+            ])
+        });
+        let encode = |compression| {
+            let mut data = Cursor::new(Vec::new());
+            OutputFormat::Dds(DdsOptions::new().with_compression(compression))
+                .encode(&image, &[], &mut data)
+                .unwrap();
+            data.into_inner()
+        };
+
+        for compression in [BlockCompression::Bc1, BlockCompression::Bc3] {
+            let data = encode(compression);
+            assert_eq!(image::guess_format(&data).unwrap(), ImageFormat::Dds);
+            let decoded = image::load_from_memory(&data).unwrap().to_rgba8();
+            assert_eq!(decoded.dimensions(), (12, 8));
+            for (x, y, pixel) in decoded.enumerate_pixels() {
+                let original = image.get_pixel(x, y);
+                // The decoder reads DXT1 as RGB, dropping its 1-bit alpha.
+                if compression == BlockCompression::Bc3 {
+                    assert_eq!(pixel[3], original[3]);
+                }
+                if original[3] == 255 {
+                    assert!((0..3).all(|c| pixel[c].abs_diff(original[c]) <= 8));
+                }
+            }
+        }
+
+        let bc7 = ddsfile::Dds::read(&mut Cursor::new(encode(BlockCompression::Bc7))).unwrap();
+        assert_eq!(bc7.get_dxgi_format(), Some(ddsfile::DxgiFormat::BC7_UNorm));
+        assert_eq!(bc7.data.len(), 3 * 2 * 16);
+        assert_eq!((bc7.get_width(), bc7.get_height()), (12, 8));
+
+        let mut data = Cursor::new(Vec::new());
+        OutputFormat::Dds(DdsOptions::new().with_mipmaps(true))
+            .encode(&image, &[], &mut data)
+            .unwrap();
+        let mipmapped = ddsfile::Dds::read(&mut Cursor::new(data.into_inner())).unwrap();
+        assert_eq!(mipmapped.get_num_mipmap_levels(), 4);
+        // Levels of 12×8, 6×4, 3×2 and 1×1 pixels take 6, 2, 1 and 1 blocks.
+        assert_eq!(mipmapped.data.len(), (6 + 2 + 1 + 1) * 16);
+        assert_eq!(
+            OutputFormat::from_extension("dds").unwrap().extension(),
+            "dds"
+        );
+    }
+
+    #[cfg(feature = "webp")]
+    #[test]
+    fn test_webp_lossy() {
+        let options = WebPOptions::lossy(150);
+        assert_eq!(options.get_quality(), Some(100));
+        let data = encode(OutputFormat::WebP(WebPOptions::lossy(50)));
+        let decoded = image::load_from_memory_with_format(&data, ImageFormat::WebP).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (8, 4));
+
+        let image = RgbaImage::new(1, WEBP_MAX_SIZE + 1);
+        let error = OutputFormat::WebP(WebPOptions::lossy(50))
+            .encode(&image, &[], Cursor::new(Vec::new()))
+            .unwrap_err();
+        assert!(error.downcast_ref::<SpriteratorError>().is_some());
+    }
+
+    #[test]
+    fn test_webp_too_large() {
+        let image = RgbaImage::new(WEBP_MAX_SIZE + 1, 1);
+        let error = OutputFormat::WebP(WebPOptions::lossless())
+            .encode(&image, &[], Cursor::new(Vec::new()))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SpriteratorError>(),
+            Some(SpriteratorError::ImageTooLarge { width, .. }) if *width == WEBP_MAX_SIZE + 1
+        ));
+    }
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(
+            OutputFormat::from_extension("JPEG"),
+            Some(OutputFormat::Jpeg(JpegOptions::new()))
+        );
+        assert_eq!(
+            OutputFormat::from_extension("tif").map(|format| format.extension()),
+            Some("tiff")
+        );
+        assert_eq!(OutputFormat::from_extension("gif"), None);
+    }
+
+    #[test]
+    fn test_png_options_affect_size() {
+        let fast = encode(OutputFormat::Png(
+            PngOptions::new()
+                .with_compression(CompressionType::Fast)
+                .with_filter(FilterType::NoFilter),
+        ));
+        let best = encode(OutputFormat::Png(
+            PngOptions::new().with_compression(CompressionType::Best),
+        ));
+        assert!(best.len() <= fast.len());
+        assert_eq!(JpegOptions::new().with_quality(0).get_quality(), 1);
+    }
+
+    #[test]
+    fn test_png_bit_depth() {
+        let options = PngOptions::new().with_bit_depth(PngBitDepth::Sixteen);
+        assert_eq!(options.get_bit_depth(), PngBitDepth::Sixteen);
+        let data = encode(OutputFormat::Png(options));
+        let decoded = image::load_from_memory(&data).unwrap();
+        assert_eq!(decoded.color(), image::ColorType::Rgba16);
+        assert_eq!(
+            decoded.to_rgba8(),
+            RgbaImage::from_pixel(8, 4, Rgba([200, 100, 50, 255]))
+        );
+    }
+
+    #[test]
+    fn test_png_srgb() {
+        let srgb = |data: Vec<u8>| {
+            let reader = png::Decoder::new(Cursor::new(data)).read_info().unwrap();
+            reader.info().srgb
+        };
+        assert_eq!(srgb(encode(OutputFormat::Png(PngOptions::new()))), None);
+
+        let options = PngOptions::new().with_srgb(true);
+        assert!(options.is_srgb());
+        let data = encode(OutputFormat::Png(options));
+        assert_eq!(
+            image::load_from_memory(&data).unwrap().to_rgba8(),
+            RgbaImage::from_pixel(8, 4, Rgba([200, 100, 50, 255]))
+        );
+        assert_eq!(srgb(data), Some(png::SrgbRenderingIntent::Perceptual));
+        let indexed = encode(OutputFormat::IndexedPng(
+            IndexedPngOptions::new().with_srgb(true),
+        ));
+        assert_eq!(srgb(indexed), Some(png::SrgbRenderingIntent::Perceptual));
+    }
+
+    #[cfg(feature = "oxipng")]
+    #[test]
+    fn test_png_optimization() {
+        let image = RgbaImage::from_fn(64, 64, |x, y| Rgba([(x * 4) as u8, (y * 4) as u8, 0, 255]));
+        let png = |options: PngOptions| {
+            let mut data = Cursor::new(Vec::new());
+            OutputFormat::Png(options)
+                .encode(&image, &[], &mut data)
+                .unwrap();
+            data.into_inner()
+        };
+
+        let plain = png(PngOptions::new().with_filter(FilterType::NoFilter));
+        assert_eq!(PngOptimization::new().with_level(9).get_level(), 6);
+        let optimization = PngOptimization::new().with_level(1).with_zopfli(true);
+        let optimized = png(PngOptions::new()
+            .with_filter(FilterType::NoFilter)
+            .with_optimization(Some(optimization)));
+        assert!(optimized.len() < plain.len());
+        assert_eq!(
+            image::load_from_memory(&optimized).unwrap().to_rgba8(),
+            image
+        );
+
+        let sixteen = png(PngOptions::new()
+            .with_bit_depth(PngBitDepth::Sixteen)
+            .with_optimization(Some(PngOptimization::new())));
+        let color = image::load_from_memory(&sixteen).unwrap().color();
+        assert_eq!(color.bytes_per_pixel() / color.channel_count(), 2);
+    }
+
+    #[test]
+    fn test_indexed_png() {
+        let mut image = RgbaImage::from_pixel(7, 3, Rgba([200, 100, 50, 255]));
+        image.put_pixel(3, 1, Rgba([0, 0, 0, 0]));
+        image.put_pixel(6, 2, Rgba([9, 9, 9, 64]));
+
+        let mut data = Cursor::new(Vec::new());
+        OutputFormat::IndexedPng(IndexedPngOptions::new())
+            .encode(&image, &[], &mut data)
+            .unwrap();
+        let reader = png::Decoder::new(data.get_ref().as_slice())
+            .read_info()
+            .unwrap();
+        assert_eq!(reader.info().color_type, png::ColorType::Indexed);
+        assert_eq!(reader.info().bit_depth, png::BitDepth::Two);
+        let decoded = image::load_from_memory(data.get_ref()).unwrap().to_rgba8();
+        assert_eq!(decoded, image);
+
+        let options = IndexedPngOptions::new()
+            .with_max_colors(1000)
+            .with_dithering(Dithering::FloydSteinberg);
+        assert_eq!(options.get_max_colors(), 256);
+        assert_eq!(
+            IndexedPngOptions::new().with_max_colors(0).get_max_colors(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_jpeg_background() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(1, 0, Rgba([0, 0, 255, 128]));
+        let flat = flatten(&image, Rgb([255, 0, 0]));
+        assert_eq!(flat.get_pixel(0, 0), &Rgb([255, 0, 0]));
+        assert_eq!(flat.get_pixel(1, 0), &Rgb([127, 0, 128]));
+
+        let options = JpegOptions::new().with_background(Rgb([0, 255, 0]));
+        assert_eq!(options.get_background(), Rgb([0, 255, 0]));
+        let mut data = Cursor::new(Vec::new());
+        OutputFormat::Jpeg(options)
+            .encode(&RgbaImage::new(16, 16), &[], &mut data)
+            .unwrap();
+        let decoded = image::load_from_memory(data.get_ref()).unwrap().to_rgb8();
+        let Rgb([r, g, b]) = *decoded.get_pixel(8, 8);
+        assert!(r < 16 && g > 240 && b < 16);
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+
+use crate::atomic_write::write_bytes;
+use crate::output_format::OutputFormat;
+use crate::sprite::Sprite;
+#[cfg(feature = "parallel")]
+use crate::spriterator::sendable_error;
+
+/// Placeholders an `OutputTemplate` can contain.
+const PLACEHOLDERS: [&str; 7] = ["name", "index", "page", "count", "scale", "hash", "ext"];
+
+/// Number of hexadecimal digits `{hash}` expands to.
+const HASH_DIGITS: usize = 8;
+
+/// Names the files of saved sheets, such as `{name}-{index}@{scale}x.{ext}`.
+///
+/// The placeholders are:
+/// - `{name}`: The base name set with `with_name`, `sheet` by default.
+/// - `{index}` and `{page}`: Index of the sheet, counting from 0 and 1 respectively.
+/// - `{count}`: Number of sheets saved together.
+/// - `{scale}`: The scale set with `with_scale`, `1` by default.
+/// - `{hash}`: Hash of the encoded sheet, changing whenever its contents do.
+/// - `{ext}`: Extension of the output format, without the leading dot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputTemplate {
+    template: String,
+    name: String,
+    scale: f32,
+}
+
+impl OutputTemplate {
+    /// Creates a template; see the type documentation for its placeholders.
+    pub fn new(template: &str) -> Self {
+        Self {
+            template: template.to_string(),
+            name: "sheet".to_string(),
+            scale: 1.0,
+        }
+    }
+
+    /// Sets the value of `{name}`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Sets the value of `{scale}`, for sheets generated from scaled images.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn get_template(&self) -> &str {
+        &self.template
+    }
+
+    /// Expands the template for one sheet.
+    ///
+    /// # Arguments
+    /// - `index`: Index of the sheet.
+    /// - `count`: Number of sheets saved together.
+    /// - `extension`: Extension of the output format.
+    /// - `data`: The encoded sheet, hashed for `{hash}`.
+    ///
+    /// # Returns
+    /// A `Result` containing the file name, or an error if the template contains an unknown
+    /// placeholder or an unclosed brace.
+    pub fn render(
+        &self,
+        index: usize,
+        count: usize,
+        extension: &str,
+        data: &[u8],
+    ) -> Result<String, Box<dyn Error>> {
+        let mut name = String::new();
+        let mut rest = self.template.as_str();
+
+        while let Some(start) = rest.find('{') {
+            name.push_str(&rest[..start]);
+            let end = rest[start..].find('}').ok_or_else(|| {
+                format!("Output template {:?} has an unclosed brace.", self.template)
+            })?;
+            let placeholder = &rest[start + 1..start + end];
+            match placeholder {
+                "name" => name.push_str(&self.name),
+                "index" => name.push_str(&index.to_string()),
+                "page" => name.push_str(&(index + 1).to_string()),
+                "count" => name.push_str(&count.to_string()),
+                "scale" => name.push_str(&self.scale.to_string()),
+                "hash" => name.push_str(&format!("{:016x}", fnv1a(data))[..HASH_DIGITS]),
+                "ext" => name.push_str(extension),
+                _ => {
+                    return Err(format!(
+                        "Unknown placeholder {{{}}} in output template {:?}; expected one of {:?}.",
+                        placeholder, self.template, PLACEHOLDERS
+                    )
+                    .into())
+                }
+            }
+            rest = &rest[start + end + 1..];
+        }
+
+        name.push_str(rest);
+        Ok(name)
+    }
+}
+
+/// Hashes `data` with 64-bit FNV-1a, which is stable across platforms and releases.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Encodes every sheet, across the rayon thread pool with the `parallel` feature.
+fn encode_all(sprites: &[Sprite], format: &OutputFormat) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        sprites
+            .par_iter()
+            .map(|sprite| sprite.encode(format).map_err(sendable_error))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| error as Box<dyn Error>)
+    }
+    #[cfg(not(feature = "parallel"))]
+    sprites.iter().map(|sprite| sprite.encode(format)).collect()
+}
+
+/// Saves every sheet into `dir`, named by `template`.
+///
+/// Every sheet is encoded and named before any file is written, so a template giving two
+/// sheets the same name fails without touching the directory.
+///
+/// # Arguments
+/// - `sprites`: The generated sheets.
+/// - `dir`: Directory to write the sheets into; missing directories are created.
+/// - `template`: Names the file of each sheet.
+/// - `format`: Format to encode the sheets in, with its encoder settings.
+///
+/// # Returns
+/// A `Result` containing the file name of each sheet relative to `dir`, as expected by
+/// `ExportContext::with_images`, or an error if encoding, naming or writing fails.
+pub fn save_all(
+    sprites: &[Sprite],
+    dir: &str,
+    template: &OutputTemplate,
+    format: &OutputFormat,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut sheets = Vec::with_capacity(sprites.len());
+    let mut names = HashSet::new();
+
+    for (index, data) in encode_all(sprites, format)?.into_iter().enumerate() {
+        let name = template.render(index, sprites.len(), format.extension(), &data)?;
+        if !names.insert(name.clone()) {
+            return Err(format!(
+                "Output template {:?} gives several sheets the name {:?}.",
+                template.get_template(),
+                name
+            )
+            .into());
+        }
+        sheets.push((name, data));
+    }
+
+    for (name, data) in &sheets {
+        let path = Path::new(dir).join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_bytes(&path, data)?;
+    }
+
+    Ok(sheets.into_iter().map(|(name, _)| name).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SpriteratorError;
+    use crate::output_format::{PngOptions, WebPOptions};
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_render() {
+        let template = OutputTemplate::new("{name}-{index}@{scale}x.{ext}")
+            .with_name("atlas")
+            .with_scale(2.0);
+        assert_eq!(template.render(3, 5, "png", b"").unwrap(), "atlas-3@2x.png");
+        assert_eq!(
+            OutputTemplate::new("{page}of{count}.{hash}.webp")
+                .render(0, 2, "webp", b"")
+                .unwrap(),
+            "1of2.cbf29ce4.webp"
+        );
+        assert!(OutputTemplate::new("{size}.png")
+            .render(0, 1, "png", b"")
+            .is_err());
+        assert!(OutputTemplate::new("{name.png")
+            .render(0, 1, "png", b"")
+            .is_err());
+    }
+
+    #[test]
+    fn test_save_all() {
+        let dir = std::env::temp_dir().join("spriterator_save_all");
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir = dir.to_str().unwrap();
+        let sprites = vec![
+            Sprite::new(RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]))),
+            Sprite::new(RgbaImage::from_pixel(2, 2, Rgba([0, 0, 255, 255]))),
+        ];
+        let png = OutputFormat::Png(PngOptions::new());
+
+        let names = save_all(
+            &sprites,
+            dir,
+            &OutputTemplate::new("out/{name}-{hash}.{ext}"),
+            &png,
+        )
+        .unwrap();
+        assert_eq!(names.len(), 2);
+        assert_ne!(names[0], names[1]);
+        for (name, sprite) in names.iter().zip(&sprites) {
+            assert!(name.starts_with("out/sheet-") && name.ends_with(".png"));
+            let saved = image::open(Path::new(dir).join(name)).unwrap();
+            assert_eq!(&saved.to_rgba8(), sprite.get_image());
+        }
+
+        let error = save_all(&sprites, dir, &OutputTemplate::new("same.{ext}"), &png);
+        assert!(error.is_err());
+        assert!(!Path::new(dir).join("same.png").exists());
+
+        let wide = vec![Sprite::new(RgbaImage::new(20_000, 1))];
+        let webp = OutputFormat::WebP(WebPOptions::lossless());
+        let error = save_all(&wide, dir, &OutputTemplate::new("wide.{ext}"), &webp).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SpriteratorError>(),
+            Some(SpriteratorError::ImageTooLarge { .. })
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt::Write;
+
+use crate::export::escape_xml;
+use crate::rect::Rect;
+use crate::sprite::Sprite;
+
+/// Number of empty regions listed per sheet.
+const LARGEST_REGIONS: usize = 3;
+
+/// Packing statistics of a single sheet.
+#[derive(Debug, Clone)]
+pub struct SheetStats {
+    width: u32,
+    height: u32,
+    frames: usize,
+    used_pixels: u64,
+    largest_empty_regions: Vec<Rect>,
+}
+
+impl SheetStats {
+    /// Measures how well the frames of `sprite` fill its sheet.
+    ///
+    /// # Arguments
+    /// - `sprite`: The sheet to measure.
+    ///
+    /// # Returns
+    /// A `SheetStats` describing the sheet.
+    pub fn new(sprite: &Sprite) -> Self {
+        let image = sprite.get_image();
+        let bounds = Rect::new(0, 0, image.width(), image.height());
+
+        let mut largest_empty_regions: Vec<Rect> = Vec::new();
+        for region in sprite
+            .free_rects()
+            .iter()
+            .filter_map(|rect| rect.intersection(&bounds))
+        {
+            if !largest_empty_regions
+                .iter()
+                .any(|listed| listed.contains_rect(&region))
+            {
+                largest_empty_regions.push(region);
+            }
+        }
+        largest_empty_regions.sort_by_key(|rect| std::cmp::Reverse(rect.area()));
+        largest_empty_regions.truncate(LARGEST_REGIONS);
+
+        Self {
+            width: image.width(),
+            height: image.height(),
+            frames: sprite.get_frames().len(),
+            used_pixels: sprite
+                .get_frames()
+                .iter()
+                .map(|frame| frame.rect().area())
+                .sum(),
+            largest_empty_regions,
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns the number of sheet pixels covered by frames.
+    pub fn get_used_pixels(&self) -> u64 {
+        self.used_pixels
+    }
+
+    /// Returns the number of sheet pixels not covered by any frame.
+    pub fn get_wasted_pixels(&self) -> u64 {
+        (u64::from(self.width) * u64::from(self.height)).saturating_sub(self.used_pixels)
+    }
+
+    /// Returns the share of the sheet covered by frames, from 0 to 1.
+    pub fn fill_ratio(&self) -> f64 {
+        let area = u64::from(self.width) * u64::from(self.height);
+        if area == 0 {
+            0.0
+        } else {
+            self.used_pixels as f64 / area as f64
+        }
+    }
+
+    /// Returns the largest empty rectangles of the sheet, largest first.
+    pub fn get_largest_empty_regions(&self) -> &[Rect] {
+        &self.largest_empty_regions
+    }
+}
+
+/// A human-readable summary of generated sheets, meant to track atlas bloat over time.
+#[derive(Debug, Clone)]
+pub struct PackingReport {
+    sheets: Vec<SheetStats>,
+}
+
+impl PackingReport {
+    /// Measures every sheet of `sprites`.
+    ///
+    /// # Arguments
+    /// - `sprites`: The generated sheets, as returned by `Spriterator::generate`.
+    ///
+    /// # Returns
+    /// A `PackingReport` with one entry per sheet.
+    pub fn new(sprites: &[Sprite]) -> Self {
+        Self {
+            sheets: sprites.iter().map(SheetStats::new).collect(),
+        }
+    }
+
+    pub fn get_sheets(&self) -> &[SheetStats] {
+        &self.sheets
+    }
+
+    /// Returns the number of pixels not covered by frames across all sheets.
+    pub fn get_wasted_pixels(&self) -> u64 {
+        self.sheets.iter().map(SheetStats::get_wasted_pixels).sum()
+    }
+
+    fn rows(&self) -> Vec<[String; 6]> {
+        self.sheets
+            .iter()
+            .enumerate()
+            .map(|(index, sheet)| {
+                [
+                    index.to_string(),
+                    format!("{}x{}", sheet.width, sheet.height),
+                    sheet.frames.to_string(),
+                    format!("{:.1}%", sheet.fill_ratio() * 100.0),
+                    sheet.get_wasted_pixels().to_string(),
+                    sheet
+                        .largest_empty_regions
+                        .iter()
+                        .map(|rect| format!("{}x{} at ({}, {})", rect.w, rect.h, rect.x, rect.y))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ]
+            })
+            .collect()
+    }
+
+    /// Renders the report as a Markdown table.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# Packing report\n\n{} sheets, {} wasted pixels\n\n\
+             | Sheet | Size | Frames | Fill | Wasted pixels | Largest empty regions |\n\
+             | ---: | ---: | ---: | ---: | ---: | --- |\n",
+            self.sheets.len(),
+            self.get_wasted_pixels(),
+        );
+
+        for row in self.rows() {
+            let _ = writeln!(markdown, "| {} |", row.join(" | "));
+        }
+
+        markdown
+    }
+
+    /// Renders the report as a standalone HTML page.
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Packing report</title>
+</head>
+<body>
+<h1>Packing report</h1>
+<p>{} sheets, {} wasted pixels</p>
+<table>
+<tr><th>Sheet</th><th>Size</th><th>Frames</th><th>Fill</th><th>Wasted pixels</th><th>Largest empty regions</th></tr>
+",
+            self.sheets.len(),
+            self.get_wasted_pixels(),
+        );
+
+        for row in self.rows() {
+            html.push_str("<tr>");
+            for cell in row {
+                let _ = write!(html, "<td>{}</td>", escape_xml(&cell));
+            }
+            html.push_str("</tr>\n");
+        }
+
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_packing_report() {
+        let mut sprite = Sprite::new(RgbaImage::new(30, 20));
+        sprite.add_named_frame("a.png", 0, 0, 10, 20);
+        sprite.add_named_frame("b.png", 10, 0, 10, 10);
+
+        let report = PackingReport::new(&[sprite]);
+        let sheet = &report.get_sheets()[0];
+        assert_eq!(sheet.get_frames(), 2);
+        assert_eq!(sheet.get_used_pixels(), 300);
+        assert_eq!(sheet.get_wasted_pixels(), 300);
+        assert_eq!(sheet.fill_ratio(), 0.5);
+        assert_eq!(
+            sheet.get_largest_empty_regions(),
+            &[Rect::new(20, 0, 10, 20), Rect::new(10, 10, 20, 10)]
+        );
+        assert_eq!(report.get_wasted_pixels(), 300);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("1 sheets, 300 wasted pixels"));
+        assert!(markdown
+            .contains("| 0 | 30x20 | 2 | 50.0% | 300 | 10x20 at (20, 0), 20x10 at (10, 10) |"));
+
+        let html = report.to_html();
+        assert!(html.contains("<tr><td>0</td><td>30x20</td><td>2</td><td>50.0%</td>"));
+    }
+
+    #[test]
+    fn test_empty_sheet() {
+        let report = PackingReport::new(&[Sprite::new(RgbaImage::new(0, 0))]);
+        assert_eq!(report.get_sheets()[0].fill_ratio(), 0.0);
+        assert!(report.get_sheets()[0]
+            .get_largest_empty_regions()
+            .is_empty());
+    }
+}
@@ -0,0 +1,403 @@
+use image::codecs::png::{CompressionType, FilterType};
+use miniz_oxide::deflate::core::{
+    compress, create_comp_flags_from_zip_params, CompressorOxide, TDEFLFlush, TDEFLStatus,
+};
+use rayon::prelude::*;
+use std::error::Error;
+use std::io::Write;
+
+/// Number of bytes of pixel rows filtered and compressed by each task.
+const CHUNK_BYTES: usize = 1 << 20;
+
+/// Modulus of the Adler-32 checksum.
+const ADLER_BASE: u32 = 65521;
+
+/// Largest number of bytes summed before the Adler-32 sums must be reduced to avoid overflow.
+const ADLER_BLOCK: usize = 5552;
+
+/// Filter types of PNG, in the order of their numbers.
+const FILTERS: [u8; 5] = [0, 1, 2, 3, 4];
+
+/// Returns how many rows of `stride` bytes each task compresses.
+fn rows_per_chunk(stride: usize) -> usize {
+    (CHUNK_BYTES / stride.max(1)).max(1)
+}
+
+/// Returns whether encoding an RGBA image with rows of `stride` bytes with `encode` is worth
+/// it: the image must span several chunks, and several threads must be available, as
+/// `PngEncoder` is faster on a single one.
+pub(crate) fn is_worth_splitting(stride: usize, height: u32) -> bool {
+    rows_per_chunk(stride) < height as usize && rayon::current_num_threads() > 1
+}
+
+/// Encodes RGBA samples as a PNG, filtering and compressing chunks of rows across the rayon
+/// thread pool.
+///
+/// Each chunk is compressed into its own run of deflate blocks ending in a sync flush, so
+/// that the runs join into one zlib stream, as pigz does. The image decodes to the same
+/// pixels as with `PngEncoder`, though chunks do not share their dictionary, so the file is
+/// slightly larger.
+///
+/// # Arguments
+/// - `data`: The samples, 16-bit ones in big-endian order.
+/// - `bit_depth`: 8 or 16 bits per channel.
+pub(crate) fn encode<W: Write>(
+    writer: W,
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    compression: CompressionType,
+    filter: FilterType,
+) -> Result<(), Box<dyn Error>> {
+    let stride = width as usize * 4 * usize::from(bit_depth / 8);
+    encode_in_chunks(
+        writer,
+        data,
+        (width, height),
+        bit_depth,
+        (compression, filter),
+        rows_per_chunk(stride),
+    )
+}
+
+fn encode_in_chunks<W: Write>(
+    mut writer: W,
+    data: &[u8],
+    (width, height): (u32, u32),
+    bit_depth: u8,
+    (compression, filter): (CompressionType, FilterType),
+    rows_per_chunk: usize,
+) -> Result<(), Box<dyn Error>> {
+    let bytes_per_pixel = 4 * usize::from(bit_depth / 8);
+    let stride = width as usize * bytes_per_pixel;
+    if data.len() != stride * height as usize {
+        return Err("Image data does not match its size.".into());
+    }
+    let (level, zlib_level) = match compression {
+        CompressionType::Default => (6, 0x9c),
+        CompressionType::Best => (9, 0xda),
+        _ => (1, 0x01),
+    };
+    let filter = match filter {
+        FilterType::NoFilter => Some(0),
+        FilterType::Sub => Some(1),
+        FilterType::Up => Some(2),
+        FilterType::Avg => Some(3),
+        FilterType::Paeth => Some(4),
+        _ => None,
+    };
+
+    let chunks = (height as usize).div_ceil(rows_per_chunk);
+    let compressed: Vec<(Vec<u8>, u32, usize)> = (0..chunks)
+        .into_par_iter()
+        .map(|chunk| {
+            let start = chunk * rows_per_chunk;
+            let end = (start + rows_per_chunk).min(height as usize);
+            let filtered = filter_rows(data, stride, bytes_per_pixel, start..end, filter);
+            let deflated = deflate(&filtered, level, chunk + 1 == chunks)?;
+            Ok((deflated, adler32(&filtered), filtered.len()))
+        })
+        .collect::<Result<_, String>>()?;
+
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // RGBA color, deflate compression, adaptive filtering and no interlacing.
+    header.extend_from_slice(&[bit_depth, 6, 0, 0, 0]);
+    write_chunk(&mut writer, b"IHDR", &header)?;
+
+    let checksum = compressed.iter().fold(1, |checksum, &(_, adler, length)| {
+        adler32_combine(checksum, adler, length)
+    });
+    let count = compressed.len();
+    for (index, (mut deflated, _, _)) in compressed.into_iter().enumerate() {
+        if index == 0 {
+            deflated.splice(0..0, [0x78, zlib_level]);
+        }
+        if index + 1 == count {
+            deflated.extend_from_slice(&checksum.to_be_bytes());
+        }
+        write_chunk(&mut writer, b"IDAT", &deflated)?;
+    }
+    write_chunk(&mut writer, b"IEND", &[])?;
+    Ok(())
+}
+
+/// Writes a PNG chunk of type `kind` holding `data`.
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    writer.write_all(&crc.finalize().to_be_bytes())
+}
+
+/// Filters the given rows of `data` with `filter`, or with the filter minimizing the sum of
+/// absolute differences of each row if `None`, as the adaptive filter of `png` does.
+fn filter_rows(
+    data: &[u8],
+    stride: usize,
+    bytes_per_pixel: usize,
+    rows: std::ops::Range<usize>,
+    filter: Option<u8>,
+) -> Vec<u8> {
+    let zeros = vec![0; stride];
+    let mut filtered = Vec::with_capacity(rows.len() * (stride + 1));
+    let (mut candidate, mut best) = (Vec::with_capacity(stride + 1), Vec::new());
+    for y in rows {
+        let row = &data[y * stride..][..stride];
+        let previous = match y {
+            0 => &zeros[..],
+            _ => &data[(y - 1) * stride..][..stride],
+        };
+        match filter {
+            Some(filter) => apply_filter(filter, row, previous, bytes_per_pixel, &mut filtered),
+            None => {
+                let mut lowest = u64::MAX;
+                for filter in FILTERS {
+                    candidate.clear();
+                    apply_filter(filter, row, previous, bytes_per_pixel, &mut candidate);
+                    let cost = cost(&candidate[1..]);
+                    if cost < lowest {
+                        lowest = cost;
+                        std::mem::swap(&mut candidate, &mut best);
+                    }
+                }
+                filtered.extend_from_slice(&best);
+            }
+        }
+    }
+    filtered
+}
+
+/// Returns the sum of the absolute values of `filtered`, read as signed bytes.
+fn cost(filtered: &[u8]) -> u64 {
+    // Sums of blocks fit in 32 bits, which vectorize better than 64-bit ones.
+    filtered
+        .chunks(1 << 16)
+        .map(|block| {
+            let sum: u32 = block
+                .iter()
+                .map(|&byte| u32::from((byte as i8).unsigned_abs()))
+                .sum();
+            u64::from(sum)
+        })
+        .sum()
+}
+
+/// Appends the type byte of `filter` and `row` filtered with it to `output`.
+///
+/// Each filter runs its own loop over zipped slices, which compilers vectorize.
+fn apply_filter(
+    filter: u8,
+    row: &[u8],
+    previous: &[u8],
+    bytes_per_pixel: usize,
+    output: &mut Vec<u8>,
+) {
+    output.push(filter);
+    let start = output.len();
+    output.extend_from_slice(row);
+    let filtered = &mut output[start..];
+    // Pixels of the first column have no left neighbours, which count as zero.
+    let first = bytes_per_pixel.min(row.len());
+    let (head, tail) = filtered.split_at_mut(first);
+    let (left, above, upper_left) = (row, &previous[first..], previous);
+    match filter {
+        0 => {}
+        1 => {
+            for (value, &left) in tail.iter_mut().zip(left) {
+                *value = value.wrapping_sub(left);
+            }
+        }
+        2 => {
+            for (value, &above) in filtered.iter_mut().zip(previous) {
+                *value = value.wrapping_sub(above);
+            }
+        }
+        3 => {
+            for (value, &above) in head.iter_mut().zip(previous) {
+                *value = value.wrapping_sub(above / 2);
+            }
+            for ((value, &left), &above) in tail.iter_mut().zip(left).zip(above) {
+                *value = value.wrapping_sub(((u16::from(left) + u16::from(above)) / 2) as u8);
+            }
+        }
+        _ => {
+            for (value, &above) in head.iter_mut().zip(previous) {
+                *value = value.wrapping_sub(above);
+            }
+            for (((value, &left), &above), &upper_left) in
+                tail.iter_mut().zip(left).zip(above).zip(upper_left)
+            {
+                *value = value.wrapping_sub(paeth(left, above, upper_left));
+            }
+        }
+    }
+}
+
+/// Returns whichever of the neighbours is closest to `left + above - upper_left`.
+fn paeth(left: u8, above: u8, upper_left: u8) -> u8 {
+    let (left, above, upper_left) = (i16::from(left), i16::from(above), i16::from(upper_left));
+    let to_left = (above - upper_left).abs();
+    let to_above = (left - upper_left).abs();
+    let to_upper_left = (left + above - 2 * upper_left).abs();
+    let predicted = if (to_left <= to_above) & (to_left <= to_upper_left) {
+        left
+    } else if to_above <= to_upper_left {
+        above
+    } else {
+        upper_left
+    };
+    predicted as u8
+}
+
+/// Compresses `data` into raw deflate blocks, ending the stream if `last`, and with a sync
+/// flush otherwise so that more blocks can follow.
+fn deflate(data: &[u8], level: u8, last: bool) -> Result<Vec<u8>, String> {
+    let mut compressor =
+        CompressorOxide::new(create_comp_flags_from_zip_params(level.into(), -15, 0));
+    let flush = if last {
+        TDEFLFlush::Finish
+    } else {
+        TDEFLFlush::Sync
+    };
+    let mut output = vec![0; data.len() + data.len() / 1000 + 64];
+    let (mut read, mut written) = (0, 0);
+    loop {
+        let (status, consumed, produced) = compress(
+            &mut compressor,
+            &data[read..],
+            &mut output[written..],
+            flush,
+        );
+        read += consumed;
+        written += produced;
+        let finished = match flush {
+            TDEFLFlush::Finish => status == TDEFLStatus::Done,
+            _ => read == data.len() && written < output.len(),
+        };
+        if finished {
+            break;
+        }
+        if matches!(status, TDEFLStatus::BadParam | TDEFLStatus::PutBufFailed) {
+            return Err(format!("Compressing image data failed with {:?}.", status));
+        }
+        output.resize(output.len() * 2, 0);
+    }
+    output.truncate(written);
+    Ok(output)
+}
+
+/// Returns the Adler-32 checksum of `data`.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut sum, mut sums) = (1u32, 0u32);
+    for block in data.chunks(ADLER_BLOCK) {
+        for &byte in block {
+            sum += u32::from(byte);
+            sums += sum;
+        }
+        sum %= ADLER_BASE;
+        sums %= ADLER_BASE;
+    }
+    (sums << 16) | sum
+}
+
+/// Returns the Adler-32 checksum of two runs of bytes from their checksums, `length` being
+/// the number of bytes of the second run.
+fn adler32_combine(first: u32, second: u32, length: usize) -> u32 {
+    let base = u64::from(ADLER_BASE);
+    let length = length as u64 % base;
+    let (first_sum, first_sums) = (u64::from(first & 0xffff), u64::from(first >> 16));
+    let (second_sum, second_sums) = (u64::from(second & 0xffff), u64::from(second >> 16));
+    let sum = (first_sum + second_sum + base - 1) % base;
+    let sums = (length * first_sum + first_sums + second_sums + base - length) % base;
+    ((sums << 16) | sum) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        let data: Vec<u8> = (0..20000u32).map(|value| (value * 7 % 251) as u8).collect();
+        for split in [0, 1, 5552, 12345, data.len()] {
+            let (first, second) = data.split_at(split);
+            assert_eq!(
+                adler32_combine(adler32(first), adler32(second), second.len()),
+                adler32(&data)
+            );
+        }
+    }
+
+    #[test]
+    fn test_decodes_to_same_pixels() {
+        let image = RgbaImage::from_fn(37, 23, |x, y| {
+            image::Rgba([
+                (x * 7) as u8,
+                (y * 11) as u8,
+                (x * y) as u8,
+                (x + y * 3) as u8,
+            ])
+        });
+        let filters = [
+            FilterType::NoFilter,
+            FilterType::Sub,
+            FilterType::Up,
+            FilterType::Avg,
+            FilterType::Paeth,
+            FilterType::Adaptive,
+        ];
+        for (compression, filter) in [CompressionType::Fast, CompressionType::Best]
+            .into_iter()
+            .flat_map(|compression| filters.map(|filter| (compression, filter)))
+        {
+            for rows_per_chunk in [1, 4, 23] {
+                let mut png = Vec::new();
+                encode_in_chunks(
+                    &mut png,
+                    image.as_raw(),
+                    image.dimensions(),
+                    8,
+                    (compression, filter),
+                    rows_per_chunk,
+                )
+                .unwrap();
+                let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+                assert_eq!(decoded, image, "{:?} {:?}", compression, filter);
+            }
+        }
+
+        let wide: Vec<u8> = image
+            .as_raw()
+            .iter()
+            .flat_map(|&value| [value, 0])
+            .collect();
+        let mut png = Vec::new();
+        encode_in_chunks(
+            &mut png,
+            &wide,
+            image.dimensions(),
+            16,
+            (CompressionType::Default, FilterType::Adaptive),
+            5,
+        )
+        .unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba16();
+        let samples: Vec<u16> = image
+            .as_raw()
+            .iter()
+            .map(|&value| u16::from(value) << 8)
+            .collect();
+        assert_eq!(decoded.as_raw(), &samples);
+    }
+}
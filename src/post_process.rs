@@ -0,0 +1,40 @@
+use image::RgbaImage;
+use std::error::Error;
+use std::fmt;
+
+use crate::frame::Frame;
+
+/// A step run on every composed sheet before it is returned from `Spriterator::generate`,
+/// such as watermarking, encryption or channel packing.
+///
+/// Processors run in the order they were added, each receiving the output of the previous one.
+/// Frame coordinates are not adjusted, so processors that move pixels around must keep frames
+/// where they were.
+///
+/// Closures taking the sheet and its frames implement this trait.
+pub trait SheetPostProcessor: Send + Sync {
+    /// Processes a composed sheet.
+    ///
+    /// # Arguments
+    /// - `sheet`: The composed sheet, after cropping and power-of-two expansion.
+    /// - `frames`: The frames placed on the sheet.
+    ///
+    /// # Returns
+    /// A `Result` containing the processed sheet, or an error that aborts generation.
+    fn process(&self, sheet: RgbaImage, frames: &[Frame]) -> Result<RgbaImage, Box<dyn Error>>;
+}
+
+impl<F> SheetPostProcessor for F
+where
+    F: Fn(RgbaImage, &[Frame]) -> Result<RgbaImage, Box<dyn Error>> + Send + Sync,
+{
+    fn process(&self, sheet: RgbaImage, frames: &[Frame]) -> Result<RgbaImage, Box<dyn Error>> {
+        self(sheet, frames)
+    }
+}
+
+impl fmt::Debug for dyn SheetPostProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SheetPostProcessor")
+    }
+}
@@ -0,0 +1,129 @@
+use std::error::Error;
+
+/// Built-in profiles as `(name, max_width, max_height)`.
+const BUILTIN_PROFILES: [(&str, u32, u32); 9] = [
+    ("square-1024", 1024, 1024),
+    ("square-2048", 2048, 2048),
+    ("square-4096", 4096, 4096),
+    ("landscape-2048", 2048, 1024),
+    ("landscape-4096", 4096, 2048),
+    ("portrait-2048", 1024, 2048),
+    ("portrait-4096", 2048, 4096),
+    ("strip-4096", 4096, 512),
+    ("mobile", 2048, 2048),
+];
+
+/// A named set of sheet dimensions shared across projects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    name: String,
+    max_width: u32,
+    max_height: u32,
+    power_of_two: bool,
+}
+
+impl Profile {
+    /// Creates a custom profile.
+    ///
+    /// # Arguments
+    /// - `name`: Name the profile is selected by.
+    /// - `max_width`: Maximum width of the spritesheet.
+    /// - `max_height`: Maximum height of the spritesheet.
+    pub fn new(name: &str, max_width: u32, max_height: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            max_width,
+            max_height,
+            power_of_two: false,
+        }
+    }
+
+    /// Requires power-of-two sheet dimensions.
+    pub fn with_power_of_two(mut self, power_of_two: bool) -> Self {
+        self.power_of_two = power_of_two;
+        self
+    }
+
+    /// Returns the names of the built-in profiles.
+    pub fn builtin_names() -> Vec<&'static str> {
+        BUILTIN_PROFILES.iter().map(|&(name, _, _)| name).collect()
+    }
+
+    /// Looks up a profile by name.
+    ///
+    /// Besides the built-in names, `WIDTHxHEIGHT` strings such as `4096x2048` are accepted.
+    /// The `mobile` profile additionally requires power-of-two dimensions.
+    ///
+    /// # Returns
+    /// A `Result` containing the profile, or an error if the name is unknown.
+    pub fn parse(name: &str) -> Result<Self, Box<dyn Error>> {
+        let name = name.trim();
+
+        if let Some(&(name, max_width, max_height)) = BUILTIN_PROFILES
+            .iter()
+            .find(|(builtin, _, _)| builtin.eq_ignore_ascii_case(name))
+        {
+            return Ok(Self::new(name, max_width, max_height).with_power_of_two(name == "mobile"));
+        }
+
+        let dimensions = name
+            .split_once(['x', 'X', '×'])
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+
+        match dimensions {
+            Some((max_width, max_height)) if max_width > 0 && max_height > 0 => {
+                Ok(Self::new(name, max_width, max_height))
+            }
+            _ => Err(format!(
+                "Unknown profile {:?}; expected one of {:?} or WIDTHxHEIGHT.",
+                name,
+                Self::builtin_names()
+            )
+            .into()),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_max_width(&self) -> u32 {
+        self.max_width
+    }
+
+    pub fn get_max_height(&self) -> u32 {
+        self.max_height
+    }
+
+    pub fn is_power_of_two(&self) -> bool {
+        self.power_of_two
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_builtin() {
+        let profile = Profile::parse("Landscape-4096").unwrap();
+        assert_eq!(profile.get_name(), "landscape-4096");
+        assert_eq!(
+            (profile.get_max_width(), profile.get_max_height()),
+            (4096, 2048)
+        );
+        assert!(!profile.is_power_of_two());
+        assert!(Profile::parse("mobile").unwrap().is_power_of_two());
+    }
+
+    #[test]
+    fn test_parse_dimensions() {
+        let profile = Profile::parse("1024x512").unwrap();
+        assert_eq!(
+            (profile.get_max_width(), profile.get_max_height()),
+            (1024, 512)
+        );
+        assert!(Profile::parse("0x512").is_err());
+        assert!(Profile::parse("huge").is_err());
+    }
+}
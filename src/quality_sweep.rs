@@ -0,0 +1,266 @@
+use image::codecs::avif::AvifEncoder;
+use image::{ExtendedColorType, ImageEncoder, ImageFormat, RgbaImage};
+use std::error::Error;
+
+use crate::metrics::{psnr, ssim};
+
+/// AVIF encoder speed used during sweeps, trading a little size for much faster encodes.
+const AVIF_SPEED: u8 = 6;
+
+/// Lossy formats a sheet can be encoded to during a quality sweep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LossyFormat {
+    /// Lossy WebP, available with the `webp` feature.
+    #[cfg(feature = "webp")]
+    WebP,
+    /// AVIF. Quality metrics are only reported when the `image` crate can also decode AVIF.
+    Avif,
+}
+
+/// A single encoding produced by a quality sweep.
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+    format: LossyFormat,
+    quality: u8,
+    data: Vec<u8>,
+    psnr: Option<f64>,
+    ssim: Option<f64>,
+}
+
+impl SweepResult {
+    pub fn get_format(&self) -> LossyFormat {
+        self.format
+    }
+
+    pub fn get_quality(&self) -> u8 {
+        self.quality
+    }
+
+    /// Returns the size of the encoded sheet in bytes.
+    pub fn get_size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the encoded sheet.
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the PSNR in decibels, or `None` if the encoding could not be decoded back.
+    pub fn get_psnr(&self) -> Option<f64> {
+        self.psnr
+    }
+
+    /// Returns the SSIM in the range `0.0..=1.0`, or `None` if the encoding could not be decoded back.
+    pub fn get_ssim(&self) -> Option<f64> {
+        self.ssim
+    }
+}
+
+/// Encodes a sheet at several lossy quality levels and picks the smallest acceptable encoding.
+#[derive(Debug, Clone)]
+pub struct QualitySweep {
+    formats: Vec<LossyFormat>,
+    qualities: Vec<u8>,
+    min_ssim: Option<f64>,
+    min_psnr: Option<f64>,
+}
+
+impl Default for QualitySweep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QualitySweep {
+    /// Creates a sweep over every available lossy format at qualities 50 to 90,
+    /// accepting encodings with an SSIM of at least 0.98.
+    pub fn new() -> Self {
+        Self {
+            formats: vec![
+                #[cfg(feature = "webp")]
+                LossyFormat::WebP,
+                LossyFormat::Avif,
+            ],
+            qualities: vec![50, 60, 70, 80, 90],
+            min_ssim: Some(0.98),
+            min_psnr: None,
+        }
+    }
+
+    /// Sets the formats to encode to.
+    pub fn with_formats(mut self, formats: &[LossyFormat]) -> Self {
+        self.formats = formats.to_vec();
+        self
+    }
+
+    /// Sets the quality levels to try, each in the range `0..=100`.
+    pub fn with_qualities(mut self, qualities: &[u8]) -> Self {
+        self.qualities = qualities.iter().map(|&quality| quality.min(100)).collect();
+        self
+    }
+
+    /// Sets the minimum SSIM an encoding needs to be acceptable, or `None` to ignore SSIM.
+    pub fn with_min_ssim(mut self, min_ssim: Option<f64>) -> Self {
+        self.min_ssim = min_ssim;
+        self
+    }
+
+    /// Sets the minimum PSNR in decibels an encoding needs to be acceptable, or `None` to ignore PSNR.
+    pub fn with_min_psnr(mut self, min_psnr: Option<f64>) -> Self {
+        self.min_psnr = min_psnr;
+        self
+    }
+
+    /// Encodes `image` at every configured format and quality.
+    ///
+    /// # Arguments
+    /// - `image`: The sheet to encode.
+    ///
+    /// # Returns
+    /// A `Result` containing one `SweepResult` per format and quality, or an error if encoding fails.
+    pub fn run(&self, image: &RgbaImage) -> Result<Vec<SweepResult>, Box<dyn Error>> {
+        let mut results = Vec::new();
+
+        for &format in &self.formats {
+            for &quality in &self.qualities {
+                let data = encode(image, format, quality)?;
+                // Metrics compare pixel by pixel, so a decode of a different size is unusable.
+                let decoded = decode(&data, format)
+                    .filter(|decoded| decoded.dimensions() == image.dimensions());
+
+                results.push(SweepResult {
+                    format,
+                    quality,
+                    psnr: decoded.as_ref().map(|decoded| psnr(image, decoded)),
+                    ssim: decoded.as_ref().map(|decoded| ssim(image, decoded)),
+                    data,
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Returns `true` if `result` meets the configured quality thresholds.
+    ///
+    /// Results without metrics never qualify while a threshold is set.
+    pub fn is_acceptable(&self, result: &SweepResult) -> bool {
+        let meets = |threshold: Option<f64>, value: Option<f64>| match (threshold, value) {
+            (None, _) => true,
+            (Some(threshold), Some(value)) => value >= threshold,
+            (Some(_), None) => false,
+        };
+
+        meets(self.min_ssim, result.ssim) && meets(self.min_psnr, result.psnr)
+    }
+
+    /// Picks the smallest acceptable encoding from `results`.
+    pub fn best<'a>(&self, results: &'a [SweepResult]) -> Option<&'a SweepResult> {
+        results
+            .iter()
+            .filter(|result| self.is_acceptable(result))
+            .min_by_key(|result| result.get_size())
+    }
+}
+
+fn encode(image: &RgbaImage, format: LossyFormat, quality: u8) -> Result<Vec<u8>, Box<dyn Error>> {
+    match format {
+        #[cfg(feature = "webp")]
+        LossyFormat::WebP => {
+            Ok(
+                webp::Encoder::from_rgba(image.as_raw(), image.width(), image.height())
+                    .encode(quality as f32)
+                    .to_vec(),
+            )
+        }
+        LossyFormat::Avif => {
+            let mut data = Vec::new();
+            AvifEncoder::new_with_speed_quality(&mut data, AVIF_SPEED, quality).write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ExtendedColorType::Rgba8,
+            )?;
+            Ok(data)
+        }
+    }
+}
+
+fn decode(data: &[u8], format: LossyFormat) -> Option<RgbaImage> {
+    match format {
+        #[cfg(feature = "webp")]
+        LossyFormat::WebP => {
+            let decoded = webp::Decoder::new(data).decode()?;
+            let (width, height) = (decoded.width(), decoded.height());
+            if decoded.is_alpha() {
+                RgbaImage::from_raw(width, height, decoded.to_vec())
+            } else {
+                image::RgbImage::from_raw(width, height, decoded.to_vec())
+                    .map(|rgb| image::DynamicImage::ImageRgb8(rgb).to_rgba8())
+            }
+        }
+        LossyFormat::Avif => {
+            if !ImageFormat::Avif.reading_enabled() {
+                return None;
+            }
+            image::load_from_memory_with_format(data, ImageFormat::Avif)
+                .ok()
+                .map(|decoded| decoded.to_rgba8())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn gradient() -> RgbaImage {
+        let mut image = RgbaImage::new(32, 32);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = Rgba([(x * 8) as u8, (y * 8) as u8, 128, 255]);
+        }
+        image
+    }
+
+    #[test]
+    fn test_sweep_produces_one_result_per_quality() {
+        let sweep = QualitySweep::new()
+            .with_formats(&[LossyFormat::Avif])
+            .with_qualities(&[40, 80]);
+        let results = sweep.run(&gradient()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.get_size() > 0));
+    }
+
+    #[test]
+    fn test_best_ignores_unmeasured_results() {
+        let sweep = QualitySweep::new().with_formats(&[LossyFormat::Avif]);
+        let result = SweepResult {
+            format: LossyFormat::Avif,
+            quality: 50,
+            data: vec![0; 10],
+            psnr: None,
+            ssim: None,
+        };
+        assert!(sweep.best(std::slice::from_ref(&result)).is_none());
+        assert!(sweep.with_min_ssim(None).best(&[result]).is_some());
+    }
+
+    #[cfg(feature = "webp")]
+    #[test]
+    fn test_webp_sweep_picks_smallest_acceptable() {
+        let sweep = QualitySweep::new()
+            .with_formats(&[LossyFormat::WebP])
+            .with_qualities(&[10, 50, 100])
+            .with_min_ssim(Some(0.9));
+        let results = sweep.run(&gradient()).unwrap();
+        let best = sweep.best(&results).unwrap();
+        assert!(best.get_ssim().unwrap() >= 0.9);
+        assert!(results
+            .iter()
+            .filter(|result| sweep.is_acceptable(result))
+            .all(|result| result.get_size() >= best.get_size()));
+    }
+}
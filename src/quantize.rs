@@ -0,0 +1,218 @@
+use color_quant::NeuQuant;
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+
+/// Sampling factor of the NeuQuant network, the compromise between speed and quality its
+/// authors recommend.
+const SAMPLE_FACTOR: i32 = 10;
+
+/// How the error of replacing a color with its closest palette entry is spread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dithering {
+    /// Every pixel takes its closest palette entry, keeping flat areas flat.
+    None,
+    /// Floyd–Steinberg error diffusion, smoothing gradients at the cost of noise.
+    FloydSteinberg,
+}
+
+/// An image reduced to a palette, with one palette index per pixel in row-major order.
+pub(crate) struct Indexed {
+    pub(crate) palette: Vec<Rgba<u8>>,
+    pub(crate) indices: Vec<u8>,
+}
+
+impl Indexed {
+    /// Returns the smallest PNG bit depth able to hold every palette index.
+    pub(crate) fn bit_depth(&self) -> u8 {
+        match self.palette.len() {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        }
+    }
+
+    /// Packs the indices into rows of `bit_depth` bits per pixel, as PNG stores them.
+    pub(crate) fn packed(&self, width: u32) -> Vec<u8> {
+        let depth = self.bit_depth();
+        if depth == 8 {
+            return self.indices.clone();
+        }
+
+        let per_byte = (8 / depth) as usize;
+        let mut data = Vec::new();
+        for row in self.indices.chunks(width as usize) {
+            for pixels in row.chunks(per_byte) {
+                let byte = pixels.iter().enumerate().fold(0, |byte, (i, &index)| {
+                    byte | index << (8 - depth * (i as u8 + 1))
+                });
+                data.push(byte);
+            }
+        }
+        data
+    }
+}
+
+/// Reduces `image` to a palette of at most `max_colors` colors.
+///
+/// Images that already use few enough colors keep them exactly; others are quantized with
+/// NeuQuant. Fully transparent pixels all map to a single transparent entry.
+///
+/// # Arguments
+/// - `image`: The image to reduce.
+/// - `max_colors`: Maximum size of the palette, from 2 to 256.
+/// - `dithering`: How the error of quantized colors is spread over neighbouring pixels.
+pub(crate) fn quantize(image: &RgbaImage, max_colors: usize, dithering: Dithering) -> Indexed {
+    let pixels: Vec<[u8; 4]> = image
+        .pixels()
+        .map(|&Rgba(pixel)| if pixel[3] == 0 { [0; 4] } else { pixel })
+        .collect();
+
+    exact(&pixels, max_colors).unwrap_or_else(|| {
+        let network = NeuQuant::new(SAMPLE_FACTOR, max_colors, pixels.as_flattened());
+        let palette: Vec<Rgba<u8>> = (0..max_colors)
+            .filter_map(|index| network.lookup(index).map(Rgba))
+            .collect();
+        let indices = match dithering {
+            Dithering::None => pixels
+                .iter()
+                .map(|pixel| network.index_of(pixel) as u8)
+                .collect(),
+            Dithering::FloydSteinberg => {
+                floyd_steinberg(&pixels, image.width() as usize, &network, &palette)
+            }
+        };
+        Indexed { palette, indices }
+    })
+}
+
+/// Builds a palette of the distinct colors of `pixels`, if there are at most `max_colors`.
+fn exact(pixels: &[[u8; 4]], max_colors: usize) -> Option<Indexed> {
+    let mut entries = HashMap::new();
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for &pixel in pixels {
+        let index = *entries.entry(pixel).or_insert_with(|| {
+            palette.push(Rgba(pixel));
+            palette.len() - 1
+        });
+        if palette.len() > max_colors {
+            return None;
+        }
+        indices.push(index as u8);
+    }
+
+    Some(Indexed { palette, indices })
+}
+
+/// Maps `pixels` to palette entries, diffusing the error of each pixel to its unvisited neighbours.
+fn floyd_steinberg(
+    pixels: &[[u8; 4]],
+    width: usize,
+    network: &NeuQuant,
+    palette: &[Rgba<u8>],
+) -> Vec<u8> {
+    let mut errors = vec![[0.0f32; 4]; pixels.len()];
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        let wanted: [f32; 4] = std::array::from_fn(|c| f32::from(pixel[c]) + errors[i][c]);
+        let rounded = wanted.map(|value| value.round().clamp(0.0, 255.0) as u8);
+        let index = network.index_of(&rounded);
+        indices.push(index as u8);
+
+        let (x, last_row) = (i % width, i + width >= pixels.len());
+        let mut spread = |target: usize, weight: f32| {
+            for c in 0..4 {
+                errors[target][c] += (wanted[c] - f32::from(palette[index][c])) * weight;
+            }
+        };
+        if x + 1 < width {
+            spread(i + 1, 7.0 / 16.0);
+        }
+        if !last_row {
+            if x > 0 {
+                spread(i + width - 1, 3.0 / 16.0);
+            }
+            spread(i + width, 5.0 / 16.0);
+            if x + 1 < width {
+                spread(i + width + 1, 1.0 / 16.0);
+            }
+        }
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient() -> RgbaImage {
+        RgbaImage::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, 128, 255])
+        })
+    }
+
+    #[test]
+    fn test_exact_palette() {
+        let mut image = RgbaImage::from_pixel(5, 2, Rgba([10, 20, 30, 255]));
+        image.put_pixel(1, 0, Rgba([99, 99, 99, 0]));
+        image.put_pixel(2, 1, Rgba([1, 2, 3, 0]));
+        image.put_pixel(4, 1, Rgba([200, 0, 0, 128]));
+
+        let indexed = quantize(&image, 256, Dithering::FloydSteinberg);
+        assert_eq!(
+            indexed.palette,
+            vec![
+                Rgba([10, 20, 30, 255]),
+                Rgba([0, 0, 0, 0]),
+                Rgba([200, 0, 0, 128])
+            ]
+        );
+        assert_eq!(indexed.indices, vec![0, 1, 0, 0, 0, 0, 0, 1, 0, 2]);
+        assert_eq!(indexed.bit_depth(), 2);
+        assert_eq!(
+            indexed.packed(5),
+            vec![0b0001_0000, 0b0000_0000, 0b0000_0100, 0b1000_0000]
+        );
+    }
+
+    #[test]
+    fn test_quantized_palette() {
+        let image = gradient();
+        for dithering in [Dithering::None, Dithering::FloydSteinberg] {
+            let indexed = quantize(&image, 64, dithering);
+            assert!(indexed.palette.len() <= 64);
+            assert_eq!(indexed.indices.len(), 64 * 64);
+            assert!(indexed
+                .indices
+                .iter()
+                .all(|&index| (index as usize) < indexed.palette.len()));
+        }
+    }
+
+    #[test]
+    fn test_dithering_preserves_average() {
+        let image = RgbaImage::from_fn(64, 64, |x, y| {
+            let value = (x + y) as u8;
+            Rgba([value, value, value, 255])
+        });
+        let row_error = |dithering| {
+            let indexed = quantize(&image, 8, dithering);
+            (0..64)
+                .map(|y| {
+                    let row = &indexed.indices[y * 64..(y + 1) * 64];
+                    let output: u32 = row
+                        .iter()
+                        .map(|&i| u32::from(indexed.palette[i as usize][0]))
+                        .sum();
+                    let input: u32 = (0..64).map(|x| (x + y) as u32).sum();
+                    output.abs_diff(input)
+                })
+                .sum::<u32>()
+        };
+        assert!(row_error(Dithering::FloydSteinberg) < row_error(Dithering::None));
+    }
+}
@@ -0,0 +1,178 @@
+/// An axis-aligned rectangle in sheet pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, w: u32, h: u32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    /// Returns the x coordinate one past the right edge.
+    pub fn right(&self) -> u32 {
+        self.x.saturating_add(self.w)
+    }
+
+    /// Returns the y coordinate one past the bottom edge.
+    pub fn bottom(&self) -> u32 {
+        self.y.saturating_add(self.h)
+    }
+
+    pub fn area(&self) -> u64 {
+        self.w as u64 * self.h as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+
+    /// Returns the center of the rectangle, which may fall between pixels.
+    pub fn center(&self) -> (f32, f32) {
+        (
+            self.x as f32 + self.w as f32 / 2.0,
+            self.y as f32 + self.h as f32 / 2.0,
+        )
+    }
+
+    /// Returns `true` if the pixel at `point` lies inside the rectangle.
+    pub fn contains(&self, point: (u32, u32)) -> bool {
+        let (x, y) = point;
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// Returns `true` if `other` lies completely inside the rectangle.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// Returns `true` if the two rectangles share at least one pixel.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    /// Returns the overlapping part of the two rectangles, if any.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Some(Rect::new(
+            x,
+            y,
+            self.right().min(other.right()) - x,
+            self.bottom().min(other.bottom()) - y,
+        ))
+    }
+
+    /// Returns the smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+
+    /// Returns the maximal rectangles covering the parts of `self` not covered by `other`.
+    ///
+    /// The returned rectangles may overlap each other.
+    pub fn subtract(&self, other: &Rect) -> Vec<Rect> {
+        if !self.intersects(other) {
+            return vec![*self];
+        }
+
+        let mut pieces = Vec::with_capacity(4);
+        if other.x > self.x {
+            pieces.push(Rect::new(self.x, self.y, other.x - self.x, self.h));
+        }
+        if other.right() < self.right() {
+            pieces.push(Rect::new(
+                other.right(),
+                self.y,
+                self.right() - other.right(),
+                self.h,
+            ));
+        }
+        if other.y > self.y {
+            pieces.push(Rect::new(self.x, self.y, self.w, other.y - self.y));
+        }
+        if other.bottom() < self.bottom() {
+            pieces.push(Rect::new(
+                self.x,
+                other.bottom(),
+                self.w,
+                self.bottom() - other.bottom(),
+            ));
+        }
+        pieces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let rect = Rect::new(10, 10, 5, 5);
+        assert!(rect.contains((10, 10)));
+        assert!(rect.contains((14, 14)));
+        assert!(!rect.contains((15, 14)));
+        assert!(!rect.contains((9, 12)));
+    }
+
+    #[test]
+    fn test_intersects() {
+        let rect = Rect::new(0, 0, 10, 10);
+        assert!(rect.intersects(&Rect::new(9, 9, 5, 5)));
+        assert!(!rect.intersects(&Rect::new(10, 0, 5, 5)));
+        assert!(!rect.intersects(&Rect::new(2, 2, 0, 5)));
+        assert_eq!(
+            rect.intersection(&Rect::new(5, 8, 10, 10)),
+            Some(Rect::new(5, 8, 5, 2))
+        );
+    }
+
+    #[test]
+    fn test_area_center_union() {
+        let rect = Rect::new(2, 4, 3, 6);
+        assert_eq!(rect.area(), 18);
+        assert_eq!(rect.center(), (3.5, 7.0));
+        assert_eq!(rect.union(&Rect::new(0, 0, 1, 1)), Rect::new(0, 0, 5, 10));
+    }
+
+    #[test]
+    fn test_subtract() {
+        let rect = Rect::new(0, 0, 10, 10);
+        assert_eq!(rect.subtract(&Rect::new(20, 20, 1, 1)), vec![rect]);
+        assert!(rect.subtract(&Rect::new(0, 0, 10, 10)).is_empty());
+        assert_eq!(
+            rect.subtract(&Rect::new(0, 0, 4, 6)),
+            vec![Rect::new(4, 0, 6, 10), Rect::new(0, 6, 10, 4)]
+        );
+    }
+}
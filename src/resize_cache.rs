@@ -0,0 +1,74 @@
+use image::imageops::FilterType;
+use image::RgbaImage;
+use std::error::Error;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use crate::atomic_write::write_bytes;
+use crate::build_cache::Fingerprint;
+
+/// Directory of resized images, stored as raw RGBA pixels in files named after the
+/// fingerprint of the original pixels and the resize settings.
+pub(crate) struct ResizeCache<'a> {
+    dir: &'a Path,
+}
+
+impl<'a> ResizeCache<'a> {
+    pub(crate) fn new(dir: &'a Path) -> Self {
+        Self { dir }
+    }
+
+    /// Returns the path of the entry for resizing `image` to `width`×`height` with `filter`.
+    pub(crate) fn entry(
+        &self,
+        image: &RgbaImage,
+        width: u32,
+        height: u32,
+        filter: FilterType,
+    ) -> PathBuf {
+        let mut fingerprint = Fingerprint::default();
+        fingerprint.add(&env!("CARGO_PKG_VERSION"));
+        fingerprint.add(&(image.dimensions(), width, height, filter));
+        fingerprint.write(image.as_raw());
+        self.dir.join(format!("{:016x}.rgba", fingerprint.finish()))
+    }
+
+    /// Reads the `width`×`height` image stored at `entry`.
+    ///
+    /// # Returns
+    /// The image, or `None` if the entry is missing or does not hold an image of that size.
+    pub(crate) fn get(&self, entry: &Path, width: u32, height: u32) -> Option<RgbaImage> {
+        RgbaImage::from_raw(width, height, std::fs::read(entry).ok()?)
+    }
+
+    /// Stores `image` at `entry`, creating the cache directory if needed.
+    pub(crate) fn put(&self, entry: &Path, image: &RgbaImage) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(self.dir)?;
+        write_bytes(entry, image.as_raw())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_entries() {
+        let dir = std::env::temp_dir().join("spriterator_resize_cache_entries");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = ResizeCache::new(&dir);
+        let image = RgbaImage::from_pixel(4, 4, Rgba([1, 2, 3, 4]));
+
+        let entry = cache.entry(&image, 2, 2, FilterType::Lanczos3);
+        assert_ne!(entry, cache.entry(&image, 2, 2, FilterType::Nearest));
+        assert_ne!(entry, cache.entry(&image, 2, 3, FilterType::Lanczos3));
+        assert_eq!(cache.get(&entry, 2, 2), None);
+
+        let resized = RgbaImage::from_pixel(2, 2, Rgba([5, 6, 7, 8]));
+        cache.put(&entry, &resized).unwrap();
+        assert_eq!(cache.get(&entry, 2, 2), Some(resized));
+        assert_eq!(cache.get(&entry, 4, 2), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::frame::Frame;
+use crate::sprite::Sprite;
+
+/// Size and frames of a sheet laid out by `Spriterator::plan_layout`, without its pixels.
+#[derive(Debug, Clone)]
+pub struct SheetLayout {
+    width: u32,
+    height: u32,
+    frames: Vec<Frame>,
+}
+
+impl SheetLayout {
+    pub(crate) fn new(width: u32, height: u32, frames: Vec<Frame>) -> Self {
+        Self {
+            width,
+            height,
+            frames,
+        }
+    }
+
+    /// Returns the layout of a generated sheet.
+    pub(crate) fn of(sprite: &Sprite) -> Self {
+        let (width, height) = sprite.get_image().dimensions();
+        Self::new(width, height, sprite.get_frames().to_vec())
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_frames(&self) -> &[Frame] {
+        &self.frames
+    }
+}
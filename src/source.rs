@@ -0,0 +1,980 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, ImageFormat, ImageReader, RgbaImage};
+use regex::Regex;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::color_space::{convert_to_srgb, read_profile};
+use crate::export::strip_extension;
+use crate::warning::{Warning, WarningHandler};
+
+#[cfg(feature = "http")]
+pub(crate) mod http;
+pub(crate) mod manifest;
+pub(crate) mod sheet;
+#[cfg(feature = "tar")]
+pub(crate) mod tar;
+#[cfg(feature = "zip")]
+pub(crate) mod zip;
+
+/// Extensions of the images packed unless `with_extensions` says otherwise.
+pub(crate) const DEFAULT_EXTENSIONS: &[&str] = &[
+    "png",
+    "webp",
+    "gif",
+    "bmp",
+    "tif",
+    "tiff",
+    "tga",
+    "ico",
+    #[cfg(feature = "svg")]
+    "svg",
+    #[cfg(feature = "avif-decode")]
+    "avif",
+];
+
+/// Name of the files listing paths to skip in gitignore syntax, honored in the walked
+/// directory and its subdirectories.
+pub(crate) const IGNORE_FILE_NAME: &str = ".spriteignore";
+
+/// Extensions of image formats that are recognized but cannot be decoded, reported with
+/// `Warning::UnsupportedFormat` instead of being skipped silently. AVIF is decoded with the
+/// `avif-decode` feature.
+const UNSUPPORTED_EXTENSIONS: &[&str] = &[
+    #[cfg(not(feature = "avif-decode"))]
+    "avif",
+];
+
+/// Lowercases `extensions` and removes leading dots.
+pub(crate) fn normalize_extensions(extensions: &[&str]) -> Vec<String> {
+    extensions
+        .iter()
+        .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+        .collect()
+}
+
+/// Returns the extensions of every format this build can decode, followed by any default
+/// extensions not decoded by the `image` crate.
+pub(crate) fn decodable_extensions() -> Vec<String> {
+    let mut extensions: Vec<String> = Vec::new();
+    for ext in ImageFormat::all()
+        .filter(ImageFormat::reading_enabled)
+        .flat_map(|format| format.extensions_str())
+        .chain(DEFAULT_EXTENSIONS)
+    {
+        if !extensions.iter().any(|known| known == ext) {
+            extensions.push(ext.to_string());
+        }
+    }
+    extensions
+}
+
+/// An input image of a `DirectorySource` read for its size only, or decoded when its size
+/// cannot be read from its header.
+pub(crate) enum Probe {
+    Size {
+        name: String,
+        width: u32,
+        height: u32,
+    },
+    Decoded(NamedImage),
+}
+
+/// An image to pack, together with the name of its frame.
+#[derive(Debug, Clone)]
+pub struct NamedImage {
+    pub(crate) name: String,
+    pub(crate) image: RgbaImage,
+    pub(crate) duration: Option<u32>,
+    pub(crate) pivot: Option<(f64, f64)>,
+}
+
+impl NamedImage {
+    /// Creates an image to pack.
+    ///
+    /// # Arguments
+    /// - `name`: Name of the frame, such as `hero/walk.png`.
+    /// - `image`: The image.
+    pub fn new(name: &str, image: RgbaImage) -> Self {
+        Self {
+            name: name.to_string(),
+            image,
+            duration: None,
+            pivot: None,
+        }
+    }
+
+    /// Sets how long the frame is shown in milliseconds, for frames of animated images.
+    pub fn with_duration(mut self, duration: Option<u32>) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the pivot of the frame, normalized to the image with the origin at the top-left
+    /// corner, see `Frame::get_pivot`.
+    pub fn with_pivot(mut self, pivot: Option<(f64, f64)>) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    pub fn get_duration(&self) -> Option<u32> {
+        self.duration
+    }
+
+    pub fn get_pivot(&self) -> Option<(f64, f64)> {
+        self.pivot
+    }
+}
+
+/// Provides the images to pack, such as the files of a directory, an archive or a network
+/// location.
+///
+/// Images are packed in the order they are returned. Add sources with
+/// `Spriterator::with_image_source`; the directory given to `Spriterator::new` is read with a
+/// `DirectorySource`.
+pub trait ImageSource: Send + Sync {
+    /// Returns the images of the source.
+    ///
+    /// # Returns
+    /// An iterator over the images, where an error aborts generation.
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_>;
+
+    /// Writes what the images of the source are read from to `hasher`, without decoding them,
+    /// so that `Spriterator::generate_incremental` can tell whether they changed.
+    ///
+    /// # Returns
+    /// A `Result` containing `false` if nothing was written, in which case the images are
+    /// decoded and hashed instead, which is the default.
+    fn fingerprint(&self, hasher: &mut dyn Hasher) -> Result<bool, Box<dyn Error>> {
+        let _ = hasher;
+        Ok(false)
+    }
+}
+
+impl ImageSource for Vec<NamedImage> {
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+        Box::new(self.iter().cloned().map(Ok))
+    }
+}
+
+impl fmt::Debug for dyn ImageSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ImageSource")
+    }
+}
+
+/// Compiles glob patterns such as `**/*_idle_*.png`, where `*` does not cross directories
+/// but `**` does.
+///
+/// # Returns
+/// `None` if there are no patterns, or an error describing an invalid pattern.
+pub(crate) fn glob_set(patterns: &[String]) -> Result<Option<GlobSet>, globset::Error> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+    }
+    builder.build().map(Some)
+}
+
+/// Decoding options shared by the sources reading image files.
+#[derive(Debug, Clone)]
+pub(crate) struct DecodeOptions {
+    extensions: Vec<String>,
+    animations: bool,
+    color_management: bool,
+    #[cfg(feature = "svg")]
+    svg_scale: f32,
+    warning_handler: WarningHandler,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            extensions: normalize_extensions(DEFAULT_EXTENSIONS),
+            animations: true,
+            color_management: false,
+            #[cfg(feature = "svg")]
+            svg_scale: 1.0,
+            warning_handler: WarningHandler::default(),
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Returns whether the file at `path` has one of the configured extensions, warning
+    /// about files in formats this build cannot decode.
+    fn accepts(&self, path: &Path) -> bool {
+        let Some(extension) = extension(path) else {
+            return false;
+        };
+        if UNSUPPORTED_EXTENSIONS.contains(&extension.as_str()) {
+            self.warning_handler.warn(Warning::UnsupportedFormat {
+                path: path.to_path_buf(),
+            });
+            return false;
+        }
+
+        self.extensions.contains(&extension)
+    }
+
+    /// Decodes the contents of the file at `path` into one image per frame named after `name`.
+    fn decode(&self, path: &Path, name: &str, data: &[u8]) -> Vec<NamedImage> {
+        let mut frames = self.decode_frames(path, data).unwrap_or_else(|| {
+            self.warning_handler.warn(Warning::UndecodableImage {
+                path: path.to_path_buf(),
+            });
+            Vec::new()
+        });
+        let count = frames.len();
+
+        if self.color_management && count > 0 {
+            if let Some(profile) = read_profile(data, ImageFormat::from_path(path).ok()) {
+                let converted = frames
+                    .iter_mut()
+                    .try_for_each(|(image, _)| convert_to_srgb(image, &profile));
+                if let Err(message) = converted {
+                    self.warning_handler.warn(Warning::UnconvertedColorProfile {
+                        path: path.to_path_buf(),
+                        message,
+                    });
+                }
+            }
+        }
+
+        frames
+            .into_iter()
+            .enumerate()
+            .map(|(index, (image, duration))| {
+                let name = if count > 1 {
+                    animation_frame_name(name, index, count)
+                } else {
+                    name.to_string()
+                };
+                NamedImage::new(&name, image).with_duration(duration)
+            })
+            .collect()
+    }
+
+    /// Reads the size of the image at `path` from its header, without decoding its pixels.
+    ///
+    /// Returns `None` for images that must be decoded to know their size or frame count: SVG
+    /// images, and GIF, APNG and animated WebP images when animations are enabled, as well
+    /// as images whose header cannot be read or whose pixels cannot be decoded. The pixels
+    /// are decoded and dropped, so that a file with a valid header but truncated data is not
+    /// laid out only to be skipped when it is loaded.
+    fn probe_size(&self, path: &Path) -> Option<(u32, u32)> {
+        let open = || Some(BufReader::new(File::open(path).ok()?));
+        let needs_decoding = match extension(path).as_deref() {
+            Some("svg") => true,
+            Some("gif") => self.animations,
+            Some("png") if self.animations => PngDecoder::new(open()?).ok()?.is_apng().ok()?,
+            Some("webp") if self.animations => WebPDecoder::new(open()?).ok()?.has_animation(),
+            _ => false,
+        };
+        if needs_decoding {
+            return None;
+        }
+
+        let open_reader = || {
+            let reader = ImageReader::open(path).ok()?;
+            match reader.format() {
+                Some(_) => Some(reader),
+                None => reader.with_guessed_format().ok(),
+            }
+        };
+        let dimensions = open_reader()?.into_dimensions().ok()?;
+        open_reader()?.decode().ok()?;
+        Some(dimensions)
+    }
+
+    /// Decodes the frames of the image at `path`, with the duration of each in milliseconds.
+    ///
+    /// Animated GIF, PNG and WebP images yield one entry per frame, or only their first frame
+    /// unless animations are enabled; other images a single entry without a duration.
+    /// SVG images are rasterized at the configured scale. Returns `None` if the file cannot
+    /// be decoded.
+    fn decode_frames(&self, path: &Path, data: &[u8]) -> Option<Vec<(RgbaImage, Option<u32>)>> {
+        let extension = extension(path);
+        let still = || {
+            let image = match ImageFormat::from_path(path) {
+                Ok(format) => image::load_from_memory_with_format(data, format),
+                Err(_) => image::load_from_memory(data),
+            };
+            Some(vec![(image.ok()?.to_rgba8(), None)])
+        };
+
+        #[cfg(feature = "svg")]
+        if extension.as_deref() == Some("svg") {
+            return Some(vec![(
+                rasterize_svg(data, path.parent(), self.svg_scale)?,
+                None,
+            )]);
+        }
+
+        let reader = Cursor::new(data);
+        let frames = match extension.as_deref() {
+            Some("gif") => GifDecoder::new(reader).ok()?.into_frames(),
+            Some("png") => {
+                let decoder = PngDecoder::new(reader).ok()?;
+                if !decoder.is_apng().ok()? {
+                    return still();
+                }
+                decoder.apng().ok()?.into_frames()
+            }
+            Some("webp") => {
+                let decoder = WebPDecoder::new(reader).ok()?;
+                if !decoder.has_animation() {
+                    return still();
+                }
+                decoder.into_frames()
+            }
+            _ => return still(),
+        };
+
+        let frames = if self.animations {
+            frames.collect_frames().ok()?
+        } else {
+            frames.take(1).collect::<Result<Vec<_>, _>>().ok()?
+        };
+        if frames.len() == 1 {
+            return frames
+                .into_iter()
+                .map(|frame| vec![(frame.into_buffer(), None)])
+                .next();
+        }
+
+        Some(
+            frames
+                .into_iter()
+                .map(|frame| {
+                    let (numerator, denominator) = frame.delay().numer_denom_ms();
+                    let duration = numerator.checked_div(denominator).unwrap_or(0);
+                    (frame.into_buffer(), Some(duration))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Implements the decoding builders of a source holding `DecodeOptions` in `options`.
+macro_rules! decode_builders {
+    ($source:ty) => {
+        impl $source {
+            /// Splits animated images into one frame each, see `Spriterator::with_animations`.
+            pub fn with_animations(mut self, animations: bool) -> Self {
+                self.options.animations = animations;
+                self
+            }
+
+            /// Converts images with embedded color profiles to sRGB, see
+            /// `Spriterator::with_color_management`.
+            pub fn with_color_management(mut self, color_management: bool) -> Self {
+                self.options.color_management = color_management;
+                self
+            }
+
+            /// Sets the scale SVG images are rasterized at, see `Spriterator::with_svg_scale`.
+            #[cfg(feature = "svg")]
+            pub fn with_svg_scale(mut self, svg_scale: f32) -> Self {
+                self.options.svg_scale = svg_scale;
+                self
+            }
+
+            /// Sets a function receiving warnings about skipped files and lossy file names.
+            pub fn with_warning_handler(
+                mut self,
+                handler: impl Fn(&$crate::Warning) + Send + Sync + 'static,
+            ) -> Self {
+                self.options.warning_handler = $crate::warning::WarningHandler::new(handler);
+                self
+            }
+        }
+    };
+    ($source:ty, extensions) => {
+        $crate::source::decode_builders!($source);
+
+        impl $source {
+            /// Sets the file extensions of the images to read, see `Spriterator::with_extensions`.
+            pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+                self.options.extensions = $crate::source::normalize_extensions(extensions);
+                self
+            }
+
+            /// Reads images of every format this build can decode.
+            pub fn with_all_decodable_extensions(mut self) -> Self {
+                self.options.extensions = $crate::source::decodable_extensions();
+                self
+            }
+
+            pub fn get_extensions(&self) -> &[String] {
+                &self.options.extensions
+            }
+        }
+    };
+}
+pub(crate) use decode_builders;
+
+/// Reads the images of a directory and its subdirectories, sorted by file name.
+///
+/// Frames are named after the path of their file relative to the directory, using `/` as the
+/// separator on every platform. Files that cannot be decoded are skipped with a warning.
+#[derive(Debug, Clone)]
+pub struct DirectorySource {
+    dir_path: PathBuf,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    name_pattern: Option<(String, Option<String>)>,
+    ignore_files: bool,
+    max_depth: Option<usize>,
+    follow_links: bool,
+    skip_hidden: bool,
+    options: DecodeOptions,
+}
+
+impl DirectorySource {
+    /// Creates a source reading the images in `dir_path`.
+    pub fn new(dir_path: impl AsRef<Path>) -> Self {
+        Self {
+            dir_path: dir_path.as_ref().to_path_buf(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            name_pattern: None,
+            ignore_files: true,
+            max_depth: None,
+            follow_links: false,
+            skip_hidden: false,
+            options: DecodeOptions::default(),
+        }
+    }
+
+    /// Only reads images whose path relative to the directory matches one of `patterns`,
+    /// such as `**/*_idle_*.png`. All images are read by default.
+    ///
+    /// `*` and `?` do not match `/`, while `**` matches any number of directories.
+    pub fn with_include(mut self, patterns: &[&str]) -> Self {
+        self.include = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    /// Skips images whose path relative to the directory matches one of `patterns`,
+    /// such as `**/raw/**`, and directories matching one of them entirely.
+    pub fn with_exclude(mut self, patterns: &[&str]) -> Self {
+        self.exclude = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    /// Only reads images whose path relative to the directory matches the regular expression
+    /// `pattern`, optionally naming their frames after `template`.
+    ///
+    /// The template refers to capture groups as `$1` or `${name}`, so that
+    /// `(?P<animation>\w+?)_(?P<index>\d+)\.png` with `${animation}/${index}.png` names the frame
+    /// of `walk_01.png` `walk/01.png`. Without a template, a pattern capturing `animation` and
+    /// `index` names frames `${animation}_${index}` with the extension of the file, the form
+    /// exporters group into animations; frames of other patterns keep their names.
+    pub fn with_name_pattern(mut self, pattern: &str, template: Option<&str>) -> Self {
+        self.name_pattern = Some((pattern.to_string(), template.map(str::to_string)));
+        self
+    }
+
+    /// Skips the paths listed in `.spriteignore` files of the directory and its
+    /// subdirectories, written in gitignore syntax such as `*.raw.png` or `scratch/`.
+    /// Enabled by default.
+    pub fn with_ignore_files(mut self, ignore_files: bool) -> Self {
+        self.ignore_files = ignore_files;
+        self
+    }
+
+    /// Limits how many levels of subdirectories are read, `Some(0)` reading only the files
+    /// directly in the directory. Unlimited by default.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Follows symbolic links to files and directories, disabled by default.
+    ///
+    /// Links pointing back to one of their parent directories are skipped.
+    pub fn with_follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Skips hidden files and directories, whose name starts with a dot, such as editor
+    /// backup folders. Disabled by default.
+    pub fn with_skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.skip_hidden = skip_hidden;
+        self
+    }
+
+    pub(crate) fn with_handler(mut self, warning_handler: WarningHandler) -> Self {
+        self.options.warning_handler = warning_handler;
+        self
+    }
+
+    pub fn get_dir_path(&self) -> &Path {
+        &self.dir_path
+    }
+
+    /// Reads and decodes the file at `path` into one image per frame.
+    fn load(&self, filter: &PathFilter, path: &Path) -> Vec<NamedImage> {
+        self.load_with(&self.options, filter, path)
+    }
+
+    /// Reads and decodes the file at `path` like `load`, with the given decoding options.
+    fn load_with(
+        &self,
+        options: &DecodeOptions,
+        filter: &PathFilter,
+        path: &Path,
+    ) -> Vec<NamedImage> {
+        let name = filter.rename(self.frame_name(&filter.root, path));
+        match std::fs::read(path) {
+            Ok(data) => options.decode(path, &name, &data),
+            Err(_) => {
+                options.warning_handler.warn(Warning::UndecodableImage {
+                    path: path.to_path_buf(),
+                });
+                Vec::new()
+            }
+        }
+    }
+
+    /// Names a frame after the path of its source image relative to the input directory,
+    /// using `/` as the separator on every platform.
+    fn frame_name(&self, root: &Path, path: &Path) -> String {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let name = relative_name(relative);
+
+        if relative.to_str().is_none() {
+            self.options.warning_handler.warn(Warning::LossyFileName {
+                path: path.to_path_buf(),
+                name: name.clone(),
+            });
+        }
+
+        name
+    }
+
+    /// Reads the size of every image from its header where possible, in the order of
+    /// `entries`, decoding only the images whose size or frame count is not in their header.
+    ///
+    /// Warnings about decoding are not reported, as `entries` reports them when the images
+    /// are decoded; other warnings are.
+    pub(crate) fn probe(&self) -> Box<dyn Iterator<Item = Result<Probe, Box<dyn Error>>> + '_> {
+        let root = walk_root(&self.dir_path);
+        let filter = match PathFilter::new(self, &root) {
+            Ok(filter) => filter,
+            Err(error) => return Box::new(std::iter::once(Err(error))),
+        };
+        let quiet = DecodeOptions {
+            warning_handler: self
+                .options
+                .warning_handler
+                .filtered(|warning| !warning.is_about_decoding()),
+            ..self.options.clone()
+        };
+
+        Box::new(
+            self.walk(&filter)
+                .flat_map(move |path| match quiet.probe_size(&path) {
+                    Some((width, height)) => vec![Probe::Size {
+                        name: filter.rename(self.frame_name(&filter.root, &path)),
+                        width,
+                        height,
+                    }],
+                    None => self
+                        .load_with(&quiet, &filter, &path)
+                        .into_iter()
+                        .map(Probe::Decoded)
+                        .collect(),
+                })
+                .map(Ok),
+        )
+    }
+
+    /// Walks the directory, yielding the files to read in file name order.
+    fn walk<'a>(&'a self, filter: &PathFilter) -> impl Iterator<Item = PathBuf> + 'a {
+        let mut walk = WalkDir::new(&filter.root)
+            .sort_by_file_name()
+            .follow_links(self.follow_links);
+        if let Some(max_depth) = self.max_depth {
+            walk = walk.max_depth(max_depth.saturating_add(1));
+        }
+        let skip_hidden = self.skip_hidden;
+
+        walk.into_iter()
+            .filter_entry({
+                let filter = filter.clone();
+                let mut ignore_files = self
+                    .ignore_files
+                    .then(|| IgnoreFiles::new(self.options.warning_handler.clone()));
+                move |entry| {
+                    let skipped = skip_hidden
+                        && entry.depth() > 0
+                        && entry.file_name().to_string_lossy().starts_with('.');
+                    !skipped
+                        && (!entry.file_type().is_dir() || filter.walks(entry.path()))
+                        && !ignore_files
+                            .as_mut()
+                            .is_some_and(|ignore_files| ignore_files.ignores(entry))
+                }
+            })
+            .filter_map({
+                let filter = filter.clone();
+                move |entry| {
+                    let path = entry.ok()?.into_path();
+                    (path.is_file() && filter.reads(&path) && self.options.accepts(&path))
+                        .then_some(path)
+                }
+            })
+    }
+
+    /// Lists the files `entries` reads, in the same order, without reading them.
+    ///
+    /// Warnings are not reported, as `entries` reports them when the files are read.
+    ///
+    /// # Returns
+    /// A `Result` containing the path of every file, or an error if a pattern is invalid.
+    pub(crate) fn files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let quiet = self.clone().with_handler(WarningHandler::default());
+        let filter = PathFilter::new(&quiet, &walk_root(&quiet.dir_path))?;
+        Ok(quiet.walk(&filter).collect())
+    }
+}
+
+decode_builders!(DirectorySource, extensions);
+
+impl ImageSource for DirectorySource {
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+        let root = walk_root(&self.dir_path);
+        let filter = match PathFilter::new(self, &root) {
+            Ok(filter) => filter,
+            Err(error) => return Box::new(std::iter::once(Err(error))),
+        };
+        let paths = self.walk(&filter);
+
+        // Files are listed first and decoded across the rayon thread pool a batch at a time,
+        // then returned in walk order so that packing stays deterministic. Batches bound how
+        // many decoded images are held ahead of the consumer.
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            let paths: Vec<PathBuf> = paths.collect();
+            let batch = rayon::current_num_threads().max(1) * 2;
+            Box::new(
+                (0..paths.len())
+                    .step_by(batch)
+                    .flat_map(move |start| {
+                        paths[start..(start + batch).min(paths.len())]
+                            .par_iter()
+                            .map(|path| self.load(&filter, path))
+                            .collect::<Vec<_>>()
+                    })
+                    .flatten()
+                    .map(Ok),
+            )
+        }
+        #[cfg(not(feature = "parallel"))]
+        Box::new(
+            paths
+                .flat_map(move |path| self.load(&filter, &path))
+                .map(Ok),
+        )
+    }
+}
+
+/// Decides which files of a walked directory are read and how their frames are named, from
+/// the patterns of a `DirectorySource` matched against paths relative to the directory.
+#[derive(Clone)]
+struct PathFilter {
+    root: PathBuf,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    name_pattern: Option<(Regex, Option<String>)>,
+}
+
+impl PathFilter {
+    fn new(source: &DirectorySource, root: &Path) -> Result<Self, Box<dyn Error>> {
+        let name_pattern = match &source.name_pattern {
+            Some((pattern, template)) => Some((Regex::new(pattern)?, template.clone())),
+            None => None,
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            include: glob_set(&source.include)?,
+            exclude: glob_set(&source.exclude)?,
+            name_pattern,
+        })
+    }
+
+    fn is_excluded(&self, relative: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(relative))
+    }
+
+    /// Returns whether the directory at `path` is walked.
+    fn walks(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        relative.as_os_str().is_empty() || !self.is_excluded(relative)
+    }
+
+    /// Returns whether the file at `path` is read.
+    fn reads(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let included = self
+            .include
+            .as_ref()
+            .is_none_or(|include| include.is_match(relative));
+        let matched = self
+            .name_pattern
+            .as_ref()
+            .is_none_or(|(pattern, _)| pattern.is_match(&relative_name(relative)));
+        included && matched && !self.is_excluded(relative)
+    }
+
+    /// Names the frame of the file named `name` after the template of the name pattern.
+    fn rename(&self, name: String) -> String {
+        let Some((pattern, template)) = &self.name_pattern else {
+            return name;
+        };
+        let Some(captures) = pattern.captures(&name) else {
+            return name;
+        };
+
+        let mut renamed = String::new();
+        match template {
+            Some(template) => captures.expand(template, &mut renamed),
+            None if captures.name("animation").is_some() && captures.name("index").is_some() => {
+                captures.expand(ANIMATION_TEMPLATE, &mut renamed);
+                if let Some(extension) = Path::new(&name).extension() {
+                    renamed.push('.');
+                    renamed.push_str(&extension.to_string_lossy());
+                }
+            }
+            None => return name,
+        }
+        renamed
+    }
+}
+
+/// The `.spriteignore` files applying to the entry being walked, with the depth of the
+/// directory holding each, outermost first.
+struct IgnoreFiles {
+    stack: Vec<(usize, Gitignore)>,
+    warning_handler: WarningHandler,
+}
+
+impl IgnoreFiles {
+    fn new(warning_handler: WarningHandler) -> Self {
+        Self {
+            stack: Vec::new(),
+            warning_handler,
+        }
+    }
+
+    /// Returns whether `entry` is ignored, reading the ignore file of each walked directory.
+    ///
+    /// Entries must be visited depth-first, as `WalkDir` does. The deepest ignore file with
+    /// a matching pattern decides, so nested files can re-include paths with `!`.
+    fn ignores(&mut self, entry: &walkdir::DirEntry) -> bool {
+        let depth = entry.depth();
+        while self.stack.last().is_some_and(|(owner, _)| *owner >= depth) {
+            self.stack.pop();
+        }
+
+        let is_dir = entry.file_type().is_dir();
+        let ignored = self
+            .stack
+            .iter()
+            .rev()
+            .map(|(_, ignore)| ignore.matched(entry.path(), is_dir))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matched.is_ignore());
+
+        let path = entry.path().join(IGNORE_FILE_NAME);
+        if is_dir && !ignored && path.is_file() {
+            let (ignore, error) = Gitignore::new(&path);
+            if let Some(error) = error {
+                self.warning_handler.warn(Warning::InvalidIgnoreFile {
+                    path,
+                    message: error.to_string(),
+                });
+            }
+            self.stack.push((depth, ignore));
+        }
+
+        ignored
+    }
+}
+
+/// Names the frames of name patterns capturing `animation` and `index` without a template.
+const ANIMATION_TEMPLATE: &str = "${animation}_${index}";
+
+/// Joins the components of a relative path with `/` on every platform.
+fn relative_name(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns the lowercase extension of `path`.
+pub(crate) fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// Rasterizes an SVG image at `scale` times its size at 96 DPI, resolving relative
+/// references against `resources_dir`.
+///
+/// Returns `None` if the data cannot be parsed or renders to an empty image.
+#[cfg(feature = "svg")]
+fn rasterize_svg(data: &[u8], resources_dir: Option<&Path>, scale: f32) -> Option<RgbaImage> {
+    use resvg::{tiny_skia, usvg};
+
+    let options = usvg::Options {
+        resources_dir: resources_dir.map(Path::to_path_buf),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_data(data, &options).ok()?;
+    let width = (tree.size().width() * scale).ceil() as u32;
+    let height = (tree.size().height() * scale).ceil() as u32;
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(width, height, pixels)
+}
+
+/// Names frame `index` of an animated image so that exporters group the frames into an
+/// animation named after the image, such as `hero/walk_03.gif` for `hero/walk.gif`.
+fn animation_frame_name(name: &str, index: usize, count: usize) -> String {
+    let stem = strip_extension(name);
+    let width = (count - 1).to_string().len();
+    format!(
+        "{}_{:0width$}{}",
+        stem,
+        index,
+        &name[stem.len()..],
+        width = width
+    )
+}
+
+/// Returns the path to walk for images in `dir_path`.
+///
+/// On Windows, the path is made absolute in its `\\?\` form, which lifts the 260 character
+/// limit on paths of nested files.
+fn walk_root(dir_path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        std::fs::canonicalize(dir_path).unwrap_or_else(|_| dir_path.to_path_buf())
+    } else {
+        dir_path.to_path_buf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::collect_warnings;
+
+    #[test]
+    fn test_animation_frame_name() {
+        assert_eq!(animation_frame_name("walk.gif", 3, 12), "walk_03.gif");
+        assert_eq!(animation_frame_name("a.b/walk", 0, 2), "a.b/walk_0");
+    }
+
+    #[test]
+    fn test_directory_source() {
+        let dir = std::env::temp_dir().join("spriterator_directory_source");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        RgbaImage::new(2, 2).save(dir.join("nested/b.png")).unwrap();
+        RgbaImage::new(3, 1).save(dir.join("a.bmp")).unwrap();
+
+        let source = DirectorySource::new(&dir);
+        let names: Vec<String> = source
+            .entries()
+            .map(|entry| entry.unwrap().get_name().to_string())
+            .collect();
+        assert_eq!(names, vec!["a.bmp", "nested/b.png"]);
+
+        let source = source.with_extensions(&[".PNG"]);
+        assert_eq!(source.get_extensions(), ["png"]);
+        assert_eq!(source.entries().count(), 1);
+    }
+
+    #[test]
+    fn test_probe() {
+        let dir = std::env::temp_dir().join("spriterator_probe");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        RgbaImage::new(5, 3).save(dir.join("a.png")).unwrap();
+        RgbaImage::new(2, 4).save(dir.join("b.gif")).unwrap();
+        std::fs::write(dir.join("c.png"), b"not a png").unwrap();
+
+        let (warnings, handler) = collect_warnings();
+        let source = DirectorySource::new(&dir).with_handler(WarningHandler::new(handler));
+        let probes: Vec<Probe> = source.probe().map(Result::unwrap).collect();
+        assert!(matches!(
+            &probes[..],
+            [
+                Probe::Size { name, width: 5, height: 3 },
+                Probe::Decoded(gif),
+            ] if name == "a.png" && gif.get_name() == "b.gif" && gif.image.dimensions() == (2, 4)
+        ));
+        // Decoding warnings are left to `entries`.
+        assert!(warnings.lock().unwrap().is_empty());
+
+        let probes: Vec<Probe> = source
+            .with_animations(false)
+            .probe()
+            .map(Result::unwrap)
+            .collect();
+        assert!(matches!(
+            &probes[1],
+            Probe::Size {
+                width: 2,
+                height: 4,
+                ..
+            }
+        ));
+    }
+}
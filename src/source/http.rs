@@ -0,0 +1,308 @@
+use std::error::Error;
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::source::{decode_builders, DecodeOptions, ImageSource, NamedImage};
+
+/// Downloads images over HTTP(S), such as avatars served by a CDN.
+///
+/// Images are downloaded in parallel but packed in the order their URLs were added. Frames
+/// are named after the path of their URL unless named explicitly. A download that still fails
+/// after all retries aborts generation; a downloaded image that cannot be decoded is skipped
+/// with a warning naming its URL.
+#[derive(Debug, Clone)]
+pub struct UrlSource {
+    urls: Vec<(String, String)>,
+    concurrency: usize,
+    retries: u32,
+    retry_delay: Duration,
+    timeout: Option<Duration>,
+    options: DecodeOptions,
+}
+
+impl UrlSource {
+    /// Creates a source downloading the images at `urls`.
+    ///
+    /// Frames are named after the path of each URL without its query, such as
+    /// `avatars/42.png` for `https://cdn.example.com/avatars/42.png?size=64`, or after the host
+    /// for URLs without a path, such as `example.com` for `https://example.com/`.
+    pub fn new(urls: &[&str]) -> Self {
+        Self {
+            urls: urls
+                .iter()
+                .map(|url| (url_name(url), url.to_string()))
+                .collect(),
+            concurrency: 4,
+            retries: 2,
+            retry_delay: Duration::from_millis(500),
+            timeout: Some(Duration::from_secs(30)),
+            options: DecodeOptions::default(),
+        }
+    }
+
+    /// Adds an image to download under the frame name `name`.
+    pub fn with_url(mut self, name: &str, url: &str) -> Self {
+        self.urls.push((name.to_string(), url.to_string()));
+        self
+    }
+
+    /// Sets how many images are downloaded at the same time, `4` by default.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets how many times a failed download is retried, `2` by default.
+    ///
+    /// Connection errors, `429 Too Many Requests` and server errors are retried, waiting
+    /// `retry_delay` before the first retry and twice as long before each following one.
+    /// Other errors, such as `404 Not Found`, fail immediately.
+    pub fn with_retries(mut self, retries: u32, retry_delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Sets the timeout of each request, 30 seconds by default.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn get_urls(&self) -> impl Iterator<Item = &str> {
+        self.urls.iter().map(|(_, url)| url.as_str())
+    }
+
+    /// Downloads the image at `url`, retrying transient failures.
+    fn download(&self, agent: &ureq::Agent, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let error = match agent.get(url).call() {
+                Ok(response) => {
+                    let mut data = Vec::new();
+                    response.into_reader().read_to_end(&mut data)?;
+                    return Ok(data);
+                }
+                Err(error) => error,
+            };
+
+            let transient = match &error {
+                ureq::Error::Status(status, _) => *status == 429 || *status >= 500,
+                ureq::Error::Transport(_) => true,
+            };
+            if !transient || attempt >= self.retries {
+                return Err(format!("Failed to download {}: {}", url, error).into());
+            }
+
+            std::thread::sleep(delay);
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Returns the `ETag` of the image at `url`, or its `Last-Modified` date, from a `HEAD`
+    /// request, or `None` if the server sends neither or the request fails.
+    fn validator(&self, agent: &ureq::Agent, url: &str) -> Option<String> {
+        let response = agent.head(url).call().ok()?;
+        let validator = response
+            .header("ETag")
+            .or_else(|| response.header("Last-Modified"))?;
+        Some(validator.to_string())
+    }
+
+    fn agent(&self) -> ureq::Agent {
+        let mut agent = ureq::AgentBuilder::new();
+        if let Some(timeout) = self.timeout {
+            agent = agent.timeout(timeout);
+        }
+        agent.build()
+    }
+
+    /// Downloads every image with at most `concurrency` requests at a time.
+    fn download_all(&self) -> Vec<Result<Vec<u8>, Box<dyn Error + Send + Sync>>> {
+        let agent = self.agent();
+
+        let next = AtomicUsize::new(0);
+        let results: Vec<Mutex<Option<_>>> = self.urls.iter().map(|_| Mutex::new(None)).collect();
+        std::thread::scope(|scope| {
+            for _ in 0..self.concurrency.min(self.urls.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((_, url)) = self.urls.get(index) else {
+                        break;
+                    };
+                    let result = self
+                        .download(&agent, url)
+                        .map_err(|error| error.to_string().into());
+                    *results[index].lock().unwrap() = Some(result);
+                });
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|result| result.into_inner().unwrap().unwrap())
+            .collect()
+    }
+}
+
+decode_builders!(UrlSource);
+
+impl ImageSource for UrlSource {
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+        let downloads = self.download_all();
+        Box::new(
+            self.urls
+                .iter()
+                .zip(downloads)
+                .flat_map(|((name, url), download)| match download {
+                    Ok(data) => self
+                        .options
+                        .decode(&url_path(url, name), name, &data)
+                        .into_iter()
+                        .map(Ok)
+                        .collect(),
+                    Err(error) => vec![Err(error as Box<dyn Error>)],
+                }),
+        )
+    }
+
+    /// Hashes the URLs with the `ETag` or `Last-Modified` header of each image, asked for with
+    /// `HEAD` requests instead of downloading the images. Nothing is written if any server
+    /// sends neither, as the images must then be downloaded to tell whether they changed.
+    fn fingerprint(&self, hasher: &mut dyn Hasher) -> Result<bool, Box<dyn Error>> {
+        let agent = self.agent();
+        let mut validators = Vec::with_capacity(self.urls.len());
+        for (name, url) in &self.urls {
+            match self.validator(&agent, url) {
+                Some(validator) => validators.push((name, url, validator)),
+                None => return Ok(false),
+            }
+        }
+
+        hasher.write(format!("{:?}", self.options).as_bytes());
+        for (name, url, validator) in validators {
+            for value in [name, url, &validator] {
+                hasher.write(value.as_bytes());
+                hasher.write_u8(0xff);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Returns the path of `url` without its scheme, host, query and fragment, or its host if
+/// the path is empty.
+fn url_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let (host, path) = path.split_once('/').unwrap_or((path, ""));
+    if path.is_empty() {
+        host.to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Returns the path reported in warnings about the image at `url`, with the extension of
+/// the frame `name` so it is decoded as the right format.
+fn url_path(url: &str, name: &str) -> PathBuf {
+    match Path::new(name).extension() {
+        Some(extension) => Path::new(url).with_extension(extension),
+        None => PathBuf::from(url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_url_name() {
+        assert_eq!(
+            url_name("https://cdn.example.com/avatars/42.png?size=64"),
+            "avatars/42.png"
+        );
+        assert_eq!(url_name("http://localhost:8080/a.png#top"), "a.png");
+        assert_eq!(url_name("https://example.com"), "example.com");
+        assert_eq!(url_name("https://example.com/?v=2"), "example.com");
+    }
+
+    #[test]
+    fn test_url_source() {
+        let mut png = Vec::new();
+        RgbaImage::new(3, 2)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut failed = false;
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = String::new();
+                BufReader::new(&stream).read_line(&mut request).unwrap();
+                let (status, body) = if request.contains("/flaky.png") && !failed {
+                    failed = true;
+                    ("503 Service Unavailable", Vec::new())
+                } else if request.contains("/missing.png") {
+                    ("404 Not Found", Vec::new())
+                } else {
+                    ("200 OK", png.clone())
+                };
+                let etag = if request.contains("/tagged.png") {
+                    "ETag: \"v1\"\r\n"
+                } else {
+                    ""
+                };
+                let header = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    etag,
+                    body.len()
+                );
+                stream.write_all(header.as_bytes()).unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+
+        let base = format!("http://{}", address);
+        let source = UrlSource::new(&[
+            &format!("{}/avatars/a.png?v=1", base),
+            &format!("{}/flaky.png", base),
+        ])
+        .with_url("b.png", &format!("{}/b", base))
+        .with_retries(1, Duration::from_millis(10))
+        .with_concurrency(2);
+        let images: Vec<NamedImage> = source.entries().map(Result::unwrap).collect();
+        let names: Vec<&str> = images.iter().map(NamedImage::get_name).collect();
+        assert_eq!(names, vec!["avatars/a.png", "flaky.png", "b.png"]);
+        assert_eq!(images[2].get_image().dimensions(), (3, 2));
+
+        let missing = UrlSource::new(&[&format!("{}/missing.png", base)]);
+        let error = missing.entries().next().unwrap().unwrap_err();
+        assert!(error.to_string().contains("404"));
+
+        // Images are only fingerprinted without downloading them when every one has an ETag.
+        let fingerprint = |source: &UrlSource| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            let written = source.fingerprint(&mut hasher).unwrap();
+            written.then(|| hasher.finish())
+        };
+        let tagged = UrlSource::new(&[&format!("{}/tagged.png", base)]);
+        assert!(fingerprint(&tagged).is_some());
+        assert_eq!(
+            fingerprint(&tagged.with_url("b.png", &format!("{}/b", base))),
+            None
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::source::{decode_builders, extension, DecodeOptions, ImageSource, NamedImage};
+
+/// An image listed in a JSON manifest.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    /// Path of the image, relative to the manifest.
+    path: String,
+    /// Name of the frame, the path as written by default.
+    #[serde(default)]
+    name: Option<String>,
+    /// Pivot normalized to the image with the origin at the top-left corner.
+    #[serde(default)]
+    pivot: Option<Pivot>,
+    /// How long the frame is shown in milliseconds.
+    #[serde(default)]
+    duration: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Pivot {
+    x: f64,
+    y: f64,
+}
+
+/// Reads the images listed in a manifest file, packed in the order they are listed.
+///
+/// A manifest is either a text file with one image path per line, where empty lines and lines
+/// starting with `#` are ignored, or a `.json` file holding an array of entries such as
+/// `{ "path": "raw/hero_01.png", "name": "hero/idle.png", "pivot": { "x": 0.5, "y": 1.0 },
+/// "duration": 100 }`, where only `path` is required. Paths are relative to the manifest's
+/// directory, and frames are named after the path as written unless named explicitly.
+///
+/// A listed file that cannot be read aborts generation; one that cannot be decoded is skipped
+/// with a warning.
+#[derive(Debug, Clone)]
+pub struct ManifestSource {
+    path: PathBuf,
+    options: DecodeOptions,
+}
+
+impl ManifestSource {
+    /// Creates a source reading the images listed in the manifest at `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            options: DecodeOptions::default(),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads and parses the manifest.
+    fn read(&self) -> Result<Vec<ManifestEntry>, Box<dyn Error>> {
+        let manifest = std::fs::read_to_string(&self.path)
+            .map_err(|error| format!("Failed to read manifest {:?}: {}", self.path, error))?;
+
+        if extension(&self.path).as_deref() == Some("json") {
+            return serde_json::from_str(&manifest)
+                .map_err(|error| format!("Invalid manifest {:?}: {}", self.path, error).into());
+        }
+
+        Ok(manifest
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| ManifestEntry {
+                path: line.to_string(),
+                name: None,
+                pivot: None,
+                duration: None,
+            })
+            .collect())
+    }
+
+    /// Reads and decodes the image of `entry` into one image per frame.
+    fn load(&self, entry: ManifestEntry) -> Result<Vec<NamedImage>, Box<dyn Error>> {
+        let path = self
+            .path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(&entry.path);
+        let data = std::fs::read(&path).map_err(|error| {
+            format!(
+                "Failed to read {:?} listed in manifest {:?}: {}",
+                path, self.path, error
+            )
+        })?;
+        let name = entry.name.unwrap_or_else(|| frame_name(&entry.path));
+        let pivot = entry.pivot.map(|pivot| (pivot.x, pivot.y));
+
+        Ok(self
+            .options
+            .decode(&path, &name, &data)
+            .into_iter()
+            .map(|image| {
+                let duration = entry.duration.or(image.get_duration());
+                image.with_duration(duration).with_pivot(pivot)
+            })
+            .collect())
+    }
+}
+
+decode_builders!(ManifestSource);
+
+impl ImageSource for ManifestSource {
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+        let entries = match self.read() {
+            Ok(entries) => entries,
+            Err(error) => return Box::new(std::iter::once(Err(error))),
+        };
+
+        Box::new(
+            entries
+                .into_iter()
+                .flat_map(|entry| match self.load(entry) {
+                    Ok(images) => images.into_iter().map(Ok).collect(),
+                    Err(error) => vec![Err(error)],
+                }),
+        )
+    }
+}
+
+/// Names a frame after its path as written in the manifest, using `/` as the separator.
+fn frame_name(path: &str) -> String {
+    let name = path.replace('\\', "/");
+    name.strip_prefix("./").unwrap_or(&name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_manifest_source() {
+        let dir = std::env::temp_dir().join("spriterator_manifest_source");
+        std::fs::create_dir_all(dir.join("raw")).unwrap();
+        RgbaImage::new(2, 2).save(dir.join("raw/b.png")).unwrap();
+        RgbaImage::new(3, 1).save(dir.join("a.png")).unwrap();
+        RgbaImage::new(1, 1).save(dir.join("unlisted.png")).unwrap();
+
+        let text = dir.join("frames.txt");
+        std::fs::write(&text, "# Packing order\n./raw/b.png\n\na.png\n").unwrap();
+        let images: Vec<NamedImage> = ManifestSource::new(&text)
+            .entries()
+            .map(Result::unwrap)
+            .collect();
+        let names: Vec<&str> = images.iter().map(NamedImage::get_name).collect();
+        assert_eq!(names, vec!["raw/b.png", "a.png"]);
+
+        let json = dir.join("frames.json");
+        std::fs::write(
+            &json,
+            r#"[
+                { "path": "a.png" },
+                { "path": "raw/b.png", "name": "hero/idle.png",
+                  "pivot": { "x": 0.5, "y": 1.0 }, "duration": 100 }
+            ]"#,
+        )
+        .unwrap();
+        let images: Vec<NamedImage> = ManifestSource::new(&json)
+            .entries()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(images[0].get_name(), "a.png");
+        assert_eq!(images[0].get_pivot(), None);
+        assert_eq!(images[1].get_name(), "hero/idle.png");
+        assert_eq!(images[1].get_image().dimensions(), (2, 2));
+        assert_eq!(images[1].get_pivot(), Some((0.5, 1.0)));
+        assert_eq!(images[1].get_duration(), Some(100));
+
+        std::fs::write(&json, r#"[{ "path": "a.png", "scale": 2 }]"#).unwrap();
+        let error = ManifestSource::new(&json).entries().next().unwrap();
+        assert!(error
+            .unwrap_err()
+            .to_string()
+            .contains("unknown field `scale`"));
+
+        std::fs::write(&text, "missing.png\n").unwrap();
+        let error = ManifestSource::new(&text).entries().next().unwrap();
+        assert!(error.unwrap_err().to_string().contains("missing.png"));
+    }
+}
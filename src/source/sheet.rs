@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::{validate_metadata, Metadata};
+use crate::source::{ImageSource, NamedImage};
+use crate::unpack::unpack;
+
+/// Reads the frames of previously generated sheets described by native metadata, so they can
+/// be repacked with new settings without the original images.
+///
+/// Frames keep their names, durations and pivots, and trimmed frames get their transparent
+/// borders back so they can be trimmed anew. Sheet paths in the metadata are relative to the
+/// metadata file. Invalid metadata or a missing sheet aborts generation.
+#[derive(Debug, Clone)]
+pub struct SheetSource {
+    path: PathBuf,
+}
+
+impl SheetSource {
+    /// Creates a source reading the sheets described by the metadata JSON at `path`,
+    /// as written by `export_metadata`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the metadata and cuts every frame out of its sheet, see `unpack`.
+    fn read(&self) -> Result<Vec<NamedImage>, Box<dyn Error>> {
+        let json = std::fs::read_to_string(&self.path)
+            .map_err(|error| format!("Failed to read metadata {:?}: {}", self.path, error))?;
+        let metadata: Metadata = validate_metadata(&json)?;
+        let dir = self.path.parent().unwrap_or(Path::new(""));
+
+        let mut images = Vec::new();
+        for sheet in &metadata.sheets {
+            let path = dir.join(&sheet.image);
+            let image = image::open(&path)
+                .map_err(|error| format!("Failed to open sheet {:?}: {}", path, error))?
+                .to_rgba8();
+            images.extend(unpack(&image, sheet)?);
+        }
+        Ok(images)
+    }
+}
+
+impl ImageSource for SheetSource {
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+        match self.read() {
+            Ok(images) => Box::new(images.into_iter().map(Ok)),
+            Err(error) => Box::new(std::iter::once(Err(error))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::export_metadata;
+    use crate::spriterator::Spriterator;
+    use image::{imageops, Rgba, RgbaImage};
+
+    #[test]
+    fn test_repack() {
+        let dir = std::env::temp_dir().join("spriterator_sheet_source");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // The content of the repacked frames spans the sheet, which is cropped to it.
+        let mut hero = RgbaImage::new(6, 6);
+        hero.put_pixel(0, 0, Rgba([200, 10, 10, 255]));
+        hero.put_pixel(2, 5, Rgba([10, 200, 10, 128]));
+        let mut coin = RgbaImage::new(4, 4);
+        coin.put_pixel(0, 0, Rgba([250, 250, 0, 255]));
+        coin.put_pixel(3, 3, Rgba([250, 250, 0, 255]));
+
+        let sprites = Spriterator::from_images(
+            vec![
+                ("hero.png".to_string(), hero.clone()),
+                ("coin.png".to_string(), coin.clone()),
+            ],
+            8,
+            8,
+            None,
+            None,
+        )
+        .with_trim(true)
+        .generate()
+        .unwrap();
+        let images: Vec<String> = (0..sprites.len())
+            .map(|index| format!("sheet-{}.png", index))
+            .collect();
+        for (sprite, image) in sprites.iter().zip(&images) {
+            sprite.save(dir.join(image).to_str().unwrap()).unwrap();
+        }
+        let images: Vec<&str> = images.iter().map(String::as_str).collect();
+        let metadata = dir.join("sheets.json");
+        export_metadata(metadata.to_str().unwrap(), &sprites, &images).unwrap();
+
+        let repacked =
+            Spriterator::from_image_source(SheetSource::new(&metadata), 64, 64, None, None)
+                .generate()
+                .unwrap();
+        assert_eq!(repacked.len(), 1);
+        let frames = repacked[0].get_frames();
+        assert_eq!(frames[0].get_name(), "hero.png");
+        assert!(!frames[0].is_trimmed());
+        for (frame, original) in frames.iter().zip([&hero, &coin]) {
+            let rect = frame.rect();
+            let placed =
+                imageops::crop_imm(repacked[0].get_image(), rect.x, rect.y, rect.w, rect.h);
+            assert_eq!(&placed.to_image(), original);
+        }
+
+        std::fs::write(dir.join("sheet-0.png"), b"").unwrap();
+        let error = SheetSource::new(&metadata)
+            .entries()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(error.to_string().contains("sheet-0.png"));
+    }
+}
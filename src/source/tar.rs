@@ -0,0 +1,154 @@
+use flate2::read::GzDecoder;
+use std::error::Error;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+use crate::source::{decode_builders, DecodeOptions, ImageSource, NamedImage};
+
+/// The first bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Reads the images of a tar archive, optionally gzip-compressed, in a single pass without
+/// extracting it.
+///
+/// Frames are named after the path of their entry in the archive and packed in the order
+/// entries are stored. Entries that cannot be decoded are skipped with a warning naming them
+/// as a path inside the archive, such as `assets.tar.gz/hero/walk.png`.
+#[derive(Debug, Clone)]
+pub struct TarSource {
+    path: PathBuf,
+    options: DecodeOptions,
+}
+
+impl TarSource {
+    /// Creates a source reading the images in the archive at `path`.
+    ///
+    /// Compression is detected from the contents of the file. The archive is read when
+    /// generating; an archive that cannot be read aborts generation.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            options: DecodeOptions::default(),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the archive and decodes its image entries.
+    fn read(&self) -> Result<Vec<NamedImage>, Box<dyn Error>> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let reader: Box<dyn Read> = if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            Box::new(GzDecoder::new(reader))
+        } else {
+            Box::new(reader)
+        };
+
+        let mut images = Vec::new();
+        for entry in Archive::new(reader).entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let name = entry
+                .path()?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .filter(|component| component != ".")
+                .collect::<Vec<_>>()
+                .join("/");
+            let path = self.path.join(&name);
+            if !self.options.accepts(&path) {
+                continue;
+            }
+
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            images.extend(self.options.decode(&path, &name, &data));
+        }
+        Ok(images)
+    }
+}
+
+decode_builders!(TarSource, extensions);
+
+impl ImageSource for TarSource {
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+        match self.read() {
+            Ok(images) => Box::new(images.into_iter().map(Ok)),
+            Err(error) => Box::new(std::iter::once(Err(error))),
+        }
+    }
+
+    /// Hashes the bytes of the archive as stored, without decompressing it.
+    fn fingerprint(&self, hasher: &mut dyn Hasher) -> Result<bool, Box<dyn Error>> {
+        hasher.write(format!("{:?}", self.options).as_bytes());
+        let mut file = File::open(&self.path)?;
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(true);
+            }
+            hasher.write(&buffer[..read]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::png;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn tarball(writer: impl Write) {
+        let mut builder = tar::Builder::new(writer);
+        for (name, data) in [
+            ("./hero/walk.png", png(2, 2)),
+            ("./a.png", png(3, 1)),
+            ("./notes.txt", b"not an image".to_vec()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, data.as_slice())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().flush().unwrap();
+    }
+
+    #[test]
+    fn test_tar_source() {
+        let dir = std::env::temp_dir().join("spriterator_tar_source");
+        std::fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("assets.tar");
+        tarball(File::create(&plain).unwrap());
+        let compressed = dir.join("assets.tgz");
+        tarball(GzEncoder::new(
+            File::create(&compressed).unwrap(),
+            Compression::default(),
+        ));
+
+        for path in [plain, compressed] {
+            let images: Vec<NamedImage> = TarSource::new(&path)
+                .entries()
+                .map(Result::unwrap)
+                .collect();
+            let names: Vec<&str> = images.iter().map(NamedImage::get_name).collect();
+            assert_eq!(names, vec!["hero/walk.png", "a.png"]);
+            assert_eq!(images[1].get_image().dimensions(), (3, 1));
+        }
+
+        let missing = TarSource::new(dir.join("missing.tar"));
+        assert!(missing.entries().next().unwrap().is_err());
+    }
+}
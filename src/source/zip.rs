@@ -0,0 +1,172 @@
+use std::error::Error;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+use crate::source::{decode_builders, DecodeOptions, ImageSource, NamedImage};
+
+/// Reads the images of a ZIP archive, such as a `.zip` or `.cbz` file, without extracting it.
+///
+/// Frames are named after the path of their entry in the archive and sorted like the files
+/// of a `DirectorySource`. Entries that cannot be decoded are skipped with a warning naming
+/// them as a path inside the archive, such as `assets.zip/hero/walk.png`.
+#[derive(Debug, Clone)]
+pub struct ZipSource {
+    path: PathBuf,
+    options: DecodeOptions,
+}
+
+impl ZipSource {
+    /// Creates a source reading the images in the archive at `path`.
+    ///
+    /// The archive is opened when generating; an archive that cannot be read aborts generation.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            options: DecodeOptions::default(),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the archive and returns it with the names of its image entries in packing order.
+    fn open(&self) -> Result<(ZipArchive<File>, Vec<String>), Box<dyn Error>> {
+        let archive = ZipArchive::new(File::open(&self.path)?)?;
+        let mut names: Vec<String> = archive
+            .file_names()
+            .filter(|name| !name.ends_with('/') && self.options.accepts(&self.path.join(name)))
+            .map(str::to_string)
+            .collect();
+        names.sort_by(|a, b| a.split('/').cmp(b.split('/')));
+        Ok((archive, names))
+    }
+}
+
+decode_builders!(ZipSource, extensions);
+
+impl ImageSource for ZipSource {
+    fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+        let (mut archive, names) = match self.open() {
+            Ok(opened) => opened,
+            Err(error) => return Box::new(std::iter::once(Err(error))),
+        };
+
+        Box::new(names.into_iter().flat_map(move |name| {
+            let mut data = Vec::new();
+            let read = archive
+                .by_name(&name)
+                .map_err(Box::<dyn Error>::from)
+                .and_then(|mut entry| Ok(entry.read_to_end(&mut data)?));
+            match read {
+                Ok(_) => self
+                    .options
+                    .decode(&self.path.join(&name), &name, &data)
+                    .into_iter()
+                    .map(Ok)
+                    .collect::<Vec<_>>(),
+                Err(error) => vec![Err(error)],
+            }
+        }))
+    }
+
+    /// Hashes the name, size and CRC-32 of every image entry from the central directory,
+    /// without inflating them.
+    fn fingerprint(&self, hasher: &mut dyn Hasher) -> Result<bool, Box<dyn Error>> {
+        let (mut archive, names) = self.open()?;
+        hasher.write(format!("{:?}", self.options).as_bytes());
+        for name in names {
+            let entry = archive.by_name(&name)?;
+            hasher.write(name.as_bytes());
+            hasher.write_u64(entry.size());
+            hasher.write_u32(entry.crc32());
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{collect_warnings, png};
+    use crate::warning::Warning;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    #[test]
+    fn test_zip_source() {
+        let dir = std::env::temp_dir().join("spriterator_zip_source");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("assets.cbz");
+
+        let mut writer = ZipWriter::new(File::create(&path).unwrap());
+        let options = SimpleFileOptions::default();
+        for (name, data) in [
+            ("hero/walk.png", png(2, 2)),
+            ("a.png", png(3, 1)),
+            ("hero.png", png(1, 1)),
+            ("notes.txt", b"not an image".to_vec()),
+            ("broken.png", b"not a png".to_vec()),
+            ("photo.avif", Vec::new()),
+        ] {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(&data).unwrap();
+        }
+        writer.add_directory("empty/", options).unwrap();
+        writer.finish().unwrap();
+
+        let (warnings, handler) = collect_warnings();
+        let source = ZipSource::new(&path).with_warning_handler(handler);
+        let images: Vec<NamedImage> = source.entries().map(Result::unwrap).collect();
+        let names: Vec<&str> = images.iter().map(NamedImage::get_name).collect();
+        assert_eq!(names, vec!["a.png", "hero/walk.png", "hero.png"]);
+        assert_eq!(images[1].get_image().dimensions(), (2, 2));
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            vec![
+                Warning::UnsupportedFormat {
+                    path: path.join("photo.avif")
+                },
+                Warning::UndecodableImage {
+                    path: path.join("broken.png")
+                },
+            ]
+        );
+
+        let missing = ZipSource::new(dir.join("missing.zip"));
+        assert!(missing.entries().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let dir = std::env::temp_dir().join("spriterator_zip_fingerprint");
+        std::fs::create_dir_all(&dir).unwrap();
+        let fingerprint = |entries: &[(&str, Vec<u8>)]| {
+            let path = dir.join("assets.zip");
+            let mut writer = ZipWriter::new(File::create(&path).unwrap());
+            for (name, data) in entries {
+                writer
+                    .start_file(*name, SimpleFileOptions::default())
+                    .unwrap();
+                writer.write_all(data).unwrap();
+            }
+            writer.finish().unwrap();
+
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            assert!(ZipSource::new(&path).fingerprint(&mut hasher).unwrap());
+            hasher.finish()
+        };
+
+        let original = fingerprint(&[("a.png", png(2, 2)), ("notes.txt", vec![1])]);
+        assert_eq!(
+            fingerprint(&[("a.png", png(2, 2)), ("notes.txt", vec![2])]),
+            original
+        );
+        assert_ne!(fingerprint(&[("a.png", png(3, 2))]), original);
+        assert_ne!(fingerprint(&[("b.png", png(2, 2))]), original);
+    }
+}
@@ -1,19 +1,123 @@
 use image::RgbaImage;
+use std::io::Write;
+use std::path::Path;
 
+use crate::alpha_scan::opaque_bounds;
+use crate::atomic_write::{write_atomic, write_bytes, OverwritePolicy};
+use crate::color_report::ColorReport;
+use crate::export::css::{self, CssOptions};
+use crate::export::unity::{self, UnityOptions};
+use crate::export::{
+    aseprite, bevy, cocos, godot, paper2d, png_text, preview, sparrow, table, texture_packer,
+    tiled, typescript,
+};
 use crate::frame::Frame;
+use crate::metadata::FrameMetadata;
+use crate::mipmap::mipmaps;
+#[cfg(feature = "avif")]
+use crate::output_format::AvifOptions;
+#[cfg(feature = "dds")]
+use crate::output_format::DdsOptions;
+#[cfg(feature = "ktx2")]
+use crate::output_format::Ktx2Options;
+use crate::output_format::{IndexedPngOptions, JpegOptions, OutputFormat, PngOptions, WebPOptions};
+use crate::quality_sweep::{QualitySweep, SweepResult};
+use crate::rect::Rect;
+use crate::source::NamedImage;
+use crate::strip_metadata::strip_metadata;
+use crate::unpack::unpack_frame;
 
 #[derive(Debug)]
 pub struct Sprite {
     image: RgbaImage,
     frames: Vec<Frame>,
+    layer: Option<u32>,
+    capacity: (u32, u32),
+    slots: Vec<Rect>,
+    strip_metadata: bool,
 }
 
 impl Sprite {
     pub fn new(image: RgbaImage) -> Self {
+        Self::with_frames(image, Vec::new())
+    }
+
+    pub(crate) fn with_frames(image: RgbaImage, frames: Vec<Frame>) -> Self {
         Self {
+            capacity: image.dimensions(),
             image,
-            frames: Vec::new(),
+            frames,
+            layer: None,
+            slots: Vec::new(),
+            strip_metadata: false,
+        }
+    }
+
+    /// Sets the dimensions the sheet may grow to and the areas reserved around frames,
+    /// such as their extrusion, for `free_rects`.
+    pub(crate) fn set_layout(&mut self, capacity: (u32, u32), slots: Vec<Rect>) {
+        self.capacity = capacity;
+        self.slots = slots;
+    }
+
+    /// Returns the areas reserved around frames during generation, in frame order.
+    pub(crate) fn get_slots(&self) -> &[Rect] {
+        &self.slots
+    }
+
+    /// Returns the areas kept apart when downsampling mipmaps: the slots of frames if the
+    /// sheet was generated, or the frames themselves otherwise.
+    fn mip_regions(&self) -> Vec<Rect> {
+        if self.slots.is_empty() {
+            self.frames.iter().map(Frame::rect).collect()
+        } else {
+            self.slots.clone()
+        }
+    }
+
+    /// Builds the mipmap chain of the sheet below level 0, down to 1×1, without bleeding
+    /// between frames.
+    ///
+    /// # Returns
+    /// The levels from 1 onwards, each half the size of the previous one.
+    pub fn mipmaps(&self) -> Vec<RgbaImage> {
+        mipmaps(&self.image, &self.mip_regions())
+    }
+
+    /// Saves each mipmap level as a separate file next to `path`, naming level `n` by
+    /// inserting `_mip{n}` before the extension, such as `sheet_mip1.png`.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the full-size sheet, which is not written.
+    /// - `format`: Format to encode the levels in, with its encoder settings.
+    ///
+    /// # Returns
+    /// A `Result` containing the path of each level, from level 1 onwards, or an error if
+    /// encoding or writing fails.
+    pub fn save_mipmaps(
+        &self,
+        path: &str,
+        format: &OutputFormat,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let path = Path::new(path);
+        let stem = path
+            .file_stem()
+            .ok_or_else(|| format!("Path {:?} has no file name.", path))?
+            .to_string_lossy();
+        let extension = path
+            .extension()
+            .map_or_else(|| format.extension().into(), |ext| ext.to_string_lossy());
+
+        let mut paths = Vec::new();
+        for (index, level) in self.mipmaps().into_iter().enumerate() {
+            let level_path =
+                path.with_file_name(format!("{}_mip{}.{}", stem, index + 1, extension));
+            let mut level = Sprite::new(level);
+            level.set_strip_metadata(self.strip_metadata);
+            level.save_with_format(&level_path.to_string_lossy(), format)?;
+            paths.push(level_path.to_string_lossy().into_owned());
         }
+        Ok(paths)
     }
 
     pub fn get_image(&self) -> &RgbaImage {
@@ -24,12 +128,823 @@ impl Sprite {
         &self.frames
     }
 
+    /// Returns the array texture layer of the sheet, set when generated in array texture mode.
+    pub fn get_layer(&self) -> Option<u32> {
+        self.layer
+    }
+
+    pub fn set_layer(&mut self, layer: u32) {
+        self.layer = Some(layer);
+    }
+
+    /// Returns whether encoded sheets are stripped of metadata, see `set_strip_metadata`.
+    pub fn strips_metadata(&self) -> bool {
+        self.strip_metadata
+    }
+
+    /// Strips every metadata block, such as EXIF, XMP, comments, text chunks and timestamps,
+    /// from the sheet whenever it is encoded or saved, so that output files reveal nothing about
+    /// their sources and stay byte-identical between builds.
+    ///
+    /// The atlas metadata written by `save_png_with_metadata` is still embedded.
+    pub fn set_strip_metadata(&mut self, strip_metadata: bool) {
+        self.strip_metadata = strip_metadata;
+    }
+
+    /// Extracts every frame back into an individual image, see `unpack`.
+    pub fn split(&self) -> Vec<NamedImage> {
+        self.frames
+            .iter()
+            .map(|frame| unpack_frame(&self.image, &FrameMetadata::new(frame)))
+            .collect()
+    }
+
     pub fn add_frame(&mut self, x: u32, y: u32, width: u32, height: u32) {
         self.frames.push(Frame::new(x, y, width, height));
     }
 
+    /// Adds a frame named after its source image, as written to the exported metadata.
+    pub fn add_named_frame(&mut self, name: &str, x: u32, y: u32, width: u32, height: u32) {
+        self.frames.push(Frame::named(name, x, y, width, height));
+    }
+
+    /// Crops transparent space off the right and bottom of the sheet, scanning its pixels.
+    ///
+    /// Generated sheets are already cropped to the frames placed on them, so this is only
+    /// useful for content the packer does not track, such as sheets built with `Sprite::new`
+    /// and `add_frame`. The origin is kept, so frame coordinates stay valid, and frames are
+    /// never cut off, even where their edges are transparent. A sheet without content or
+    /// frames keeps a single transparent pixel.
+    pub fn trim_transparent(&mut self) {
+        let (mut right, mut bottom) =
+            opaque_bounds(&self.image).map_or((0, 0), |bounds| (bounds.right(), bounds.bottom()));
+        for frame in &self.frames {
+            right = right.max(frame.rect().right());
+            bottom = bottom.max(frame.rect().bottom());
+        }
+
+        let (width, height) = self.image.dimensions();
+        let (right, bottom) = (right.max(1).min(width), bottom.max(1).min(height));
+        if (right, bottom) != (width, height) {
+            self.image = image::imageops::crop_imm(&self.image, 0, 0, right, bottom).to_image();
+        }
+    }
+
+    /// Returns the maximal free rectangles left on the sheet, largest first.
+    ///
+    /// Free space is measured within the maximum sheet dimensions used during generation,
+    /// so rectangles may extend beyond the current, trimmed image. Rectangles may overlap.
+    pub fn free_rects(&self) -> Vec<Rect> {
+        let (width, height) = self.capacity;
+        let mut free = vec![Rect::new(0, 0, width, height)];
+
+        for occupied in self
+            .slots
+            .iter()
+            .copied()
+            .chain(self.frames.iter().map(|frame| frame.rect()))
+        {
+            free = prune_contained(
+                free.iter()
+                    .flat_map(|rect| rect.subtract(&occupied))
+                    .collect(),
+            );
+        }
+
+        free.sort_by_key(|rect| std::cmp::Reverse(rect.area()));
+        free
+    }
+
+    /// Returns `true` if an image of the given size fits into the free space of the sheet.
+    pub fn fits(&self, width: u32, height: u32) -> bool {
+        self.free_rects()
+            .iter()
+            .any(|rect| rect.w >= width && rect.h >= height)
+    }
+
+    /// Analyzes the colors of the sheet to help choose an output encoding.
+    pub fn color_report(&self) -> ColorReport {
+        ColorReport::new(&self.image)
+    }
+
+    /// Encodes the sheet at every format and quality of `sweep`.
+    ///
+    /// Use `QualitySweep::best` on the returned results to pick the smallest acceptable encoding.
+    pub fn quality_sweep(
+        &self,
+        sweep: &QualitySweep,
+    ) -> Result<Vec<SweepResult>, Box<dyn std::error::Error>> {
+        sweep.run(&self.image)
+    }
+
+    /// Saves the sheet in the format matching the extension of `path`.
+    ///
+    /// Like every `save*` method, it writes a temporary file next to `path` and renames it into
+    /// place once complete, so an interrupted build never leaves a truncated image behind.
     pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        self.image.save(path)?;
+        let format = image::ImageFormat::from_path(path)?;
+        write_atomic(Path::new(path), OverwritePolicy::Overwrite, |writer| {
+            if self.strip_metadata {
+                let mut data = std::io::Cursor::new(Vec::new());
+                self.image.write_to(&mut data, format)?;
+                writer.write_all(&strip_metadata(data.get_ref())?)?;
+                return Ok(());
+            }
+            Ok(self.image.write_to(writer, format)?)
+        })?;
         Ok(())
     }
+
+    /// Saves the sheet in an explicit format, regardless of the extension of `path`.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the image file to write.
+    /// - `format`: Format to encode the sheet in, with its encoder settings.
+    pub fn save_with_format(
+        &self,
+        path: &str,
+        format: &OutputFormat,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_policy(path, format, OverwritePolicy::Overwrite)?;
+        Ok(())
+    }
+
+    /// Saves the sheet in an explicit format, unless `policy` keeps an existing file.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the image file to write.
+    /// - `format`: Format to encode the sheet in, with its encoder settings.
+    /// - `policy`: What to do if `path` already exists.
+    ///
+    /// # Returns
+    /// A `Result` containing `false` if an existing file was kept, or an error if writing fails
+    /// or `policy` is `OverwritePolicy::Error` and the file exists.
+    pub fn save_with_policy(
+        &self,
+        path: &str,
+        format: &OutputFormat,
+        policy: OverwritePolicy,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        write_atomic(Path::new(path), policy, |writer| {
+            if self.strip_metadata {
+                return Ok(writer.write_all(&self.encode(format)?)?);
+            }
+            format.encode(&self.image, &self.mip_regions(), writer)
+        })
+    }
+
+    /// Saves the sheet as PNG with tuned encoder settings, which often produce smaller files
+    /// than `save`.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the PNG file to write.
+    /// - `options`: Compression, filter and bit depth of the encoder.
+    pub fn save_png(
+        &self,
+        path: &str,
+        options: &PngOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_format(path, &OutputFormat::Png(*options))
+    }
+
+    /// Saves the sheet as an indexed PNG with a palette of at most 256 colors, usually several
+    /// times smaller than a truecolor PNG for pixel art.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the PNG file to write.
+    /// - `options`: Palette size and dithering of the quantization.
+    pub fn save_indexed_png(
+        &self,
+        path: &str,
+        options: &IndexedPngOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_format(path, &OutputFormat::IndexedPng(*options))
+    }
+
+    /// Saves the sheet as JPEG, composited over the background color of `options`.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the JPEG file to write.
+    /// - `options`: Quality and background color of the encoder.
+    pub fn save_jpeg(
+        &self,
+        path: &str,
+        options: &JpegOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_format(path, &OutputFormat::Jpeg(*options))
+    }
+
+    /// Saves the sheet as WebP, for sheets served on the web.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the WebP file to write.
+    /// - `options`: Lossless mode, or the quality of lossy encoding.
+    pub fn save_webp(
+        &self,
+        path: &str,
+        options: &WebPOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_format(path, &OutputFormat::WebP(*options))
+    }
+
+    /// Saves the sheet as AVIF, available with the `avif` feature.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the AVIF file to write.
+    /// - `options`: Quality and speed of the encoder.
+    #[cfg(feature = "avif")]
+    pub fn save_avif(
+        &self,
+        path: &str,
+        options: &AvifOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_format(path, &OutputFormat::Avif(*options))
+    }
+
+    /// Saves the sheet as a block-compressed DDS texture, available with the `dds` feature.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the DDS file to write.
+    /// - `options`: Block compression format of the texture.
+    #[cfg(feature = "dds")]
+    pub fn save_dds(
+        &self,
+        path: &str,
+        options: &DdsOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_format(path, &OutputFormat::Dds(*options))
+    }
+
+    /// Saves the sheet as a KTX2 texture, available with the `ktx2` feature.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the KTX2 file to write.
+    /// - `options`: Texel format and supercompression of the texture.
+    #[cfg(feature = "ktx2")]
+    pub fn save_ktx2(
+        &self,
+        path: &str,
+        options: &Ktx2Options,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_with_format(path, &OutputFormat::Ktx2(*options))
+    }
+
+    /// Encodes the sheet in memory, for uploading it to object storage or caching it.
+    ///
+    /// # Arguments
+    /// - `format`: Format to encode the sheet in, with its encoder settings.
+    ///
+    /// # Returns
+    /// A `Result` containing the encoded image, or an error if encoding fails.
+    pub fn encode(&self, format: &OutputFormat) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut data = std::io::Cursor::new(Vec::new());
+        format.encode(&self.image, &self.mip_regions(), &mut data)?;
+        if self.strip_metadata {
+            return strip_metadata(data.get_ref());
+        }
+        Ok(data.into_inner())
+    }
+
+    /// Encodes the sheet into `writer` without touching the filesystem.
+    ///
+    /// The sheet is encoded in memory first, so `writer` does not need to support seeking,
+    /// even for formats such as TIFF whose encoders do.
+    ///
+    /// # Arguments
+    /// - `writer`: Destination of the encoded image, such as an HTTP response body.
+    /// - `format`: Format to encode the sheet in, with its encoder settings.
+    ///
+    /// # Returns
+    /// A `Result` containing the number of bytes written, or an error if encoding or writing fails.
+    pub fn write_to<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        format: &OutputFormat,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let data = self.encode(format)?;
+        writer.write_all(&data)?;
+        Ok(data.len())
+    }
+
+    /// Saves the sheet as a PNG with its metadata embedded in a compressed text chunk,
+    /// so that a single self-describing file can be shipped.
+    ///
+    /// The embedded document is the one `export_json_hash` writes, referring to the PNG by its
+    /// file name. Read it back with `read_png_metadata`.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the PNG file to write.
+    /// - `options`: Settings of the PNG encoder.
+    pub fn save_png_with_metadata(
+        &self,
+        path: &str,
+        options: &PngOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let image = std::path::Path::new(path)
+            .file_name()
+            .map_or_else(|| path.into(), |name| name.to_string_lossy());
+        let metadata = serde_json::to_string(&texture_packer::json_hash(self, &image))?;
+
+        let data = self.encode(&OutputFormat::Png(*options))?;
+        write_bytes(Path::new(path), &png_text::embed(&data, &metadata)?)
+    }
+
+    /// Encodes the sheet and scrambles the encoded bytes before writing them to `path`.
+    ///
+    /// Load the file by reading it, applying the same scrambler and decoding the result.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the scrambled file to write.
+    /// - `format`: Format to encode the sheet in, with its encoder settings.
+    /// - `scrambler`: Scrambling applied to the encoded bytes.
+    #[cfg(feature = "encryption")]
+    pub fn save_scrambled(
+        &self,
+        path: &str,
+        format: &OutputFormat,
+        scrambler: &crate::scramble::Scrambler,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut data = self.encode(format)?;
+        scrambler.apply(&mut data);
+        write_bytes(Path::new(path), &data)
+    }
+
+    /// Writes the frames in the TexturePacker "JSON (hash)" format.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the JSON file to write.
+    /// - `image`: Path of the saved sheet as referenced from the JSON file.
+    pub fn export_json_hash(
+        &self,
+        path: &str,
+        image: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let document = texture_packer::json_hash(self, image);
+        std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+
+    /// Writes the frames in the TexturePacker "JSON (array)" format, preserving frame order.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the JSON file to write.
+    /// - `image`: Path of the saved sheet as referenced from the JSON file.
+    pub fn export_json_array(
+        &self,
+        path: &str,
+        image: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let document = texture_packer::json_array(self, image);
+        std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+
+    /// Writes the metadata of `export_json_hash` as YAML.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the YAML file to write.
+    /// - `image`: Path of the saved sheet as referenced from the YAML file.
+    #[cfg(feature = "yaml")]
+    pub fn export_yaml(&self, path: &str, image: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, crate::export::config::yaml(self, image)?)?;
+        Ok(())
+    }
+
+    /// Writes the metadata of `export_json_hash` as TOML.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the TOML file to write.
+    /// - `image`: Path of the saved sheet as referenced from the TOML file.
+    #[cfg(feature = "toml")]
+    pub fn export_toml(&self, path: &str, image: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, crate::export::config::toml(self, image)?)?;
+        Ok(())
+    }
+
+    /// Writes a CSV table with a header row and one row per frame:
+    /// `name,x,y,w,h,rotated,trimmed,source_w,source_h,offset_x,offset_y`.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the CSV file to write.
+    pub fn export_csv(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, table::frame_table(self, ','))?;
+        Ok(())
+    }
+
+    /// Writes the table of `export_csv` with tab-separated columns.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the TSV file to write.
+    pub fn export_tsv(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, table::frame_table(self, '\t'))?;
+        Ok(())
+    }
+
+    /// Writes a TypeScript module exporting the frames as a `const` map, so that frame names are
+    /// checked at compile time. The module is also valid JavaScript once the type annotations are
+    /// stripped by the bundler.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the `.ts` file to write.
+    /// - `image`: Path of the saved sheet, exported as `image`.
+    /// - `with_enum`: Whether to also export a `Sprites` string enum of the frame names.
+    pub fn export_typescript(
+        &self,
+        path: &str,
+        image: &str,
+        with_enum: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, typescript::module(self, image, with_enum))?;
+        Ok(())
+    }
+
+    /// Writes a standalone HTML page showing the sheet with an outline per frame; hovering a
+    /// frame reveals its name and coordinates. The sheet is embedded, so the page can be opened
+    /// or attached on its own.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the HTML file to write.
+    pub fn export_preview_html(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, preview::page(self)?)?;
+        Ok(())
+    }
+
+    /// Writes a CSS stylesheet with one class per frame.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the CSS file to write.
+    /// - `image`: Path of the saved sheet, substituted into the URL template of `options`.
+    /// - `options`: Class name prefix and URL template.
+    pub fn export_css(
+        &self,
+        path: &str,
+        image: &str,
+        options: &CssOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, css::stylesheet(self, image, options))?;
+        Ok(())
+    }
+
+    /// Writes an SCSS sprite map with a `@mixin sprite($name)` applying a frame to any selector.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the SCSS file to write.
+    /// - `image`: URL of the saved sheet as referenced from the stylesheet.
+    pub fn export_scss(&self, path: &str, image: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, css::scss(self, image))?;
+        Ok(())
+    }
+
+    /// Writes LESS mixins so that `.sprite("name")` applies a frame to any selector.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the LESS file to write.
+    /// - `image`: URL of the saved sheet as referenced from the stylesheet.
+    pub fn export_less(&self, path: &str, image: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, css::less(self, image))?;
+        Ok(())
+    }
+
+    /// Writes the frames as a Sparrow/Starling `<TextureAtlas>` XML file.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the XML file to write.
+    /// - `image`: Path of the saved sheet as referenced from the XML file.
+    pub fn export_sparrow_xml(
+        &self,
+        path: &str,
+        image: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, sparrow::texture_atlas(self, image))?;
+        Ok(())
+    }
+
+    /// Writes the frames as a Cocos2d-x property list (format version 3).
+    ///
+    /// # Arguments
+    /// - `path`: Path of the plist file to write.
+    /// - `image`: Path of the saved sheet as referenced from the plist file.
+    pub fn export_cocos_plist(
+        &self,
+        path: &str,
+        image: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, cocos::sprite_frames(self, image).to_xml())?;
+        Ok(())
+    }
+
+    /// Writes the frames in Aseprite's `--data` JSON format, array form.
+    ///
+    /// Frames named like `walk_01.png` become the frame tag `walk`, ordered by their numeric suffix.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the JSON file to write.
+    /// - `image`: Path of the saved sheet as referenced from the JSON file.
+    /// - `duration`: Duration in milliseconds of frames that do not come from animated images.
+    pub fn export_aseprite_json(
+        &self,
+        path: &str,
+        image: &str,
+        duration: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let document = aseprite::sprite_sheet(self, image, duration);
+        std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+
+    /// Writes the frames as JSON for Unreal Engine's Paper2D sprite sheet importer,
+    /// which creates one `PaperSprite` per frame.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the JSON file to write.
+    /// - `image`: Path of the saved sheet as referenced from the JSON file.
+    /// - `pivot`: Pivot of every sprite, normalized to the untrimmed image with the origin
+    ///   at its top-left corner; `(0.5, 0.5)` is the center.
+    pub fn export_paper2d_json(
+        &self,
+        path: &str,
+        image: &str,
+        pivot: (f64, f64),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let document = paper2d::sprite_sheet(self, image, pivot);
+        std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+
+    /// Writes JSON describing the sprites for slicing the sheet in Unity's importer,
+    /// with rects in Unity's bottom-left pixel space.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the JSON file to write.
+    /// - `image`: Path of the saved sheet as referenced from the JSON file.
+    /// - `options`: Pixels per unit and pivot of the sprites.
+    pub fn export_unity_json(
+        &self,
+        path: &str,
+        image: &str,
+        options: &UnityOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let document = unity::sprite_metadata(self, image, options);
+        std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+
+    /// Writes a Tiled `.tsx` tileset referencing the sheet, for sheets generated on a grid,
+    /// see `Spriterator::with_grid`.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the tileset file to write.
+    /// - `image`: Path of the saved sheet as referenced from the tileset file.
+    /// - `name`: Name of the tileset shown in Tiled.
+    ///
+    /// # Returns
+    /// A `Result` that is an error if the frames are not laid out on a uniform grid.
+    pub fn export_tiled_tsx(
+        &self,
+        path: &str,
+        image: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, tiled::tileset(self, image, name)?)?;
+        Ok(())
+    }
+
+    /// Writes one Godot AtlasTexture `.tres` resource per frame, named after the frame
+    /// without its extension, so frames can be used wherever Godot expects a texture.
+    ///
+    /// # Arguments
+    /// - `dir`: Directory to write the resources to; subdirectories are created as needed.
+    /// - `image`: Resource path of the saved sheet, such as `res://sheets/sheet.png`.
+    pub fn export_godot_atlas_textures(
+        &self,
+        dir: &str,
+        image: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        godot::write_atlas_textures(self, dir, image)
+    }
+
+    /// Writes the frame rects as a RON file that deserializes into Bevy's `TextureAtlasLayout`.
+    ///
+    /// Layout indices follow the order of `get_frames`.
+    pub fn export_bevy_ron(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, bevy::layout_ron(self))?;
+        Ok(())
+    }
+
+    /// Writes Rust source building Bevy's `TextureAtlasLayout` for the sheet.
+    ///
+    /// The file defines a function `name` returning the layout and a `NAME_FRAMES` constant
+    /// with the frame names by layout index; `name` is converted to a snake case identifier.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the Rust file to write.
+    /// - `name`: Name of the generated function.
+    pub fn export_bevy_rust(
+        &self,
+        path: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, bevy::layout_rust(self, name))?;
+        Ok(())
+    }
+}
+
+/// Removes rectangles contained in other rectangles of the list, including duplicates.
+fn prune_contained(rects: Vec<Rect>) -> Vec<Rect> {
+    let mut maximal: Vec<Rect> = Vec::with_capacity(rects.len());
+    for (index, rect) in rects.iter().enumerate() {
+        let redundant = rects.iter().enumerate().any(|(other_index, other)| {
+            other_index != index
+                && other.contains_rect(rect)
+                && (other != rect || other_index < index)
+        });
+        if !redundant {
+            maximal.push(*rect);
+        }
+    }
+    maximal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::png_text::read_png_metadata;
+    use image::Rgba;
+
+    #[test]
+    fn test_split() {
+        let mut image = RgbaImage::new(8, 4);
+        image.put_pixel(5, 1, image::Rgba([9, 9, 9, 255]));
+        let sprite = Sprite::with_frames(
+            image,
+            vec![
+                Frame::named("a.png", 0, 0, 4, 4),
+                Frame::named("b.png", 4, 0, 2, 2).with_source(4, 4, 1, 1),
+            ],
+        );
+
+        let frames = sprite.split();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].get_name(), "b.png");
+        assert_eq!(frames[1].get_image().dimensions(), (4, 4));
+        assert_eq!(
+            frames[1].get_image().get_pixel(2, 2),
+            &image::Rgba([9, 9, 9, 255])
+        );
+    }
+
+    #[test]
+    fn test_save_mipmaps() {
+        let mut image = RgbaImage::from_pixel(8, 4, Rgba([0, 0, 255, 255]));
+        for y in 0..4 {
+            for x in 0..3 {
+                image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+        let sprite = Sprite::with_frames(
+            image,
+            vec![
+                Frame::named("red", 0, 0, 3, 4),
+                Frame::named("blue", 3, 0, 5, 4),
+            ],
+        );
+
+        let dir = std::env::temp_dir().join("spriterator_save_mipmaps");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sheet.png");
+        let paths = sprite
+            .save_mipmaps(
+                path.to_str().unwrap(),
+                &OutputFormat::Png(PngOptions::new()),
+            )
+            .unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(paths[0].ends_with("sheet_mip1.png"));
+
+        let level = image::open(&paths[0]).unwrap().to_rgba8();
+        assert_eq!(level.dimensions(), (4, 2));
+        assert_eq!(level.get_pixel(1, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(level.get_pixel(2, 0), &Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_save_with_policy() {
+        let dir = std::env::temp_dir().join("spriterator_save_policy");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sheet.png");
+        let path = path.to_str().unwrap();
+
+        let red = Sprite::new(RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255])));
+        let blue = Sprite::new(RgbaImage::from_pixel(2, 2, image::Rgba([0, 0, 255, 255])));
+        red.save(path).unwrap();
+        let png = OutputFormat::Png(PngOptions::new());
+        assert!(!blue
+            .save_with_policy(path, &png, OverwritePolicy::Skip)
+            .unwrap());
+        assert!(blue
+            .save_with_policy(path, &png, OverwritePolicy::Error)
+            .is_err());
+        assert_eq!(&image::open(path).unwrap().to_rgba8(), red.get_image());
+
+        blue.save_with_format(path, &png).unwrap();
+        assert_eq!(&image::open(path).unwrap().to_rgba8(), blue.get_image());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_to() {
+        let sprite = Sprite::new(RgbaImage::from_pixel(6, 3, image::Rgba([1, 2, 3, 255])));
+        let mut data = Vec::new();
+        let written = sprite
+            .write_to(&mut data, &OutputFormat::Png(Default::default()))
+            .unwrap();
+
+        assert_eq!(written, data.len());
+        let decoded = image::load_from_memory(&data).unwrap().to_rgba8();
+        assert_eq!(&decoded, sprite.get_image());
+
+        let tiff = sprite.encode(&OutputFormat::Tiff).unwrap();
+        assert_eq!(
+            image::guess_format(&tiff).unwrap(),
+            image::ImageFormat::Tiff
+        );
+    }
+
+    #[test]
+    fn test_png_metadata() {
+        let mut sprite = Sprite::new(RgbaImage::from_pixel(6, 3, image::Rgba([1, 2, 3, 255])));
+        sprite.add_named_frame("a.png", 0, 0, 3, 3);
+        sprite.add_named_frame("\u{30a2}.png", 3, 0, 3, 3);
+        let path = std::env::temp_dir().join("spriterator_test_png_metadata.png");
+        let path = path.to_str().unwrap();
+
+        sprite
+            .save_png_with_metadata(path, &PngOptions::new())
+            .unwrap();
+        let metadata: serde_json::Value =
+            serde_json::from_str(&read_png_metadata(path).unwrap().unwrap()).unwrap();
+        assert_eq!(
+            metadata["meta"]["image"],
+            "spriterator_test_png_metadata.png"
+        );
+        assert_eq!(metadata["frames"]["\u{30a2}.png"]["frame"]["x"], 3);
+        assert_eq!(&image::open(path).unwrap().to_rgba8(), sprite.get_image());
+
+        sprite.save(path).unwrap();
+        assert_eq!(read_png_metadata(path).unwrap(), None);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_strip_metadata() {
+        let mut sprite = Sprite::new(RgbaImage::from_pixel(6, 3, image::Rgba([1, 2, 3, 255])));
+        sprite.add_named_frame("a.png", 0, 0, 3, 3);
+        let srgb = OutputFormat::Png(PngOptions::new().with_srgb(true));
+        let plain = sprite.encode(&srgb).unwrap();
+        sprite.set_strip_metadata(true);
+        assert!(sprite.strips_metadata());
+        assert_eq!(sprite.encode(&srgb).unwrap(), plain);
+
+        let path = std::env::temp_dir().join("spriterator_test_strip_metadata.png");
+        let path = path.to_str().unwrap();
+        sprite
+            .save_png_with_metadata(path, &PngOptions::new())
+            .unwrap();
+        assert!(read_png_metadata(path).unwrap().is_some());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_trim_transparent() {
+        let mut image = RgbaImage::new(20, 10);
+        image.put_pixel(7, 2, Rgba([1, 2, 3, 255]));
+        let mut sprite = Sprite::new(image);
+        sprite.add_frame(0, 0, 4, 6);
+
+        sprite.trim_transparent();
+        assert_eq!(sprite.get_image().dimensions(), (8, 6));
+        assert_eq!(sprite.get_image().get_pixel(7, 2), &Rgba([1, 2, 3, 255]));
+
+        let mut empty = Sprite::new(RgbaImage::new(4, 4));
+        empty.trim_transparent();
+        assert_eq!(empty.get_image().dimensions(), (1, 1));
+    }
+
+    #[test]
+    fn test_free_rects() {
+        let mut sprite = Sprite::new(RgbaImage::new(10, 10));
+        sprite.add_frame(0, 0, 10, 4);
+        sprite.add_frame(0, 4, 4, 6);
+        sprite.set_layout((16, 10), Vec::new());
+
+        assert_eq!(
+            sprite.free_rects(),
+            vec![Rect::new(4, 4, 12, 6), Rect::new(10, 0, 6, 10)]
+        );
+        assert!(sprite.fits(12, 6));
+        assert!(sprite.fits(6, 10));
+        assert!(!sprite.fits(12, 7));
+    }
 }
@@ -1,19 +1,197 @@
-use image::{imageops::FilterType, RgbaImage};
+use image::codecs::png::PngEncoder;
+use image::{imageops::FilterType, ExtendedColorType, ImageEncoder, Rgba, RgbaImage};
+use regex::Regex;
+use std::collections::HashMap;
 use std::error::Error;
-use walkdir::WalkDir;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
 
+use crate::alpha_scan::opaque_bounds;
+use crate::build_cache::{BuildCache, CachedSheet, Fingerprint};
+use crate::error::SpriteratorError;
+use crate::frame::Frame;
+#[cfg(feature = "gpu")]
+use crate::gpu::GpuCompositor;
+use crate::limits::EngineLimits;
+use crate::post_process::SheetPostProcessor;
+use crate::profile::Profile;
+use crate::rect::Rect;
+use crate::resize_cache::ResizeCache;
+use crate::sheet_layout::SheetLayout;
+use crate::source::{
+    decodable_extensions, glob_set, normalize_extensions, DirectorySource, ImageSource, NamedImage,
+    Probe, DEFAULT_EXTENSIONS,
+};
 use crate::sprite::Sprite;
+use crate::warning::{Warning, WarningHandler};
 
-const SUPPORTED_EXTENSIONS: [&str; 2] = ["png", "webp"];
+/// Reported when inputs read again while packing within the memory budget differ from their
+/// outlines.
+const INPUTS_CHANGED: &str =
+    "Input images changed, or could not be decoded after their size was read, while generating the sheets.";
+
+/// Makes an error returned on a thread pool sendable back to the calling thread, keeping
+/// `SpriteratorError`s intact and describing other errors by their message.
+#[cfg(feature = "parallel")]
+pub(crate) fn sendable_error(error: Box<dyn Error>) -> Box<dyn Error + Send + Sync> {
+    match error.downcast::<SpriteratorError>() {
+        Ok(error) => error,
+        Err(error) => error.to_string().into(),
+    }
+}
+
+/// An input image prepared for packing, together with how it was trimmed.
+struct SourceImage {
+    name: String,
+    image: RgbaImage,
+    source_width: u32,
+    source_height: u32,
+    offset_x: u32,
+    offset_y: u32,
+    trimmed: bool,
+    /// How long the image is shown in milliseconds, for frames of animated images.
+    duration: Option<u32>,
+    /// Pivot normalized to the untrimmed image, if set by its source.
+    pivot: Option<(f64, f64)>,
+    /// Images packed into the color channels of `image`, in R, G, B, A order, in channel packing mode.
+    channels: Vec<SourceImage>,
+}
+
+impl SourceImage {
+    /// Adds the pixels and every property affecting the frame of this image to `fingerprint`.
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.add(&(&self.name, self.image.dimensions(), self.trimmed));
+        fingerprint.add(&(self.source_width, self.source_height));
+        fingerprint.add(&(self.offset_x, self.offset_y));
+        fingerprint.add(&(self.duration, self.pivot, self.channels.len()));
+        fingerprint.write(self.image.as_raw());
+        for channel in &self.channels {
+            channel.fingerprint(fingerprint);
+        }
+    }
+
+    /// Creates the frame of this image placed at `x`, `y` on a sheet.
+    fn frame(&self, x: u32, y: u32) -> Frame {
+        let frame = Frame::named(&self.name, x, y, self.image.width(), self.image.height())
+            .with_duration(self.duration)
+            .with_pivot(self.pivot);
+        if self.trimmed {
+            frame.with_source(
+                self.source_width,
+                self.source_height,
+                self.offset_x,
+                self.offset_y,
+            )
+        } else {
+            frame
+        }
+    }
+}
+
+/// Name and size of an image to lay out, whether or not its pixels are loaded.
+trait Placeable {
+    fn name(&self) -> &str;
+    fn dimensions(&self) -> (u32, u32);
+}
+
+impl Placeable for SourceImage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+}
+
+/// A prepared image without its pixels, laid out by its size and loaded when its sheet is
+/// composed.
+struct Outline {
+    /// Position of the image among all input images.
+    index: usize,
+    /// Frame of the image placed at the origin.
+    frame: Frame,
+}
+
+impl Outline {
+    fn new(index: usize, source: &SourceImage) -> Self {
+        Self {
+            index,
+            frame: source.frame(0, 0),
+        }
+    }
+}
+
+impl Placeable for Outline {
+    fn name(&self) -> &str {
+        self.frame.get_name()
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.frame.get_width(), self.frame.get_height())
+    }
+}
+
+/// Input images loaded by `Spriterator::load_within_budget`.
+enum Inputs {
+    /// Every prepared image, as they fit the memory budget.
+    Images(Vec<SourceImage>),
+    /// The outlines of the prepared images, which together exceed the memory budget.
+    Outlines(Vec<Outline>),
+}
 
 /// Represents a spritesheet generator.
 #[derive(Debug)]
 pub struct Spriterator {
-    dir_path: String,
+    dir_path: Option<PathBuf>,
+    sources: Vec<Box<dyn ImageSource>>,
+    images: Vec<NamedImage>,
     max_width: u32,
     max_height: u32,
     image_width: Option<u32>,
     image_height: Option<u32>,
+    trim: bool,
+    extensions: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    name_pattern: Option<(String, Option<String>)>,
+    ignore_files: bool,
+    max_depth: Option<usize>,
+    follow_links: bool,
+    skip_hidden: bool,
+    animations: bool,
+    color_management: bool,
+    #[cfg(feature = "svg")]
+    svg_scale: f32,
+    #[cfg(feature = "parallel")]
+    threads: Option<usize>,
+    #[cfg(feature = "gpu")]
+    gpu: bool,
+    /// Compositor opened on first use, or `None` if no GPU could be opened.
+    #[cfg(feature = "gpu")]
+    gpu_compositor: std::sync::OnceLock<Option<GpuCompositor>>,
+    resize_cache: Option<PathBuf>,
+    memory_budget: Option<usize>,
+    max_encoded_bytes: Option<usize>,
+    usage_weights: HashMap<String, f64>,
+    array_texture: bool,
+    block_alignment: bool,
+    strip_metadata: bool,
+    grid: bool,
+    columns: Option<u32>,
+    extrude: u32,
+    padding: u32,
+    spacing: u32,
+    margin: u32,
+    power_of_two: bool,
+    resize_filter: FilterType,
+    clear_transparent: bool,
+    alpha_bleed: u32,
+    background: Option<Rgba<u8>>,
+    channel_packing: bool,
+    post_processors: Vec<Box<dyn SheetPostProcessor>>,
+    warning_handler: WarningHandler,
+    engine_limits: EngineLimits,
 }
 
 impl Spriterator {
@@ -36,204 +214,3804 @@ impl Spriterator {
         image_height: Option<u32>,
     ) -> Self {
         Self {
-            dir_path: dir_path.to_string(),
+            dir_path: Some(PathBuf::from(dir_path)),
+            sources: Vec::new(),
+            images: Vec::new(),
             max_width,
             max_height,
             image_width,
             image_height,
+            trim: false,
+            extensions: normalize_extensions(DEFAULT_EXTENSIONS),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            name_pattern: None,
+            ignore_files: true,
+            max_depth: None,
+            follow_links: false,
+            skip_hidden: false,
+            animations: true,
+            color_management: false,
+            #[cfg(feature = "svg")]
+            svg_scale: 1.0,
+            #[cfg(feature = "parallel")]
+            threads: None,
+            #[cfg(feature = "gpu")]
+            gpu: false,
+            #[cfg(feature = "gpu")]
+            gpu_compositor: std::sync::OnceLock::new(),
+            resize_cache: None,
+            memory_budget: None,
+            max_encoded_bytes: None,
+            usage_weights: HashMap::new(),
+            array_texture: false,
+            block_alignment: false,
+            strip_metadata: false,
+            grid: false,
+            columns: None,
+            extrude: 0,
+            padding: 0,
+            spacing: 0,
+            margin: 0,
+            power_of_two: false,
+            resize_filter: FilterType::Lanczos3,
+            clear_transparent: false,
+            alpha_bleed: 0,
+            background: None,
+            channel_packing: false,
+            post_processors: Vec::new(),
+            warning_handler: WarningHandler::default(),
+            engine_limits: EngineLimits::new(),
         }
     }
 
+    /// Creates a `Spriterator` packing images produced at runtime instead of files.
+    ///
+    /// In-memory images are resized and trimmed like files; their names become frame names.
+    /// Add a directory with `with_dir_path` to pack its images first.
+    ///
+    /// # Arguments
+    /// - `images`: Frame names and images to pack, in order.
+    /// - `max_width`: Maximum width of the spritesheet.
+    /// - `max_height`: Maximum height of the spritesheet.
+    /// - `image_width`: Optional target width for resizing images.
+    /// - `image_height`: Optional target height for resizing images.
+    ///
+    /// # Returns
+    /// A new `Spriterator` instance.
+    pub fn from_images(
+        images: Vec<(String, RgbaImage)>,
+        max_width: u32,
+        max_height: u32,
+        image_width: Option<u32>,
+        image_height: Option<u32>,
+    ) -> Self {
+        let mut spriterator = Self::new("", max_width, max_height, image_width, image_height);
+        spriterator.dir_path = None;
+        spriterator.images = images
+            .into_iter()
+            .map(|(name, image)| NamedImage::new(&name, image))
+            .collect();
+        spriterator
+    }
+
+    /// Creates a `Spriterator` packing the images of `source` instead of a directory,
+    /// such as the files listed in a `ManifestSource`.
+    ///
+    /// # Arguments
+    /// - `source`: Provides the images to pack, in order.
+    /// - `max_width`: Maximum width of the spritesheet.
+    /// - `max_height`: Maximum height of the spritesheet.
+    /// - `image_width`: Optional target width for resizing images.
+    /// - `image_height`: Optional target height for resizing images.
+    ///
+    /// # Returns
+    /// A new `Spriterator` instance.
+    pub fn from_image_source(
+        source: impl ImageSource + 'static,
+        max_width: u32,
+        max_height: u32,
+        image_width: Option<u32>,
+        image_height: Option<u32>,
+    ) -> Self {
+        Self::from_images(Vec::new(), max_width, max_height, image_width, image_height)
+            .with_image_source(source)
+    }
+
+    /// Adds an image produced at runtime, packed after the images of the directory and of
+    /// other sources.
+    ///
+    /// # Arguments
+    /// - `name`: Name of the frame, such as `generated/noise.png`.
+    /// - `image`: The image to pack.
+    pub fn with_image(mut self, name: &str, image: RgbaImage) -> Self {
+        self.images.push(NamedImage::new(name, image));
+        self
+    }
+
+    /// Adds a source of images, packed after the images of the directory in the order
+    /// sources are added.
+    ///
+    /// Images of a source are resized and trimmed like files.
+    pub fn with_image_source(mut self, source: impl ImageSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Sets the directory containing images, for paths that are not valid Unicode.
+    pub fn with_dir_path(mut self, dir_path: impl AsRef<Path>) -> Self {
+        self.dir_path = Some(dir_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets a function called with every warning raised during generation.
+    /// Warnings are ignored by default.
+    pub fn with_warning_handler(
+        mut self,
+        handler: impl Fn(&Warning) + Send + Sync + 'static,
+    ) -> Self {
+        self.warning_handler = WarningHandler::new(handler);
+        self
+    }
+
+    /// Checks the generated sheets against the limits of a target engine, raising a warning
+    /// for every exceeded limit, or failing if the limits are strict.
+    pub fn with_engine_limits(mut self, engine_limits: EngineLimits) -> Self {
+        self.engine_limits = engine_limits;
+        self
+    }
+
+    /// Limits the size of each sheet encoded as PNG; frames that would push a sheet over
+    /// the limit are moved to additional sheets.
+    ///
+    /// Generation fails if a single image cannot fit within the limit on its own. The limit
+    /// applies to sheets before post processors run, which are only run on the final sheets.
+    pub fn with_max_encoded_bytes(mut self, max_encoded_bytes: Option<usize>) -> Self {
+        self.max_encoded_bytes = max_encoded_bytes;
+        self
+    }
+
+    /// Sets how often each frame is used, keyed by frame name.
+    ///
+    /// Frames are packed in descending order of weight so that frequently used frames end up
+    /// next to each other on the first sheets, reducing texture switches at runtime.
+    /// Frames without a weight count as `0.0`; frames with equal weights keep their input order.
+    pub fn with_usage_weights(mut self, usage_weights: HashMap<String, f64>) -> Self {
+        self.usage_weights = usage_weights;
+        self
+    }
+
+    /// Produces pages meant to be uploaded as layers of a GPU array texture.
+    ///
+    /// Every page keeps exactly the maximum dimensions instead of being cropped to its content,
+    /// and records its layer index, see `Sprite::get_layer`.
+    pub fn with_array_texture(mut self, array_texture: bool) -> Self {
+        self.array_texture = array_texture;
+        self
+    }
+
+    /// Strips source metadata and timestamps from every sheet when it is saved or encoded,
+    /// disabled by default; see `Sprite::set_strip_metadata`.
+    pub fn with_strip_metadata(mut self, strip_metadata: bool) -> Self {
+        self.strip_metadata = strip_metadata;
+        self
+    }
+
+    /// Applies the sheet dimensions of a named profile, see `Profile::parse`.
+    ///
+    /// # Returns
+    /// A `Result` containing the updated `Spriterator`, or an error if the profile is unknown.
+    pub fn with_profile(self, name: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(self.with_sheet_profile(&Profile::parse(name)?))
+    }
+
+    /// Applies the sheet dimensions of `profile`.
+    pub fn with_sheet_profile(mut self, profile: &Profile) -> Self {
+        self.max_width = profile.get_max_width();
+        self.max_height = profile.get_max_height();
+        self.power_of_two = self.power_of_two || profile.is_power_of_two();
+        self
+    }
+
+    /// Places frames on a uniform grid whose cells fit the largest image.
+    pub fn with_grid(mut self, grid: bool) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    /// Places exactly `columns` frames in every row of a uniform grid, so that the cell of a
+    /// frame can be computed as `index % columns` and `index / columns` within its sheet.
+    ///
+    /// Sheets keep the full width of all columns even when their last row is incomplete.
+    pub fn with_columns(mut self, columns: Option<u32>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Repeats the border pixels of each frame outward by `extrude` pixels to prevent
+    /// neighboring frames from bleeding into each other when sampled with filtering.
+    ///
+    /// Frame coordinates keep pointing at the un-extruded image.
+    pub fn with_extrude(mut self, extrude: u32) -> Self {
+        self.extrude = extrude;
+        self
+    }
+
+    /// Leaves `padding` transparent pixels around each frame, beyond its extrusion.
+    ///
+    /// Frame coordinates point at the image inside the padding.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Leaves `spacing` pixels between neighboring frames, including their extrusion and padding.
+    pub fn with_spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Leaves `margin` pixels between the frames and the edges of each sheet.
+    pub fn with_margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Aligns every slot, holding a frame with its extrusion and padding, to the 4×4 pixel blocks
+    /// of GPU block compression, such as the BC formats of DDS and KTX2 output.
+    ///
+    /// Slot origins and sizes, and the sheet size where the maximum dimensions allow, are
+    /// rounded up to multiples of 4, so no block spans two frames and compression never bleeds
+    /// the colors of one frame into its neighbours. Frames without extrusion or padding are
+    /// then aligned themselves.
+    pub fn with_block_alignment(mut self, block_alignment: bool) -> Self {
+        self.block_alignment = block_alignment;
+        self
+    }
+
+    /// Rounds sheet dimensions up to the next power of two.
+    ///
+    /// Images are then packed within the largest power-of-two dimensions not exceeding the
+    /// maximum sheet size, so the rounded sheets stay within it.
+    pub fn with_power_of_two(mut self, power_of_two: bool) -> Self {
+        self.power_of_two = power_of_two;
+        self
+    }
+
+    /// Sets the filter used when resizing images to `image_width`/`image_height`.
+    /// Defaults to `FilterType::Lanczos3`.
+    pub fn with_resize_filter(mut self, resize_filter: FilterType) -> Self {
+        self.resize_filter = resize_filter;
+        self
+    }
+
+    /// Configures seam-free packing for tilemaps: a uniform grid, 2 pixels of extrusion,
+    /// power-of-two sheets and nearest-neighbor resizing.
+    pub fn with_tile_preset(self) -> Self {
+        self.with_grid(true)
+            .with_extrude(2)
+            .with_power_of_two(true)
+            .with_resize_filter(FilterType::Nearest)
+    }
+
+    /// Sets the color of every fully transparent pixel of the sheets to transparent black.
+    ///
+    /// Invisible color left over in transparent areas costs space in compressed output
+    /// and shows up in image diffs, even though it never renders.
+    pub fn with_clear_transparent(mut self, clear_transparent: bool) -> Self {
+        self.clear_transparent = clear_transparent;
+        self
+    }
+
+    /// Extends the colors at the edges of frames into fully transparent pixels up to `radius`
+    /// pixels away, leaving them transparent.
+    ///
+    /// Bilinear filtering blends the colors of transparent neighbors into edge pixels; without
+    /// bleeding they are black, producing dark halos around scaled sprites.
+    pub fn with_alpha_bleed(mut self, radius: u32) -> Self {
+        self.alpha_bleed = radius;
+        self
+    }
+
+    /// Fills the sheets with `background` behind the frames instead of leaving them transparent,
+    /// for targets without alpha such as JPEG output, or to make frame bounds visible.
+    ///
+    /// Frames are blended over the background, which also fills extruded and power-of-two
+    /// padding areas.
+    pub fn with_background(mut self, background: Option<Rgba<u8>>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Packs images as grayscale masks, four to a slot, into the red, green, blue and alpha
+    /// channels of the sheet; see `Frame::get_channel`.
+    ///
+    /// Each mask is the luma of its image multiplied by its alpha, so both white shapes on
+    /// transparent backgrounds and opaque grayscale images work. Consecutive images share a slot,
+    /// sized to fit the largest of them.
+    pub fn with_channel_packing(mut self, channel_packing: bool) -> Self {
+        self.channel_packing = channel_packing;
+        self
+    }
+
+    /// Adds a step run on every composed sheet, after all built-in processing.
+    ///
+    /// Processors run in the order they were added, once per final sheet, see
+    /// `SheetPostProcessor`. With `max_encoded_bytes`, the size limit applies to sheets before
+    /// they are processed. `generate_incremental` only records how many processors are added,
+    /// so delete its cache file after changing one.
+    pub fn with_post_processor(
+        mut self,
+        post_processor: impl SheetPostProcessor + 'static,
+    ) -> Self {
+        self.post_processors.push(Box::new(post_processor));
+        self
+    }
+
+    /// Enables trimming of fully transparent borders from each image before packing.
+    ///
+    /// Trimmed frames record their original size and the offset of the kept area,
+    /// see `Frame::is_trimmed`.
+    pub fn with_trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Sets the file extensions of the images to pack, such as `&["png", "jpg"]`.
+    ///
+    /// Extensions are matched case-insensitively and may be given with a leading dot.
+    /// Defaults to PNG, WebP, GIF, BMP, TIFF, TGA and ICO, plus SVG with the `svg` feature.
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = normalize_extensions(extensions);
+        self
+    }
+
+    /// Packs images of every format this build can decode, such as JPEG and QOI,
+    /// in addition to the default ones.
+    pub fn with_all_decodable_extensions(mut self) -> Self {
+        self.extensions = decodable_extensions();
+        self
+    }
+
+    /// Only packs images of the directory whose path relative to it matches one of `patterns`,
+    /// such as `**/*_idle_*.png`. All images are packed by default.
+    ///
+    /// `*` and `?` do not match `/`, while `**` matches any number of directories.
+    /// Invalid patterns are reported by `validate`.
+    pub fn with_include(mut self, patterns: &[&str]) -> Self {
+        self.include = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    /// Skips images of the directory whose path relative to it matches one of `patterns`,
+    /// such as `**/raw/**`, and directories matching one of them entirely.
+    pub fn with_exclude(mut self, patterns: &[&str]) -> Self {
+        self.exclude = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    /// Only packs images of the directory whose path relative to it matches the regular
+    /// expression `pattern`, optionally naming their frames after `template`.
+    ///
+    /// The template refers to capture groups as `$1` or `${name}`. Without a template, a pattern
+    /// capturing `animation` and `index` names frames `${animation}_${index}` with the extension
+    /// of the file, the form exporters group into animations; frames of other patterns keep
+    /// their names. An invalid pattern is reported by `validate`.
+    ///
+    /// ```
+    /// # use spriterator::Spriterator;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = std::env::temp_dir().join("spriterator_doc_name_pattern");
+    /// std::fs::create_dir_all(&dir)?;
+    /// for name in ["chr_walk_01.png", "chr_walk_00.png", "notes.png"] {
+    ///     image::RgbaImage::new(2, 2).save(dir.join(name))?;
+    /// }
+    ///
+    /// let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+    ///     .with_name_pattern(
+    ///         r"chr_(?P<animation>\w+?)_(?P<index>\d+)\.png$",
+    ///         Some("${animation}/${index}.png"),
+    ///     )
+    ///     .generate()?;
+    /// let frames = sprites[0].get_frames();
+    /// let names: Vec<&str> = frames.iter().map(|frame| frame.get_name()).collect();
+    /// assert_eq!(names, ["walk/00.png", "walk/01.png"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_name_pattern(mut self, pattern: &str, template: Option<&str>) -> Self {
+        self.name_pattern = Some((pattern.to_string(), template.map(str::to_string)));
+        self
+    }
+
+    /// Skips the paths listed in `.spriteignore` files of the directory and its
+    /// subdirectories, written in gitignore syntax such as `*.raw.png` or `scratch/`.
+    /// Enabled by default.
+    pub fn with_ignore_files(mut self, ignore_files: bool) -> Self {
+        self.ignore_files = ignore_files;
+        self
+    }
+
+    /// Limits how many levels of subdirectories are walked, `Some(0)` packing only the images
+    /// directly in the directory. Unlimited by default.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Follows symbolic links to files and directories, disabled by default.
+    ///
+    /// Links pointing back to one of their parent directories are skipped.
+    pub fn with_follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Skips hidden files and directories, whose name starts with a dot, such as editor
+    /// backup folders. Disabled by default.
+    pub fn with_skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.skip_hidden = skip_hidden;
+        self
+    }
+
+    /// Splits animated GIF, PNG (APNG) and WebP images into one frame each, enabled by default.
+    ///
+    /// Frames of an animated image are named after it with a numeric suffix, such as
+    /// `walk_03.gif` for `walk.gif`, so exporters group them into an animation, and record
+    /// their duration, see `Frame::get_duration`. When disabled, only the first frame is packed.
+    pub fn with_animations(mut self, animations: bool) -> Self {
+        self.animations = animations;
+        self
+    }
+
+    /// Converts input images to sRGB from the color space they embed, disabled by default.
+    ///
+    /// PNG gamma (gAMA) is always converted. Embedded ICC profiles need the `icc` feature;
+    /// without it, or if a profile cannot be read, the image is packed unchanged and
+    /// `Warning::UnconvertedColorProfile` is reported. Images embedding nothing are assumed to
+    /// be sRGB already.
+    pub fn with_color_management(mut self, color_management: bool) -> Self {
+        self.color_management = color_management;
+        self
+    }
+
+    /// Sets how many threads decode and resize input images and compose sheets, available
+    /// with the `parallel` feature. Uses rayon's global thread pool, sized to the number of
+    /// CPUs, by default.
+    ///
+    /// Images are packed in the same order whatever the thread count, but warnings may be
+    /// reported in any order.
+    #[cfg(feature = "parallel")]
+    pub fn with_threads(mut self, threads: Option<usize>) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets whether sheets are composed on a GPU, available with the `gpu` feature. Disabled
+    /// by default.
+    ///
+    /// Frames are uploaded into their place in a texture of the sheet, which is read back
+    /// before extrusion and the other processing, done on the CPU as usual. Partially
+    /// transparent pixels keep their exact color, where blending them on the CPU may round
+    /// color channels by one. If no GPU can be opened, `Warning::GpuUnavailable` is reported
+    /// once and sheets are composed on the CPU; so are sheets exceeding the limits of the GPU.
+    #[cfg(feature = "gpu")]
+    pub fn with_gpu(mut self, gpu: bool) -> Self {
+        self.gpu = gpu;
+        self
+    }
+
+    /// Keeps resized input images in `dir`, so that later runs resizing the same images to the
+    /// same size with the same filter load them instead of resampling again. Disabled by default.
+    ///
+    /// Entries are looked up by the decoded pixels, so edited files miss the cache while
+    /// renamed or moved ones still hit it. Only resampling is skipped on a hit: inputs are
+    /// still read and decoded on every run, so the cache pays off for costly filters and large
+    /// inputs rather than for decoding. Pass `None` to disable it again. The directory
+    /// is created when needed and never pruned. If an entry cannot be written, the image is
+    /// resized as usual and `Warning::ResizeCacheUnavailable` is reported.
+    pub fn with_resize_cache(mut self, dir: Option<PathBuf>) -> Self {
+        self.resize_cache = dir;
+        self
+    }
+
+    /// Sets how many bytes of prepared input images `generate` may hold in memory at once,
+    /// unlimited by default.
+    ///
+    /// The sheets are laid out by the size of the prepared images first, read from image
+    /// headers unless trimming, see `plan_layout`. When the images together exceed the budget,
+    /// each is only loaded when its sheet is composed. With trimming, sizes are only known once
+    /// the images are loaded, so they are loaded twice. The generated sheets are the same
+    /// either way. The budget does not cover the sheets themselves, nor inputs packed with
+    /// channel packing or `max_encoded_bytes`, which need every image at once.
+    pub fn with_memory_budget(mut self, memory_budget: Option<usize>) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    /// Sets the scale SVG images are rasterized at, `1.0` being their size at 96 DPI.
+    ///
+    /// Resizing with `image_width` and `image_height` still applies afterwards, so prefer
+    /// rasterizing at the final size to keep edges sharp.
+    #[cfg(feature = "svg")]
+    pub fn with_svg_scale(mut self, svg_scale: f32) -> Self {
+        self.svg_scale = svg_scale;
+        self
+    }
+
     /// Generates a list of sprites from the images in the specified directory.
     ///
+    /// Images are packed in input order: the directory is walked with entries sorted by file name,
+    /// then reordered by usage weights if any are set. Sheets are filled one after another, so
+    /// frames never interleave across sheets — every frame on a sheet comes before all frames on
+    /// later sheets, and frames within a sheet keep that order. Sequential animations can rely
+    /// on this when played back across sheet boundaries.
+    ///
     /// # Returns
     /// A `Result` containing a vector of `Sprite` instances on success, or an error on failure.
     pub fn generate(&self) -> Result<Vec<Sprite>, Box<dyn Error>> {
-        let images = self.get_images()?;
+        self.run(|| self.generate_sheets())
+    }
+
+    /// Generates the sheets one at a time, so that only the sheet being used is held in memory
+    /// alongside the input images, instead of every sheet as with `generate`.
+    ///
+    /// Input images are loaded and checked up front. Sheets match those of `generate`, but
+    /// engine limits are checked as each sheet is produced: with strict limits, the sheets
+    /// before the one exceeding a limit have already been yielded.
+    ///
+    /// # Returns
+    /// A `Result` containing an iterator over the sheets, or an error if the configuration is
+    /// invalid or loading the images fails. The iterator yields an error and ends if
+    /// composing a sheet fails.
+    pub fn iter_sprites(&self) -> Result<SpriteIter<'_>, Box<dyn Error>> {
+        self.validate()?;
+        let mut images = self.get_images()?;
+        let cell = self.prepare_packing(&mut images)?;
+        Ok(SpriteIter {
+            spriterator: self,
+            images,
+            cell,
+            start: 0,
+            page: 0,
+            frames: 0,
+        })
+    }
+
+    /// Generates only the sheets that changed since the previous call with the same
+    /// `cache_path`, recording the fingerprints of this build in the cache file there.
+    ///
+    /// The input files are compared first, by content: when neither they nor the
+    /// configuration changed, nothing is decoded and every sheet is reported unchanged.
+    /// Otherwise the images are loaded and laid out as by `generate`, and only the sheets whose
+    /// layout or frame pixels changed are composed. With `max_encoded_bytes` set, the layout
+    /// depends on the composed sheets, so every sheet is composed and the unchanged ones are
+    /// then left out. Post processors are assumed to behave the same on every call; delete the
+    /// cache file after changing one.
+    ///
+    /// The cache is written before returning, so the returned sheets must be saved for the
+    /// files on disk to match it.
+    ///
+    /// # Arguments
+    /// - `cache_path`: Path of the cache file, which is created if missing.
+    ///
+    /// # Returns
+    /// A `Result` containing one entry per sheet, holding the sheet if it must be saved again or
+    /// `None` if the file saved from the previous build is still current. Files of sheets past
+    /// the end of the vector are left over from a larger previous build.
+    pub fn generate_incremental(
+        &self,
+        cache_path: impl AsRef<Path>,
+    ) -> Result<Vec<Option<Sprite>>, Box<dyn Error>> {
+        let cache_path = cache_path.as_ref();
+        self.run(|| self.generate_changed_sheets(cache_path))
+    }
+
+    /// Runs `task` on a thread pool of the configured size with the `parallel` feature, or on
+    /// the global pool and the calling thread otherwise.
+    fn run<T: Send>(
+        &self,
+        task: impl FnOnce() -> Result<T, Box<dyn Error>> + Send,
+    ) -> Result<T, Box<dyn Error>> {
+        #[cfg(feature = "parallel")]
+        if let Some(threads) = self.threads {
+            return rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?
+                .install(|| task().map_err(sendable_error))
+                .map_err(|error| -> Box<dyn Error> { error });
+        }
+        task()
+    }
 
-        let mut sprites: Vec<Sprite> = Vec::new();
-        let mut current_sprite = RgbaImage::new(self.max_width, self.max_height);
-        let mut current_frames: Vec<(u32, u32, u32, u32)> = Vec::new();
-        let (mut current_x, mut current_y, mut row_height) = (0, 0, 0);
+    fn generate_changed_sheets(
+        &self,
+        cache_path: &Path,
+    ) -> Result<Vec<Option<Sprite>>, Box<dyn Error>> {
+        self.validate()?;
+        let cache = BuildCache::load(cache_path);
+        let config = self.config_fingerprint();
+        let inputs = self.inputs_fingerprint()?;
 
-        for img in images {
-            if current_x + img.width() > self.max_width {
-                current_y += row_height;
-                current_x = 0;
-                row_height = 0;
+        if cache.is_current(config, inputs) {
+            let sheets: Vec<_> = cache
+                .sheets
+                .iter()
+                .map(|sheet| ((sheet.width, sheet.height), sheet.frames))
+                .collect();
+            self.report_engine_limits(self.engine_limits.check_sizes(&sheets))?;
+            return Ok(cache.sheets.iter().map(|_| None).collect());
+        }
+
+        let mut images = self.get_images()?;
+        let cell = self.prepare_packing(&mut images)?;
+        let (sprites, fingerprints) = match self.max_encoded_bytes {
+            Some(_) => {
+                let sprites = self.pack_sequentially(&images, cell)?;
+                let fingerprints = sprites
+                    .iter()
+                    .map(|sprite| sprite_fingerprint(config, sprite))
+                    .collect();
+                (sprites.into_iter().map(Some).collect(), fingerprints)
+            }
+            None => {
+                let pages = self.layout_pages(&images, cell);
+                let fingerprints: Vec<u64> = pages
+                    .iter()
+                    .map(|(start, slots)| page_fingerprint(config, &images[*start..], slots))
+                    .collect();
+                let (changed, layouts): (Vec<usize>, Vec<_>) = pages
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(page, _)| {
+                        cache.sheet(*page).map(|sheet| sheet.fingerprint)
+                            != Some(fingerprints[*page])
+                    })
+                    .unzip();
+                let mut sprites: Vec<Option<Sprite>> = fingerprints.iter().map(|_| None).collect();
+                for (page, sprite) in changed
+                    .into_iter()
+                    .zip(self.compose_pages(&images, layouts)?)
+                {
+                    sprites[page] = Some(sprite);
+                }
+                (sprites, fingerprints)
             }
+        };
 
-            if current_y + img.height() > self.max_height {
-                if !current_frames.is_empty() {
-                    let mut trimmed_sprite = Sprite::new(self.trim_transparent(&current_sprite));
-                    for &(x, y, width, height) in &current_frames {
-                        trimmed_sprite.add_frame(x, y, width, height);
+        let sheets: Vec<CachedSheet> = sprites
+            .iter()
+            .zip(fingerprints)
+            .enumerate()
+            .map(|(page, (sprite, fingerprint))| match sprite {
+                Some(sprite) => {
+                    let (width, height) = sprite.get_image().dimensions();
+                    CachedSheet {
+                        fingerprint,
+                        width,
+                        height,
+                        frames: sprite.get_frames().len(),
                     }
-                    sprites.push(trimmed_sprite);
                 }
+                None => cache.sheets[page].clone(),
+            })
+            .collect();
+        let sizes: Vec<_> = sheets
+            .iter()
+            .map(|sheet| ((sheet.width, sheet.height), sheet.frames))
+            .collect();
+        self.report_engine_limits(self.engine_limits.check_sizes(&sizes))?;
 
-                current_sprite = RgbaImage::new(self.max_width, self.max_height);
-                current_frames.clear();
-                current_x = 0;
-                current_y = 0;
-                row_height = 0;
-            }
+        let sprites = sprites
+            .into_iter()
+            .zip(&sheets)
+            .enumerate()
+            .map(|(page, (sprite, sheet))| {
+                sprite.and_then(|mut sprite| {
+                    self.finish_sprite(&mut sprite, page);
+                    // Sheets identical to the previous build need not be saved again.
+                    let unchanged = cache.sheet(page) == Some(sheet);
+                    (!unchanged).then_some(sprite)
+                })
+            })
+            .collect();
+        BuildCache::new(config, inputs, sheets).save(cache_path)?;
+        Ok(sprites)
+    }
 
-            image::imageops::overlay(
-                &mut current_sprite,
-                &img,
-                current_x as i64,
-                current_y as i64,
-            );
-            current_frames.push((current_x, current_y, img.width(), img.height()));
+    /// Fingerprints every setting affecting the generated sheets, such that builds with equal
+    /// fingerprints produce the same sheets from the same inputs.
+    fn config_fingerprint(&self) -> u64 {
+        let mut fingerprint = Fingerprint::default();
+        fingerprint.add(&env!("CARGO_PKG_VERSION"));
+        fingerprint.add(&(&self.dir_path, self.max_width, self.max_height));
+        fingerprint.add(&(self.image_width, self.image_height, self.trim));
+        fingerprint.add(&(&self.extensions, &self.include, &self.exclude));
+        fingerprint.add(&(&self.name_pattern, self.ignore_files, self.max_depth));
+        fingerprint.add(&(self.follow_links, self.skip_hidden, self.animations));
+        fingerprint.add(&self.color_management);
+        #[cfg(feature = "svg")]
+        fingerprint.add(&self.svg_scale);
+        let mut usage_weights: Vec<_> = self.usage_weights.iter().collect();
+        usage_weights.sort_by(|a, b| a.0.cmp(b.0));
+        fingerprint.add(&(self.max_encoded_bytes, usage_weights, self.array_texture));
+        fingerprint.add(&(self.strip_metadata, self.grid, self.columns));
+        fingerprint.add(&(self.extrude, self.padding, self.spacing, self.margin));
+        fingerprint.add(&self.block_alignment);
+        fingerprint.add(&(self.power_of_two, self.resize_filter));
+        fingerprint.add(&(self.clear_transparent, self.alpha_bleed, self.background));
+        // Closures cannot be compared, so changing a post processor needs a cache reset.
+        fingerprint.add(&(self.channel_packing, self.post_processors.len()));
+        fingerprint.add(&(&self.engine_limits, self.memory_budget));
+        #[cfg(feature = "gpu")]
+        fingerprint.add(&self.gpu);
+        fingerprint.finish()
+    }
 
-            row_height = row_height.max(img.height());
-            current_x += img.width();
+    /// Fingerprints the inputs: the files of the directory by their path and content, and
+    /// other sources as `ImageSource::fingerprint` writes them, without decoding them, or by
+    /// the pixels of their images for sources that write nothing.
+    fn inputs_fingerprint(&self) -> Result<u64, Box<dyn Error>> {
+        let mut fingerprint = Fingerprint::default();
+        if let Some(dir_path) = &self.dir_path {
+            for path in self.directory_source(dir_path).files()? {
+                let data = std::fs::read(&path)?;
+                fingerprint.add(&(path.strip_prefix(dir_path).unwrap_or(&path), data.len()));
+                fingerprint.write(&data);
+            }
         }
 
-        if !current_frames.is_empty() {
-            let mut trimmed_sprite = Sprite::new(self.trim_transparent(&current_sprite));
-            for &(x, y, width, height) in &current_frames {
-                trimmed_sprite.add_frame(x, y, width, height);
+        for source in self.other_sources() {
+            if source.fingerprint(&mut fingerprint)? {
+                continue;
+            }
+            for entry in source.entries() {
+                let entry = entry?;
+                fingerprint.add(&(&entry.name, entry.image.dimensions()));
+                fingerprint.add(&(entry.duration, entry.pivot));
+                fingerprint.write(entry.image.as_raw());
             }
-            sprites.push(trimmed_sprite);
         }
+        Ok(fingerprint.finish())
+    }
 
+    fn generate_sheets(&self) -> Result<Vec<Sprite>, Box<dyn Error>> {
+        self.validate()?;
+        let sprites = match self.load_within_budget()? {
+            Inputs::Images(images) => self.pack(images)?,
+            Inputs::Outlines(outlines) => self.pack_in_chunks(outlines)?,
+        };
+        self.check_engine_limits(&sprites)?;
         Ok(sprites)
     }
 
-    fn get_images(&self) -> Result<Vec<RgbaImage>, Box<dyn Error>> {
-        let images: Vec<RgbaImage> = WalkDir::new(&self.dir_path)
-            .into_iter()
-            .filter_map(|entry| {
-                let path = entry.ok()?.path().to_path_buf();
-
-                let is_image = path
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
-                    .unwrap_or(false);
-
-                if path.is_file() && is_image {
-                    let img = image::open(&path).ok()?.to_rgba8();
-
-                    if (self.image_width.is_none() && img.width() > self.max_width)
-                        || (self.image_height.is_none() && img.height() > self.max_height)
-                    {
-                        return Some(Err::<RgbaImage, Box<dyn Error>>(
-                            format!(
-                                "Image {} dimensions {}x{} exceed max dimensions {}x{}.",
-                                path.display(),
-                                img.width(),
-                                img.height(),
-                                self.max_width,
-                                self.max_height
-                            )
-                            .into(),
-                        ));
-                    } else {
-                        Some(Ok(self.resize_image(img)))
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+    fn check_engine_limits(&self, sprites: &[Sprite]) -> Result<(), SpriteratorError> {
+        self.report_engine_limits(self.engine_limits.check(sprites))
+    }
 
-        if images.is_empty() {
-            return Err(format!(
-                "No images with supported extensions {:?} were found in the specified directory: {}",
-                SUPPORTED_EXTENSIONS,
-                self.dir_path
-            )
-            .into());
+    /// Fails on engine limit violations with strict limits, or warns about them otherwise.
+    fn report_engine_limits(&self, violations: Vec<String>) -> Result<(), SpriteratorError> {
+        if violations.is_empty() {
+            return Ok(());
         }
 
-        Ok(images)
+        if self.engine_limits.is_strict() {
+            return Err(SpriteratorError::EngineLimitsExceeded { violations });
+        }
+        for message in violations {
+            self.warning_handler
+                .warn(Warning::EngineLimitExceeded { message });
+        }
+        Ok(())
     }
 
-    fn trim_transparent(&self, image: &RgbaImage) -> RgbaImage {
-        let (mut max_x, mut max_y) = (0, 0);
-        let mut min_x = image.width();
-        let mut min_y = image.height();
-        let mut is_completely_transparent = true;
+    /// Checks the configuration for settings that cannot work together, without loading any image.
+    ///
+    /// `generate` runs this first, so problems are reported before any work is done.
+    ///
+    /// # Returns
+    /// `Ok(())` if the configuration is usable, `SpriteratorError::InvalidDimensions` if the
+    /// sheet or resize dimensions are unusable, which is checked first, or
+    /// `SpriteratorError::InvalidConfiguration` listing every other problem found.
+    pub fn validate(&self) -> Result<(), SpriteratorError> {
+        self.validate_dimensions()?;
+
+        let mut diagnostics = Vec::new();
 
-        for (x, y, pixel) in image.enumerate_pixels() {
-            if pixel[3] > 0 {
-                max_x = max_x.max(x);
-                max_y = max_y.max(y);
-                min_x = min_x.min(x);
-                min_y = min_y.min(y);
-                is_completely_transparent = false;
+        let padding = self.border().saturating_mul(2);
+        let (inner_width, inner_height) = self.inner_size();
+        if padding >= inner_width || padding >= inner_height {
+            diagnostics.push(format!(
+                "Reserving {} pixels of extrusion and padding and {} pixels of margin on each \
+                 side leaves no room for images on a {}x{} sheet.",
+                self.border(),
+                self.margin,
+                self.max_width,
+                self.max_height
+            ));
+        } else {
+            if let Some(width) = self.image_width.filter(|&width| width > 0) {
+                if width.saturating_add(padding) > inner_width {
+                    diagnostics.push(format!(
+                        "Images resized to a width of {} pixels with {} pixels of extrusion and \
+                         padding do not fit the usable sheet width of {}.",
+                        width,
+                        self.border(),
+                        inner_width
+                    ));
+                }
+            }
+            if let Some(height) = self.image_height.filter(|&height| height > 0) {
+                if height.saturating_add(padding) > inner_height {
+                    diagnostics.push(format!(
+                        "Images resized to a height of {} pixels with {} pixels of extrusion and \
+                         padding do not fit the usable sheet height of {}.",
+                        height,
+                        self.border(),
+                        inner_height
+                    ));
+                }
             }
         }
 
-        if is_completely_transparent {
-            return RgbaImage::new(1, 1);
+        if self.columns == Some(0) {
+            diagnostics.push("A grid needs at least one column.".to_string());
+        } else if let (Some(columns), Some(width)) = (self.columns, self.image_width) {
+            let cell = width.saturating_add(padding);
+            if self.row_width(cell, columns) > inner_width {
+                diagnostics.push(format!(
+                    "{} columns of {} pixel wide cells do not fit the usable sheet width of {}.",
+                    columns, cell, inner_width
+                ));
+            }
         }
 
-        image::imageops::crop_imm(image, min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
-            .to_image()
-    }
+        if self.array_texture
+            && self.power_of_two
+            && !(self.max_width.is_power_of_two() && self.max_height.is_power_of_two())
+        {
+            diagnostics.push(format!(
+                "Array texture pages keep the maximum dimensions {}x{}, \
+                 which are not powers of two as requested.",
+                self.max_width, self.max_height
+            ));
+        }
 
-    fn resize_image(&self, img: RgbaImage) -> RgbaImage {
-        let (original_width, original_height) = img.dimensions();
+        if self.channel_packing && self.clear_transparent {
+            diagnostics.push(
+                "Clearing transparent pixels would erase the masks packed into the color channels \
+                 wherever the alpha channel is zero."
+                    .to_string(),
+            );
+        }
 
-        match (self.image_width, self.image_height) {
-            (Some(width), Some(height)) => {
-                image::imageops::resize(&img, width, height, FilterType::Lanczos3)
-            }
-            (Some(width), None) => {
-                let height = (original_height * width) / original_width;
-                image::imageops::resize(&img, width, height, FilterType::Lanczos3)
-            }
-            (None, Some(height)) => {
-                let width = (original_width * height) / original_height;
-                image::imageops::resize(&img, width, height, FilterType::Lanczos3)
-            }
-            (None, None) => img,
+        if self.alpha_bleed > 0 && (self.clear_transparent || self.channel_packing) {
+            diagnostics.push(
+                "Alpha bleeding fills the color of transparent pixels, which clearing transparent \
+                 pixels and channel packing rely on."
+                    .to_string(),
+            );
         }
-    }
-}
 
-#[cfg(test)]
+        if self.channel_packing && self.background.is_some() {
+            diagnostics.push(
+                "A background color would blend into the masks packed into the color channels."
+                    .to_string(),
+            );
+        }
+
+        if self.max_encoded_bytes == Some(0) {
+            diagnostics.push("A maximum encoded size of 0 bytes fits no sheet.".to_string());
+        }
+
+        let mut invalid_weights: Vec<&str> = self
+            .usage_weights
+            .iter()
+            .filter(|(_, weight)| !weight.is_finite())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        invalid_weights.sort_unstable();
+        for name in invalid_weights {
+            diagnostics.push(format!(
+                "Usage weight of {:?} is not a finite number.",
+                name
+            ));
+        }
+
+        for pattern in self.include.iter().chain(&self.exclude) {
+            if let Err(error) = glob_set(std::slice::from_ref(pattern)) {
+                diagnostics.push(format!("Invalid glob pattern: {}", error));
+            }
+        }
+
+        if let Some((pattern, _)) = &self.name_pattern {
+            if let Err(error) = Regex::new(pattern) {
+                diagnostics.push(format!("Invalid name pattern: {}", error));
+            }
+        }
+
+        #[cfg(feature = "svg")]
+        if !(self.svg_scale.is_finite() && self.svg_scale > 0.0) {
+            diagnostics.push(format!(
+                "SVG scale {} is not a positive number.",
+                self.svg_scale
+            ));
+        }
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(SpriteratorError::InvalidConfiguration { diagnostics })
+        }
+    }
+
+    /// Checks that the sheet and resize dimensions are usable before any image is loaded.
+    fn validate_dimensions(&self) -> Result<(), SpriteratorError> {
+        if self.max_width == 0 || self.max_height == 0 {
+            return Err(SpriteratorError::InvalidDimensions {
+                what: "maximum sheet".to_string(),
+                width: self.max_width,
+                height: self.max_height,
+            });
+        }
+        allocation_size(self.max_width, self.max_height, "maximum sheet")?;
+
+        if self.image_width == Some(0) || self.image_height == Some(0) {
+            return Err(SpriteratorError::InvalidDimensions {
+                what: "resize".to_string(),
+                width: self.image_width.unwrap_or(0),
+                height: self.image_height.unwrap_or(0),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Lays out and composes prepared images into sheets.
+    fn pack(&self, mut images: Vec<SourceImage>) -> Result<Vec<Sprite>, Box<dyn Error>> {
+        let cell = self.prepare_packing(&mut images)?;
+        let mut sprites = match self.max_encoded_bytes {
+            Some(_) => self.pack_sequentially(&images, cell)?,
+            None => self.compose_pages(&images, self.layout_pages(&images, cell))?,
+        };
+
+        for (index, sprite) in sprites.iter_mut().enumerate() {
+            self.finish_sprite(sprite, index);
+        }
+
+        Ok(sprites)
+    }
+
+    /// Checks that every prepared image fits a sheet and puts the images in packing order.
+    ///
+    /// # Returns
+    /// A `Result` containing the grid cell size in grid mode.
+    fn prepare_packing(
+        &self,
+        images: &mut Vec<SourceImage>,
+    ) -> Result<Option<(u32, u32)>, Box<dyn Error>> {
+        self.check_fit(images)?;
+        self.order_by_usage(images);
+        if self.channel_packing {
+            *images = pack_channels(std::mem::take(images));
+        }
+        self.layout_cell(images)
+    }
+
+    /// Checks that every image fits a sheet with its extrusion and padding.
+    fn check_fit(&self, images: &[impl Placeable]) -> Result<(), Box<dyn Error>> {
+        self.validate_dimensions()?;
+
+        let padding = self.border().saturating_mul(2);
+        let (inner_width, inner_height) = self.inner_size();
+        for source in images {
+            let (width, height) = source.dimensions();
+            // An empty image has no pixels to place and would leave an empty sheet.
+            if width == 0 || height == 0 {
+                return Err(SpriteratorError::InvalidDimensions {
+                    what: format!("image {}", source.name()),
+                    width,
+                    height,
+                }
+                .into());
+            }
+            if width.saturating_add(padding) > inner_width
+                || height.saturating_add(padding) > inner_height
+            {
+                let (max_width, max_height) = self.max_size();
+                return Err(SpriteratorError::ImageTooLarge {
+                    name: source.name().to_string(),
+                    width,
+                    height,
+                    max_width,
+                    max_height,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the grid cell size in grid mode, checking that the configured columns fit.
+    fn layout_cell(&self, images: &[impl Placeable]) -> Result<Option<(u32, u32)>, Box<dyn Error>> {
+        let cell = self.grid_cell(images);
+        let (inner_width, _) = self.inner_size();
+        if let (Some(columns), Some((width, _))) = (self.columns, cell) {
+            if self.row_width(width, columns) > inner_width {
+                return Err(SpriteratorError::InvalidConfiguration {
+                    diagnostics: vec![format!(
+                        "{} columns of {} pixel wide cells do not fit the usable sheet width of {}.",
+                        columns, width, inner_width
+                    )],
+                }
+                .into());
+            }
+        }
+
+        Ok(cell)
+    }
+
+    /// Applies the settings recorded on every generated sheet, `index` being its page.
+    fn finish_sprite(&self, sprite: &mut Sprite, index: usize) {
+        if self.array_texture {
+            sprite.set_layer(index as u32);
+        }
+        sprite.set_strip_metadata(self.strip_metadata);
+    }
+
+    /// Lays out every page, returning the index of the first image of each page and the
+    /// slots of its images.
+    fn layout_pages(
+        &self,
+        images: &[impl Placeable],
+        cell: Option<(u32, u32)>,
+    ) -> Vec<(usize, Vec<Rect>)> {
+        let mut pages = Vec::new();
+        let mut start = 0;
+        while start < images.len() {
+            let slots = self.layout_page(&images[start..], cell);
+            let count = slots.len();
+            pages.push((start, slots));
+            start += count;
+        }
+        pages
+    }
+
+    /// Composes laid out pages into sheets. Pages are independent once laid out, so with the
+    /// `parallel` feature they are composed across the rayon thread pool.
+    fn compose_pages(
+        &self,
+        images: &[SourceImage],
+        pages: Vec<(usize, Vec<Rect>)>,
+    ) -> Result<Vec<Sprite>, Box<dyn Error>> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            pages
+                .par_iter()
+                .map(|(start, slots)| {
+                    self.compose_page(&images[*start..], slots)
+                        .map_err(sendable_error)
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| -> Box<dyn Error> { error })
+        }
+        #[cfg(not(feature = "parallel"))]
+        pages
+            .iter()
+            .map(|(start, slots)| self.compose_page(&images[*start..], slots))
+            .collect()
+    }
+
+    /// Lays out and composes pages one after another, each starting where the previous one
+    /// ended, for layouts that depend on the composed sheets.
+    fn pack_sequentially(
+        &self,
+        images: &[SourceImage],
+        cell: Option<(u32, u32)>,
+    ) -> Result<Vec<Sprite>, Box<dyn Error>> {
+        let mut sprites = Vec::new();
+        let mut start = 0;
+
+        while start < images.len() {
+            let (sprite, count) = self.next_page(&images[start..], cell)?;
+            start += count;
+            sprites.push(sprite);
+        }
+
+        Ok(sprites)
+    }
+
+    /// Lays out and composes the page holding the leading images, moving images to the next
+    /// page until its PNG encoding stays within `max_encoded_bytes`, if set.
+    ///
+    /// # Returns
+    /// A `Result` containing the sheet and the number of images it holds.
+    fn next_page(
+        &self,
+        images: &[SourceImage],
+        cell: Option<(u32, u32)>,
+    ) -> Result<(Sprite, usize), Box<dyn Error>> {
+        let mut slots = self.layout_page(images, cell);
+        let (mut sheet, mut frames) = self.compose_sheet(images, &slots)?;
+
+        if let Some(max_encoded_bytes) = self.max_encoded_bytes {
+            let count = self.fit_encoded_size(images, &slots, &sheet, max_encoded_bytes)?;
+            if count < slots.len() {
+                slots.truncate(count);
+                (sheet, frames) = self.compose_sheet(images, &slots)?;
+            }
+        }
+
+        let sprite = self.post_process(sheet, frames, &slots)?;
+        Ok((sprite, slots.len()))
+    }
+
+    fn order_by_usage<T: Placeable>(&self, images: &mut [T]) {
+        if self.usage_weights.is_empty() {
+            return;
+        }
+
+        let weight = |source: &T| {
+            self.usage_weights
+                .get(source.name())
+                .copied()
+                .unwrap_or(0.0)
+        };
+        images.sort_by(|a, b| weight(b).total_cmp(&weight(a)));
+    }
+
+    /// Returns the pixels reserved on each side of every frame for extrusion and padding.
+    fn border(&self) -> u32 {
+        self.extrude.saturating_add(self.padding)
+    }
+
+    /// Returns the largest sheet size images are packed into: the maximum dimensions, rounded
+    /// down to powers of two when sheets are rounded up to them.
+    fn max_size(&self) -> (u32, u32) {
+        if self.power_of_two && !self.array_texture {
+            let floor = |value: u32| value.checked_ilog2().map_or(0, |exponent| 1 << exponent);
+            (floor(self.max_width), floor(self.max_height))
+        } else {
+            (self.max_width, self.max_height)
+        }
+    }
+
+    /// Returns the size of the sheet area inside the margin.
+    fn inner_size(&self) -> (u32, u32) {
+        let margin = self.margin.saturating_mul(2);
+        let (max_width, max_height) = self.max_size();
+        (
+            max_width.saturating_sub(margin),
+            max_height.saturating_sub(margin),
+        )
+    }
+
+    /// Returns the width of a row of `columns` cells, including the spacing between them.
+    fn row_width(&self, cell: u32, columns: u32) -> u32 {
+        let stride = self.block_align(cell.saturating_add(self.spacing));
+        stride
+            .saturating_mul(columns.saturating_sub(1))
+            .saturating_add(cell)
+    }
+
+    /// Rounds `value` up to a multiple of the block size of GPU block compression when block
+    /// alignment is enabled.
+    fn block_align(&self, value: u32) -> u32 {
+        if self.block_alignment {
+            value.saturating_add(3) & !3
+        } else {
+            value
+        }
+    }
+
+    /// Returns the size of the grid cells fitting every image including its extrusion and
+    /// padding, or `None` when not packing on a grid.
+    fn grid_cell(&self, images: &[impl Placeable]) -> Option<(u32, u32)> {
+        if !self.grid && self.columns.is_none() {
+            return None;
+        }
+
+        let (width, height) = images.iter().fold((0, 0), |(width, height), source| {
+            let (image_width, image_height) = source.dimensions();
+            (width.max(image_width), height.max(image_height))
+        });
+        let padding = self.border().saturating_mul(2);
+        Some((
+            self.block_align(width.saturating_add(padding)),
+            self.block_align(height.saturating_add(padding)),
+        ))
+    }
+
+    /// Places images starting from the top-left corner of a new page, inside its margin, until
+    /// one no longer fits, either in rows of varying height or, given a `cell` size, on a uniform
+    /// grid. With a fixed number of columns, rows wrap after exactly that many images.
+    ///
+    /// Returns the slots of the leading images that fit, each including the extrusion and
+    /// padding around its image; at least one image is always placed.
+    fn layout_page(&self, images: &[impl Placeable], cell: Option<(u32, u32)>) -> Vec<Rect> {
+        let mut slots = Vec::new();
+        let left = self.block_align(self.margin);
+        let (mut current_x, mut current_y, mut row_height) = (left, left, 0u32);
+        let (max_width, max_height) = self.max_size();
+        let right = max_width.saturating_sub(self.margin);
+        let bottom = max_height.saturating_sub(self.margin);
+        let mut column = 0;
+
+        for source in images {
+            let padding = self.border().saturating_mul(2);
+            let (image_width, image_height) = source.dimensions();
+            let (width, height) = cell.unwrap_or((
+                self.block_align(image_width.saturating_add(padding)),
+                self.block_align(image_height.saturating_add(padding)),
+            ));
+
+            let row_full = match self.columns {
+                Some(columns) => column == columns,
+                None => current_x.saturating_add(width) > right && current_x > left,
+            };
+            if row_full {
+                current_y = self.block_align(
+                    current_y
+                        .saturating_add(row_height)
+                        .saturating_add(self.spacing),
+                );
+                current_x = left;
+                row_height = 0;
+                column = 0;
+            }
+
+            if current_y.saturating_add(height) > bottom && !slots.is_empty() {
+                break;
+            }
+
+            slots.push(Rect::new(current_x, current_y, width, height));
+
+            row_height = row_height.max(height);
+            current_x =
+                self.block_align(current_x.saturating_add(width).saturating_add(self.spacing));
+            column += 1;
+        }
+
+        slots
+    }
+
+    /// Returns the size of the sheet holding `slots`: the maximum size for array textures, or
+    /// the occupied slots and the margin otherwise.
+    ///
+    /// The packer knows where every frame is placed, so sheets never need scanning for their
+    /// content; `Sprite::trim_transparent` does so for sheets assembled by other means.
+    /// Sizing by the pixel content could also shift the sheet origin or cut off frames with
+    /// transparent edges.
+    fn page_size(&self, slots: &[Rect]) -> (u32, u32) {
+        if self.array_texture {
+            return (self.max_width, self.max_height);
+        }
+
+        let mut right = slots.iter().map(Rect::right).max().unwrap_or(0);
+        if let (Some(columns), Some(slot)) = (self.columns, slots.first()) {
+            right = right.max(self.block_align(self.margin) + self.row_width(slot.w, columns));
+        }
+        let bottom = slots.iter().map(Rect::bottom).max().unwrap_or(0);
+        let (max_width, max_height) = self.max_size();
+        (
+            self.block_align(right + self.margin).min(max_width),
+            self.block_align(bottom + self.margin).min(max_height),
+        )
+    }
+
+    /// Returns a `width`×`height` sheet holding each image at its slot, extruded, on a GPU if
+    /// enabled.
+    ///
+    /// Images are copied rather than blended, which is what blending onto the transparent
+    /// sheet amounts to as slots do not overlap, so sheets match exactly wherever they are
+    /// composed.
+    fn place_images(
+        &self,
+        images: &[SourceImage],
+        slots: &[Rect],
+        width: u32,
+        height: u32,
+    ) -> Result<RgbaImage, Box<dyn Error>> {
+        #[cfg(feature = "gpu")]
+        if let Some(compositor) = self.gpu_compositor() {
+            let placements: Vec<_> = images
+                .iter()
+                .zip(slots)
+                .map(|(source, slot)| {
+                    (
+                        &source.image,
+                        slot.x + self.border(),
+                        slot.y + self.border(),
+                    )
+                })
+                .collect();
+            let composed = compositor.compose(
+                width,
+                height,
+                &placements,
+                self.channel_packing,
+                self.extrude,
+            )?;
+            if let Some(sheet) = composed {
+                return Ok(sheet);
+            }
+        }
+
+        let mut sheet = new_sheet(width, height)?;
+        for (source, slot) in images.iter().zip(slots) {
+            let (x, y) = (slot.x + self.border(), slot.y + self.border());
+            // Channel-packed masks keep their colors wherever the alpha mask is zero.
+            blit(&mut sheet, &source.image, x, y, !source.channels.is_empty());
+        }
+        if self.extrude > 0 {
+            for (source, slot) in images.iter().zip(slots) {
+                let (x, y) = (slot.x + self.border(), slot.y + self.border());
+                let rect = Rect::new(x, y, source.image.width(), source.image.height());
+                extrude(&mut sheet, rect, self.extrude);
+            }
+        }
+        Ok(sheet)
+    }
+
+    /// Returns the GPU compositor if enabled, opening it on first use.
+    #[cfg(feature = "gpu")]
+    fn gpu_compositor(&self) -> Option<&GpuCompositor> {
+        if !self.gpu {
+            return None;
+        }
+        self.gpu_compositor
+            .get_or_init(|| match GpuCompositor::new() {
+                Ok(compositor) => Some(compositor),
+                Err(message) => {
+                    self.warning_handler
+                        .warn(Warning::GpuUnavailable { message });
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    /// Composes the leading images onto a sheet at the given slots.
+    fn compose_page(
+        &self,
+        images: &[SourceImage],
+        slots: &[Rect],
+    ) -> Result<Sprite, Box<dyn Error>> {
+        let (sheet, frames) = self.compose_sheet(images, slots)?;
+        self.post_process(sheet, frames, slots)
+    }
+
+    /// Composes the leading images onto a sheet at the given slots with the built-in
+    /// processing, returning the sheet and its frames before the post processors run.
+    fn compose_sheet(
+        &self,
+        images: &[SourceImage],
+        slots: &[Rect],
+    ) -> Result<(RgbaImage, Vec<Frame>), Box<dyn Error>> {
+        let (width, height) = self.page_size(slots);
+        let mut sheet = self.place_images(images, slots, width, height)?;
+        let mut frames = Vec::with_capacity(slots.len());
+
+        for (source, slot) in images.iter().zip(slots) {
+            let (x, y) = (slot.x + self.border(), slot.y + self.border());
+
+            if source.channels.is_empty() {
+                frames.push(source.frame(x, y));
+            } else {
+                for (channel, member) in source.channels.iter().enumerate() {
+                    frames.push(member.frame(x, y).with_channel(channel as u8));
+                }
+            }
+        }
+
+        if self.clear_transparent {
+            clear_transparent(&mut sheet);
+        }
+
+        let mut sheet = if self.power_of_two && !self.array_texture {
+            expand_to_power_of_two(sheet)?
+        } else {
+            sheet
+        };
+
+        if self.alpha_bleed > 0 {
+            alpha_bleed(&mut sheet, self.alpha_bleed);
+        }
+
+        if let Some(background) = self.background {
+            fill_background(&mut sheet, background);
+        }
+
+        Ok((sheet, frames))
+    }
+
+    /// Runs the post processors on a composed sheet and wraps it with its frames.
+    fn post_process(
+        &self,
+        mut sheet: RgbaImage,
+        frames: Vec<Frame>,
+        slots: &[Rect],
+    ) -> Result<Sprite, Box<dyn Error>> {
+        for post_processor in &self.post_processors {
+            sheet = post_processor.process(sheet, &frames)?;
+        }
+
+        let mut sprite = Sprite::with_frames(sheet, frames);
+        sprite.set_layout((self.max_width, self.max_height), slots.to_vec());
+        Ok(sprite)
+    }
+
+    /// Finds how many of the placed images fit on the page while keeping its PNG encoding
+    /// within `max_encoded_bytes`.
+    ///
+    /// Since pages are filled in order, any leading subset of `slots` is itself a valid layout.
+    fn fit_encoded_size(
+        &self,
+        images: &[SourceImage],
+        slots: &[Rect],
+        sheet: &RgbaImage,
+        max_encoded_bytes: usize,
+    ) -> Result<usize, Box<dyn Error>> {
+        if encoded_size(sheet)? <= max_encoded_bytes {
+            return Ok(slots.len());
+        }
+
+        let (mut fits, mut exceeds) = (0, slots.len());
+        while exceeds - fits > 1 {
+            let count = (fits + exceeds) / 2;
+            let (candidate, _) = self.compose_sheet(images, &slots[..count])?;
+            if encoded_size(&candidate)? <= max_encoded_bytes {
+                fits = count;
+            } else {
+                exceeds = count;
+            }
+        }
+
+        if fits == 0 {
+            let (single, _) = self.compose_sheet(images, &slots[..1])?;
+            return Err(SpriteratorError::EncodedSizeExceeded {
+                size: encoded_size(&single)?,
+                max_encoded_bytes,
+            }
+            .into());
+        }
+
+        Ok(fits)
+    }
+
+    fn get_images(&self) -> Result<Vec<SourceImage>, Box<dyn Error>> {
+        self.load_images(&self.warning_handler)
+    }
+
+    /// Loads every prepared image, reporting warnings about the directory to `warning_handler`.
+    fn load_images(
+        &self,
+        warning_handler: &WarningHandler,
+    ) -> Result<Vec<SourceImage>, Box<dyn Error>> {
+        #[cfg(feature = "parallel")]
+        let images = {
+            use rayon::prelude::*;
+
+            let mut entries = Vec::new();
+            self.for_each_entry(warning_handler, |entry| {
+                entries.push(entry);
+                Ok(())
+            })?;
+            entries
+                .into_par_iter()
+                .map(|entry| self.load_image(entry))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let images = {
+            let mut images = Vec::new();
+            self.for_each_entry(warning_handler, |entry| {
+                images.push(self.load_image(entry)?);
+                Ok(())
+            })?;
+            images
+        };
+
+        if images.is_empty() {
+            return Err(self.no_images_error());
+        }
+
+        Ok(images)
+    }
+
+    /// Lays out the sheets without composing them, returning the size and frames of each.
+    ///
+    /// Without trimming, the size of input files is read from their headers, and their pixels
+    /// are only decoded to skip the files that cannot be, without being kept or resized. Trimming
+    /// depends on the pixels, so every input is loaded with it. With channel packing or
+    /// `max_encoded_bytes`, the layout depends on the composed sheets, which are generated.
+    /// Sizes do not account for post processors changing the size of sheets.
+    ///
+    /// Warnings about decoding are only reported for the images decoded.
+    ///
+    /// # Returns
+    /// A `Result` containing the layout of every sheet, matching the sheets of `generate`, or
+    /// an error if the configuration is invalid or the inputs cannot be read.
+    pub fn plan_layout(&self) -> Result<Vec<SheetLayout>, Box<dyn Error>> {
+        self.validate()?;
+        if self.channel_packing || self.max_encoded_bytes.is_some() {
+            return Ok(self.generate()?.iter().map(SheetLayout::of).collect());
+        }
+
+        let mut outlines = self.load_outlines()?;
+        self.check_fit(&outlines)?;
+        self.order_by_usage(&mut outlines);
+        let cell = self.layout_cell(&outlines)?;
+
+        let mut layouts = Vec::new();
+        for (start, slots) in self.layout_pages(&outlines, cell) {
+            let (mut width, mut height) = self.page_size(&slots);
+            if self.power_of_two && !self.array_texture {
+                width = width.checked_next_power_of_two().unwrap_or(width);
+                height = height.checked_next_power_of_two().unwrap_or(height);
+            }
+            let frames = outlines[start..]
+                .iter()
+                .zip(&slots)
+                .map(|(outline, slot)| {
+                    let (x, y) = (slot.x + self.border(), slot.y + self.border());
+                    outline.frame.clone().at(x, y)
+                })
+                .collect();
+            layouts.push(SheetLayout::new(width, height, frames));
+        }
+        Ok(layouts)
+    }
+
+    /// Loads the prepared images, keeping only their outlines when their pixels together
+    /// exceed the memory budget.
+    ///
+    /// Without trimming, the outlines are taken first, mostly from image headers, and the
+    /// images are only loaded if they fit the budget. With trimming, outlines need the pixels,
+    /// so images are loaded as they are decoded and kept until the budget is exceeded. Without
+    /// a budget, or when the layout needs every image at once, all images are loaded.
+    fn load_within_budget(&self) -> Result<Inputs, Box<dyn Error>> {
+        let Some(memory_budget) = self.memory_budget else {
+            return Ok(Inputs::Images(self.get_images()?));
+        };
+        if self.channel_packing || self.max_encoded_bytes.is_some() {
+            return Ok(Inputs::Images(self.get_images()?));
+        }
+
+        if !self.trim {
+            let outlines = self.load_outlines()?;
+            let bytes = outlines.iter().fold(0usize, |bytes, outline| {
+                let (width, height) = outline.dimensions();
+                bytes.saturating_add(width as usize * height as usize * 4)
+            });
+            if bytes > memory_budget {
+                return Ok(Inputs::Outlines(outlines));
+            }
+
+            // Images that cannot be decoded are skipped with a warning as they are loaded, and
+            // the others are laid out again from their pixels, so only images missing from the
+            // outlines or of a different size mean the inputs changed.
+            let images = self.load_images(&self.rereading_handler())?;
+            let mut remaining = outlines.iter();
+            let unchanged = images.iter().all(|source| {
+                remaining
+                    .find(|outline| outline.name() == source.name)
+                    .is_some_and(|outline| source.dimensions() == outline.dimensions())
+            });
+            if !unchanged {
+                return Err(INPUTS_CHANGED.into());
+            }
+            return Ok(Inputs::Images(images));
+        }
+
+        let mut images = Vec::new();
+        let mut outlines = Vec::new();
+        let mut bytes = 0usize;
+        self.for_each_entry(&self.warning_handler, |entry| {
+            let source = self.load_image(entry)?;
+            bytes = bytes.saturating_add(source.image.as_raw().len());
+            outlines.push(Outline::new(outlines.len(), &source));
+            if bytes <= memory_budget {
+                images.push(source);
+            } else {
+                images.clear();
+            }
+            Ok(())
+        })?;
+
+        if outlines.is_empty() {
+            return Err(self.no_images_error());
+        }
+        Ok(if bytes <= memory_budget {
+            Inputs::Images(images)
+        } else {
+            Inputs::Outlines(outlines)
+        })
+    }
+
+    /// Returns the outline of every prepared image.
+    ///
+    /// Without trimming, files of the directory are measured by their header where possible
+    /// and other images by their decoded size, without resizing any. With trimming, every
+    /// image is loaded.
+    fn load_outlines(&self) -> Result<Vec<Outline>, Box<dyn Error>> {
+        let mut outlines = Vec::new();
+        if self.trim {
+            self.for_each_entry(&self.warning_handler, |entry| {
+                outlines.push(Outline::new(outlines.len(), &self.load_image(entry)?));
+                Ok(())
+            })?;
+        } else {
+            if let Some(dir_path) = &self.dir_path {
+                for probe in self.directory_source(dir_path).probe() {
+                    let outline = match probe? {
+                        Probe::Size {
+                            name,
+                            width,
+                            height,
+                        } => self.measure(outlines.len(), &name, width, height)?,
+                        Probe::Decoded(entry) => self.measure_entry(outlines.len(), &entry)?,
+                    };
+                    outlines.push(outline);
+                }
+            }
+            for source in self.other_sources() {
+                for entry in source.entries() {
+                    outlines.push(self.measure_entry(outlines.len(), &entry?)?);
+                }
+            }
+        }
+
+        if outlines.is_empty() {
+            return Err(self.no_images_error());
+        }
+        Ok(outlines)
+    }
+
+    /// Returns the outline of `entry` when not trimming, without resizing it.
+    fn measure_entry(&self, index: usize, entry: &NamedImage) -> Result<Outline, SpriteratorError> {
+        let (width, height) = entry.image.dimensions();
+        let mut outline = self.measure(index, &entry.name, width, height)?;
+        outline.frame = outline
+            .frame
+            .with_duration(entry.duration)
+            .with_pivot(entry.pivot);
+        Ok(outline)
+    }
+
+    /// Returns the outline of an input image of the given size when not trimming.
+    fn measure(
+        &self,
+        index: usize,
+        name: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<Outline, SpriteratorError> {
+        self.check_input_size(name, width, height)?;
+        let (width, height) = match self.resized_dimensions(width, height) {
+            Some((width, height)) => {
+                allocation_size(width, height, "resized image")?;
+                (width, height)
+            }
+            None => (width, height),
+        };
+        Ok(Outline {
+            index,
+            frame: Frame::named(name, 0, 0, width, height),
+        })
+    }
+
+    /// Returns the handler for inputs read again after their outlines were taken, dropping
+    /// the warnings reported then.
+    fn rereading_handler(&self) -> WarningHandler {
+        if self.trim {
+            WarningHandler::default()
+        } else {
+            self.warning_handler.filtered(Warning::is_about_decoding)
+        }
+    }
+
+    /// Lays out images by their outlines, then composes each sheet as soon as its images are
+    /// loaded again, so that only the images of sheets not yet composed are held in memory.
+    ///
+    /// Inputs are read in order, so images of a sheet arrive together unless usage weights
+    /// reorder them.
+    fn pack_in_chunks(&self, mut outlines: Vec<Outline>) -> Result<Vec<Sprite>, Box<dyn Error>> {
+        self.check_fit(&outlines)?;
+        self.order_by_usage(&mut outlines);
+        let cell = self.layout_cell(&outlines)?;
+        let pages = self.layout_pages(&outlines, cell);
+
+        // Sheet, position on the sheet and outline of every input image, by input order.
+        let mut placements: Vec<Option<(usize, usize, &Outline)>> =
+            outlines.iter().map(|_| None).collect();
+        for (page, (start, slots)) in pages.iter().enumerate() {
+            for (position, outline) in outlines[*start..][..slots.len()].iter().enumerate() {
+                placements[outline.index] = Some((page, position, outline));
+            }
+        }
+
+        let mut pending: Vec<Vec<Option<SourceImage>>> = pages
+            .iter()
+            .map(|(_, slots)| slots.iter().map(|_| None).collect())
+            .collect();
+        let mut missing: Vec<usize> = pages.iter().map(|(_, slots)| slots.len()).collect();
+        let mut sprites: Vec<Option<Sprite>> = pages.iter().map(|_| None).collect();
+        let mut index = 0;
+
+        self.for_each_entry(&self.rereading_handler(), |entry| {
+            let (page, position, outline) = placements
+                .get(index)
+                .copied()
+                .flatten()
+                .ok_or(INPUTS_CHANGED)?;
+            index += 1;
+            let source = self.load_image(entry)?;
+            if source.name != outline.name() || source.dimensions() != outline.dimensions() {
+                return Err(INPUTS_CHANGED.into());
+            }
+
+            pending[page][position] = Some(source);
+            missing[page] -= 1;
+            if missing[page] == 0 {
+                let images: Vec<SourceImage> = std::mem::take(&mut pending[page])
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                sprites[page] = Some(self.compose_page(&images, &pages[page].1)?);
+            }
+            Ok(())
+        })?;
+
+        let mut sprites: Vec<Sprite> = sprites
+            .into_iter()
+            .collect::<Option<_>>()
+            .ok_or(INPUTS_CHANGED)?;
+        for (index, sprite) in sprites.iter_mut().enumerate() {
+            self.finish_sprite(sprite, index);
+        }
+        Ok(sprites)
+    }
+
+    /// Calls `f` with every input image in order: those of the directory, then those of the
+    /// added sources, then the in-memory images.
+    ///
+    /// # Arguments
+    /// - `warning_handler`: Receives the warnings about the directory.
+    fn for_each_entry(
+        &self,
+        warning_handler: &WarningHandler,
+        mut f: impl FnMut(NamedImage) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let directory = self.dir_path.as_ref().map(|dir_path| {
+            self.directory_source(dir_path)
+                .with_handler(warning_handler.clone())
+        });
+        let sources = directory
+            .iter()
+            .map(|directory| directory as &dyn ImageSource)
+            .chain(self.other_sources());
+
+        for source in sources {
+            for entry in source.entries() {
+                f(entry?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the added sources followed by the in-memory images.
+    fn other_sources(&self) -> impl Iterator<Item = &dyn ImageSource> {
+        self.sources
+            .iter()
+            .map(Box::as_ref)
+            .chain([&self.images as &dyn ImageSource])
+    }
+
+    fn no_images_error(&self) -> Box<dyn Error> {
+        SpriteratorError::NoImages {
+            dir_path: self
+                .dir_path
+                .as_ref()
+                .map(|dir_path| dir_path.display().to_string())
+                .unwrap_or_default(),
+            extensions: self.extensions.clone(),
+        }
+        .into()
+    }
+
+    /// Returns the source reading the images of `dir_path` with the configured options.
+    fn directory_source(&self, dir_path: &Path) -> DirectorySource {
+        let source = DirectorySource::new(dir_path)
+            .with_extensions(&as_strs(&self.extensions))
+            .with_include(&as_strs(&self.include))
+            .with_exclude(&as_strs(&self.exclude))
+            .with_ignore_files(self.ignore_files)
+            .with_max_depth(self.max_depth)
+            .with_follow_links(self.follow_links)
+            .with_skip_hidden(self.skip_hidden)
+            .with_animations(self.animations)
+            .with_color_management(self.color_management)
+            .with_handler(self.warning_handler.clone());
+        let source = match &self.name_pattern {
+            Some((pattern, template)) => source.with_name_pattern(pattern, template.as_deref()),
+            None => source,
+        };
+        #[cfg(feature = "svg")]
+        let source = source.with_svg_scale(self.svg_scale);
+        source
+    }
+
+    /// Checks the size of a decoded image, then resizes and trims it for packing.
+    fn load_image(&self, image: NamedImage) -> Result<SourceImage, SpriteratorError> {
+        let NamedImage {
+            name,
+            image: img,
+            duration,
+            pivot,
+        } = image;
+        self.check_input_size(&name, img.width(), img.height())?;
+
+        let mut source = self.prepare_image(&name, self.resize_image(img)?);
+        source.duration = duration;
+        source.pivot = pivot;
+        Ok(source)
+    }
+
+    /// Checks that an input image of the given size fits a sheet unless it is resized.
+    fn check_input_size(
+        &self,
+        name: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<(), SpriteratorError> {
+        let (max_width, max_height) = self.max_size();
+        if (self.image_width.is_none() && width > max_width)
+            || (self.image_height.is_none() && height > max_height)
+        {
+            return Err(SpriteratorError::ImageTooLarge {
+                name: name.to_string(),
+                width,
+                height,
+                max_width,
+                max_height,
+            });
+        }
+        Ok(())
+    }
+
+    fn prepare_image(&self, name: &str, image: RgbaImage) -> SourceImage {
+        let (source_width, source_height) = image.dimensions();
+
+        if !self.trim {
+            return SourceImage {
+                name: name.to_string(),
+                image,
+                source_width,
+                source_height,
+                offset_x: 0,
+                offset_y: 0,
+                trimmed: false,
+                duration: None,
+                pivot: None,
+                channels: Vec::new(),
+            };
+        }
+
+        let bounds = opaque_bounds(&image).unwrap_or(Rect::new(0, 0, 1, 1));
+        let trimmed = bounds != Rect::new(0, 0, source_width, source_height);
+
+        SourceImage {
+            name: name.to_string(),
+            image: image::imageops::crop_imm(&image, bounds.x, bounds.y, bounds.w, bounds.h)
+                .to_image(),
+            source_width,
+            source_height,
+            offset_x: bounds.x,
+            offset_y: bounds.y,
+            trimmed,
+            duration: None,
+            pivot: None,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Returns the size an image of the given size is resized to, or `None` if it is not
+    /// resized.
+    fn resized_dimensions(&self, original_width: u32, original_height: u32) -> Option<(u32, u32)> {
+        if original_width == 0 || original_height == 0 {
+            return None;
+        }
+
+        // Keeps the aspect ratio, computed in 64 bits to avoid overflow on large images.
+        let scale = |value: u32, numerator: u32, denominator: u32| {
+            let scaled = value as u64 * numerator as u64 / denominator as u64;
+            scaled.clamp(1, u32::MAX as u64) as u32
+        };
+
+        match (self.image_width, self.image_height) {
+            (Some(width), Some(height)) => Some((width, height)),
+            (Some(width), None) => Some((width, scale(original_height, width, original_width))),
+            (None, Some(height)) => Some((scale(original_width, height, original_height), height)),
+            (None, None) => None,
+        }
+    }
+
+    fn resize_image(&self, img: RgbaImage) -> Result<RgbaImage, SpriteratorError> {
+        let Some((width, height)) = self.resized_dimensions(img.width(), img.height()) else {
+            return Ok(img);
+        };
+
+        allocation_size(width, height, "resized image")?;
+        let Some(dir) = &self.resize_cache else {
+            return Ok(image::imageops::resize(
+                &img,
+                width,
+                height,
+                self.resize_filter,
+            ));
+        };
+
+        let cache = ResizeCache::new(dir);
+        let entry = cache.entry(&img, width, height, self.resize_filter);
+        if let Some(resized) = cache.get(&entry, width, height) {
+            return Ok(resized);
+        }
+        let resized = image::imageops::resize(&img, width, height, self.resize_filter);
+        if let Err(error) = cache.put(&entry, &resized) {
+            self.warning_handler.warn(Warning::ResizeCacheUnavailable {
+                path: entry,
+                message: error.to_string(),
+            });
+        }
+        Ok(resized)
+    }
+}
+
+/// Fingerprints a laid out page from the configuration fingerprint, the slots and the
+/// images placed in them, which together determine the composed sheet.
+fn page_fingerprint(config: u64, images: &[SourceImage], slots: &[Rect]) -> u64 {
+    let mut fingerprint = Fingerprint::default();
+    fingerprint.add(&config);
+    for (source, slot) in images.iter().zip(slots) {
+        fingerprint.add(slot);
+        source.fingerprint(&mut fingerprint);
+    }
+    fingerprint.finish()
+}
+
+/// Fingerprints a composed sheet from the configuration fingerprint, its pixels and frames.
+fn sprite_fingerprint(config: u64, sprite: &Sprite) -> u64 {
+    let mut fingerprint = Fingerprint::default();
+    fingerprint.add(&config);
+    fingerprint.add(&sprite.get_image().dimensions());
+    fingerprint.write(sprite.get_image().as_raw());
+    for frame in sprite.get_frames() {
+        fingerprint.add(frame);
+    }
+    fingerprint.finish()
+}
+
+/// Iterator over generated sheets, created by `Spriterator::iter_sprites`.
+pub struct SpriteIter<'a> {
+    spriterator: &'a Spriterator,
+    images: Vec<SourceImage>,
+    cell: Option<(u32, u32)>,
+    /// Index of the first image of the next sheet.
+    start: usize,
+    page: usize,
+    /// Number of frames on the sheets yielded so far.
+    frames: usize,
+}
+
+impl SpriteIter<'_> {
+    fn next_sprite(&mut self) -> Result<Sprite, Box<dyn Error>> {
+        let spriterator = self.spriterator;
+        let (mut sprite, count) = spriterator.next_page(&self.images[self.start..], self.cell)?;
+        spriterator.finish_sprite(&mut sprite, self.page);
+        self.start += count;
+
+        spriterator.report_engine_limits(spriterator.engine_limits.check_sheet(
+            self.page,
+            &sprite,
+            self.frames,
+        ))?;
+
+        self.page += 1;
+        self.frames += sprite.get_frames().len();
+        Ok(sprite)
+    }
+}
+
+impl Iterator for SpriteIter<'_> {
+    type Item = Result<Sprite, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start >= self.images.len() {
+            return None;
+        }
+        let sprite = self.next_sprite();
+        if sprite.is_err() {
+            // Ends the iteration, as the remaining images cannot be placed reliably.
+            self.start = self.images.len();
+        }
+        Some(sprite)
+    }
+}
+
+/// Borrows `strings` for builders taking string slices.
+fn as_strs(strings: &[String]) -> Vec<&str> {
+    strings.iter().map(String::as_str).collect()
+}
+
+/// Combines every four consecutive images into one whose channels hold their masks.
+fn pack_channels(images: Vec<SourceImage>) -> Vec<SourceImage> {
+    let mut packed = Vec::with_capacity(images.len().div_ceil(4));
+    let mut images = images.into_iter().peekable();
+
+    while images.peek().is_some() {
+        let channels: Vec<SourceImage> = images.by_ref().take(4).collect();
+        let width = channels
+            .iter()
+            .map(|member| member.image.width())
+            .max()
+            .unwrap_or(0);
+        let height = channels
+            .iter()
+            .map(|member| member.image.height())
+            .max()
+            .unwrap_or(0);
+
+        let mut image = RgbaImage::new(width, height);
+        for (channel, member) in channels.iter().enumerate() {
+            for (x, y, pixel) in member.image.enumerate_pixels() {
+                let [r, g, b, a] = pixel.0;
+                let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+                image.get_pixel_mut(x, y)[channel] = (luma * a as f32 / 255.0).round() as u8;
+            }
+        }
+
+        packed.push(SourceImage {
+            name: channels[0].name.clone(),
+            image,
+            source_width: width,
+            source_height: height,
+            offset_x: 0,
+            offset_y: 0,
+            trimmed: false,
+            duration: None,
+            pivot: None,
+            channels,
+        });
+    }
+
+    packed
+}
+
+/// Sets the color of fully transparent pixels to zero.
+fn clear_transparent(sheet: &mut RgbaImage) {
+    for pixel in sheet.pixels_mut() {
+        if pixel[3] == 0 {
+            pixel.0 = [0, 0, 0, 0];
+        }
+    }
+}
+
+/// Sets the color of fully transparent pixels up to `radius` pixels away from visible ones to
+/// the average color of their already colored neighbors, growing outward one pixel at a time.
+fn alpha_bleed(sheet: &mut RgbaImage, radius: u32) {
+    let (width, height) = (sheet.width() as usize, sheet.height() as usize);
+    let mut colored: Vec<bool> = sheet.pixels().map(|pixel| pixel[3] > 0).collect();
+    let neighbors = |index: usize| {
+        let (x, y) = (index % width, index / width);
+        (y.saturating_sub(1)..(y + 2).min(height))
+            .flat_map(move |ny| (x.saturating_sub(1)..(x + 2).min(width)).map(move |nx| (nx, ny)))
+            .map(move |(nx, ny)| ny * width + nx)
+            .filter(move |&neighbor| neighbor != index)
+    };
+
+    let mut queued = colored.clone();
+    let mut frontier = Vec::new();
+    for index in 0..colored.len() {
+        if !colored[index] && neighbors(index).any(|neighbor| colored[neighbor]) {
+            queued[index] = true;
+            frontier.push(index);
+        }
+    }
+
+    for _ in 0..radius {
+        if frontier.is_empty() {
+            break;
+        }
+
+        let pixels: &mut [u8] = sheet.as_mut();
+        let colors: Vec<[u8; 3]> = frontier
+            .iter()
+            .map(|&index| {
+                let (sum, count) = neighbors(index).filter(|&neighbor| colored[neighbor]).fold(
+                    ([0u32; 3], 0u32),
+                    |(mut sum, count), neighbor| {
+                        for (c, total) in sum.iter_mut().enumerate() {
+                            *total += u32::from(pixels[neighbor * 4 + c]);
+                        }
+                        (sum, count + 1)
+                    },
+                );
+                sum.map(|total| ((total + count / 2) / count) as u8)
+            })
+            .collect();
+
+        for (&index, color) in frontier.iter().zip(&colors) {
+            pixels[index * 4..index * 4 + 3].copy_from_slice(color);
+            colored[index] = true;
+        }
+
+        let mut next = Vec::new();
+        for &index in &frontier {
+            for neighbor in neighbors(index) {
+                if !queued[neighbor] {
+                    queued[neighbor] = true;
+                    next.push(neighbor);
+                }
+            }
+        }
+        frontier = next;
+    }
+}
+
+/// Blends every pixel of the sheet over `background`, keeping the result exactly opaque
+/// wherever either is.
+fn fill_background(sheet: &mut RgbaImage, background: Rgba<u8>) {
+    let background = background.0.map(u32::from);
+    for pixel in sheet.pixels_mut() {
+        let color = pixel.0.map(u32::from);
+        let (alpha, below) = (color[3] * 255, background[3] * (255 - color[3]));
+        let total = alpha + below;
+        if total == 0 {
+            continue;
+        }
+        let blend =
+            |c: usize| ((color[c] * alpha + background[c] * below + total / 2) / total) as u8;
+        pixel.0 = [blend(0), blend(1), blend(2), ((total + 127) / 255) as u8];
+    }
+}
+
+/// Repeats the outermost pixels of `rect` outward by `amount` pixels, clipped to the sheet.
+fn extrude(sheet: &mut RgbaImage, rect: Rect, amount: u32) {
+    if rect.is_empty() {
+        return;
+    }
+
+    let left = rect.x.saturating_sub(amount);
+    let top = rect.y.saturating_sub(amount);
+    let right = (rect.right() + amount).min(sheet.width());
+    let bottom = (rect.bottom() + amount).min(sheet.height());
+
+    for y in top..bottom {
+        for x in left..right {
+            if rect.contains((x, y)) {
+                continue;
+            }
+            let source_x = x.clamp(rect.x, rect.right() - 1);
+            let source_y = y.clamp(rect.y, rect.bottom() - 1);
+            let pixel = *sheet.get_pixel(source_x, source_y);
+            sheet.put_pixel(x, y, pixel);
+        }
+    }
+}
+
+/// Copies `image` onto `sheet` at `(x, y)` row by row, clipped to the sheet.
+///
+/// Without `keep_hidden`, fully transparent pixels are cleared to transparent black, as
+/// blending them onto a transparent sheet leaves it unchanged.
+fn blit(sheet: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32, keep_hidden: bool) {
+    let width = image.width().min(sheet.width().saturating_sub(x)) as usize;
+    let height = image.height().min(sheet.height().saturating_sub(y));
+    if width == 0 {
+        return;
+    }
+
+    let (stride, sheet_stride) = (image.width() as usize * 4, sheet.width() as usize * 4);
+    let source_rows = image.as_raw().chunks_exact(stride);
+    let target_rows = sheet.chunks_exact_mut(sheet_stride).skip(y as usize);
+    for (source, target) in source_rows.zip(target_rows).take(height as usize) {
+        let target = &mut target[x as usize * 4..][..width * 4];
+        target.copy_from_slice(&source[..width * 4]);
+        if !keep_hidden {
+            for pixel in target.chunks_exact_mut(4) {
+                if pixel[3] == 0 {
+                    pixel.fill(0);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the number of bytes an RGBA image of the given size needs,
+/// or an error if it cannot be addressed in memory.
+fn allocation_size(width: u32, height: u32, what: &str) -> Result<usize, SpriteratorError> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|&bytes| bytes <= isize::MAX as usize)
+        .ok_or_else(|| SpriteratorError::InvalidDimensions {
+            what: what.to_string(),
+            width,
+            height,
+        })
+}
+
+/// Allocates a transparent sheet, failing instead of panicking on unaddressable sizes.
+fn new_sheet(width: u32, height: u32) -> Result<RgbaImage, SpriteratorError> {
+    allocation_size(width, height, "sheet")?;
+    Ok(RgbaImage::new(width, height))
+}
+
+/// Places `sheet` in the top-left corner of a transparent image with power-of-two dimensions.
+fn expand_to_power_of_two(sheet: RgbaImage) -> Result<RgbaImage, SpriteratorError> {
+    let (width, height) = sheet.dimensions();
+    if width.is_power_of_two() && height.is_power_of_two() {
+        return Ok(sheet);
+    }
+
+    let invalid = || SpriteratorError::InvalidDimensions {
+        what: "power-of-two sheet".to_string(),
+        width,
+        height,
+    };
+    let mut expanded = new_sheet(
+        width.checked_next_power_of_two().ok_or_else(invalid)?,
+        height.checked_next_power_of_two().ok_or_else(invalid)?,
+    )?;
+    image::imageops::replace(&mut expanded, &sheet, 0, 0);
+    Ok(expanded)
+}
+
+/// Returns the size in bytes of `image` encoded as PNG.
+fn encoded_size(image: &RgbaImage) -> Result<usize, Box<dyn Error>> {
+    let mut data = Vec::new();
+    PngEncoder::new(&mut data).write_image(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        ExtendedColorType::Rgba8,
+    )?;
+    Ok(data.len())
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::source::manifest::ManifestSource;
+    use crate::test_util::collect_warnings;
     use image::Rgba;
+    use proptest::prelude::*;
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    /// Creates an empty directory in the system temp dir, unique to the calling test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("spriterator_{}", name));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Creates an opaque image filled with pseudo-random noise, which compresses poorly.
+    fn noise_image(width: u32, height: u32, seed: u32) -> RgbaImage {
+        let mut state = seed.wrapping_mul(2654435761).wrapping_add(1);
+        RgbaImage::from_fn(width, height, |_, _| {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            let [r, g, b, _] = state.to_le_bytes();
+            Rgba([r, g, b, 255])
+        })
+    }
+
+    #[test]
+    fn test_spriterator_creation() {
+        let spriterator = Spriterator::new("test_dir", 1024, 1024, None, None);
+        assert_eq!(spriterator.dir_path.as_deref(), Some(Path::new("test_dir")));
+        assert_eq!(spriterator.max_width, 1024);
+        assert_eq!(spriterator.max_height, 1024);
+    }
+
+    #[test]
+    fn test_with_profile() {
+        let spriterator = Spriterator::new("test_dir", 1024, 1024, None, None)
+            .with_profile("landscape-4096")
+            .unwrap();
+        assert_eq!(spriterator.max_width, 4096);
+        assert_eq!(spriterator.max_height, 2048);
+        assert!(Spriterator::new("test_dir", 1024, 1024, None, None)
+            .with_profile("unknown")
+            .is_err());
+    }
+
+    #[test]
+    fn test_empty_directory_error() {
+        let spriterator = Spriterator::new("empty_dir", 1024, 1024, None, None);
+        let result = spriterator.generate();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_max_encoded_bytes_splits_sheets() {
+        let dir = test_dir("max_encoded_bytes");
+        for i in 0..4 {
+            noise_image(16, 16, i)
+                .save(dir.join(format!("{}.png", i)))
+                .unwrap();
+        }
+
+        let dir_path = dir.to_str().unwrap();
+        let unlimited = Spriterator::new(dir_path, 64, 64, None, None)
+            .generate()
+            .unwrap();
+        assert_eq!(unlimited.len(), 1);
+
+        let limit = encoded_size(unlimited[0].get_image()).unwrap() * 2 / 3;
+        let limited = Spriterator::new(dir_path, 64, 64, None, None)
+            .with_max_encoded_bytes(Some(limit))
+            .generate()
+            .unwrap();
+        assert!(limited.len() > 1);
+        assert_eq!(
+            limited
+                .iter()
+                .map(|sprite| sprite.get_frames().len())
+                .sum::<usize>(),
+            4
+        );
+        for sprite in &limited {
+            assert!(encoded_size(sprite.get_image()).unwrap() <= limit);
+        }
+
+        let processed = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = std::sync::Arc::clone(&processed);
+        let post_processed = Spriterator::new(dir_path, 64, 64, None, None)
+            .with_max_encoded_bytes(Some(limit))
+            .with_post_processor(move |sheet: RgbaImage, _: &[Frame]| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Ok(sheet)
+            })
+            .generate()
+            .unwrap();
+        assert_eq!(post_processed.len(), limited.len());
+        assert_eq!(
+            processed.load(std::sync::atomic::Ordering::Relaxed),
+            limited.len()
+        );
+
+        let too_small = Spriterator::new(dir_path, 64, 64, None, None)
+            .with_max_encoded_bytes(Some(16))
+            .generate();
+        assert!(too_small.is_err());
+    }
+
+    #[test]
+    fn test_input_order_preserved_across_sheets() {
+        let dir = test_dir("input_order");
+        let names = [
+            "d.png", "a.png", "c.png", "e.png", "b.png", "f.png", "g.png",
+        ];
+        for (i, name) in names.iter().enumerate() {
+            noise_image(10 + i as u32 % 3 * 4, 12, i as u32)
+                .save(dir.join(name))
+                .unwrap();
+        }
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 32, 24, None, None)
+            .generate()
+            .unwrap();
+        assert!(sprites.len() > 1);
+
+        let packed: Vec<&str> = sprites
+            .iter()
+            .flat_map(|sprite| sprite.get_frames().iter().map(|frame| frame.get_name()))
+            .collect();
+        assert_eq!(
+            packed,
+            vec!["a.png", "b.png", "c.png", "d.png", "e.png", "f.png", "g.png"]
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_generation_order() {
+        let dir = test_dir("parallel_decoding");
+        for i in 0..40 {
+            noise_image(4 + i % 5, 6, i)
+                .save(dir.join(format!("{:02}.png", i)))
+                .unwrap();
+        }
+
+        let generate = |threads: Option<usize>| {
+            Spriterator::new(dir.to_str().unwrap(), 16, 16, Some(3), None)
+                .with_threads(threads)
+                .generate()
+                .unwrap()
+        };
+        let sequential = generate(Some(1));
+        let parallel = generate(Some(4));
+        assert!(sequential.len() > 1);
+        assert_eq!(sequential.len(), parallel.len());
+        for (a, b) in sequential.iter().zip(&parallel) {
+            assert_eq!(a.get_image(), b.get_image());
+            let names = |sprite: &Sprite| -> Vec<String> {
+                sprite
+                    .get_frames()
+                    .iter()
+                    .map(|frame| frame.get_name().to_string())
+                    .collect()
+            };
+            assert_eq!(names(a), names(b));
+        }
+        assert_eq!(sequential[0].get_frames()[0].get_name(), "00.png");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_file_name() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = test_dir("non_utf8");
+        let file_name = std::ffi::OsStr::from_bytes(b"caf\xe9.png");
+        noise_image(4, 4, 0)
+            .save_with_format(dir.join(file_name), image::ImageFormat::Png)
+            .unwrap();
+
+        let (warnings, handler) = collect_warnings();
+        let sprites = Spriterator::new("unused", 64, 64, None, None)
+            .with_dir_path(&dir)
+            .with_warning_handler(handler)
+            .generate()
+            .unwrap();
+
+        assert_eq!(sprites[0].get_frames()[0].get_name(), "caf\u{FFFD}.png");
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            matches!(&warnings[0], Warning::LossyFileName { name, .. } if name == "caf\u{FFFD}.png")
+        );
+    }
+
+    #[test]
+    fn test_engine_limits() {
+        let dir = test_dir("engine_limits");
+        for index in 0..3 {
+            noise_image(16, 16, index)
+                .save(dir.join(format!("{}.png", index)))
+                .unwrap();
+        }
+        let limits = EngineLimits::new().with_max_pages(Some(1));
+
+        let (warnings, handler) = collect_warnings();
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 16, 16, None, None)
+            .with_engine_limits(limits.clone())
+            .with_warning_handler(handler)
+            .generate()
+            .unwrap();
+        assert_eq!(sprites.len(), 3);
+        let warnings: Vec<String> = warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(Warning::to_string)
+            .collect();
+        assert_eq!(warnings, vec!["3 sheets exceed the maximum of 1."]);
+
+        let error = Spriterator::new(dir.to_str().unwrap(), 16, 16, None, None)
+            .with_engine_limits(limits.with_strict(true))
+            .generate()
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SpriteratorError>(),
+            Some(SpriteratorError::EngineLimitsExceeded { violations }) if violations.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_iter_sprites() {
+        let dir = test_dir("iter_sprites");
+        for index in 0..5 {
+            noise_image(8 + index % 2 * 4, 10, index)
+                .save(dir.join(format!("{}.png", index)))
+                .unwrap();
+        }
+        let spriterator =
+            Spriterator::new(dir.to_str().unwrap(), 24, 12, None, None).with_array_texture(true);
+
+        let generated = spriterator.generate().unwrap();
+        let streamed: Vec<Sprite> = spriterator
+            .iter_sprites()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(generated.len() > 1);
+        assert_eq!(streamed.len(), generated.len());
+        for (streamed, generated) in streamed.iter().zip(&generated) {
+            assert_eq!(streamed.get_image(), generated.get_image());
+            assert_eq!(streamed.get_layer(), generated.get_layer());
+            assert_eq!(streamed.get_frames().len(), generated.get_frames().len());
+        }
+
+        let limits = EngineLimits::new()
+            .with_max_pages(Some(1))
+            .with_strict(true);
+        let spriterator = spriterator.with_engine_limits(limits);
+        let mut sprites = spriterator.iter_sprites().unwrap();
+        assert!(sprites.next().unwrap().is_ok());
+        assert!(sprites.next().unwrap().is_err());
+        assert!(sprites.next().is_none());
+    }
+
+    #[test]
+    fn test_generate_incremental() {
+        let dir = test_dir("generate_incremental");
+        let cache_path = dir.with_extension("json");
+        let _ = std::fs::remove_file(&cache_path);
+        for index in 0..4 {
+            noise_image(10, 10, index)
+                .save(dir.join(format!("{}.png", index)))
+                .unwrap();
+        }
+        let spriterator = Spriterator::new(dir.to_str().unwrap(), 24, 12, None, None);
+        let is_composed = |sprites: Vec<Option<Sprite>>| -> Vec<bool> {
+            sprites.iter().map(Option::is_some).collect()
+        };
+
+        let sprites = spriterator.generate_incremental(&cache_path).unwrap();
+        let generated = spriterator.generate().unwrap();
+        assert_eq!(sprites.len(), 2);
+        for (sprite, generated) in sprites.iter().zip(&generated) {
+            assert_eq!(sprite.as_ref().unwrap().get_image(), generated.get_image());
+        }
+        assert_eq!(
+            is_composed(spriterator.generate_incremental(&cache_path).unwrap()),
+            [false, false]
+        );
+
+        noise_image(10, 10, 9).save(dir.join("3.png")).unwrap();
+        assert_eq!(
+            is_composed(spriterator.generate_incremental(&cache_path).unwrap()),
+            [false, true]
+        );
+        assert_eq!(
+            is_composed(spriterator.generate_incremental(&cache_path).unwrap()),
+            [false, false]
+        );
+
+        let spriterator = spriterator.with_background(Some(Rgba([0, 0, 0, 255])));
+        assert_eq!(
+            is_composed(spriterator.generate_incremental(&cache_path).unwrap()),
+            [true, true]
+        );
+        let _ = std::fs::remove_file(&cache_path);
+    }
+
+    #[test]
+    fn test_blit_matches_overlay() {
+        let mut image = noise_image(9, 6, 3);
+        image.put_pixel(4, 2, Rgba([1, 2, 3, 0]));
+        image.put_pixel(8, 5, Rgba([4, 5, 6, 0]));
+        for (x, y) in [(0, 0), (3, 5), (7, 9)] {
+            let mut blitted = RgbaImage::new(12, 12);
+            blit(&mut blitted, &image, x, y, false);
+            let mut blended = RgbaImage::new(12, 12);
+            image::imageops::overlay(&mut blended, &image, x.into(), y.into());
+            assert_eq!(blitted, blended);
+
+            let mut replaced = RgbaImage::new(12, 12);
+            image::imageops::replace(&mut replaced, &image, x.into(), y.into());
+            blit(&mut blitted, &image, x, y, true);
+            assert_eq!(blitted, replaced);
+        }
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn test_gpu_matches_cpu() {
+        let dir = test_dir("gpu");
+        for index in 0..5 {
+            let mut image = noise_image(9 + index, 7, index);
+            // Hidden colors must be cleared, and partially transparent ones kept exactly.
+            image.put_pixel(index, 3, Rgba([1, 2, 3, 0]));
+            image.put_pixel(0, index, Rgba([201, 77, 13, 90]));
+            image.save(dir.join(format!("{}.png", index))).unwrap();
+        }
+        let spriterator =
+            Spriterator::new(dir.to_str().unwrap(), 32, 32, None, None).with_extrude(1);
+        let expected = spriterator.generate().unwrap();
+
+        let sprites = spriterator.with_gpu(true).generate().unwrap();
+        assert_eq!(sprites.len(), expected.len());
+        for (sprite, expected) in sprites.iter().zip(&expected) {
+            assert_eq!(sprite.get_image(), expected.get_image());
+        }
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        let dir = test_dir("memory_budget");
+        for index in 0..7 {
+            noise_image(10, 8 + index % 3, index)
+                .save(dir.join(format!("{}.png", index)))
+                .unwrap();
+        }
+        let spriterator = Spriterator::new(dir.to_str().unwrap(), 24, 24, None, None)
+            .with_usage_weights(HashMap::from([("5.png".to_string(), 1.0)]));
+        let expected = spriterator.generate().unwrap();
+        assert!(expected.len() > 1);
+
+        let within = spriterator.with_memory_budget(Some(10 * 10 * 4 * 7));
+        assert!(matches!(within.load_within_budget(), Ok(Inputs::Images(_))));
+        let chunked = within.with_memory_budget(Some(10 * 10 * 4));
+        assert!(matches!(
+            chunked.load_within_budget(),
+            Ok(Inputs::Outlines(_))
+        ));
+
+        let sprites = chunked.generate().unwrap();
+        assert_eq!(sprites.len(), expected.len());
+        for (sprite, expected) in sprites.iter().zip(&expected) {
+            assert_eq!(sprite.get_image(), expected.get_image());
+            let names = |sprite: &Sprite| -> Vec<String> {
+                sprite
+                    .get_frames()
+                    .iter()
+                    .map(|frame| frame.get_name().to_string())
+                    .collect()
+            };
+            assert_eq!(names(sprite), names(expected));
+        }
+    }
 
     #[test]
-    fn test_spriterator_creation() {
-        let spriterator = Spriterator::new("test_dir", 1024, 1024, None, None);
-        assert_eq!(spriterator.dir_path, "test_dir");
-        assert_eq!(spriterator.max_width, 1024);
-        assert_eq!(spriterator.max_height, 1024);
+    fn test_memory_budget_undecodable() {
+        let dir = test_dir("memory_budget_undecodable");
+        noise_image(10, 10, 0).save(dir.join("0.png")).unwrap();
+        noise_image(10, 10, 1).save(dir.join("1.png")).unwrap();
+        let data = std::fs::read(dir.join("1.png")).unwrap();
+        std::fs::write(dir.join("1.png"), &data[..data.len() / 2]).unwrap();
+
+        let (warnings, handler) = collect_warnings();
+        let spriterator = |memory_budget| {
+            Spriterator::new(dir.to_str().unwrap(), 32, 32, None, None)
+                .with_memory_budget(memory_budget)
+                .with_warning_handler(handler.clone())
+        };
+        let expected = spriterator(None).generate().unwrap();
+        assert_eq!(expected.len(), 1);
+        assert_eq!(expected[0].get_frames().len(), 1);
+
+        for memory_budget in [10 * 10 * 4 * 2, 10] {
+            let sprites = spriterator(Some(memory_budget)).generate().unwrap();
+            assert_eq!(sprites.len(), 1);
+            assert_eq!(sprites[0].get_image(), expected[0].get_image());
+            assert_eq!(sprites[0].get_frames().len(), 1);
+        }
+        let layouts = spriterator(None).plan_layout().unwrap();
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(layouts[0].get_frames().len(), 1);
+
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings.iter().all(|warning| matches!(
+            warning,
+            Warning::UndecodableImage { path } if path.ends_with("1.png")
+        )));
     }
 
     #[test]
-    fn test_empty_directory_error() {
-        let spriterator = Spriterator::new("empty_dir", 1024, 1024, None, None);
-        let result = spriterator.generate();
-        assert!(result.is_err());
+    fn test_plan_layout() {
+        let dir = test_dir("plan_layout");
+        for index in 0..5 {
+            let mut image = noise_image(12 + index * 3, 10, index);
+            image.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+            image.save(dir.join(format!("{}.png", index))).unwrap();
+        }
+        RgbaImage::from_pixel(6, 6, Rgba([0, 0, 255, 255]))
+            .save(dir.join("still.gif"))
+            .unwrap();
+
+        let check = |spriterator: Spriterator| {
+            let layouts = spriterator.plan_layout().unwrap();
+            let sprites = spriterator.generate().unwrap();
+            assert_eq!(layouts.len(), sprites.len());
+            for (layout, sprite) in layouts.iter().zip(&sprites) {
+                let size = (layout.get_width(), layout.get_height());
+                assert_eq!(size, sprite.get_image().dimensions());
+                let frames = |frames: &[Frame]| -> Vec<String> {
+                    frames.iter().map(|frame| format!("{:?}", frame)).collect()
+                };
+                assert_eq!(frames(layout.get_frames()), frames(sprite.get_frames()));
+            }
+        };
+
+        let spriterator = Spriterator::new(dir.to_str().unwrap(), 40, 40, None, Some(12));
+        check(spriterator.with_padding(1).with_power_of_two(true));
+        let spriterator = Spriterator::new(dir.to_str().unwrap(), 40, 40, None, None);
+        check(spriterator.with_trim(true));
+        let spriterator = Spriterator::new(dir.to_str().unwrap(), 40, 40, None, None);
+        check(spriterator.with_image("memory.png", noise_image(7, 7, 9)));
+    }
+
+    #[test]
+    fn test_order_by_usage() {
+        let spriterator = Spriterator::new("test_dir", 1024, 1024, None, None).with_usage_weights(
+            HashMap::from([("hot.png".to_string(), 10.0), ("warm.png".to_string(), 2.0)]),
+        );
+        let mut images: Vec<SourceImage> = ["a.png", "warm.png", "b.png", "hot.png"]
+            .iter()
+            .map(|name| spriterator.prepare_image(name, RgbaImage::new(1, 1)))
+            .collect();
+        spriterator.order_by_usage(&mut images);
+        let names: Vec<&str> = images.iter().map(|source| source.name.as_str()).collect();
+        assert_eq!(names, vec!["hot.png", "warm.png", "a.png", "b.png"]);
+    }
+
+    #[test]
+    fn test_array_texture_pages() {
+        let dir = test_dir("array_texture");
+        for i in 0..3 {
+            noise_image(20, 20, i)
+                .save(dir.join(format!("{}.png", i)))
+                .unwrap();
+        }
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 40, 20, None, None)
+            .with_array_texture(true)
+            .generate()
+            .unwrap();
+        assert_eq!(sprites.len(), 2);
+        for (index, sprite) in sprites.iter().enumerate() {
+            assert_eq!(sprite.get_image().dimensions(), (40, 20));
+            assert_eq!(sprite.get_layer(), Some(index as u32));
+        }
+    }
+
+    #[test]
+    fn test_tile_preset() {
+        let dir = test_dir("tile_preset");
+        noise_image(8, 8, 0).save(dir.join("a.png")).unwrap();
+        noise_image(6, 4, 1).save(dir.join("b.png")).unwrap();
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_tile_preset()
+            .generate()
+            .unwrap();
+        assert_eq!(sprites.len(), 1);
+
+        let sprite = &sprites[0];
+        // Two 12×12 cells (8×8 plus 2 pixels of extrusion on each side), padded to 32×16.
+        assert_eq!(sprite.get_image().dimensions(), (32, 16));
+
+        let mut frames: Vec<_> = sprite
+            .get_frames()
+            .iter()
+            .map(|frame| frame.rect())
+            .collect();
+        frames.sort_by_key(|rect| rect.x);
+        assert_eq!(frames[0].x, 2);
+        assert_eq!(frames[0].y, 2);
+        assert_eq!(frames[1].x, 14);
+        assert_eq!(frames[1].y, 2);
+    }
+
+    #[test]
+    fn test_columns() {
+        let spriterator = Spriterator::new("test_dir", 64, 16, None, None).with_columns(Some(3));
+        let images = (0..7)
+            .map(|index| {
+                let size = 4 + index % 3;
+                spriterator.prepare_image(&format!("{}.png", index), noise_image(size, size, index))
+            })
+            .collect();
+        let sprites = spriterator.pack(images).unwrap();
+
+        assert_eq!(sprites.len(), 2);
+        for sprite in &sprites {
+            assert_eq!(sprite.get_image().width(), 18);
+            for (index, frame) in sprite.get_frames().iter().enumerate() {
+                assert_eq!(frame.get_x(), (index as u32 % 3) * 6);
+                assert_eq!(frame.get_y(), (index as u32 / 3) * 6);
+            }
+        }
+        assert_eq!(sprites[1].get_frames().len(), 1);
+
+        let too_wide = Spriterator::new("test_dir", 16, 16, None, None).with_columns(Some(3));
+        let images = vec![too_wide.prepare_image("a.png", noise_image(6, 6, 0))];
+        assert!(too_wide.pack(images).is_err());
+        assert!(too_wide.with_columns(Some(0)).validate().is_err());
+    }
+
+    #[test]
+    fn test_animated_gif() {
+        use image::codecs::gif::GifEncoder;
+        use image::{Delay, Frame as GifFrame, Rgba};
+
+        let dir = test_dir("animated_gif");
+        std::fs::create_dir(dir.join("hero")).unwrap();
+        let mut encoder = GifEncoder::new(File::create(dir.join("hero/walk.gif")).unwrap());
+        let frames = (0..3u8).map(|index| {
+            GifFrame::from_parts(
+                RgbaImage::from_pixel(4, 4, Rgba([index * 80, 0, 0, 255])),
+                0,
+                0,
+                Delay::from_numer_denom_ms(100 + u32::from(index) * 10, 1),
+            )
+        });
+        encoder.encode_frames(frames).unwrap();
+        drop(encoder);
+        RgbaImage::from_pixel(4, 4, Rgba([0, 0, 255, 255]))
+            .save(dir.join("still.gif"))
+            .unwrap();
+
+        let sprites = Spriterator::new(&dir.to_string_lossy(), 64, 64, None, None)
+            .generate()
+            .unwrap();
+        let frames: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(|frame| (frame.get_name(), frame.get_duration()))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                ("hero/walk_0.gif", Some(100)),
+                ("hero/walk_1.gif", Some(110)),
+                ("hero/walk_2.gif", Some(120)),
+                ("still.gif", None),
+            ]
+        );
+
+        let sprites = Spriterator::new(&dir.to_string_lossy(), 64, 64, None, None)
+            .with_animations(false)
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["hero/walk.gif", "still.gif"]);
+        assert_eq!(sprites[0].get_frames()[0].get_duration(), None);
+    }
+
+    /// Builds an animated WebP from lossless frames, each shown for `duration` milliseconds.
+    fn animated_webp(frames: &[RgbaImage], duration: u32) -> Vec<u8> {
+        use image::codecs::webp::WebPEncoder;
+
+        let u24 = |value: u32| value.to_le_bytes()[..3].to_vec();
+        let chunk = |name: &[u8], data: &[u8]| {
+            let mut chunk = name.to_vec();
+            chunk.extend((data.len() as u32).to_le_bytes());
+            chunk.extend(data);
+            if data.len() % 2 == 1 {
+                chunk.push(0);
+            }
+            chunk
+        };
+        let (width, height) = frames[0].dimensions();
+
+        let mut vp8x = vec![0x12, 0, 0, 0];
+        vp8x.extend(u24(width - 1));
+        vp8x.extend(u24(height - 1));
+        let mut body = b"WEBP".to_vec();
+        body.extend(chunk(b"VP8X", &vp8x));
+        body.extend(chunk(b"ANIM", &[0, 0, 0, 0, 0, 0]));
+
+        for frame in frames {
+            let mut still = Vec::new();
+            WebPEncoder::new_lossless(&mut still)
+                .encode(frame.as_raw(), width, height, ExtendedColorType::Rgba8)
+                .unwrap();
+            let mut anmf = [
+                u24(0),
+                u24(0),
+                u24(width - 1),
+                u24(height - 1),
+                u24(duration),
+            ]
+            .concat();
+            anmf.push(0);
+            anmf.extend(&still[12..]);
+            body.extend(chunk(b"ANMF", &anmf));
+        }
+
+        let mut data = b"RIFF".to_vec();
+        data.extend((body.len() as u32).to_le_bytes());
+        data.extend(body);
+        data
+    }
+
+    #[test]
+    fn test_animated_webp() {
+        let dir = test_dir("animated_webp");
+        let frames: Vec<_> = (0..2).map(|index| noise_image(4, 3, index)).collect();
+        std::fs::write(dir.join("spin.webp"), animated_webp(&frames, 80)).unwrap();
+
+        let sprites = Spriterator::new(&dir.to_string_lossy(), 64, 64, None, None)
+            .generate()
+            .unwrap();
+        let sprite_frames = sprites[0].get_frames();
+        assert_eq!(sprite_frames.len(), 2);
+        assert_eq!(sprite_frames[1].get_name(), "spin_1.webp");
+        assert_eq!(sprite_frames[1].get_duration(), Some(80));
+        let rect = sprite_frames[1].rect();
+        let placed =
+            image::imageops::crop_imm(sprites[0].get_image(), rect.x, rect.y, rect.w, rect.h);
+        assert_eq!(placed.to_image(), frames[1]);
+
+        let sprites = Spriterator::new(&dir.to_string_lossy(), 64, 64, None, None)
+            .with_animations(false)
+            .generate()
+            .unwrap();
+        assert_eq!(sprites[0].get_frames().len(), 1);
+        assert_eq!(sprites[0].get_frames()[0].get_name(), "spin.webp");
+    }
+
+    #[test]
+    fn test_apng() {
+        let dir = test_dir("apng");
+        let frames: Vec<_> = (0..3).map(|index| noise_image(5, 2, index)).collect();
+        let mut encoder = png::Encoder::new(File::create(dir.join("wave.png")).unwrap(), 5, 2);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_animated(3, 0).unwrap();
+        encoder.set_frame_delay(1, 20).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        for frame in &frames {
+            writer.write_image_data(frame.as_raw()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let sprites = Spriterator::new(&dir.to_string_lossy(), 64, 64, None, None)
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["wave_0.png", "wave_1.png", "wave_2.png"]);
+        assert_eq!(sprites[0].get_frames()[2].get_duration(), Some(50));
+        let rect = sprites[0].get_frames()[2].rect();
+        let placed =
+            image::imageops::crop_imm(sprites[0].get_image(), rect.x, rect.y, rect.w, rect.h);
+        assert_eq!(placed.to_image(), frames[2]);
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_svg() {
+        let dir = test_dir("svg");
+        std::fs::write(
+            dir.join("icon.svg"),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="4"><rect x="4" width="4" height="4" fill="#ff0000"/></svg>"##,
+        )
+        .unwrap();
+
+        let spriterator = Spriterator::new(&dir.to_string_lossy(), 64, 64, None, None);
+        let sprites = spriterator.generate().unwrap();
+        let frame = &sprites[0].get_frames()[0];
+        assert_eq!(frame.get_name(), "icon.svg");
+        assert_eq!((frame.get_width(), frame.get_height()), (8, 4));
+        assert_eq!(sprites[0].get_image().get_pixel(6, 2).0, [255, 0, 0, 255]);
+        assert_eq!(sprites[0].get_image().get_pixel(1, 2).0[3], 0);
+
+        let sprites = spriterator.with_svg_scale(2.5).generate().unwrap();
+        assert_eq!(sprites[0].get_frames()[0].get_width(), 20);
+
+        let invalid = Spriterator::new("test_dir", 64, 64, None, None).with_svg_scale(0.0);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_legacy_formats() {
+        let dir = test_dir("legacy_formats");
+        for (index, name) in ["a.bmp", "b.tif", "c.TGA", "d.ico"].iter().enumerate() {
+            noise_image(4, 4, index as u32)
+                .save(dir.join(name))
+                .unwrap();
+        }
+
+        let sprites = Spriterator::new(&dir.to_string_lossy(), 64, 64, None, None)
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["a.bmp", "b.tif", "c.TGA", "d.ico"]);
+        for (index, frame) in sprites[0].get_frames().iter().enumerate() {
+            let rect = frame.rect();
+            let placed =
+                image::imageops::crop_imm(sprites[0].get_image(), rect.x, rect.y, rect.w, rect.h);
+            assert_eq!(placed.to_image(), noise_image(4, 4, index as u32));
+        }
+    }
+
+    #[test]
+    fn test_color_management() {
+        let dir = test_dir("color_management");
+        let mut encoder = png::Encoder::new(File::create(dir.join("linear.png")).unwrap(), 1, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_source_gamma(png::ScaledFloat::new(1.0));
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[128, 128, 128, 255])
+            .unwrap();
+
+        let pixel = |color_management: bool| {
+            let sprites = Spriterator::new(dir.to_str().unwrap(), 8, 8, None, None)
+                .with_color_management(color_management)
+                .generate()
+                .unwrap();
+            *sprites[0].get_image().get_pixel(0, 0)
+        };
+        assert_eq!(pixel(false), Rgba([128, 128, 128, 255]));
+        assert_eq!(pixel(true), Rgba([188, 188, 188, 255]));
+    }
+
+    #[test]
+    fn test_skipped_image_warnings() {
+        let dir = test_dir("skipped_image_warnings");
+        noise_image(4, 4, 0).save(dir.join("a.png")).unwrap();
+        std::fs::write(dir.join("b.avif"), b"avif").unwrap();
+        std::fs::write(dir.join("c.png"), b"not a png").unwrap();
+        std::fs::write(dir.join("d.txt"), b"notes").unwrap();
+
+        let (warnings, handler) = collect_warnings();
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_warning_handler(handler)
+            .generate()
+            .unwrap();
+
+        assert_eq!(sprites[0].get_frames().len(), 1);
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            vec![
+                #[cfg(not(feature = "avif-decode"))]
+                Warning::UnsupportedFormat {
+                    path: dir.join("b.avif")
+                },
+                #[cfg(feature = "avif-decode")]
+                Warning::UndecodableImage {
+                    path: dir.join("b.avif")
+                },
+                Warning::UndecodableImage {
+                    path: dir.join("c.png")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resize_cache() {
+        let dir = test_dir("resize_cache");
+        let cache_dir = dir.with_extension("cache");
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        noise_image(40, 20, 0).save(dir.join("a.png")).unwrap();
+        noise_image(20, 40, 1).save(dir.join("b.png")).unwrap();
+        let spriterator = Spriterator::new(dir.to_str().unwrap(), 64, 64, Some(10), None);
+        let expected = spriterator.generate().unwrap();
+
+        let spriterator = spriterator.with_resize_cache(Some(cache_dir.clone()));
+        let sprites = spriterator.generate().unwrap();
+        assert_eq!(sprites[0].get_image(), expected[0].get_image());
+        let entries: Vec<PathBuf> = std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(entries.len(), 2);
+
+        // Later runs load the cached pixels instead of resizing.
+        for entry in &entries {
+            let size = std::fs::metadata(entry).unwrap().len() as usize;
+            std::fs::write(entry, vec![7; size]).unwrap();
+        }
+        let sprites = spriterator.generate().unwrap();
+        let frame = &sprites[0].get_frames()[0];
+        assert_eq!(
+            sprites[0]
+                .get_image()
+                .get_pixel(frame.get_x(), frame.get_y()),
+            &Rgba([7; 4])
+        );
+
+        // Disabling the cache again resizes instead of loading the altered entries.
+        let spriterator = spriterator.with_resize_cache(None);
+        let sprites = spriterator.generate().unwrap();
+        assert_eq!(sprites[0].get_image(), expected[0].get_image());
+
+        let (warnings, handler) = collect_warnings();
+        let blocked = dir.join("a.png");
+        let sprites = spriterator
+            .with_resize_cache(Some(blocked))
+            .with_warning_handler(handler)
+            .generate()
+            .unwrap();
+        assert_eq!(sprites[0].get_image(), expected[0].get_image());
+        assert!(matches!(
+            warnings.lock().unwrap()[0],
+            Warning::ResizeCacheUnavailable { .. }
+        ));
+        let _ = std::fs::remove_dir_all(&cache_dir);
+    }
+
+    #[test]
+    fn test_extensions() {
+        let dir = test_dir("extensions");
+        noise_image(4, 4, 0).save(dir.join("a.png")).unwrap();
+        noise_image(4, 4, 1).save(dir.join("b.webp")).unwrap();
+        image::DynamicImage::ImageRgba8(noise_image(4, 4, 2))
+            .to_rgb8()
+            .save(dir.join("c.JPG"))
+            .unwrap();
+        noise_image(4, 4, 3).save(dir.join("d.qoi")).unwrap();
+
+        let names = |spriterator: Spriterator| {
+            let sprites = spriterator.generate().unwrap();
+            sprites[0]
+                .get_frames()
+                .iter()
+                .map(|frame| frame.get_name().to_string())
+                .collect::<Vec<_>>()
+        };
+        let spriterator = || Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None);
+
+        assert_eq!(names(spriterator()), vec!["a.png", "b.webp"]);
+        assert_eq!(
+            names(spriterator().with_extensions(&["PNG", ".jpg"])),
+            vec!["a.png", "c.JPG"]
+        );
+        assert_eq!(
+            names(spriterator().with_all_decodable_extensions()),
+            vec!["a.png", "b.webp", "c.JPG", "d.qoi"]
+        );
+
+        let error = spriterator()
+            .with_extensions(&["psd"])
+            .generate()
+            .unwrap_err();
+        assert!(error.to_string().contains("[\"psd\"]"));
+    }
+
+    #[test]
+    fn test_sheet_allocated_at_final_size() {
+        // A sheet of the maximum size would need 10 GB.
+        let images = vec![
+            ("a.png".to_string(), noise_image(4, 4, 0)),
+            ("b.png".to_string(), noise_image(6, 3, 1)),
+        ];
+        let sprites = Spriterator::from_images(images, 50_000, 50_000, None, None)
+            .with_margin(1)
+            .generate()
+            .unwrap();
+        assert_eq!(sprites[0].get_image().dimensions(), (12, 6));
+    }
+
+    #[test]
+    fn test_in_memory_images() {
+        let images = vec![
+            ("noise.png".to_string(), noise_image(4, 4, 0)),
+            ("wide.png".to_string(), noise_image(8, 2, 1)),
+        ];
+        let sprites = Spriterator::from_images(images, 64, 64, None, None)
+            .with_image("late.png", noise_image(2, 2, 2))
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["noise.png", "wide.png", "late.png"]);
+        let rect = sprites[0].get_frames()[1].rect();
+        let placed =
+            image::imageops::crop_imm(sprites[0].get_image(), rect.x, rect.y, rect.w, rect.h);
+        assert_eq!(placed.to_image(), noise_image(8, 2, 1));
+
+        let dir = test_dir("in_memory_images");
+        noise_image(4, 4, 3).save(dir.join("file.png")).unwrap();
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_image("memory.png", noise_image(4, 4, 4))
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["file.png", "memory.png"]);
+
+        let error = Spriterator::from_images(
+            vec![("big.png".to_string(), noise_image(9, 9, 0))],
+            8,
+            8,
+            None,
+            None,
+        )
+        .generate()
+        .unwrap_err();
+        assert!(error.to_string().contains("big.png"));
+        assert!(Spriterator::from_images(Vec::new(), 8, 8, None, None)
+            .generate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_image_source() {
+        struct Generated;
+
+        impl ImageSource for Generated {
+            fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+                Box::new((0..3).map(|seed| {
+                    Ok(
+                        NamedImage::new(&format!("gen_{}.png", seed), noise_image(4, 4, seed))
+                            .with_duration(Some(100)),
+                    )
+                }))
+            }
+        }
+
+        let dir = test_dir("image_source");
+        noise_image(4, 4, 3).save(dir.join("file.png")).unwrap();
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_image("memory.png", noise_image(4, 4, 4))
+            .with_image_source(Generated)
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "file.png",
+                "gen_0.png",
+                "gen_1.png",
+                "gen_2.png",
+                "memory.png"
+            ]
+        );
+        assert_eq!(sprites[0].get_frames()[1].get_duration(), Some(100));
+
+        struct Failing;
+
+        impl ImageSource for Failing {
+            fn entries(&self) -> Box<dyn Iterator<Item = Result<NamedImage, Box<dyn Error>>> + '_> {
+                Box::new(std::iter::once(Err("unreachable archive".into())))
+            }
+        }
+
+        let error = Spriterator::from_images(Vec::new(), 64, 64, None, None)
+            .with_image_source(Failing)
+            .generate()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "unreachable archive");
+    }
+
+    #[test]
+    fn test_include_exclude() {
+        let dir = test_dir("include_exclude");
+        for name in [
+            "hero_idle_0.png",
+            "hero_walk_0.png",
+            "npc/guard_idle_0.png",
+            "npc/raw/guard_idle_1.png",
+            "raw/hero_idle_1.png",
+        ] {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            noise_image(2, 2, 0).save(path).unwrap();
+        }
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_include(&["**/*_idle_*.png"])
+            .with_exclude(&["**/raw/**"])
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["hero_idle_0.png", "npc/guard_idle_0.png"]);
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_include(&["*.png"])
+            .generate()
+            .unwrap();
+        assert_eq!(sprites[0].get_frames().len(), 2);
+
+        let error = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_exclude(&["raw/[a-"])
+            .validate()
+            .unwrap_err();
+        assert!(error.to_string().contains("Invalid glob pattern"));
+    }
+
+    #[test]
+    fn test_name_pattern() {
+        let dir = test_dir("name_pattern");
+        for name in [
+            "chr_walk_01.png",
+            "chr_walk_00.png",
+            "readme.png",
+            "chr_idle_00.png",
+        ] {
+            noise_image(2, 2, 0).save(dir.join(name)).unwrap();
+        }
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_name_pattern(
+                r"chr_(?P<animation>\w+?)_(?P<index>\d+)\.png$",
+                Some("${animation}/${index}.png"),
+            )
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["idle/00.png", "walk/00.png", "walk/01.png"]);
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_name_pattern(r"chr_(?P<animation>\w+?)_(?P<index>\d+)\.png$", None)
+            .generate()
+            .unwrap();
+        let names: Vec<_> = sprites[0]
+            .get_frames()
+            .iter()
+            .map(Frame::get_name)
+            .collect();
+        assert_eq!(names, vec!["idle_00.png", "walk_00.png", "walk_01.png"]);
+
+        let sprites = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_name_pattern(r"^chr_walk_", None)
+            .generate()
+            .unwrap();
+        assert_eq!(sprites[0].get_frames()[0].get_name(), "chr_walk_00.png");
+        assert_eq!(sprites[0].get_frames().len(), 2);
+
+        let error = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None)
+            .with_name_pattern("(?P<animation", None)
+            .validate()
+            .unwrap_err();
+        assert!(error.to_string().contains("Invalid name pattern"));
+    }
+
+    #[test]
+    fn test_ignore_files() {
+        let dir = test_dir("ignore_files");
+        for name in [
+            "hero.png",
+            "hero.raw.png",
+            "scratch/wip.png",
+            "npc/guard.png",
+            "npc/keep.raw.png",
+        ] {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            noise_image(2, 2, 0).save(path).unwrap();
+        }
+        std::fs::write(dir.join(".spriteignore"), "*.raw.png\nscratch/\n").unwrap();
+        std::fs::write(dir.join("npc/.spriteignore"), "!keep.raw.png\nguard.png\n").unwrap();
+
+        let names = |spriterator: Spriterator| -> Vec<String> {
+            spriterator.generate().unwrap()[0]
+                .get_frames()
+                .iter()
+                .map(|frame| frame.get_name().to_string())
+                .collect()
+        };
+        let spriterator = Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None);
+        assert_eq!(names(spriterator), vec!["hero.png", "npc/keep.raw.png"]);
+
+        let spriterator =
+            Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None).with_ignore_files(false);
+        assert_eq!(names(spriterator).len(), 5);
+    }
+
+    #[test]
+    fn test_traversal_controls() {
+        let dir = test_dir("traversal_controls");
+        for name in [
+            "a.png",
+            ".backup/a.png",
+            "nested/b.png",
+            "nested/deeper/c.png",
+        ] {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            noise_image(2, 2, 0).save(path).unwrap();
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("nested"), dir.join("linked")).unwrap();
+            std::os::unix::fs::symlink(&dir, dir.join("nested/loop")).unwrap();
+        }
+
+        let names = |spriterator: Spriterator| -> Vec<String> {
+            spriterator.generate().unwrap()[0]
+                .get_frames()
+                .iter()
+                .map(|frame| frame.get_name().to_string())
+                .collect()
+        };
+        let new = || Spriterator::new(dir.to_str().unwrap(), 64, 64, None, None);
+
+        assert_eq!(
+            names(new()),
+            vec![
+                ".backup/a.png",
+                "a.png",
+                "nested/b.png",
+                "nested/deeper/c.png"
+            ]
+        );
+        assert_eq!(
+            names(new().with_skip_hidden(true).with_max_depth(Some(1))),
+            vec!["a.png", "nested/b.png"]
+        );
+        assert_eq!(names(new().with_max_depth(Some(0))), vec!["a.png"]);
+        #[cfg(unix)]
+        assert_eq!(
+            names(new().with_follow_links(true).with_skip_hidden(true)),
+            vec![
+                "a.png",
+                "linked/b.png",
+                "linked/deeper/c.png",
+                "nested/b.png",
+                "nested/deeper/c.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_order_and_pivots() {
+        let dir = test_dir("manifest_order");
+        noise_image(4, 4, 0).save(dir.join("a.png")).unwrap();
+        noise_image(4, 4, 1).save(dir.join("b.png")).unwrap();
+        let manifest = dir.join("frames.json");
+        std::fs::write(
+            &manifest,
+            r#"[{ "path": "b.png", "pivot": { "x": 0.5, "y": 1.0 } }, { "path": "a.png" }]"#,
+        )
+        .unwrap();
+
+        let sprites =
+            Spriterator::from_image_source(ManifestSource::new(&manifest), 64, 64, None, None)
+                .generate()
+                .unwrap();
+        let frames = sprites[0].get_frames();
+        assert_eq!(frames[0].get_name(), "b.png");
+        assert_eq!(frames[0].get_pivot(), Some((0.5, 1.0)));
+        assert_eq!(frames[1].get_name(), "a.png");
+        assert_eq!(frames[1].get_pivot(), None);
+    }
+
+    #[test]
+    fn test_extrude() {
+        let mut sheet = RgbaImage::new(6, 6);
+        sheet.put_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        sheet.put_pixel(3, 3, Rgba([0, 0, 255, 255]));
+        extrude(&mut sheet, Rect::new(2, 2, 2, 2), 2);
+        assert_eq!(*sheet.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*sheet.get_pixel(2, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*sheet.get_pixel(5, 5), Rgba([0, 0, 255, 255]));
+        assert_eq!(*sheet.get_pixel(3, 2), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_extrude_frames() {
+        let spriterator = Spriterator::new("test_dir", 32, 32, None, None).with_extrude(3);
+        let images = vec![
+            spriterator.prepare_image("a.png", noise_image(4, 4, 0)),
+            spriterator.prepare_image("b.png", noise_image(2, 5, 1)),
+        ];
+        let sprite = spriterator.pack(images).unwrap().remove(0);
+        let sheet = sprite.get_image();
+        let frames = sprite.get_frames();
+
+        assert_eq!(sheet.dimensions(), (18, 11));
+        assert_eq!(frames[0].rect(), Rect::new(3, 3, 4, 4));
+        assert_eq!(frames[1].rect(), Rect::new(13, 3, 2, 5));
+        assert_eq!(sheet.get_pixel(0, 0), sheet.get_pixel(3, 3));
+        assert_eq!(sheet.get_pixel(9, 4), sheet.get_pixel(6, 4));
+        assert_eq!(sheet.get_pixel(10, 4), sheet.get_pixel(13, 4));
+        assert_eq!(sheet.get_pixel(14, 10), sheet.get_pixel(14, 7));
+    }
+
+    #[test]
+    fn test_padding_spacing_margin() {
+        let spriterator = Spriterator::new("test_dir", 22, 32, None, None)
+            .with_extrude(1)
+            .with_padding(1)
+            .with_spacing(2)
+            .with_margin(3);
+        let images = vec![
+            spriterator.prepare_image("a.png", noise_image(4, 4, 0)),
+            spriterator.prepare_image("b.png", noise_image(2, 5, 1)),
+            spriterator.prepare_image("c.png", noise_image(4, 4, 2)),
+        ];
+        let sprite = spriterator.pack(images).unwrap().remove(0);
+        let sheet = sprite.get_image();
+        let frames = sprite.get_frames();
+
+        assert_eq!(frames[0].rect(), Rect::new(5, 5, 4, 4));
+        assert_eq!(frames[1].rect(), Rect::new(15, 5, 2, 5));
+        assert_eq!(frames[2].rect(), Rect::new(5, 16, 4, 4));
+        assert_eq!(sheet.dimensions(), (22, 25));
+        assert_eq!(sheet.get_pixel(4, 5), sheet.get_pixel(5, 5));
+        assert_eq!(sheet.get_pixel(3, 5)[3], 0);
+        assert_eq!(sheet.get_pixel(0, 0)[3], 0);
+
+        let invalid = Spriterator::new("test_dir", 16, 16, Some(9), None)
+            .with_padding(2)
+            .with_margin(2);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_block_alignment() {
+        let check = |spriterator: Spriterator, border: u32| {
+            let images = (0..9)
+                .map(|index| {
+                    let image = noise_image(5 + index % 4, 3 + index % 5, index);
+                    spriterator.prepare_image(&format!("{}.png", index), image)
+                })
+                .collect();
+            let sheets = spriterator.pack(images).unwrap();
+            for sheet in &sheets {
+                let (width, height) = sheet.get_image().dimensions();
+                assert!(width.is_multiple_of(4) && height.is_multiple_of(4));
+                for frame in sheet.get_frames() {
+                    let slot = Rect::new(
+                        frame.get_x() - border,
+                        frame.get_y() - border,
+                        frame.get_width() + border * 2,
+                        frame.get_height() + border * 2,
+                    );
+                    assert!(
+                        slot.x.is_multiple_of(4) && slot.y.is_multiple_of(4),
+                        "{:?}",
+                        slot
+                    );
+                    // No other frame reaches into the blocks covering this slot.
+                    let blocks = Rect::new(
+                        slot.x,
+                        slot.y,
+                        slot.w.next_multiple_of(4),
+                        slot.h.next_multiple_of(4),
+                    );
+                    for other in sheet.get_frames() {
+                        if other.get_name() != frame.get_name() {
+                            assert!(!blocks.intersects(&other.rect()));
+                        }
+                    }
+                }
+            }
+        };
+
+        let spriterator = Spriterator::new("test_dir", 30, 30, None, None).with_margin(1);
+        check(spriterator.with_spacing(1).with_block_alignment(true), 0);
+        let spriterator = Spriterator::new("test_dir", 40, 40, None, None).with_padding(1);
+        check(
+            spriterator.with_columns(Some(3)).with_block_alignment(true),
+            1,
+        );
+        let spriterator = Spriterator::new("test_dir", 40, 40, None, None).with_extrude(2);
+        check(spriterator.with_grid(true).with_block_alignment(true), 2);
+    }
+
+    #[test]
+    fn test_post_processors() {
+        let spriterator = Spriterator::new("test_dir", 16, 16, None, None)
+            .with_post_processor(|mut sheet: RgbaImage, frames: &[Frame]| {
+                let frame = &frames[0];
+                sheet.put_pixel(frame.get_x(), frame.get_y(), Rgba([1, 2, 3, 4]));
+                Ok(sheet)
+            })
+            .with_post_processor(|mut sheet: RgbaImage, _: &[Frame]| {
+                sheet.get_pixel_mut(0, 0)[3] = 255;
+                Ok(sheet)
+            });
+        let images = vec![spriterator.prepare_image("a.png", noise_image(4, 4, 0))];
+        let sheet = spriterator.pack(images).unwrap().remove(0);
+        assert_eq!(*sheet.get_image().get_pixel(0, 0), Rgba([1, 2, 3, 255]));
+
+        let failing = Spriterator::new("test_dir", 16, 16, None, None)
+            .with_post_processor(|_: RgbaImage, _: &[Frame]| Err("rejected".into()));
+        let images = vec![failing.prepare_image("a.png", noise_image(4, 4, 0))];
+        assert_eq!(failing.pack(images).unwrap_err().to_string(), "rejected");
+    }
+
+    #[test]
+    fn test_channel_packing() {
+        let spriterator =
+            Spriterator::new("test_dir", 64, 64, None, None).with_channel_packing(true);
+        let masks = [
+            RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 255])),
+            RgbaImage::from_pixel(2, 6, Rgba([255, 255, 255, 128])),
+            RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255])),
+            RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 0])),
+            RgbaImage::from_pixel(3, 3, Rgba([255, 255, 255, 255])),
+        ];
+        let images = masks
+            .into_iter()
+            .enumerate()
+            .map(|(index, mask)| spriterator.prepare_image(&format!("{}.png", index), mask))
+            .collect();
+        let sprites = spriterator.pack(images).unwrap();
+        let sheet = sprites[0].get_image();
+        let frames = sprites[0].get_frames();
+
+        assert_eq!(frames.len(), 5);
+        let channels: Vec<Option<u8>> = frames.iter().map(Frame::get_channel).collect();
+        assert_eq!(channels, vec![Some(0), Some(1), Some(2), Some(3), Some(0)]);
+        assert_eq!(frames[1].rect(), Rect::new(0, 0, 2, 6));
+        assert_eq!(frames[4].rect(), Rect::new(4, 0, 3, 3));
+        assert_eq!(*sheet.get_pixel(1, 1), Rgba([255, 128, 0, 0]));
+        assert_eq!(*sheet.get_pixel(1, 5), Rgba([0, 128, 0, 0]));
+        assert_eq!(sheet.get_pixel(5, 1)[0], 255);
+    }
+
+    #[test]
+    fn test_clear_transparent() {
+        let mut image = noise_image(4, 4, 3);
+        image.put_pixel(1, 1, Rgba([200, 10, 30, 0]));
+        image.put_pixel(2, 1, Rgba([200, 10, 30, 1]));
+
+        let spriterator =
+            Spriterator::new("test_dir", 16, 16, None, None).with_clear_transparent(true);
+        let images = vec![spriterator.prepare_image("a.png", image)];
+        let sheet = spriterator.pack(images).unwrap().remove(0);
+
+        assert_eq!(*sheet.get_image().get_pixel(1, 1), Rgba([0, 0, 0, 0]));
+        assert_eq!(*sheet.get_image().get_pixel(2, 1), Rgba([200, 10, 30, 1]));
+    }
+
+    #[test]
+    fn test_alpha_bleed() {
+        let mut sheet = RgbaImage::new(5, 1);
+        sheet.put_pixel(0, 0, Rgba([200, 100, 0, 255]));
+        sheet.put_pixel(4, 0, Rgba([0, 0, 50, 0]));
+        alpha_bleed(&mut sheet, 2);
+        assert_eq!(*sheet.get_pixel(1, 0), Rgba([200, 100, 0, 0]));
+        assert_eq!(*sheet.get_pixel(2, 0), Rgba([200, 100, 0, 0]));
+        assert_eq!(*sheet.get_pixel(3, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(*sheet.get_pixel(4, 0), Rgba([0, 0, 50, 0]));
+
+        let mut sheet = RgbaImage::new(3, 3);
+        sheet.put_pixel(0, 1, Rgba([100, 0, 0, 255]));
+        sheet.put_pixel(2, 1, Rgba([0, 200, 0, 1]));
+        alpha_bleed(&mut sheet, 1);
+        assert_eq!(*sheet.get_pixel(1, 1), Rgba([50, 100, 0, 0]));
+        assert_eq!(*sheet.get_pixel(0, 0), Rgba([100, 0, 0, 0]));
+
+        let invalid = Spriterator::new("test_dir", 16, 16, None, None)
+            .with_alpha_bleed(2)
+            .with_clear_transparent(true);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_background() {
+        let mut image = RgbaImage::from_pixel(3, 2, Rgba([255, 255, 255, 255]));
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        image.put_pixel(1, 0, Rgba([0, 0, 0, 128]));
+
+        let spriterator = Spriterator::new("test_dir", 16, 16, None, None)
+            .with_power_of_two(true)
+            .with_background(Some(Rgba([255, 0, 0, 255])));
+        let images = vec![spriterator.prepare_image("a.png", image)];
+        let sheet = spriterator.pack(images).unwrap().remove(0);
+        let sheet = sheet.get_image();
+
+        assert_eq!(sheet.dimensions(), (4, 2));
+        assert_eq!(*sheet.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*sheet.get_pixel(1, 0), Rgba([127, 0, 0, 255]));
+        assert_eq!(*sheet.get_pixel(2, 1), Rgba([255, 255, 255, 255]));
+        assert_eq!(*sheet.get_pixel(3, 1), Rgba([255, 0, 0, 255]));
+
+        let invalid = Spriterator::new("test_dir", 16, 16, None, None)
+            .with_channel_packing(true)
+            .with_background(Some(Rgba([0, 0, 0, 255])));
+        assert!(matches!(
+            invalid.validate(),
+            Err(SpriteratorError::InvalidConfiguration { .. })
+        ));
+    }
+
+    #[test]
+    fn test_expand_to_power_of_two() {
+        let expanded = expand_to_power_of_two(RgbaImage::new(20, 16)).unwrap();
+        assert_eq!(expanded.dimensions(), (32, 16));
+
+        let spriterator =
+            Spriterator::new("test_dir", 1000, 1000, None, None).with_power_of_two(true);
+        let images: Vec<_> = (0..4)
+            .map(|index| {
+                spriterator.prepare_image(&format!("{}.png", index), RgbaImage::new(300, 300))
+            })
+            .collect();
+        let sheets = spriterator.pack(images).unwrap();
+        assert_eq!(sheets.len(), 4);
+        for sheet in &sheets {
+            assert_eq!(sheet.get_image().dimensions(), (512, 512));
+        }
+
+        let oversized = vec![spriterator.prepare_image("a.png", RgbaImage::new(600, 600))];
+        assert!(matches!(
+            spriterator.pack(oversized),
+            Err(error) if matches!(
+                error.downcast_ref::<SpriteratorError>(),
+                Some(SpriteratorError::ImageTooLarge { max_width: 512, max_height: 512, .. })
+            )
+        ));
     }
 
     #[test]
     fn test_trim_transparent() {
-        let spriterator = Spriterator::new("test_dir", 1024, 1024, None, None);
         let mut image = RgbaImage::new(10, 10);
         for x in 2..8 {
             for y in 2..8 {
                 image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
             }
         }
-        let trimmed = spriterator.trim_transparent(&image);
-        assert_eq!(trimmed.width(), 6);
-        assert_eq!(trimmed.height(), 6);
+        let bounds = opaque_bounds(&image).unwrap();
+        assert_eq!(bounds.w, 6);
+        assert_eq!(bounds.h, 6);
+        assert!(opaque_bounds(&RgbaImage::new(4, 4)).is_none());
+    }
+
+    #[test]
+    fn test_transparent_frames_stay_on_sheet() {
+        let spriterator = Spriterator::new("test_dir", 64, 64, None, None);
+        let images = vec![
+            spriterator.prepare_image("a.png", noise_image(8, 8, 1)),
+            spriterator.prepare_image("b.png", RgbaImage::new(8, 8)),
+        ];
+        let sprites = spriterator.pack(images).unwrap();
+        assert_eq!(sprites[0].get_image().dimensions(), (16, 8));
+    }
+
+    #[test]
+    fn test_prepare_image_trim() {
+        let spriterator = Spriterator::new("test_dir", 1024, 1024, None, None).with_trim(true);
+        let mut image = RgbaImage::new(10, 8);
+        image.put_pixel(3, 2, Rgba([255, 0, 0, 255]));
+        image.put_pixel(6, 4, Rgba([255, 0, 0, 255]));
+        let source = spriterator.prepare_image("frame.png", image);
+        assert!(source.trimmed);
+        assert_eq!(source.image.dimensions(), (4, 3));
+        assert_eq!((source.source_width, source.source_height), (10, 8));
+        assert_eq!((source.offset_x, source.offset_y), (3, 2));
+    }
+
+    #[test]
+    fn test_prepare_image_without_trim() {
+        let spriterator = Spriterator::new("test_dir", 1024, 1024, None, None);
+        let source = spriterator.prepare_image("frame.png", RgbaImage::new(10, 8));
+        assert!(!source.trimmed);
+        assert_eq!(source.image.dimensions(), (10, 8));
     }
 
     #[test]
     fn test_resize_image1() {
         let spriterator = Spriterator::new("test_dir", 100, 100, Some(10), Some(10));
-        let resized = spriterator.resize_image(RgbaImage::new(10, 10));
+        let resized = spriterator.resize_image(RgbaImage::new(10, 10)).unwrap();
         assert_eq!(resized.width(), 10);
         assert_eq!(resized.height(), 10);
     }
@@ -241,7 +4019,7 @@ mod tests {
     #[test]
     fn test_resize_image2() {
         let spriterator = Spriterator::new("test_dir", 100, 100, Some(10), None);
-        let resized = spriterator.resize_image(RgbaImage::new(20, 20));
+        let resized = spriterator.resize_image(RgbaImage::new(20, 20)).unwrap();
         assert_eq!(resized.width(), 10);
         assert_eq!(resized.height(), (20 * 10) / 20);
     }
@@ -249,8 +4027,169 @@ mod tests {
     #[test]
     fn test_resize_image3() {
         let spriterator = Spriterator::new("test_dir", 100, 100, None, Some(10));
-        let resized = spriterator.resize_image(RgbaImage::new(30, 30));
+        let resized = spriterator.resize_image(RgbaImage::new(30, 30)).unwrap();
         assert_eq!(resized.width(), (30 * 10) / 30);
         assert_eq!(resized.height(), 10);
     }
+
+    #[test]
+    fn test_invalid_dimensions() {
+        let error = Spriterator::new("test_dir", 0, 64, None, None)
+            .generate()
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SpriteratorError>(),
+            Some(SpriteratorError::InvalidDimensions { .. })
+        ));
+
+        let spriterator = Spriterator::new("test_dir", 64, 64, Some(0), None);
+        assert!(spriterator.validate_dimensions().is_err());
+        assert!(Spriterator::new("test_dir", u32::MAX, u32::MAX, None, None)
+            .validate_dimensions()
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Spriterator::new("test_dir", 64, 64, Some(32), None)
+            .with_tile_preset()
+            .validate()
+            .is_ok());
+
+        let mut usage_weights = HashMap::new();
+        usage_weights.insert("a.png".to_string(), f64::NAN);
+        let spriterator = Spriterator::new("test_dir", 64, 100, Some(62), None)
+            .with_extrude(2)
+            .with_array_texture(true)
+            .with_power_of_two(true)
+            .with_max_encoded_bytes(Some(0))
+            .with_usage_weights(usage_weights);
+
+        match spriterator.validate() {
+            Err(SpriteratorError::InvalidConfiguration { diagnostics }) => {
+                assert_eq!(diagnostics.len(), 4);
+                assert!(diagnostics[0].contains("width of 62"));
+                assert!(diagnostics[1].contains("64x100"));
+                assert!(diagnostics[3].contains("\"a.png\""));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let error = Spriterator::new("test_dir", 4, 64, None, None)
+            .with_extrude(2)
+            .validate()
+            .unwrap_err();
+        assert!(error.to_string().contains("leaves no room"));
+    }
+
+    #[test]
+    fn test_resize_degenerate_images() {
+        let spriterator = Spriterator::new("test_dir", 64, 64, Some(10), None);
+        let resized = spriterator.resize_image(RgbaImage::new(1000, 1)).unwrap();
+        assert_eq!(resized.dimensions(), (10, 1));
+        assert_eq!(
+            spriterator
+                .resize_image(RgbaImage::new(0, 5))
+                .unwrap()
+                .dimensions(),
+            (0, 5)
+        );
+    }
+
+    #[test]
+    fn test_image_too_large_after_extrusion() {
+        let spriterator = Spriterator::new("test_dir", 16, 16, None, None).with_extrude(1);
+        let images = vec![spriterator.prepare_image("a.png", noise_image(16, 8, 1))];
+        let error = spriterator.pack(images).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SpriteratorError>(),
+            Some(SpriteratorError::ImageTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_empty_image() {
+        let spriterator = Spriterator::new("test_dir", 16, 16, None, None);
+        let images = vec![
+            spriterator.prepare_image("a.png", noise_image(4, 4, 1)),
+            spriterator.prepare_image("b.png", noise_image(3, 0, 2)),
+        ];
+        let error = spriterator.pack(images).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SpriteratorError>(),
+            Some(SpriteratorError::InvalidDimensions { what, .. }) if what == "image b.png"
+        ));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_pack_never_panics(
+            sizes in prop::collection::vec((0u32..40, 0u32..40), 1..10),
+            max_width in 0u32..96,
+            max_height in 0u32..96,
+            trim in any::<bool>(),
+            grid in any::<bool>(),
+            columns in prop::option::of(1u32..4),
+            extrude in 0u32..3,
+            (padding, spacing, margin) in (0u32..3, 0u32..3, 0u32..3),
+            power_of_two in any::<bool>(),
+            array_texture in any::<bool>(),
+        ) {
+            let spriterator = Spriterator::new("test_dir", max_width, max_height, None, None)
+                .with_trim(trim)
+                .with_grid(grid)
+                .with_columns(columns)
+                .with_extrude(extrude)
+                .with_padding(padding)
+                .with_spacing(spacing)
+                .with_margin(margin)
+                .with_power_of_two(power_of_two)
+                .with_array_texture(array_texture);
+            let images = sizes
+                .iter()
+                .enumerate()
+                .map(|(index, &(width, height))| {
+                    spriterator.prepare_image(
+                        &format!("{}.png", index),
+                        noise_image(width, height, index as u32),
+                    )
+                })
+                .collect();
+
+            if let Ok(sprites) = spriterator.pack(images) {
+                let frames: Vec<&Frame> =
+                    sprites.iter().flat_map(|sprite| sprite.get_frames()).collect();
+                prop_assert_eq!(frames.len(), sizes.len());
+
+                for sprite in &sprites {
+                    let (width, height) = sprite.get_image().dimensions();
+                    let sheet = Rect::new(0, 0, width, height);
+                    for (index, frame) in sprite.get_frames().iter().enumerate() {
+                        prop_assert!(sheet.contains_rect(&frame.rect()));
+                        for other in &sprite.get_frames()[index + 1..] {
+                            prop_assert!(!frame.rect().intersects(&other.rect()));
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn test_resize_never_panics(
+            width in 0u32..64,
+            height in 0u32..64,
+            image_width in prop::option::of(0u32..128),
+            image_height in prop::option::of(0u32..128),
+        ) {
+            let spriterator = Spriterator::new("test_dir", 256, 256, image_width, image_height);
+            if spriterator.validate_dimensions().is_ok() {
+                let resized = spriterator.resize_image(RgbaImage::new(width, height)).unwrap();
+                if let Some(image_width) = image_width.filter(|_| width > 0 && height > 0) {
+                    prop_assert_eq!(resized.width(), image_width);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,229 @@
+use std::error::Error;
+
+use crate::export::png_text::KEYWORD;
+
+/// Ancillary PNG chunks kept when stripping: transparency and the sRGB tag, which change how
+/// pixels are displayed rather than describe where they came from.
+const PNG_KEPT_CHUNKS: [&[u8; 4]; 2] = [b"tRNS", b"sRGB"];
+
+/// Text chunks that may hold the atlas metadata embedded by `Sprite::save_png_with_metadata`.
+const PNG_TEXT_CHUNKS: [&[u8; 4]; 3] = [b"tEXt", b"zTXt", b"iTXt"];
+
+/// JPEG application segments kept when stripping: JFIF (APP0), which only records the pixel
+/// density, and Adobe (APP14), which decoders need to interpret the color channels.
+const JPEG_KEPT_SEGMENTS: [u8; 2] = [0xe0, 0xee];
+
+/// Flags of a WebP VP8X chunk announcing EXIF and XMP chunks.
+const WEBP_METADATA_FLAGS: u8 = 0x08 | 0x04;
+
+/// Removes every metadata block from an encoded sheet, such as EXIF, XMP, comments, text
+/// chunks and timestamps, so that the file reveals nothing about its sources and is
+/// byte-identical between builds.
+///
+/// PNG text chunks holding the atlas metadata of `Sprite::save_png_with_metadata` are kept.
+/// Formats other than PNG, JPEG and WebP carry no metadata as encoded by this crate and are
+/// returned unchanged.
+///
+/// # Returns
+/// A `Result` containing the stripped data, or an error if a PNG, JPEG or WebP is malformed.
+pub(crate) fn strip_metadata(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        strip_png(data)
+    } else if data.starts_with(&[0xff, 0xd8]) {
+        strip_jpeg(data)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        strip_webp(data)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
+/// Keeps the critical chunks of a PNG, those in `PNG_KEPT_CHUNKS` and the atlas text chunk.
+fn strip_png(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut stripped = data[..8].to_vec();
+    let mut rest = &data[8..];
+
+    while !rest.is_empty() {
+        let length = read_u32_be(rest).ok_or("Truncated PNG chunk.")? as usize;
+        let end = length
+            .checked_add(12)
+            .filter(|&end| end <= rest.len())
+            .ok_or("Truncated PNG chunk.")?;
+        let (chunk, next) = rest.split_at(end);
+        let kind = &chunk[4..8];
+        let content = &chunk[8..8 + length];
+
+        let critical = kind[0].is_ascii_uppercase();
+        let atlas = PNG_TEXT_CHUNKS.iter().any(|text| kind == *text)
+            && content.split(|&byte| byte == 0).next() == Some(KEYWORD.as_bytes());
+        if critical || atlas || PNG_KEPT_CHUNKS.iter().any(|kept| kind == *kept) {
+            stripped.extend_from_slice(chunk);
+        }
+        rest = next;
+    }
+    Ok(stripped)
+}
+
+/// Drops the comments and the application segments not in `JPEG_KEPT_SEGMENTS` of a JPEG.
+///
+/// Only the segments before the first scan are inspected; everything from the start of scan
+/// marker on is entropy-coded image data and copied as is.
+fn strip_jpeg(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut stripped = data[..2].to_vec();
+    let mut position = 2;
+
+    loop {
+        if data.get(position) != Some(&0xff) {
+            return Err("Expected a JPEG marker.".into());
+        }
+        let marker = *data.get(position + 1).ok_or("Truncated JPEG marker.")?;
+        match marker {
+            // Fill bytes may precede any marker.
+            0xff => {
+                position += 1;
+                continue;
+            }
+            // Start of scan: image data follows up to the end of the file.
+            0xda => {
+                stripped.extend_from_slice(&data[position..]);
+                return Ok(stripped);
+            }
+            _ => {}
+        }
+
+        let length = data
+            .get(position + 2..position + 4)
+            .map(|bytes| usize::from(u16::from_be_bytes([bytes[0], bytes[1]])))
+            .ok_or("Truncated JPEG segment.")?;
+        let end = position + 2 + length;
+        if length < 2 || end > data.len() {
+            return Err("Truncated JPEG segment.".into());
+        }
+
+        let application = (0xe0..=0xef).contains(&marker);
+        let comment = marker == 0xfe;
+        if !comment && (!application || JPEG_KEPT_SEGMENTS.contains(&marker)) {
+            stripped.extend_from_slice(&data[position..end]);
+        }
+        position = end;
+    }
+}
+
+/// Drops the EXIF and XMP chunks of a WebP and clears the flags announcing them.
+fn strip_webp(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut stripped = b"RIFF\0\0\0\0WEBP".to_vec();
+    let mut rest = &data[12..];
+
+    while !rest.is_empty() {
+        let length =
+            read_u32_le(rest.get(4..).unwrap_or_default()).ok_or("Truncated WebP chunk.")? as usize;
+        // Chunks are padded to an even size.
+        let end = (length + length % 2)
+            .checked_add(8)
+            .filter(|&end| end <= rest.len())
+            .ok_or("Truncated WebP chunk.")?;
+        let (chunk, next) = rest.split_at(end);
+
+        match &chunk[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" if length > 0 => {
+                let start = stripped.len();
+                stripped.extend_from_slice(chunk);
+                stripped[start + 8] &= !WEBP_METADATA_FLAGS;
+            }
+            _ => stripped.extend_from_slice(chunk),
+        }
+        rest = next;
+    }
+
+    let size = u32::try_from(stripped.len() - 8)?;
+    stripped[4..8].copy_from_slice(&size.to_le_bytes());
+    Ok(stripped)
+}
+
+fn read_u32_be(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+fn read_u32_le(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::png_text::embed;
+    use image::{Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    fn image() -> RgbaImage {
+        RgbaImage::from_pixel(4, 4, Rgba([200, 100, 50, 255]))
+    }
+
+    #[test]
+    fn test_strip_png() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 4, 4);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder
+            .add_text_chunk("Author".to_string(), "someone".to_string())
+            .unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_chunk(png::chunk::tIME, &[7, 234, 1, 2, 3, 4, 5])
+            .unwrap();
+        writer.write_chunk(png::chunk::eXIf, b"MM\0*").unwrap();
+        writer.write_image_data(image().as_raw()).unwrap();
+        writer.finish().unwrap();
+        let data = embed(&data, "{}").unwrap();
+
+        let stripped = strip_metadata(&data).unwrap();
+        let reader = png::Decoder::new(Cursor::new(&stripped))
+            .read_info()
+            .unwrap();
+        let info = reader.info();
+        assert!(info.uncompressed_latin1_text.is_empty());
+        assert!(info.exif_metadata.is_none());
+        assert_eq!(info.compressed_latin1_text[0].keyword, KEYWORD);
+        assert!(!stripped.windows(4).any(|kind| kind == b"tIME"));
+        assert_eq!(
+            image::load_from_memory(&stripped).unwrap().to_rgba8(),
+            image()
+        );
+    }
+
+    #[test]
+    fn test_strip_jpeg() {
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new(&mut jpeg)
+            .encode_image(&image::DynamicImage::ImageRgba8(image()).to_rgb8())
+            .unwrap();
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xff, 0xe1, 0, 6, b'E', b'x', b'i', b'f']);
+        data.extend_from_slice(&[0xff, 0xfe, 0, 4, b'h', b'i']);
+        data.extend_from_slice(&jpeg[2..]);
+
+        assert_eq!(strip_metadata(&data).unwrap(), jpeg);
+        assert!(strip_jpeg(&[0xff, 0xd8, 0xff, 0xe1, 0, 9]).is_err());
+    }
+
+    #[test]
+    fn test_strip_webp() {
+        let mut webp = Vec::new();
+        image::codecs::webp::WebPEncoder::new_lossless(&mut webp)
+            .encode(image().as_raw(), 4, 4, image::ExtendedColorType::Rgba8)
+            .unwrap();
+        let mut data = webp.clone();
+        data.extend_from_slice(b"EXIF\x03\0\0\0abc\0");
+        let size = (data.len() - 8) as u32;
+        data[4..8].copy_from_slice(&size.to_le_bytes());
+
+        assert_eq!(strip_metadata(&data).unwrap(), webp);
+    }
+
+    #[test]
+    fn test_other_formats_unchanged() {
+        assert_eq!(strip_metadata(b"BM1234").unwrap(), b"BM1234");
+        assert!(strip_metadata(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR").is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use std::sync::{Arc, Mutex};
+
+use crate::Warning;
+
+/// Returns a transparent `width`×`height` image encoded as PNG.
+#[cfg(any(feature = "tar", feature = "zip"))]
+pub(crate) fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    image::RgbaImage::new(width, height)
+        .write_to(
+            &mut std::io::Cursor::new(&mut data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+    data
+}
+
+/// Returns the list of warnings received so far and a warning handler adding to it.
+pub(crate) fn collect_warnings() -> (
+    Arc<Mutex<Vec<Warning>>>,
+    impl Fn(&Warning) + Clone + Send + Sync + 'static,
+) {
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&warnings);
+    let handler = move |warning: &Warning| received.lock().unwrap().push(warning.clone());
+    (warnings, handler)
+}
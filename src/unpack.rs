@@ -0,0 +1,173 @@
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+use std::error::Error;
+use std::path::Path;
+
+use crate::atomic_write::{write_atomic, OverwritePolicy};
+use crate::export::{relative_output_path, strip_extension};
+use crate::metadata::{FrameMetadata, SheetMetadata};
+use crate::source::NamedImage;
+
+/// Cuts the image of `frame` out of `sheet`, restoring its source size and trimmed borders.
+///
+/// Frames packed into a color channel become white images whose alpha is the mask, which
+/// packs into the same mask again. The frame must lie within the sheet, as checked by
+/// `validate_metadata`.
+pub(crate) fn frame_image(sheet: &RgbaImage, frame: &FrameMetadata) -> RgbaImage {
+    let mut packed =
+        imageops::crop_imm(sheet, frame.x, frame.y, frame.width, frame.height).to_image();
+    if let Some(channel) = frame.channel {
+        for pixel in packed.pixels_mut() {
+            *pixel = Rgba([255, 255, 255, pixel[channel as usize]]);
+        }
+    }
+
+    if !frame.trimmed {
+        return packed;
+    }
+
+    let mut image = RgbaImage::new(frame.source_width, frame.source_height);
+    imageops::replace(
+        &mut image,
+        &packed,
+        frame.offset_x as i64,
+        frame.offset_y as i64,
+    );
+    image
+}
+
+/// Extracts every frame of a saved sheet back into an individual image.
+///
+/// Trimmed frames get their transparent borders back, and frames keep their names, durations
+/// and pivots. Frames packed into a color channel become white images whose alpha is the mask.
+///
+/// # Arguments
+/// - `sheet`: The sheet image.
+/// - `metadata`: The native metadata of the sheet, such as an entry of `Metadata::sheets`.
+///
+/// # Returns
+/// A `Result` containing the frames in packing order, or an error if the sheet does not match
+/// its metadata.
+pub fn unpack(
+    sheet: &RgbaImage,
+    metadata: &SheetMetadata,
+) -> Result<Vec<NamedImage>, Box<dyn Error>> {
+    if sheet.dimensions() != (metadata.width, metadata.height) {
+        return Err(format!(
+            "Sheet {:?} is {}x{} but its metadata describes {}x{}.",
+            metadata.image,
+            sheet.width(),
+            sheet.height(),
+            metadata.width,
+            metadata.height
+        )
+        .into());
+    }
+    if let Some(frame) = metadata.frames.iter().find(|frame| {
+        u64::from(frame.x) + u64::from(frame.width) > u64::from(sheet.width())
+            || u64::from(frame.y) + u64::from(frame.height) > u64::from(sheet.height())
+    }) {
+        return Err(format!("Frame {:?} lies outside the sheet.", frame.name).into());
+    }
+
+    Ok(metadata
+        .frames
+        .iter()
+        .map(|frame| unpack_frame(sheet, frame))
+        .collect())
+}
+
+/// Extracts `frame` out of `sheet` with its name, duration and pivot.
+pub(crate) fn unpack_frame(sheet: &RgbaImage, frame: &FrameMetadata) -> NamedImage {
+    NamedImage::new(&frame.name, frame_image(sheet, frame))
+        .with_duration(frame.duration)
+        .with_pivot(frame.pivot)
+}
+
+/// Writes unpacked frames into `dir` as PNG images named after the frames, creating
+/// subdirectories for names such as `hero/walk.png`.
+///
+/// Frames whose name does not end with `.png` get it in place of their extension, so
+/// `walk_03.gif` is written as `walk_03.png`. Names that would place a file outside `dir`,
+/// such as `../walk.png` or absolute paths, are rejected.
+///
+/// # Arguments
+/// - `dir`: Directory to write the images into.
+/// - `frames`: The frames, as returned by `unpack` or `Sprite::split`.
+pub fn write_frames(dir: &str, frames: &[NamedImage]) -> Result<(), Box<dyn Error>> {
+    for frame in frames {
+        let name = frame.get_name();
+        let file_name = if name.to_ascii_lowercase().ends_with(".png") {
+            name.to_string()
+        } else {
+            format!("{}.png", strip_extension(name))
+        };
+
+        let path = Path::new(dir).join(relative_output_path(&file_name)?);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(&path, OverwritePolicy::Overwrite, |writer| {
+            Ok(frame.get_image().write_to(writer, ImageFormat::Png)?)
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+
+    #[test]
+    fn test_frame_image() {
+        let mut sheet = RgbaImage::new(8, 4);
+        sheet.put_pixel(5, 1, Rgba([10, 20, 30, 255]));
+
+        let frame = Frame::named("a.png", 4, 0, 2, 2).with_source(6, 5, 3, 1);
+        let image = frame_image(&sheet, &FrameMetadata::new(&frame));
+        assert_eq!(image.dimensions(), (6, 5));
+        assert_eq!(image.get_pixel(4, 2), &Rgba([10, 20, 30, 255]));
+        assert_eq!(image.get_pixel(0, 0), &Rgba([0, 0, 0, 0]));
+
+        let mask = Frame::named("mask.png", 4, 0, 2, 2).with_channel(1);
+        let image = frame_image(&sheet, &FrameMetadata::new(&mask));
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(1, 1), &Rgba([255, 255, 255, 20]));
+    }
+
+    #[test]
+    fn test_unpack() {
+        let mut sheet = RgbaImage::new(8, 4);
+        sheet.put_pixel(1, 1, Rgba([1, 2, 3, 255]));
+        let metadata = SheetMetadata {
+            image: "sheet.png".to_string(),
+            width: 8,
+            height: 4,
+            layer: None,
+            frames: vec![
+                FrameMetadata::new(&Frame::named("hero/walk_03.gif", 0, 0, 4, 4)),
+                FrameMetadata::new(&Frame::named("coin.png", 4, 0, 2, 2).with_duration(Some(80))),
+            ],
+        };
+
+        let frames = unpack(&sheet, &metadata).unwrap();
+        assert_eq!(frames[0].get_name(), "hero/walk_03.gif");
+        assert_eq!(frames[0].get_image().get_pixel(1, 1), &Rgba([1, 2, 3, 255]));
+        assert_eq!(frames[1].get_duration(), Some(80));
+
+        let dir = std::env::temp_dir().join("spriterator_unpack");
+        let _ = std::fs::remove_dir_all(&dir);
+        write_frames(dir.to_str().unwrap(), &frames).unwrap();
+        let walk = image::open(dir.join("hero/walk_03.png"))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(&walk, frames[0].get_image());
+        assert!(dir.join("coin.png").is_file());
+
+        let escaping = NamedImage::new("../escaped.png", RgbaImage::new(1, 1));
+        assert!(write_frames(dir.to_str().unwrap(), &[escaping]).is_err());
+        assert!(!dir.with_file_name("escaped.png").exists());
+
+        assert!(unpack(&RgbaImage::new(4, 4), &metadata).is_err());
+    }
+}
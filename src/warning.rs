@@ -0,0 +1,117 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A problem found during generation that does not stop it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// The path of an input image is not valid Unicode; its frame is named with the
+    /// invalid sequences replaced by `U+FFFD`.
+    LossyFileName { path: PathBuf, name: String },
+    /// An input image is in a format this build cannot decode, such as AVIF without the
+    /// `avif-decode` feature, and was skipped.
+    UnsupportedFormat { path: PathBuf },
+    /// An input image could not be decoded, for example because it is corrupt, and was skipped.
+    UndecodableImage { path: PathBuf },
+    /// An input image has a color profile that could not be converted to sRGB, such as an
+    /// ICC profile in a build without the `icc` feature; its colors are used unchanged.
+    UnconvertedColorProfile { path: PathBuf, message: String },
+    /// A `.spriteignore` file contains invalid patterns, which are disregarded.
+    InvalidIgnoreFile { path: PathBuf, message: String },
+    /// A resized image could not be stored in the directory set with
+    /// `Spriterator::with_resize_cache`; it is resized again on the next run.
+    ResizeCacheUnavailable { path: PathBuf, message: String },
+    /// The generated sheets exceed a limit set with `Spriterator::with_engine_limits`.
+    EngineLimitExceeded { message: String },
+    /// No GPU could be used to compose sheets as set with `Spriterator::with_gpu`; they are
+    /// composed on the CPU instead.
+    GpuUnavailable { message: String },
+}
+
+impl Warning {
+    /// Returns whether the warning is about decoding an image, rather than finding it.
+    pub(crate) fn is_about_decoding(&self) -> bool {
+        matches!(
+            self,
+            Warning::UndecodableImage { .. } | Warning::UnconvertedColorProfile { .. }
+        )
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::LossyFileName { path, name } => write!(
+                f,
+                "Path {:?} is not valid Unicode; the frame is named {:?}.",
+                path, name
+            ),
+            Warning::UnsupportedFormat { path } => write!(
+                f,
+                "Image {:?} is in a format this build cannot decode; it was skipped.",
+                path
+            ),
+            Warning::UndecodableImage { path } => {
+                write!(f, "Image {:?} could not be decoded; it was skipped.", path)
+            }
+            Warning::UnconvertedColorProfile { path, message } => write!(
+                f,
+                "The color profile of image {:?} could not be converted to sRGB; its colors are used unchanged: {}",
+                path, message
+            ),
+            Warning::InvalidIgnoreFile { path, message } => write!(
+                f,
+                "Ignore file {:?} has invalid patterns, which are disregarded: {}",
+                path, message
+            ),
+            Warning::ResizeCacheUnavailable { path, message } => write!(
+                f,
+                "Resized image could not be cached as {:?}; it is resized again on the next run: {}",
+                path, message
+            ),
+            Warning::EngineLimitExceeded { message } => f.write_str(message),
+            Warning::GpuUnavailable { message } => write!(
+                f,
+                "No GPU is available, so sheets are composed on the CPU: {}",
+                message
+            ),
+        }
+    }
+}
+
+/// Receives the warnings of a `Spriterator`.
+#[derive(Clone)]
+pub(crate) struct WarningHandler(Arc<dyn Fn(&Warning) + Send + Sync>);
+
+impl WarningHandler {
+    pub(crate) fn new(handler: impl Fn(&Warning) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    pub(crate) fn warn(&self, warning: Warning) {
+        (self.0)(&warning)
+    }
+
+    /// Returns a handler passing on only the warnings for which `keep` returns `true`.
+    pub(crate) fn filtered(&self, keep: fn(&Warning) -> bool) -> Self {
+        let handler = self.clone();
+        Self::new(move |warning| {
+            if keep(warning) {
+                handler.warn(warning.clone());
+            }
+        })
+    }
+}
+
+impl Default for WarningHandler {
+    /// Ignores all warnings.
+    fn default() -> Self {
+        Self::new(|_| {})
+    }
+}
+
+impl fmt::Debug for WarningHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WarningHandler")
+    }
+}
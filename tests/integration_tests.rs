@@ -1,3 +1,5 @@
+#![allow(clippy::useless_vec)]
+
 use dotenv::dotenv;
 use spriterator::Spriterator;
 use std::env;